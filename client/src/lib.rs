@@ -0,0 +1,716 @@
+//! A typed client for driving nightshade viewers remotely.
+//!
+//! The client connects to a `nightshade server`, which relays its commands
+//! to every connected frontend and relays their events back.
+//! Frontends are websocket clients themselves, so they cannot be connected to directly
+//! and are only reachable through the relay.
+//!
+//! ```no_run
+//! # async fn example() -> Result<(), nightshade_client::ClientError> {
+//! let client = nightshade_client::Client::connect("ws://localhost:9003").await?;
+//! let cube = client.spawn_cube([0.0, 0.0, 0.0], 1.0, "Cube").await?;
+//! let cameras = client.list_cameras().await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use futures_util::{SinkExt, StreamExt};
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc, Mutex,
+};
+use tokio_tungstenite::tungstenite::{self, client::IntoClientRequest};
+
+pub use nightshade_core::api::{
+    Bloom, Command, EntitySummary, Environment, Event, FileBytes, Label, LabelMode, Line,
+    LineBuffer, LineCap, MeshBuffer, Message, Outline, PaneSettings, PointBuffer, PointShape,
+    PointSizing, PolylineBuffer, Quad, QuadBuffer, Quat, QueryCommand, QueryResult, Report,
+    SpawnCommand, TonemapOperator, Tonemapping, Transform, Vec2, Vec3, Vec4, WebsocketEvent,
+};
+pub use nightshade_core::prelude::EntityId;
+
+/// Packed buffers can make single messages far larger than the websocket default limit
+const MAX_MESSAGE_SIZE: usize = 1 << 30;
+
+/// How long queries and spawns wait for a frontend to answer, unless changed with `with_timeout`
+pub const DEFAULT_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// A request is completed with its answer, or with the error the server refused it with
+type Pending<T> =
+    Arc<Mutex<std::collections::HashMap<u64, tokio::sync::oneshot::Sender<Result<T, String>>>>>;
+
+/// Queries awaiting a result, keyed by query id
+type PendingQueries = Pending<QueryResult>;
+
+/// Spawns awaiting their created entity, keyed by spawn id
+type PendingSpawns = Pending<EntityId>;
+
+#[derive(Debug)]
+pub enum ClientError {
+    /// The websocket connection could not be established
+    Websocket(tungstenite::Error),
+
+    /// A command could not be encoded
+    Serialization(serde_json::Error),
+
+    /// A bulk command could not be encoded as binary
+    BinarySerialization(bincode::Error),
+
+    /// The connection closed before the request completed
+    Disconnected,
+
+    /// A query was answered with a result of the wrong kind
+    UnexpectedResult(QueryResult),
+
+    /// The server refused the request, such as a mutating command sent with a viewer token
+    Rejected(String),
+
+    /// No frontend answered the request in time
+    Timeout,
+}
+
+impl std::fmt::Display for ClientError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Websocket(error) => write!(f, "Websocket error: {error}"),
+            Self::Serialization(error) => write!(f, "Serialization error: {error}"),
+            Self::BinarySerialization(error) => write!(f, "Binary serialization error: {error}"),
+            Self::Disconnected => write!(f, "Disconnected"),
+            Self::UnexpectedResult(result) => write!(f, "Unexpected query result: {result}"),
+            Self::Rejected(error) => write!(f, "Rejected: {error}"),
+            Self::Timeout => write!(f, "No frontend answered in time"),
+        }
+    }
+}
+
+impl std::error::Error for ClientError {}
+
+impl From<tungstenite::Error> for ClientError {
+    fn from(error: tungstenite::Error) -> Self {
+        Self::Websocket(error)
+    }
+}
+
+impl From<serde_json::Error> for ClientError {
+    fn from(error: serde_json::Error) -> Self {
+        Self::Serialization(error)
+    }
+}
+
+impl From<bincode::Error> for ClientError {
+    fn from(error: bincode::Error) -> Self {
+        Self::BinarySerialization(error)
+    }
+}
+
+/// A connection to a nightshade server.
+/// Dropping the client closes the connection.
+pub struct Client {
+    outgoing: tokio::sync::mpsc::UnboundedSender<tungstenite::Message>,
+    events: tokio::sync::broadcast::Sender<Event>,
+    pending_queries: PendingQueries,
+    pending_spawns: PendingSpawns,
+    next_query_id: AtomicU64,
+    timeout: std::time::Duration,
+}
+
+impl Client {
+    /// Connects to a server, such as `ws://localhost:9003` or `wss://example.com:9003`
+    pub async fn connect(url: &str) -> Result<Self, ClientError> {
+        Self::connect_request(url.into_client_request()?).await
+    }
+
+    /// Connects to a server that requires a viewer or producer token
+    pub async fn connect_with_token(url: &str, token: &str) -> Result<Self, ClientError> {
+        let mut request = url.into_client_request()?;
+        let authorization = format!("Bearer {token}").parse().map_err(|_| {
+            tungstenite::Error::Url(tungstenite::error::UrlError::UnableToConnect(
+                "The token is not a valid header value".to_string(),
+            ))
+        })?;
+        request
+            .headers_mut()
+            .insert(tungstenite::http::header::AUTHORIZATION, authorization);
+        Self::connect_request(request).await
+    }
+
+    async fn connect_request(
+        request: tungstenite::handshake::client::Request,
+    ) -> Result<Self, ClientError> {
+        let config = tungstenite::protocol::WebSocketConfig {
+            max_message_size: Some(MAX_MESSAGE_SIZE),
+            max_frame_size: Some(MAX_MESSAGE_SIZE),
+            ..Default::default()
+        };
+        let (websocket_stream, _) =
+            tokio_tungstenite::connect_async_with_config(request, Some(config), false).await?;
+        let (outgoing, outgoing_receiver) = tokio::sync::mpsc::unbounded_channel();
+        let (events, _) = tokio::sync::broadcast::channel(1024);
+        let pending_queries = PendingQueries::default();
+        let pending_spawns = PendingSpawns::default();
+        tokio::spawn(run_connection(
+            websocket_stream,
+            outgoing_receiver,
+            events.clone(),
+            pending_queries.clone(),
+            pending_spawns.clone(),
+        ));
+        Ok(Self {
+            outgoing,
+            events,
+            pending_queries,
+            pending_spawns,
+            next_query_id: AtomicU64::new(first_query_id()),
+            timeout: DEFAULT_TIMEOUT,
+        })
+    }
+
+    /// Changes how long queries and spawns wait for a frontend to answer
+    pub fn with_timeout(mut self, timeout: std::time::Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Sends a command without waiting for it to execute.
+    /// Bulk commands are sent as binary frames, everything else as JSON text.
+    pub async fn send(&self, command: Command) -> Result<(), ClientError> {
+        let message = Message::Command { command };
+        let websocket_message = if message.is_bulk() {
+            tungstenite::Message::Binary(message.to_bytes()?)
+        } else {
+            tungstenite::Message::Text(serde_json::to_string(&message)?)
+        };
+        self.outgoing
+            .send(websocket_message)
+            .map_err(|_| ClientError::Disconnected)
+    }
+
+    /// Sends a query and waits for the first frontend to answer it
+    pub async fn query(&self, command: QueryCommand) -> Result<QueryResult, ClientError> {
+        let id = self.next_query_id.fetch_add(1, Ordering::Relaxed);
+        self.request(&self.pending_queries, id, Command::Query { id, command })
+            .await
+    }
+
+    /// Spawns an entity and waits for the first frontend to report it created
+    pub async fn spawn(&self, command: SpawnCommand) -> Result<EntityId, ClientError> {
+        let id = self.next_query_id.fetch_add(1, Ordering::Relaxed);
+        self.request(&self.pending_spawns, id, Command::Spawn { id, command })
+            .await
+    }
+
+    /// Sends a command and waits for the answer correlated with its id
+    async fn request<T>(
+        &self,
+        pending: &Pending<T>,
+        id: u64,
+        command: Command,
+    ) -> Result<T, ClientError> {
+        let (sender, receiver) = tokio::sync::oneshot::channel();
+        if let Ok(mut pending) = pending.lock() {
+            pending.insert(id, sender);
+        }
+        let result = match self.send(command).await {
+            Ok(()) => match tokio::time::timeout(self.timeout, receiver).await {
+                Ok(Ok(answer)) => answer.map_err(ClientError::Rejected),
+                Ok(Err(_)) => Err(ClientError::Disconnected),
+                Err(_) => Err(ClientError::Timeout),
+            },
+            Err(error) => Err(error),
+        };
+        if let Ok(mut pending) = pending.lock() {
+            pending.remove(&id);
+        }
+        result
+    }
+
+    /// Receives every event relayed by the server from the moment of subscribing,
+    /// including reports, query results and errors for rejected commands
+    pub fn subscribe(&self) -> tokio::sync::broadcast::Receiver<Event> {
+        self.events.subscribe()
+    }
+
+    pub async fn spawn_cube(
+        &self,
+        position: impl Into<Vec3>,
+        size: f32,
+        name: impl Into<String>,
+    ) -> Result<EntityId, ClientError> {
+        self.spawn(SpawnCommand::Cube {
+            position: position.into(),
+            size,
+            name: name.into(),
+        })
+        .await
+    }
+
+    pub async fn spawn_camera(
+        &self,
+        position: impl Into<Vec3>,
+        name: impl Into<String>,
+    ) -> Result<EntityId, ClientError> {
+        self.spawn(SpawnCommand::Camera {
+            position: position.into(),
+            name: name.into(),
+        })
+        .await
+    }
+
+    /// Spawns an empty entity, which lines and quads can be attached to
+    pub async fn spawn_entity(
+        &self,
+        position: impl Into<Vec3>,
+        name: impl Into<String>,
+    ) -> Result<EntityId, ClientError> {
+        self.spawn(SpawnCommand::Entity {
+            position: position.into(),
+            name: name.into(),
+        })
+        .await
+    }
+
+    pub async fn despawn(&self, entity_id: EntityId) -> Result<(), ClientError> {
+        self.send(Command::Despawn { entity_id }).await
+    }
+
+    pub async fn set_transform(
+        &self,
+        entity_id: EntityId,
+        transform: Transform,
+    ) -> Result<(), ClientError> {
+        self.send(Command::SetTransform {
+            entity_id,
+            transform,
+        })
+        .await
+    }
+
+    /// Replaces the lines drawn by an entity
+    pub async fn set_lines(
+        &self,
+        entity_id: EntityId,
+        lines: Vec<Line>,
+    ) -> Result<(), ClientError> {
+        self.send(Command::SetLines { entity_id, lines }).await
+    }
+
+    /// Replaces the quads drawn by an entity
+    pub async fn set_quads(
+        &self,
+        entity_id: EntityId,
+        quads: Vec<Quad>,
+    ) -> Result<(), ClientError> {
+        self.send(Command::SetQuads { entity_id, quads }).await
+    }
+
+    /// Replaces the lines drawn by an entity with packed arrays,
+    /// which is far cheaper than `set_lines` for large line sets
+    pub async fn set_line_buffer(
+        &self,
+        entity_id: EntityId,
+        buffer: LineBuffer,
+    ) -> Result<(), ClientError> {
+        self.send(Command::SetLineBuffer { entity_id, buffer })
+            .await
+    }
+
+    /// Replaces the quads drawn by an entity with packed arrays,
+    /// which is far cheaper than `set_quads` for large quad sets
+    pub async fn set_quad_buffer(
+        &self,
+        entity_id: EntityId,
+        buffer: QuadBuffer,
+    ) -> Result<(), ClientError> {
+        self.send(Command::SetQuadBuffer { entity_id, buffer })
+            .await
+    }
+
+    pub async fn set_mesh(&self, entity_id: EntityId, mesh: MeshBuffer) -> Result<(), ClientError> {
+        self.send(Command::SetMesh { entity_id, mesh }).await
+    }
+
+    pub async fn set_points(
+        &self,
+        entity_id: EntityId,
+        points: PointBuffer,
+    ) -> Result<(), ClientError> {
+        self.send(Command::SetPoints { entity_id, points }).await
+    }
+
+    pub async fn set_polyline(
+        &self,
+        entity_id: EntityId,
+        polyline: PolylineBuffer,
+    ) -> Result<(), ClientError> {
+        self.send(Command::SetPolyline {
+            entity_id,
+            polyline,
+        })
+        .await
+    }
+
+    /// Replaces the label drawn at an entity
+    pub async fn set_label(&self, entity_id: EntityId, label: Label) -> Result<(), ClientError> {
+        self.send(Command::SetLabel { entity_id, label }).await
+    }
+
+    /// Replaces the rendering settings of a pane, with panes numbered in tile tree order
+    pub async fn set_pane_settings(
+        &self,
+        index: usize,
+        settings: PaneSettings,
+    ) -> Result<(), ClientError> {
+        self.send(Command::SetPaneSettings { index, settings })
+            .await
+    }
+
+    pub async fn list_cameras(&self) -> Result<Vec<EntityId>, ClientError> {
+        match self.query(QueryCommand::ListCameras).await? {
+            QueryResult::CameraList { cameras } => Ok(cameras),
+            result => Err(ClientError::UnexpectedResult(result)),
+        }
+    }
+
+    pub async fn list_entities(&self) -> Result<Vec<EntitySummary>, ClientError> {
+        match self.query(QueryCommand::ListEntities).await? {
+            QueryResult::EntityList { entities } => Ok(entities),
+            result => Err(ClientError::UnexpectedResult(result)),
+        }
+    }
+
+    /// Reads the local transform of an entity, or `None` if it has no transform
+    pub async fn get_transform(
+        &self,
+        entity_id: EntityId,
+    ) -> Result<Option<Transform>, ClientError> {
+        match self.query(QueryCommand::GetTransform { entity_id }).await? {
+            QueryResult::Transform { transform, .. } => Ok(Some(transform)),
+            QueryResult::NotFound { .. } => Ok(None),
+            result => Err(ClientError::UnexpectedResult(result)),
+        }
+    }
+
+    /// Reads the lines drawn by an entity as packed arrays, or `None` if it has no lines
+    pub async fn get_line_buffer(
+        &self,
+        entity_id: EntityId,
+    ) -> Result<Option<LineBuffer>, ClientError> {
+        match self
+            .query(QueryCommand::GetLineBuffer { entity_id })
+            .await?
+        {
+            QueryResult::LineBuffer { buffer, .. } => Ok(Some(buffer)),
+            QueryResult::NotFound { .. } => Ok(None),
+            result => Err(ClientError::UnexpectedResult(result)),
+        }
+    }
+
+    /// Reads the quads drawn by an entity as packed arrays, or `None` if it has no quads
+    pub async fn get_quad_buffer(
+        &self,
+        entity_id: EntityId,
+    ) -> Result<Option<QuadBuffer>, ClientError> {
+        match self
+            .query(QueryCommand::GetQuadBuffer { entity_id })
+            .await?
+        {
+            QueryResult::QuadBuffer { buffer, .. } => Ok(Some(buffer)),
+            QueryResult::NotFound { .. } => Ok(None),
+            result => Err(ClientError::UnexpectedResult(result)),
+        }
+    }
+
+    pub async fn get_mesh(&self, entity_id: EntityId) -> Result<Option<MeshBuffer>, ClientError> {
+        match self.query(QueryCommand::GetMesh { entity_id }).await? {
+            QueryResult::Mesh { mesh, .. } => Ok(Some(mesh)),
+            QueryResult::NotFound { .. } => Ok(None),
+            result => Err(ClientError::UnexpectedResult(result)),
+        }
+    }
+
+    pub async fn get_points(
+        &self,
+        entity_id: EntityId,
+    ) -> Result<Option<PointBuffer>, ClientError> {
+        match self.query(QueryCommand::GetPoints { entity_id }).await? {
+            QueryResult::Points { points, .. } => Ok(Some(points)),
+            QueryResult::NotFound { .. } => Ok(None),
+            result => Err(ClientError::UnexpectedResult(result)),
+        }
+    }
+
+    pub async fn get_polyline(
+        &self,
+        entity_id: EntityId,
+    ) -> Result<Option<PolylineBuffer>, ClientError> {
+        match self.query(QueryCommand::GetPolyline { entity_id }).await? {
+            QueryResult::Polyline { polyline, .. } => Ok(Some(polyline)),
+            QueryResult::NotFound { .. } => Ok(None),
+            result => Err(ClientError::UnexpectedResult(result)),
+        }
+    }
+
+    /// Reads the label drawn at an entity, or `None` if it has no label
+    pub async fn get_label(&self, entity_id: EntityId) -> Result<Option<Label>, ClientError> {
+        match self.query(QueryCommand::GetLabel { entity_id }).await? {
+            QueryResult::Label { label, .. } => Ok(Some(label)),
+            QueryResult::NotFound { .. } => Ok(None),
+            result => Err(ClientError::UnexpectedResult(result)),
+        }
+    }
+}
+
+/// Query results are relayed to every client of a server, so each client starts its ids
+/// at a different point to avoid completing the queries of another client.
+/// Ids stay in the lower half of the range, which the server's HTTP API does not use.
+fn first_query_id() -> u64 {
+    let nanos = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|duration| duration.as_nanos() as u64)
+        .unwrap_or_default();
+    (nanos ^ (u64::from(std::process::id()) << 32)) >> 2
+}
+
+async fn run_connection<S>(
+    websocket_stream: tokio_tungstenite::WebSocketStream<S>,
+    mut outgoing: tokio::sync::mpsc::UnboundedReceiver<tungstenite::Message>,
+    events: tokio::sync::broadcast::Sender<Event>,
+    pending_queries: PendingQueries,
+    pending_spawns: PendingSpawns,
+) where
+    S: tokio::io::AsyncRead + tokio::io::AsyncWrite + Unpin,
+{
+    let (mut write, mut read) = websocket_stream.split();
+    loop {
+        tokio::select! {
+            websocket_message = outgoing.recv() => {
+                let Some(websocket_message) = websocket_message else {
+                    let _ = write.close().await;
+                    break;
+                };
+                if let Err(error) = write.send(websocket_message).await {
+                    log::error!("[Client] Failed to send message: {error}");
+                    break;
+                }
+            }
+            message = read.next() => {
+                match message {
+                    Some(Ok(tungstenite::Message::Text(text))) => {
+                        match serde_json::from_str::<Message>(&text) {
+                            Ok(message) => receive_message(message, &events, &pending_queries, &pending_spawns),
+                            Err(error) => log::trace!("[Client] Received a message that is not an API message: {error}"),
+                        }
+                    }
+                    Some(Ok(tungstenite::Message::Binary(bytes))) => {
+                        match Message::from_bytes(&bytes) {
+                            Ok(message) => receive_message(message, &events, &pending_queries, &pending_spawns),
+                            Err(error) => log::trace!("[Client] Received a binary message that is not an API message: {error}"),
+                        }
+                    }
+                    Some(Ok(tungstenite::Message::Close(_))) | None => break,
+                    Some(Ok(_)) => {}
+                    Some(Err(error)) => {
+                        log::error!("[Client] Failed to receive message: {error}");
+                        break;
+                    }
+                }
+            }
+        }
+    }
+
+    // Dropping the senders fails every outstanding query and spawn
+    if let Ok(mut pending_queries) = pending_queries.lock() {
+        pending_queries.clear();
+    }
+    if let Ok(mut pending_spawns) = pending_spawns.lock() {
+        pending_spawns.clear();
+    }
+}
+
+fn receive_message(
+    message: Message,
+    events: &tokio::sync::broadcast::Sender<Event>,
+    pending_queries: &PendingQueries,
+    pending_spawns: &PendingSpawns,
+) {
+    let Message::Event { event } = message else {
+        return;
+    };
+    match &event {
+        Event::QueryResult { id, result } => complete(pending_queries, *id, Ok(result.clone())),
+        Event::Report {
+            report: Report::EntityCreated { id, entity_id },
+        } => complete(pending_spawns, *id, Ok(*entity_id)),
+        Event::Websocket {
+            event: WebsocketEvent::Rejected { id, error },
+        } => {
+            complete(pending_queries, *id, Err(error.clone()));
+            complete(pending_spawns, *id, Err(error.clone()));
+        }
+        _ => {}
+    }
+    // Sending only fails when nothing is subscribed
+    let _ = events.send(event);
+}
+
+fn complete<T>(pending: &Pending<T>, id: u64, answer: Result<T, String>) {
+    let sender = pending
+        .lock()
+        .ok()
+        .and_then(|mut pending| pending.remove(&id));
+    if let Some(sender) = sender {
+        let _ = sender.send(answer);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nightshade_core::server::{listen_for_rpc, ServerConfig};
+
+    const VIEWER_TOKEN: &str = "viewer";
+    const PRODUCER_TOKEN: &str = "producer";
+    const FRONTEND_TOKEN: &str = "frontend";
+
+    const CAMERA: EntityId = EntityId {
+        id: 3,
+        generation: 1,
+    };
+    const CUBE: EntityId = EntityId {
+        id: 4,
+        generation: 0,
+    };
+
+    /// Starts a relay on a free local port and returns its url once it accepts connections
+    async fn start_server() -> String {
+        let port = std::net::TcpListener::bind("127.0.0.1:0")
+            .and_then(|listener| listener.local_addr())
+            .unwrap()
+            .port();
+        tokio::spawn(listen_for_rpc(ServerConfig {
+            bind: "127.0.0.1".to_string(),
+            port,
+            token: Some(VIEWER_TOKEN.to_string()),
+            producer_token: Some(PRODUCER_TOKEN.to_string()),
+            frontend_token: Some(FRONTEND_TOKEN.to_string()),
+            ..Default::default()
+        }));
+        let address = format!("127.0.0.1:{port}");
+        for _ in 0..100 {
+            if tokio::net::TcpStream::connect(&address).await.is_ok() {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        format!("ws://{address}")
+    }
+
+    /// Connects a stub frontend that answers every query with a camera list and every spawn
+    /// with a created cube. It reports once on connecting, so waiting for that report
+    /// ensures the relay is delivering to it.
+    async fn start_frontend(url: &str) {
+        let (mut websocket, _) =
+            tokio_tungstenite::connect_async(format!("{url}/?token={FRONTEND_TOKEN}"))
+                .await
+                .unwrap();
+        let send = |event: Event| {
+            tungstenite::Message::Text(serde_json::to_string(&Message::Event { event }).unwrap())
+        };
+        websocket
+            .send(send(Event::Report {
+                report: Report::Empty,
+            }))
+            .await
+            .unwrap();
+        tokio::spawn(async move {
+            while let Some(Ok(tungstenite::Message::Text(text))) = websocket.next().await {
+                let event = match serde_json::from_str::<Message>(&text) {
+                    Ok(Message::Command {
+                        command: Command::Query { id, .. },
+                    }) => Event::QueryResult {
+                        id,
+                        result: QueryResult::CameraList {
+                            cameras: vec![CAMERA],
+                        },
+                    },
+                    Ok(Message::Command {
+                        command: Command::Spawn { id, .. },
+                    }) => Event::Report {
+                        report: Report::EntityCreated {
+                            id,
+                            entity_id: CUBE,
+                        },
+                    },
+                    _ => continue,
+                };
+                if websocket.send(send(event)).await.is_err() {
+                    break;
+                }
+            }
+        });
+    }
+
+    #[tokio::test]
+    async fn queries_and_spawns_are_answered_through_the_relay() {
+        let url = start_server().await;
+        let client = Client::connect_with_token(&url, PRODUCER_TOKEN)
+            .await
+            .unwrap();
+        let mut events = client.subscribe();
+        start_frontend(&url).await;
+        assert!(matches!(
+            events.recv().await,
+            Ok(Event::Report {
+                report: Report::Empty
+            })
+        ));
+
+        assert_eq!(client.list_cameras().await.unwrap(), [CAMERA]);
+        assert_eq!(
+            client
+                .spawn_cube([0.0, 0.0, 0.0], 1.0, "Cube")
+                .await
+                .unwrap(),
+            CUBE
+        );
+        assert!(matches!(
+            events.recv().await,
+            Ok(Event::QueryResult {
+                result: QueryResult::CameraList { .. },
+                ..
+            })
+        ));
+    }
+
+    #[tokio::test]
+    async fn requests_time_out_without_a_frontend() {
+        let url = start_server().await;
+        let client = Client::connect_with_token(&url, PRODUCER_TOKEN)
+            .await
+            .unwrap()
+            .with_timeout(std::time::Duration::from_millis(100));
+        assert!(matches!(
+            client.list_cameras().await,
+            Err(ClientError::Timeout)
+        ));
+    }
+
+    #[tokio::test]
+    async fn refused_requests_fail_by_id() {
+        let url = start_server().await;
+        let client = Client::connect_with_token(&url, VIEWER_TOKEN)
+            .await
+            .unwrap();
+        let mut events = client.subscribe();
+        assert!(matches!(
+            client.spawn_cube([0.0, 0.0, 0.0], 1.0, "Cube").await,
+            Err(ClientError::Rejected(_))
+        ));
+        assert!(matches!(
+            events.recv().await,
+            Ok(Event::Websocket {
+                event: WebsocketEvent::Rejected { .. }
+            })
+        ));
+    }
+}
@@ -0,0 +1,29 @@
+import math
+import time
+
+import nightshade
+
+# Requires a running `nightshade server` with a viewer connected to it
+client = nightshade.connect("ws://localhost:9003")
+client.on_event(lambda event: print(event))
+
+spiral = client.spawn_entity((0.0, 0.0, 0.0), "Spiral")
+
+lines = []
+for index in range(200):
+    start = index * 0.1
+    end = start + 0.1
+    lines.append(
+        (
+            (math.cos(start), start * 0.1, math.sin(start)),
+            (math.cos(end), end * 0.1, math.sin(end)),
+            (1.0, 0.5, 0.0, 1.0),
+        )
+    )
+client.set_lines(spiral, lines)
+
+for step in range(100):
+    client.set_transform(spiral, translation=(0.0, math.sin(step * 0.1), 0.0))
+    time.sleep(1.0 / 30.0)
+
+print(client.get_transform(spiral))
@@ -1,16 +1,1378 @@
-use pyo3::{exceptions::PyConnectionError, prelude::*};
+use nightshade_client::{
+    Bloom, Command, EntityId, Environment, Event, FileBytes, Label, LabelMode, Line, LineBuffer,
+    LineCap, MeshBuffer, Outline, PaneSettings, PointBuffer, PointShape, PointSizing,
+    PolylineBuffer, Quad, QuadBuffer, QueryCommand, QueryResult, Report, SpawnCommand,
+    TonemapOperator, Tonemapping, Transform,
+};
+use numpy::{
+    AllowTypeChange, PyArray1, PyArray2, PyArrayLikeDyn, PyArrayMethods, PyUntypedArrayMethods,
+};
+use pyo3::{
+    exceptions::{PyConnectionError, PyRuntimeError, PyTimeoutError, PyValueError},
+    prelude::*,
+    types::PyBytes,
+};
 
-/// Runs the native viewer
+/// Entities are passed to and from python as `(id, generation)` tuples
+type PyEntity = (u32, u32);
+
+/// Transforms are passed to python as `(translation, rotation, scale)` tuples
+type PyTransform = ([f32; 3], [f32; 4], [f32; 3]);
+
+/// Packed buffers are passed to python as `(N, width)` float32 arrays
+type PyArrays<'py> = (
+    Bound<'py, PyArray2<f32>>,
+    Bound<'py, PyArray2<f32>>,
+    Bound<'py, PyArray2<f32>>,
+);
+
+/// Point clouds are read back as `(positions, colors, scalars)` arrays
+type PyPointArrays<'py> = (
+    Bound<'py, PyArray2<f32>>,
+    Bound<'py, PyArray2<f32>>,
+    Bound<'py, PyArray1<f32>>,
+);
+
+/// Meshes are read back as `(positions, indices, normals, colors, uvs)` arrays
+type PyMeshArrays<'py> = (
+    Bound<'py, PyArray2<f32>>,
+    Bound<'py, PyArray2<u32>>,
+    Bound<'py, PyArray2<f32>>,
+    Bound<'py, PyArray2<f32>>,
+    Bound<'py, PyArray2<f32>>,
+);
+
+/// Polylines are read back as `(points, colors)` arrays
+type PyPolylineArrays<'py> = (Bound<'py, PyArray2<f32>>, Bound<'py, PyArray2<f32>>);
+
+/// Arrays of anything convertible to float32, such as NumPy arrays or nested lists
+type PyFloats<'py> = PyArrayLikeDyn<'py, f32, AllowTypeChange>;
+
+/// Arrays of anything convertible to uint32, used for vertex indices
+type PyIndices<'py> = PyArrayLikeDyn<'py, u32, AllowTypeChange>;
+
+fn entity_id((id, generation): PyEntity) -> EntityId {
+    EntityId { id, generation }
+}
+
+fn py_entity(entity_id: EntityId) -> PyEntity {
+    (entity_id.id, entity_id.generation)
+}
+
+fn client_error(error: nightshade_client::ClientError) -> PyErr {
+    match error {
+        nightshade_client::ClientError::Websocket(_)
+        | nightshade_client::ClientError::Disconnected => {
+            PyConnectionError::new_err(error.to_string())
+        }
+        nightshade_client::ClientError::Timeout => {
+            PyTimeoutError::new_err("No viewer answered in time")
+        }
+        _ => PyRuntimeError::new_err(error.to_string()),
+    }
+}
+
+fn unexpected_result(result: QueryResult) -> PyErr {
+    client_error(nightshade_client::ClientError::UnexpectedResult(result))
+}
+
+/// Copies an array of shape `(count, width)` into a packed buffer in one pass.
+/// Shareable arrays may instead have shape `(width,)`, holding one element shared by every item.
+fn packed_array(
+    name: &str,
+    array: &PyFloats<'_>,
+    count: usize,
+    width: usize,
+    shareable: bool,
+) -> PyResult<std::sync::Arc<[f32]>> {
+    let valid = match array.shape() {
+        [rows, columns] => *rows == count && *columns == width,
+        [columns] => shareable && *columns == width,
+        _ => false,
+    };
+    if !valid {
+        let shared = if shareable {
+            format!(" or ({width},)")
+        } else {
+            String::new()
+        };
+        return Err(PyValueError::new_err(format!(
+            "{name} must have shape ({count}, {width}){shared}, not {:?}",
+            array.shape()
+        )));
+    }
+    Ok(match array.as_slice() {
+        Ok(values) => values.into(),
+        // Strided views are gathered element by element
+        Err(_) => array.as_array().iter().copied().collect(),
+    })
+}
+
+/// Copies an array of shape `(count,)` into a packed buffer.
+/// Shareable arrays may instead be a single number shared by every item.
+fn packed_values(
+    name: &str,
+    array: &PyFloats<'_>,
+    count: usize,
+    shareable: bool,
+) -> PyResult<std::sync::Arc<[f32]>> {
+    let valid = match array.shape() {
+        [length] => *length == count,
+        [] => shareable,
+        _ => false,
+    };
+    if !valid {
+        let shared = if shareable { " or a number" } else { "" };
+        return Err(PyValueError::new_err(format!(
+            "{name} must have shape ({count},){shared}, not {:?}",
+            array.shape()
+        )));
+    }
+    Ok(match array.as_slice() {
+        Ok(values) => values.into(),
+        Err(_) => array.as_array().iter().copied().collect(),
+    })
+}
+
+/// The number of items in an array of per-item rows
+fn row_count(array: &PyFloats<'_>) -> usize {
+    array.shape().first().copied().unwrap_or_default()
+}
+
+/// Reshapes a packed buffer into a `(N, width)` array
+fn unpacked_array<'py, T: numpy::Element>(
+    py: Python<'py>,
+    values: &[T],
+    width: usize,
+) -> PyResult<Bound<'py, PyArray2<T>>> {
+    PyArray1::from_slice(py, values).reshape([values.len() / width, width])
+}
+
+fn line_arrays<'py>(py: Python<'py>, buffer: &LineBuffer) -> PyResult<PyArrays<'py>> {
+    Ok((
+        unpacked_array(py, &buffer.starts, 3)?,
+        unpacked_array(py, &buffer.ends, 3)?,
+        unpacked_array(py, &buffer.colors, 4)?,
+    ))
+}
+
+fn quad_arrays<'py>(py: Python<'py>, buffer: &QuadBuffer) -> PyResult<PyArrays<'py>> {
+    Ok((
+        unpacked_array(py, &buffer.offsets, 3)?,
+        unpacked_array(py, &buffer.sizes, 2)?,
+        unpacked_array(py, &buffer.colors, 4)?,
+    ))
+}
+
+fn point_arrays<'py>(py: Python<'py>, buffer: &PointBuffer) -> PyResult<PyPointArrays<'py>> {
+    Ok((
+        unpacked_array(py, &buffer.positions, 3)?,
+        unpacked_array(py, &buffer.colors, 4)?,
+        PyArray1::from_slice(py, &buffer.scalars),
+    ))
+}
+
+fn mesh_arrays<'py>(py: Python<'py>, mesh: &MeshBuffer) -> PyResult<PyMeshArrays<'py>> {
+    Ok((
+        unpacked_array(py, &mesh.positions, 3)?,
+        unpacked_array(py, &mesh.indices, 3)?,
+        unpacked_array(py, &mesh.normals, 3)?,
+        unpacked_array(py, &mesh.colors, 4)?,
+        unpacked_array(py, &mesh.uvs, 2)?,
+    ))
+}
+
+fn polyline_arrays<'py>(
+    py: Python<'py>,
+    polyline: &PolylineBuffer,
+) -> PyResult<PyPolylineArrays<'py>> {
+    Ok((
+        unpacked_array(py, &polyline.points, 3)?,
+        unpacked_array(py, &polyline.colors, 4)?,
+    ))
+}
+
+/// Packs `(N, 3)` start and end points, `(N, 4)` or `(4,)` colors, `(N,)` or single pixel widths
+/// and `(N, 2)` or `(2,)` dash and gap lengths into a line buffer.
+/// Cap is `"butt"`, `"square"` or `"round"`.
+fn line_buffer(
+    starts: PyFloats<'_>,
+    ends: PyFloats<'_>,
+    colors: Option<PyFloats<'_>>,
+    widths: Option<PyFloats<'_>>,
+    dashes: Option<PyFloats<'_>>,
+    cap: &str,
+) -> PyResult<LineBuffer> {
+    let count = row_count(&starts);
+    let cap = match cap {
+        "butt" => LineCap::Butt,
+        "square" => LineCap::Square,
+        "round" => LineCap::Round,
+        _ => {
+            return Err(PyValueError::new_err(format!(
+                "cap must be \"butt\", \"square\" or \"round\", not {cap:?}"
+            )))
+        }
+    };
+    Ok(LineBuffer {
+        starts: packed_array("starts", &starts, count, 3, false)?,
+        ends: packed_array("ends", &ends, count, 3, false)?,
+        colors: match colors {
+            Some(colors) => packed_array("colors", &colors, count, 4, true)?,
+            None => std::sync::Arc::new([]),
+        },
+        widths: match widths {
+            Some(widths) => packed_values("widths", &widths, count, true)?,
+            None => std::sync::Arc::new([]),
+        },
+        dashes: match dashes {
+            Some(dashes) => packed_array("dashes", &dashes, count, 2, true)?,
+            None => std::sync::Arc::new([]),
+        },
+        cap,
+    })
+}
+
+/// Packs `(N, 3)` offsets, `(N, 2)` or `(2,)` sizes and `(N, 4)` or `(4,)` colors into a quad buffer
+fn quad_buffer(
+    offsets: PyFloats<'_>,
+    sizes: Option<PyFloats<'_>>,
+    colors: Option<PyFloats<'_>>,
+) -> PyResult<QuadBuffer> {
+    let count = row_count(&offsets);
+    Ok(QuadBuffer {
+        offsets: packed_array("offsets", &offsets, count, 3, false)?,
+        sizes: match sizes {
+            Some(sizes) => packed_array("sizes", &sizes, count, 2, true)?,
+            None => std::sync::Arc::new([]),
+        },
+        colors: match colors {
+            Some(colors) => packed_array("colors", &colors, count, 4, true)?,
+            None => std::sync::Arc::new([]),
+        },
+    })
+}
+
+/// Packs `(N, 3)` positions with `(N, 4)` or `(4,)` colors or `(N,)` scalars into a point buffer.
+/// Sizing is `"world"` or `"screen"` and shape is `"round"` or `"square"`.
+fn point_buffer(
+    positions: PyFloats<'_>,
+    colors: Option<PyFloats<'_>>,
+    scalars: Option<PyFloats<'_>>,
+    scalar_range: Option<[f32; 2]>,
+    size: f32,
+    sizing: &str,
+    shape: &str,
+) -> PyResult<PointBuffer> {
+    let count = row_count(&positions);
+    let sizing = match sizing {
+        "world" => PointSizing::World,
+        "screen" => PointSizing::Screen,
+        _ => {
+            return Err(PyValueError::new_err(format!(
+                "sizing must be \"world\" or \"screen\", not {sizing:?}"
+            )))
+        }
+    };
+    let shape = match shape {
+        "round" => PointShape::Round,
+        "square" => PointShape::Square,
+        _ => {
+            return Err(PyValueError::new_err(format!(
+                "shape must be \"round\" or \"square\", not {shape:?}"
+            )))
+        }
+    };
+    let mut buffer = PointBuffer {
+        positions: packed_array("positions", &positions, count, 3, false)?,
+        scalars: match scalars {
+            Some(scalars) => packed_values("scalars", &scalars, count, false)?,
+            None => std::sync::Arc::new([]),
+        },
+        scalar_range: scalar_range.map(Into::into),
+        size,
+        sizing,
+        shape,
+        ..Default::default()
+    };
+    if let Some(colors) = colors {
+        // A single color is applied to every point rather than repeated per point
+        let shared = colors.shape() == [4];
+        let colors = packed_array("colors", &colors, count, 4, true)?;
+        if shared {
+            buffer.color = [colors[0], colors[1], colors[2], colors[3]].into();
+        } else {
+            buffer.colors = colors;
+        }
+    }
+    Ok(buffer)
+}
+
+/// Packs `(N, 3)` points and `(N, 4)` colors into a polyline buffer
+fn polyline_buffer(
+    points: PyFloats<'_>,
+    colors: Option<PyFloats<'_>>,
+    color: [f32; 4],
+    width: f32,
+) -> PyResult<PolylineBuffer> {
+    let count = row_count(&points);
+    Ok(PolylineBuffer {
+        points: packed_array("points", &points, count, 3, false)?,
+        colors: match colors {
+            Some(colors) => packed_array("colors", &colors, count, 4, false)?,
+            None => std::sync::Arc::new([]),
+        },
+        color: color.into(),
+        width,
+    })
+}
+
+/// Builds a label, where mode is `"billboard"` or `"world"`
+fn label(
+    text: String,
+    offset: [f32; 3],
+    mode: &str,
+    size: f32,
+    color: [f32; 4],
+    background: Option<[f32; 4]>,
+    leader: Option<[f32; 4]>,
+) -> PyResult<Label> {
+    let mode = match mode {
+        "billboard" => LabelMode::Billboard,
+        "world" => LabelMode::World,
+        _ => {
+            return Err(PyValueError::new_err(format!(
+                "mode must be \"billboard\" or \"world\", not {mode:?}"
+            )))
+        }
+    };
+    Ok(Label {
+        text,
+        offset: offset.into(),
+        mode,
+        size,
+        color: color.into(),
+        background: background.map(Into::into),
+        leader: leader.map(Into::into),
+    })
+}
+
+/// Environments are passed from python as `"sky"`, the path of an `.hdr` file, its contents
+/// as bytes, a color for a solid background or a `(top, bottom)` pair of colors for a gradient
+#[derive(FromPyObject)]
+enum PyEnvironment<'py> {
+    Bytes(Bound<'py, PyBytes>),
+    Gradient([f32; 4], [f32; 4]),
+    Solid([f32; 4]),
+    Name(String),
+}
+
+/// Converts an environment from python, where `None` leaves the background black
+fn environment(environment: Option<PyEnvironment<'_>>) -> Environment {
+    match environment {
+        None => Environment::None,
+        Some(PyEnvironment::Bytes(bytes)) => Environment::HdrBytes {
+            bytes: FileBytes(bytes.as_bytes().into()),
+        },
+        Some(PyEnvironment::Gradient(top, bottom)) => Environment::Gradient {
+            top: top.into(),
+            bottom: bottom.into(),
+        },
+        Some(PyEnvironment::Solid(color)) => Environment::Solid {
+            color: color.into(),
+        },
+        Some(PyEnvironment::Name(name)) if name == "sky" => Environment::Sky,
+        Some(PyEnvironment::Name(path)) => Environment::HdrFile { path },
+    }
+}
+
+/// Builds tonemapping settings from the name of an operator, which is `None` without tonemapping
+fn tonemapping(operator: Option<&str>, exposure: f32, gamma: f32) -> PyResult<Tonemapping> {
+    let (enabled, operator) = match operator {
+        None => (false, TonemapOperator::default()),
+        Some("exponential") => (true, TonemapOperator::Exponential),
+        Some("reinhard") => (true, TonemapOperator::Reinhard),
+        Some("aces") => (true, TonemapOperator::Aces),
+        Some(operator) => {
+            return Err(PyValueError::new_err(format!(
+            "tonemapping must be \"exponential\", \"reinhard\", \"aces\" or None, not {operator:?}"
+        )))
+        }
+    };
+    Ok(Tonemapping {
+        enabled,
+        operator,
+        exposure,
+        gamma,
+    })
+}
+
+/// Packs `(V, 3)` positions and `(T, 3)` or flat triangle indices into a mesh buffer,
+/// along with optional `(V, 3)` normals, `(V, 4)` colors and `(V, 2)` texture coordinates
+fn mesh_buffer(
+    positions: PyFloats<'_>,
+    indices: PyIndices<'_>,
+    normals: Option<PyFloats<'_>>,
+    colors: Option<PyFloats<'_>>,
+    uvs: Option<PyFloats<'_>>,
+    color: [f32; 4],
+    wireframe: Option<[f32; 4]>,
+) -> PyResult<MeshBuffer> {
+    let count = row_count(&positions);
+    let valid_indices = match indices.shape() {
+        [_, columns] => *columns == 3,
+        [length] => length % 3 == 0,
+        _ => false,
+    };
+    if !valid_indices {
+        return Err(PyValueError::new_err(format!(
+            "indices must have shape (T, 3) or (3T,), not {:?}",
+            indices.shape()
+        )));
+    }
+    let optional = |name, array: Option<PyFloats<'_>>, width| match array {
+        Some(array) => packed_array(name, &array, count, width, false),
+        None => Ok(std::sync::Arc::new([]) as std::sync::Arc<[f32]>),
+    };
+    Ok(MeshBuffer {
+        positions: packed_array("positions", &positions, count, 3, false)?,
+        normals: optional("normals", normals, 3)?,
+        uvs: optional("uvs", uvs, 2)?,
+        colors: optional("colors", colors, 4)?,
+        indices: match indices.as_slice() {
+            Ok(values) => values.into(),
+            Err(_) => indices.as_array().iter().copied().collect(),
+        },
+        color: color.into(),
+        wireframe: wireframe.map(Into::into),
+    })
+}
+
+fn transform(translation: [f32; 3], rotation: [f32; 4], scale: [f32; 3]) -> Transform {
+    Transform {
+        translation: translation.into(),
+        rotation: rotation.into(),
+        scale: scale.into(),
+    }
+}
+
+/// Converts a value to python through its JSON encoding
+fn to_python<T: serde::Serialize>(py: Python<'_>, value: &T) -> PyResult<PyObject> {
+    let text =
+        serde_json::to_string(value).map_err(|error| PyValueError::new_err(error.to_string()))?;
+    Ok(py.import("json")?.call_method1("loads", (text,))?.unbind())
+}
+
+/// Runs the native viewer, blocking until its window is closed
+#[pyfunction]
+fn run(py: Python<'_>) {
+    py.allow_threads(nightshade_core::run_frontend);
+}
+
+/// Opens the native viewer on a background thread and returns a handle for driving it,
+/// so scripts and notebooks keep running while the window is open
+#[pyfunction]
+#[pyo3(signature = (timeout=5.0))]
+fn spawn(timeout: f64) -> PyResult<Viewer> {
+    let handle = nightshade_core::spawn_frontend().map_err(PyRuntimeError::new_err)?;
+    Ok(Viewer {
+        handle: std::sync::Mutex::new(Some(handle)),
+        skipped_events: std::sync::Mutex::new(std::collections::VecDeque::new()),
+        next_id: std::sync::atomic::AtomicU64::new(1),
+        timeout: std::time::Duration::from_secs_f64(timeout),
+    })
+}
+
+/// Connects to a nightshade server, which relays commands to every connected viewer
 #[pyfunction]
-fn run() -> PyResult<()> {
-    match nightshade_core::start() {
-        Err(error) => Err(PyConnectionError::new_err(error.to_string())),
-        _ => PyResult::Ok(()),
+#[pyo3(signature = (url, token=None, timeout=5.0))]
+fn connect(py: Python<'_>, url: &str, token: Option<&str>, timeout: f64) -> PyResult<Client> {
+    let runtime = tokio::runtime::Builder::new_multi_thread()
+        .worker_threads(1)
+        .enable_all()
+        .build()?;
+    let client = py.allow_threads(|| {
+        runtime.block_on(async {
+            match token {
+                Some(token) => nightshade_client::Client::connect_with_token(url, token).await,
+                None => nightshade_client::Client::connect(url).await,
+            }
+        })
+    });
+    Ok(Client {
+        runtime: std::sync::Arc::new(runtime),
+        client: client
+            .map_err(client_error)?
+            .with_timeout(std::time::Duration::from_secs_f64(timeout)),
+        timeout: std::time::Duration::from_secs_f64(timeout),
+    })
+}
+
+/// A connection to a nightshade server.
+/// Queries wait up to `timeout` seconds for a viewer to answer.
+#[pyclass]
+struct Client {
+    runtime: std::sync::Arc<tokio::runtime::Runtime>,
+    client: nightshade_client::Client,
+    timeout: std::time::Duration,
+}
+
+impl Client {
+    fn block_on<T>(
+        &self,
+        py: Python<'_>,
+        future: impl std::future::Future<Output = Result<T, nightshade_client::ClientError>> + Send,
+    ) -> PyResult<T>
+    where
+        T: Send,
+    {
+        let timeout = self.timeout;
+        py.allow_threads(|| {
+            self.runtime
+                .block_on(async { tokio::time::timeout(timeout, future).await })
+        })
+        .map_err(|_| PyTimeoutError::new_err("No viewer answered in time"))?
+        .map_err(client_error)
+    }
+}
+
+#[pymethods]
+impl Client {
+    /// Sends a JSON encoded command
+    fn send(&self, py: Python<'_>, command: &str) -> PyResult<()> {
+        let command = serde_json::from_str::<nightshade_client::Command>(command)
+            .map_err(|error| PyValueError::new_err(error.to_string()))?;
+        self.block_on(py, self.client.send(command))
+    }
+
+    #[pyo3(signature = (position, size=1.0, name="Cube".to_string()))]
+    fn spawn_cube(
+        &self,
+        py: Python<'_>,
+        position: [f32; 3],
+        size: f32,
+        name: String,
+    ) -> PyResult<PyEntity> {
+        self.block_on(py, self.client.spawn_cube(position, size, name))
+            .map(py_entity)
+    }
+
+    #[pyo3(signature = (position, name="Camera".to_string()))]
+    fn spawn_camera(&self, py: Python<'_>, position: [f32; 3], name: String) -> PyResult<PyEntity> {
+        self.block_on(py, self.client.spawn_camera(position, name))
+            .map(py_entity)
+    }
+
+    /// Spawns an empty entity, which lines and quads can be attached to
+    #[pyo3(signature = (position=[0.0, 0.0, 0.0], name="Entity".to_string()))]
+    fn spawn_entity(&self, py: Python<'_>, position: [f32; 3], name: String) -> PyResult<PyEntity> {
+        self.block_on(py, self.client.spawn_entity(position, name))
+            .map(py_entity)
+    }
+
+    fn despawn(&self, py: Python<'_>, entity: PyEntity) -> PyResult<()> {
+        self.block_on(py, self.client.despawn(entity_id(entity)))
+    }
+
+    /// Rotations are `(x, y, z, w)` quaternions
+    #[pyo3(signature = (entity, translation=[0.0, 0.0, 0.0], rotation=[0.0, 0.0, 0.0, 1.0], scale=[1.0, 1.0, 1.0]))]
+    fn set_transform(
+        &self,
+        py: Python<'_>,
+        entity: PyEntity,
+        translation: [f32; 3],
+        rotation: [f32; 4],
+        scale: [f32; 3],
+    ) -> PyResult<()> {
+        let transform = transform(translation, rotation, scale);
+        self.block_on(py, self.client.set_transform(entity_id(entity), transform))
+    }
+
+    /// Replaces the lines drawn by an entity with `(start, end, color)` tuples
+    fn set_lines(
+        &self,
+        py: Python<'_>,
+        entity: PyEntity,
+        lines: Vec<([f32; 3], [f32; 3], [f32; 4])>,
+    ) -> PyResult<()> {
+        let lines = lines
+            .into_iter()
+            .map(|(start, end, color)| Line {
+                start: start.into(),
+                end: end.into(),
+                color: color.into(),
+                ..Default::default()
+            })
+            .collect();
+        self.block_on(py, self.client.set_lines(entity_id(entity), lines))
+    }
+
+    /// Replaces the quads drawn by an entity with `(offset, size, color)` tuples
+    fn set_quads(
+        &self,
+        py: Python<'_>,
+        entity: PyEntity,
+        quads: Vec<([f32; 3], [f32; 2], [f32; 4])>,
+    ) -> PyResult<()> {
+        let quads = quads
+            .into_iter()
+            .map(|(offset, size, color)| Quad {
+                offset: offset.into(),
+                size: size.into(),
+                color: color.into(),
+            })
+            .collect();
+        self.block_on(py, self.client.set_quads(entity_id(entity), quads))
+    }
+
+    /// Replaces the lines drawn by an entity with arrays of `(N, 3)` start and end points
+    /// and `(N, 4)` colors, or a single `(4,)` color shared by every line.
+    /// Lines are `widths` pixels wide, and are dashed by `(N, 2)` or `(2,)` dash and gap lengths
+    /// in pixels. Each line and dash ends in a `"butt"`, `"square"` or `"round"` cap.
+    /// Arrays are copied straight into a packed buffer, so millions of lines are cheap to send.
+    #[pyo3(signature = (entity, starts, ends, colors=None, widths=None, dashes=None, cap="butt"))]
+    #[allow(clippy::too_many_arguments)]
+    fn set_lines_array(
+        &self,
+        py: Python<'_>,
+        entity: PyEntity,
+        starts: PyFloats<'_>,
+        ends: PyFloats<'_>,
+        colors: Option<PyFloats<'_>>,
+        widths: Option<PyFloats<'_>>,
+        dashes: Option<PyFloats<'_>>,
+        cap: &str,
+    ) -> PyResult<()> {
+        let buffer = line_buffer(starts, ends, colors, widths, dashes, cap)?;
+        self.block_on(py, self.client.set_line_buffer(entity_id(entity), buffer))
+    }
+
+    /// Replaces the quads drawn by an entity with arrays of `(N, 3)` offsets,
+    /// `(N, 2)` sizes and `(N, 4)` colors. Sizes and colors may instead be a single
+    /// `(2,)` size or `(4,)` color shared by every quad.
+    #[pyo3(signature = (entity, offsets, sizes=None, colors=None))]
+    fn set_quads_array(
+        &self,
+        py: Python<'_>,
+        entity: PyEntity,
+        offsets: PyFloats<'_>,
+        sizes: Option<PyFloats<'_>>,
+        colors: Option<PyFloats<'_>>,
+    ) -> PyResult<()> {
+        let buffer = quad_buffer(offsets, sizes, colors)?;
+        self.block_on(py, self.client.set_quad_buffer(entity_id(entity), buffer))
+    }
+
+    /// Draws a point cloud on an entity from `(N, 3)` positions and `(N, 4)` or `(4,)` colors.
+    /// Without colors, `(N,)` scalars are mapped through a colormap over `scalar_range`,
+    /// which defaults to the range of the scalars. Points are `size` world units across,
+    /// or `size` pixels with `sizing="screen"`, and are drawn `"round"` or `"square"`.
+    #[pyo3(signature = (entity, positions, colors=None, scalars=None, scalar_range=None, size=0.05, sizing="world", shape="round"))]
+    #[allow(clippy::too_many_arguments)]
+    fn set_points(
+        &self,
+        py: Python<'_>,
+        entity: PyEntity,
+        positions: PyFloats<'_>,
+        colors: Option<PyFloats<'_>>,
+        scalars: Option<PyFloats<'_>>,
+        scalar_range: Option<[f32; 2]>,
+        size: f32,
+        sizing: &str,
+        shape: &str,
+    ) -> PyResult<()> {
+        let buffer = point_buffer(
+            positions,
+            colors,
+            scalars,
+            scalar_range,
+            size,
+            sizing,
+            shape,
+        )?;
+        self.block_on(py, self.client.set_points(entity_id(entity), buffer))
+    }
+
+    /// Draws a connected strip through `(N, 3)` points on an entity, `width` pixels wide.
+    /// `(N, 4)` colors are blended along each segment and multiplied with `color`.
+    /// Zoomed out views of long polylines draw decimated copies that keep their peaks.
+    #[pyo3(signature = (entity, points, colors=None, color=[1.0, 1.0, 1.0, 1.0], width=1.0))]
+    fn set_polyline(
+        &self,
+        py: Python<'_>,
+        entity: PyEntity,
+        points: PyFloats<'_>,
+        colors: Option<PyFloats<'_>>,
+        color: [f32; 4],
+        width: f32,
+    ) -> PyResult<()> {
+        let buffer = polyline_buffer(points, colors, color, width)?;
+        self.block_on(py, self.client.set_polyline(entity_id(entity), buffer))
+    }
+
+    /// Draws text centered above `offset` from an entity's origin. Billboards face the camera
+    /// and are `size` pixels tall per line, while `mode="world"` labels lie in the entity's
+    /// XY plane and are `size` world units tall. A `background` color draws a panel behind
+    /// the text, and a `leader` color draws a line from the entity's origin to the text.
+    #[pyo3(signature = (entity, text, offset=[0.0, 0.0, 0.0], mode="billboard", size=16.0, color=[1.0, 1.0, 1.0, 1.0], background=None, leader=None))]
+    #[allow(clippy::too_many_arguments)]
+    fn set_label(
+        &self,
+        py: Python<'_>,
+        entity: PyEntity,
+        text: String,
+        offset: [f32; 3],
+        mode: &str,
+        size: f32,
+        color: [f32; 4],
+        background: Option<[f32; 4]>,
+        leader: Option<[f32; 4]>,
+    ) -> PyResult<()> {
+        let label = label(text, offset, mode, size, color, background, leader)?;
+        self.block_on(py, self.client.set_label(entity_id(entity), label))
+    }
+
+    /// Replaces the rendering settings of a pane, with panes numbered in tile tree order.
+    /// Scenes are multisampled with `msaa_samples` samples per pixel, lowered to the nearest
+    /// count the GPU supports, and `fxaa` smooths the remaining edges after they are resolved.
+    /// The `environment` behind the scene is `"sky"`, the path of an `.hdr` file on the machine
+    /// running the viewer, the contents of one as bytes, a solid color, a `(top, bottom)` pair
+    /// of colors for a gradient or `None`. HDR environments are tonemapped with `"exponential"`,
+    /// `"reinhard"` or `"aces"` after scaling them by `exposure`, or left linear with `None`.
+    /// Colors brighter than `bloom_threshold` glow when `bloom` is on,
+    /// and the selected entity is outlined when `outline` is on.
+    #[pyo3(signature = (pane, msaa_samples=4, fxaa=false, environment=Some(PyEnvironment::Name("sky".to_string())), tonemapping=Some("exponential"), exposure=1.0, gamma=2.2, bloom=true, bloom_threshold=1.0, bloom_intensity=0.6, outline=true, outline_color=[0.98, 0.57, 0.24, 1.0], outline_width=2.0))]
+    #[allow(clippy::too_many_arguments)]
+    fn set_pane_settings(
+        &self,
+        py: Python<'_>,
+        pane: usize,
+        msaa_samples: u32,
+        fxaa: bool,
+        environment: Option<PyEnvironment<'_>>,
+        tonemapping: Option<&str>,
+        exposure: f32,
+        gamma: f32,
+        bloom: bool,
+        bloom_threshold: f32,
+        bloom_intensity: f32,
+        outline: bool,
+        outline_color: [f32; 4],
+        outline_width: f32,
+    ) -> PyResult<()> {
+        let settings = PaneSettings {
+            msaa_samples,
+            fxaa,
+            environment: self::environment(environment),
+            tonemapping: self::tonemapping(tonemapping, exposure, gamma)?,
+            bloom: Bloom {
+                enabled: bloom,
+                threshold: bloom_threshold,
+                intensity: bloom_intensity,
+            },
+            outline: Outline {
+                enabled: outline,
+                color: outline_color.into(),
+                width: outline_width,
+            },
+        };
+        self.block_on(py, self.client.set_pane_settings(pane, settings))
+    }
+
+    /// Draws an indexed triangle mesh on an entity from `(V, 3)` positions and `(T, 3)` indices.
+    /// Normals are computed from the triangles when absent, and vertex colors are multiplied
+    /// with the mesh color. A wireframe color draws the triangle edges over the surface.
+    #[pyo3(signature = (entity, positions, indices, normals=None, colors=None, uvs=None, color=[1.0, 1.0, 1.0, 1.0], wireframe=None))]
+    #[allow(clippy::too_many_arguments)]
+    fn set_mesh(
+        &self,
+        py: Python<'_>,
+        entity: PyEntity,
+        positions: PyFloats<'_>,
+        indices: PyIndices<'_>,
+        normals: Option<PyFloats<'_>>,
+        colors: Option<PyFloats<'_>>,
+        uvs: Option<PyFloats<'_>>,
+        color: [f32; 4],
+        wireframe: Option<[f32; 4]>,
+    ) -> PyResult<()> {
+        let mesh = mesh_buffer(positions, indices, normals, colors, uvs, color, wireframe)?;
+        self.block_on(py, self.client.set_mesh(entity_id(entity), mesh))
+    }
+
+    /// Reads the lines drawn by an entity as `(starts, ends, colors)` arrays,
+    /// or `None` if it has no lines
+    fn get_lines_array<'py>(
+        &self,
+        py: Python<'py>,
+        entity: PyEntity,
+    ) -> PyResult<Option<PyArrays<'py>>> {
+        self.block_on(py, self.client.get_line_buffer(entity_id(entity)))?
+            .map(|buffer| line_arrays(py, &buffer))
+            .transpose()
+    }
+
+    /// Reads the quads drawn by an entity as `(offsets, sizes, colors)` arrays,
+    /// or `None` if it has no quads
+    fn get_quads_array<'py>(
+        &self,
+        py: Python<'py>,
+        entity: PyEntity,
+    ) -> PyResult<Option<PyArrays<'py>>> {
+        self.block_on(py, self.client.get_quad_buffer(entity_id(entity)))?
+            .map(|buffer| quad_arrays(py, &buffer))
+            .transpose()
+    }
+
+    /// Reads the point cloud drawn by an entity as `(positions, colors, scalars)` arrays,
+    /// or `None` if it has no points. Colors and scalars are empty when the points do not have them.
+    fn get_points_array<'py>(
+        &self,
+        py: Python<'py>,
+        entity: PyEntity,
+    ) -> PyResult<Option<PyPointArrays<'py>>> {
+        self.block_on(py, self.client.get_points(entity_id(entity)))?
+            .map(|points| point_arrays(py, &points))
+            .transpose()
+    }
+
+    /// Reads the mesh drawn by an entity as `(positions, indices, normals, colors, uvs)` arrays,
+    /// with `(T, 3)` indices, or `None` if it has no mesh.
+    /// Normals, colors and uvs are empty when the mesh does not have them.
+    fn get_mesh_arrays<'py>(
+        &self,
+        py: Python<'py>,
+        entity: PyEntity,
+    ) -> PyResult<Option<PyMeshArrays<'py>>> {
+        self.block_on(py, self.client.get_mesh(entity_id(entity)))?
+            .map(|mesh| mesh_arrays(py, &mesh))
+            .transpose()
+    }
+
+    /// Reads the polyline drawn by an entity as `(points, colors)` arrays,
+    /// or `None` if it has no polyline. Colors are empty when the points do not have them.
+    fn get_polyline_array<'py>(
+        &self,
+        py: Python<'py>,
+        entity: PyEntity,
+    ) -> PyResult<Option<PyPolylineArrays<'py>>> {
+        self.block_on(py, self.client.get_polyline(entity_id(entity)))?
+            .map(|polyline| polyline_arrays(py, &polyline))
+            .transpose()
+    }
+
+    fn list_cameras(&self, py: Python<'_>) -> PyResult<Vec<PyEntity>> {
+        let cameras = self.block_on(py, self.client.list_cameras())?;
+        Ok(cameras.into_iter().map(py_entity).collect())
+    }
+
+    /// Lists every named entity as `(entity, name, parent)` tuples
+    fn list_entities(&self, py: Python<'_>) -> PyResult<Vec<(PyEntity, String, Option<PyEntity>)>> {
+        let entities = self.block_on(py, self.client.list_entities())?;
+        Ok(entities
+            .into_iter()
+            .map(|entity| {
+                (
+                    py_entity(entity.entity_id),
+                    entity.name,
+                    entity.parent.map(py_entity),
+                )
+            })
+            .collect())
+    }
+
+    /// Finds the first entity with a name, or `None` if no entity has it
+    fn find_entity(&self, py: Python<'_>, name: &str) -> PyResult<Option<PyEntity>> {
+        let entities = self.block_on(py, self.client.list_entities())?;
+        Ok(entities
+            .into_iter()
+            .find(|entity| entity.name == name)
+            .map(|entity| py_entity(entity.entity_id)))
+    }
+
+    /// Reads the local transform of an entity, or `None` if it has no transform
+    fn get_transform(&self, py: Python<'_>, entity: PyEntity) -> PyResult<Option<PyTransform>> {
+        let transform = self.block_on(py, self.client.get_transform(entity_id(entity)))?;
+        Ok(transform.map(
+            |Transform {
+                 translation,
+                 rotation,
+                 scale,
+             }| {
+                (
+                    [translation.x, translation.y, translation.z],
+                    [rotation.x, rotation.y, rotation.z, rotation.w],
+                    [scale.x, scale.y, scale.z],
+                )
+            },
+        ))
+    }
+
+    /// Calls `callback` with every event relayed by the server, decoded from JSON,
+    /// on a background thread until the client is dropped
+    fn on_event(&self, callback: PyObject) {
+        let mut receiver = self.client.subscribe();
+        let runtime = self.runtime.clone();
+        std::thread::spawn(move || loop {
+            let event = match runtime.block_on(receiver.recv()) {
+                Ok(event) => event,
+                Err(tokio::sync::broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+            };
+            Python::with_gil(|py| {
+                if let Err(error) =
+                    to_python(py, &event).and_then(|event| callback.call1(py, (event,)))
+                {
+                    error.print(py);
+                }
+            });
+        });
+    }
+}
+
+/// A viewer running in this process on a background thread.
+/// Commands are executed on the next frame and events are queued until they are polled.
+/// Spawns wait up to `timeout` seconds for the viewer to create the entity.
+#[pyclass]
+struct Viewer {
+    handle: std::sync::Mutex<Option<nightshade_core::ViewerHandle>>,
+    /// Events received while waiting for a spawn, returned before newer events
+    skipped_events: std::sync::Mutex<std::collections::VecDeque<Event>>,
+    next_id: std::sync::atomic::AtomicU64,
+    timeout: std::time::Duration,
+}
+
+impl Viewer {
+    fn lock(&self) -> PyResult<std::sync::MutexGuard<'_, Option<nightshade_core::ViewerHandle>>> {
+        self.handle
+            .lock()
+            .map_err(|_| PyRuntimeError::new_err("The viewer handle is poisoned"))
+    }
+
+    fn with_handle<T>(
+        &self,
+        action: impl FnOnce(&nightshade_core::ViewerHandle) -> T,
+    ) -> PyResult<T> {
+        self.lock()?
+            .as_ref()
+            .map(action)
+            .ok_or_else(|| PyConnectionError::new_err("The viewer is closed"))
+    }
+
+    fn send_command(&self, command: Command) -> PyResult<()> {
+        self.with_handle(|handle| handle.send(command))?
+            .map_err(|_| PyConnectionError::new_err("The viewer is closed"))
+    }
+
+    fn skipped_events(
+        &self,
+    ) -> PyResult<std::sync::MutexGuard<'_, std::collections::VecDeque<Event>>> {
+        self.skipped_events
+            .lock()
+            .map_err(|_| PyRuntimeError::new_err("The viewer events are poisoned"))
+    }
+
+    /// Sends a command and waits for the event the viewer publishes in reply,
+    /// keeping every other event received meanwhile for the next poll
+    fn request<T: Send>(
+        &self,
+        py: Python<'_>,
+        command: Command,
+        reply: impl Fn(&Event) -> Option<T> + Send + Sync,
+    ) -> PyResult<T> {
+        self.send_command(command)?;
+        let deadline = std::time::Instant::now() + self.timeout;
+        py.allow_threads(|| loop {
+            let remaining = deadline.saturating_duration_since(std::time::Instant::now());
+            let event = self
+                .with_handle(|handle| handle.recv_timeout(remaining))?
+                .ok_or_else(|| PyTimeoutError::new_err("The viewer did not answer in time"))?;
+            match reply(&event) {
+                Some(value) => return Ok(value),
+                None => self.skipped_events()?.push_back(event),
+            }
+        })
+    }
+
+    fn next_id(&self) -> u64 {
+        self.next_id
+            .fetch_add(1, std::sync::atomic::Ordering::Relaxed)
+    }
+
+    /// Spawns an entity and waits for the viewer to report it
+    fn spawn_command(&self, py: Python<'_>, command: SpawnCommand) -> PyResult<PyEntity> {
+        let id = self.next_id();
+        self.request(py, Command::Spawn { id, command }, |event| match event {
+            Event::Report {
+                report:
+                    Report::EntityCreated {
+                        id: created,
+                        entity_id,
+                    },
+            } if *created == id => Some(py_entity(*entity_id)),
+            _ => None,
+        })
+    }
+
+    fn query(&self, py: Python<'_>, command: QueryCommand) -> PyResult<QueryResult> {
+        let id = self.next_id();
+        self.request(py, Command::Query { id, command }, |event| match event {
+            Event::QueryResult {
+                id: answered,
+                result,
+            } if *answered == id => Some(result.clone()),
+            _ => None,
+        })
+    }
+}
+
+#[pymethods]
+impl Viewer {
+    /// Sends a JSON encoded command
+    fn send(&self, command: &str) -> PyResult<()> {
+        let command = serde_json::from_str::<Command>(command)
+            .map_err(|error| PyValueError::new_err(error.to_string()))?;
+        self.send_command(command)
+    }
+
+    #[pyo3(signature = (position, size=1.0, name="Cube".to_string()))]
+    fn spawn_cube(
+        &self,
+        py: Python<'_>,
+        position: [f32; 3],
+        size: f32,
+        name: String,
+    ) -> PyResult<PyEntity> {
+        self.spawn_command(
+            py,
+            SpawnCommand::Cube {
+                position: position.into(),
+                size,
+                name,
+            },
+        )
+    }
+
+    #[pyo3(signature = (position, name="Camera".to_string()))]
+    fn spawn_camera(&self, py: Python<'_>, position: [f32; 3], name: String) -> PyResult<PyEntity> {
+        self.spawn_command(
+            py,
+            SpawnCommand::Camera {
+                position: position.into(),
+                name,
+            },
+        )
+    }
+
+    /// Spawns an empty entity, which lines and quads can be attached to
+    #[pyo3(signature = (position=[0.0, 0.0, 0.0], name="Entity".to_string()))]
+    fn spawn_entity(&self, py: Python<'_>, position: [f32; 3], name: String) -> PyResult<PyEntity> {
+        self.spawn_command(
+            py,
+            SpawnCommand::Entity {
+                position: position.into(),
+                name,
+            },
+        )
+    }
+
+    fn despawn(&self, entity: PyEntity) -> PyResult<()> {
+        self.send_command(Command::Despawn {
+            entity_id: entity_id(entity),
+        })
+    }
+
+    /// Rotations are `(x, y, z, w)` quaternions
+    #[pyo3(signature = (entity, translation=[0.0, 0.0, 0.0], rotation=[0.0, 0.0, 0.0, 1.0], scale=[1.0, 1.0, 1.0]))]
+    fn set_transform(
+        &self,
+        entity: PyEntity,
+        translation: [f32; 3],
+        rotation: [f32; 4],
+        scale: [f32; 3],
+    ) -> PyResult<()> {
+        self.send_command(Command::SetTransform {
+            entity_id: entity_id(entity),
+            transform: transform(translation, rotation, scale),
+        })
+    }
+
+    /// Replaces the lines drawn by an entity, see `Client.set_lines_array`
+    #[pyo3(signature = (entity, starts, ends, colors=None, widths=None, dashes=None, cap="butt"))]
+    #[allow(clippy::too_many_arguments)]
+    fn set_lines_array(
+        &self,
+        entity: PyEntity,
+        starts: PyFloats<'_>,
+        ends: PyFloats<'_>,
+        colors: Option<PyFloats<'_>>,
+        widths: Option<PyFloats<'_>>,
+        dashes: Option<PyFloats<'_>>,
+        cap: &str,
+    ) -> PyResult<()> {
+        self.send_command(Command::SetLineBuffer {
+            entity_id: entity_id(entity),
+            buffer: line_buffer(starts, ends, colors, widths, dashes, cap)?,
+        })
+    }
+
+    /// Replaces the quads drawn by an entity, see `Client.set_quads_array`
+    #[pyo3(signature = (entity, offsets, sizes=None, colors=None))]
+    fn set_quads_array(
+        &self,
+        entity: PyEntity,
+        offsets: PyFloats<'_>,
+        sizes: Option<PyFloats<'_>>,
+        colors: Option<PyFloats<'_>>,
+    ) -> PyResult<()> {
+        self.send_command(Command::SetQuadBuffer {
+            entity_id: entity_id(entity),
+            buffer: quad_buffer(offsets, sizes, colors)?,
+        })
+    }
+
+    /// Draws a point cloud on an entity, see `Client.set_points`
+    #[pyo3(signature = (entity, positions, colors=None, scalars=None, scalar_range=None, size=0.05, sizing="world", shape="round"))]
+    #[allow(clippy::too_many_arguments)]
+    fn set_points(
+        &self,
+        entity: PyEntity,
+        positions: PyFloats<'_>,
+        colors: Option<PyFloats<'_>>,
+        scalars: Option<PyFloats<'_>>,
+        scalar_range: Option<[f32; 2]>,
+        size: f32,
+        sizing: &str,
+        shape: &str,
+    ) -> PyResult<()> {
+        self.send_command(Command::SetPoints {
+            entity_id: entity_id(entity),
+            points: point_buffer(
+                positions,
+                colors,
+                scalars,
+                scalar_range,
+                size,
+                sizing,
+                shape,
+            )?,
+        })
+    }
+
+    /// Draws a connected strip through points on an entity, see `Client.set_polyline`
+    #[pyo3(signature = (entity, points, colors=None, color=[1.0, 1.0, 1.0, 1.0], width=1.0))]
+    fn set_polyline(
+        &self,
+        entity: PyEntity,
+        points: PyFloats<'_>,
+        colors: Option<PyFloats<'_>>,
+        color: [f32; 4],
+        width: f32,
+    ) -> PyResult<()> {
+        self.send_command(Command::SetPolyline {
+            entity_id: entity_id(entity),
+            polyline: polyline_buffer(points, colors, color, width)?,
+        })
+    }
+
+    /// Draws text at an entity, see `Client.set_label`
+    #[pyo3(signature = (entity, text, offset=[0.0, 0.0, 0.0], mode="billboard", size=16.0, color=[1.0, 1.0, 1.0, 1.0], background=None, leader=None))]
+    #[allow(clippy::too_many_arguments)]
+    fn set_label(
+        &self,
+        entity: PyEntity,
+        text: String,
+        offset: [f32; 3],
+        mode: &str,
+        size: f32,
+        color: [f32; 4],
+        background: Option<[f32; 4]>,
+        leader: Option<[f32; 4]>,
+    ) -> PyResult<()> {
+        self.send_command(Command::SetLabel {
+            entity_id: entity_id(entity),
+            label: label(text, offset, mode, size, color, background, leader)?,
+        })
+    }
+
+    /// Replaces the rendering settings of a pane, see `Client.set_pane_settings`
+    #[pyo3(signature = (pane, msaa_samples=4, fxaa=false, environment=Some(PyEnvironment::Name("sky".to_string())), tonemapping=Some("exponential"), exposure=1.0, gamma=2.2, bloom=true, bloom_threshold=1.0, bloom_intensity=0.6, outline=true, outline_color=[0.98, 0.57, 0.24, 1.0], outline_width=2.0))]
+    #[allow(clippy::too_many_arguments)]
+    fn set_pane_settings(
+        &self,
+        pane: usize,
+        msaa_samples: u32,
+        fxaa: bool,
+        environment: Option<PyEnvironment<'_>>,
+        tonemapping: Option<&str>,
+        exposure: f32,
+        gamma: f32,
+        bloom: bool,
+        bloom_threshold: f32,
+        bloom_intensity: f32,
+        outline: bool,
+        outline_color: [f32; 4],
+        outline_width: f32,
+    ) -> PyResult<()> {
+        self.send_command(Command::SetPaneSettings {
+            index: pane,
+            settings: PaneSettings {
+                msaa_samples,
+                fxaa,
+                environment: self::environment(environment),
+                tonemapping: self::tonemapping(tonemapping, exposure, gamma)?,
+                bloom: Bloom {
+                    enabled: bloom,
+                    threshold: bloom_threshold,
+                    intensity: bloom_intensity,
+                },
+                outline: Outline {
+                    enabled: outline,
+                    color: outline_color.into(),
+                    width: outline_width,
+                },
+            },
+        })
+    }
+
+    /// Draws an indexed triangle mesh on an entity, see `Client.set_mesh`
+    #[pyo3(signature = (entity, positions, indices, normals=None, colors=None, uvs=None, color=[1.0, 1.0, 1.0, 1.0], wireframe=None))]
+    #[allow(clippy::too_many_arguments)]
+    fn set_mesh(
+        &self,
+        entity: PyEntity,
+        positions: PyFloats<'_>,
+        indices: PyIndices<'_>,
+        normals: Option<PyFloats<'_>>,
+        colors: Option<PyFloats<'_>>,
+        uvs: Option<PyFloats<'_>>,
+        color: [f32; 4],
+        wireframe: Option<[f32; 4]>,
+    ) -> PyResult<()> {
+        self.send_command(Command::SetMesh {
+            entity_id: entity_id(entity),
+            mesh: mesh_buffer(positions, indices, normals, colors, uvs, color, wireframe)?,
+        })
+    }
+
+    /// Reads the lines drawn by an entity as `(starts, ends, colors)` arrays,
+    /// or `None` if it has no lines
+    fn get_lines_array<'py>(
+        &self,
+        py: Python<'py>,
+        entity: PyEntity,
+    ) -> PyResult<Option<PyArrays<'py>>> {
+        let entity_id = entity_id(entity);
+        match self.query(py, QueryCommand::GetLineBuffer { entity_id })? {
+            QueryResult::LineBuffer { buffer, .. } => line_arrays(py, &buffer).map(Some),
+            QueryResult::NotFound { .. } => Ok(None),
+            result => Err(unexpected_result(result)),
+        }
+    }
+
+    /// Reads the quads drawn by an entity as `(offsets, sizes, colors)` arrays,
+    /// or `None` if it has no quads
+    fn get_quads_array<'py>(
+        &self,
+        py: Python<'py>,
+        entity: PyEntity,
+    ) -> PyResult<Option<PyArrays<'py>>> {
+        let entity_id = entity_id(entity);
+        match self.query(py, QueryCommand::GetQuadBuffer { entity_id })? {
+            QueryResult::QuadBuffer { buffer, .. } => quad_arrays(py, &buffer).map(Some),
+            QueryResult::NotFound { .. } => Ok(None),
+            result => Err(unexpected_result(result)),
+        }
+    }
+
+    /// Reads the point cloud drawn by an entity as `(positions, colors, scalars)` arrays,
+    /// or `None` if it has no points. Colors and scalars are empty when the points do not have them.
+    fn get_points_array<'py>(
+        &self,
+        py: Python<'py>,
+        entity: PyEntity,
+    ) -> PyResult<Option<PyPointArrays<'py>>> {
+        let entity_id = entity_id(entity);
+        match self.query(py, QueryCommand::GetPoints { entity_id })? {
+            QueryResult::Points { points, .. } => point_arrays(py, &points).map(Some),
+            QueryResult::NotFound { .. } => Ok(None),
+            result => Err(unexpected_result(result)),
+        }
+    }
+
+    /// Reads the mesh drawn by an entity as `(positions, indices, normals, colors, uvs)` arrays,
+    /// with `(T, 3)` indices, or `None` if it has no mesh.
+    /// Normals, colors and uvs are empty when the mesh does not have them.
+    fn get_mesh_arrays<'py>(
+        &self,
+        py: Python<'py>,
+        entity: PyEntity,
+    ) -> PyResult<Option<PyMeshArrays<'py>>> {
+        let entity_id = entity_id(entity);
+        match self.query(py, QueryCommand::GetMesh { entity_id })? {
+            QueryResult::Mesh { mesh, .. } => mesh_arrays(py, &mesh).map(Some),
+            QueryResult::NotFound { .. } => Ok(None),
+            result => Err(unexpected_result(result)),
+        }
+    }
+
+    /// Reads the polyline drawn by an entity as `(points, colors)` arrays,
+    /// or `None` if it has no polyline. Colors are empty when the points do not have them.
+    fn get_polyline_array<'py>(
+        &self,
+        py: Python<'py>,
+        entity: PyEntity,
+    ) -> PyResult<Option<PyPolylineArrays<'py>>> {
+        let entity_id = entity_id(entity);
+        match self.query(py, QueryCommand::GetPolyline { entity_id })? {
+            QueryResult::Polyline { polyline, .. } => polyline_arrays(py, &polyline).map(Some),
+            QueryResult::NotFound { .. } => Ok(None),
+            result => Err(unexpected_result(result)),
+        }
+    }
+
+    /// Returns every event published since the last poll, decoded from JSON
+    fn poll_events(&self, py: Python<'_>) -> PyResult<Vec<PyObject>> {
+        let mut events = self.skipped_events()?.drain(..).collect::<Vec<_>>();
+        events.extend(self.with_handle(|handle| handle.events().collect::<Vec<_>>())?);
+        events.iter().map(|event| to_python(py, event)).collect()
+    }
+
+    /// Waits up to `timeout` seconds for the next event, returning `None` if none arrived
+    #[pyo3(signature = (timeout=1.0))]
+    fn wait_event(&self, py: Python<'_>, timeout: f64) -> PyResult<Option<PyObject>> {
+        if let Some(event) = self.skipped_events()?.pop_front() {
+            return to_python(py, &event).map(Some);
+        }
+        let timeout = std::time::Duration::from_secs_f64(timeout);
+        let event = py.allow_threads(|| self.with_handle(|handle| handle.recv_timeout(timeout)))?;
+        event.map(|event| to_python(py, &event)).transpose()
+    }
+
+    /// Whether the viewer window is still open
+    fn is_running(&self) -> PyResult<bool> {
+        Ok(self
+            .lock()?
+            .as_ref()
+            .is_some_and(|handle| handle.is_running()))
+    }
+
+    /// Closes the viewer window
+    fn close(&self, py: Python<'_>) -> PyResult<()> {
+        let handle = self.lock()?.take();
+        if let Some(handle) = handle {
+            py.allow_threads(|| handle.close());
+        }
+        Ok(())
     }
 }
 
 #[pymodule]
 fn nightshade(m: &Bound<'_, PyModule>) -> PyResult<()> {
     m.add_function(wrap_pyfunction!(run, m)?)?;
+    m.add_function(wrap_pyfunction!(spawn, m)?)?;
+    m.add_function(wrap_pyfunction!(connect, m)?)?;
+    m.add_class::<Client>()?;
+    m.add_class::<Viewer>()?;
     Ok(())
 }
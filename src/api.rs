@@ -1,9 +1,14 @@
-use crate::{prelude::*, rpc::execute_rpc_command, ui::ApiLogEntry};
+use crate::{
+    prelude::*,
+    rpc::{attach_to_scene_root, execute_rpc_command, forward_events},
+    ui::ApiLogEntry,
+};
 use enum2egui::{Gui, GuiInspect};
 use enum2str::EnumStr;
+use serde::{Deserialize, Serialize};
 
 // Commands - Input to engine
-#[derive(Default, Debug, Clone, Gui, EnumStr)]
+#[derive(Default, Debug, Clone, Gui, EnumStr, Serialize, Deserialize)]
 pub enum Command {
     #[default]
     Empty,
@@ -11,61 +16,1191 @@ pub enum Command {
         id: u64,
         command: QueryCommand,
     },
+    /// The id is echoed in the `EntityCreated` report, so the sender can tell which entity is theirs
     Spawn {
+        #[serde(default)]
+        id: u64,
         command: SpawnCommand,
     },
+    Despawn {
+        entity_id: EntityId,
+    },
+    SetTransform {
+        entity_id: EntityId,
+        transform: Transform,
+    },
+    SetLines {
+        entity_id: EntityId,
+        lines: Vec<Line>,
+    },
+    SetQuads {
+        entity_id: EntityId,
+        quads: Vec<Quad>,
+    },
+    SetLineBuffer {
+        entity_id: EntityId,
+        buffer: LineBuffer,
+    },
+    SetQuadBuffer {
+        entity_id: EntityId,
+        buffer: QuadBuffer,
+    },
+    SetMesh {
+        entity_id: EntityId,
+        mesh: MeshBuffer,
+    },
+    SetPoints {
+        entity_id: EntityId,
+        points: PointBuffer,
+    },
+    SetPolyline {
+        entity_id: EntityId,
+        polyline: PolylineBuffer,
+    },
+    SetLabel {
+        entity_id: EntityId,
+        label: Label,
+    },
     Rpc {
         command: RpcCommand,
     },
+    Capture {
+        id: u64,
+        target: CaptureTarget,
+        output: CaptureOutput,
+    },
+    Record {
+        command: RecordCommand,
+    },
+    SetPaneSettings {
+        index: usize,
+        settings: PaneSettings,
+    },
+    Import {
+        id: u64,
+        source: ImportSource,
+    },
+}
+
+impl Command {
+    /// Whether executing this command changes the world, the engine's connections or its files.
+    /// Read-only server clients are only allowed to send non-mutating commands.
+    pub fn is_mutating(&self) -> bool {
+        !matches!(
+            self,
+            Command::Empty
+                | Command::Query { .. }
+                | Command::Capture {
+                    output: CaptureOutput::Bytes,
+                    ..
+                }
+        )
+    }
+
+    /// The id a command's answer is correlated by, for commands that are answered
+    pub fn request_id(&self) -> Option<u64> {
+        match self {
+            Command::Query { id, .. }
+            | Command::Spawn { id, .. }
+            | Command::Capture { id, .. }
+            | Command::Import { id, .. } => Some(*id),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Default, Debug, Clone, Gui, EnumStr, Serialize, Deserialize)]
+pub enum CaptureTarget {
+    /// The whole window, including the user interface
+    #[default]
+    Window,
+
+    /// A scene or color pane as it is shown, with panes numbered in tile tree order
+    Pane { index: usize },
+
+    /// A camera rendered offscreen at a fixed size, which also works without a window
+    Camera {
+        camera_entity: EntityId,
+        width: u32,
+        height: u32,
+    },
+}
+
+/// How a scene pane is rendered, with panes numbered in tile tree order
+#[derive(Debug, Clone, PartialEq, Gui, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PaneSettings {
+    /// The number of samples taken per pixel, lowered to the nearest count the GPU supports.
+    /// A single sample turns multisampling off.
+    pub msaa_samples: u32,
+
+    /// Whether FXAA smooths the edges of the scene after it is resolved
+    pub fxaa: bool,
+    pub environment: Environment,
+    pub tonemapping: Tonemapping,
+    pub bloom: Bloom,
+    pub outline: Outline,
+}
+
+impl Default for PaneSettings {
+    fn default() -> Self {
+        Self {
+            msaa_samples: 4,
+            fxaa: false,
+            environment: Environment::default(),
+            tonemapping: Tonemapping::default(),
+            bloom: Bloom::default(),
+            outline: Outline::default(),
+        }
+    }
+}
+
+/// What is drawn behind the entities of a scene pane
+#[derive(Default, Debug, Clone, PartialEq, Gui, EnumStr, Serialize, Deserialize)]
+pub enum Environment {
+    /// The HDR sky built into the viewer
+    #[default]
+    Sky,
+
+    /// An equirectangular `.hdr` image in a file on the machine running the engine
+    HdrFile {
+        path: String,
+    },
+
+    /// The contents of an equirectangular `.hdr` image sent along with the settings
+    HdrBytes {
+        bytes: FileBytes,
+    },
+
+    /// Blends from the bottom color below the horizon to the top color above it
+    Gradient {
+        top: Vec4,
+        bottom: Vec4,
+    },
+    Solid {
+        color: Vec4,
+    },
+
+    /// Leaves the background black
+    None,
+}
+
+/// Maps the radiance of HDR environments to display colors.
+/// Everything else, including gradients and solid colors, is drawn in display colors already,
+/// so it keeps the colors it was given.
+#[derive(Debug, Clone, PartialEq, Gui, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Tonemapping {
+    pub enabled: bool,
+    pub operator: TonemapOperator,
+
+    /// Scales the radiance before it is mapped
+    pub exposure: f32,
+    pub gamma: f32,
+}
+
+impl Default for Tonemapping {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            operator: TonemapOperator::Exponential,
+            exposure: 1.0,
+            gamma: 2.2,
+        }
+    }
+}
+
+/// The curve radiance is mapped to display colors with
+#[derive(Default, Debug, Copy, Clone, PartialEq, Gui, EnumStr, Serialize, Deserialize)]
+pub enum TonemapOperator {
+    /// `1 - exp(-x)`, which approaches white smoothly
+    #[default]
+    Exponential,
+
+    /// `x / (1 + x)`, which keeps more detail in bright areas
+    Reinhard,
+
+    /// The ACES filmic curve, which adds contrast
+    Aces,
+}
+
+/// Makes colors brighter than white glow, so emissive highlights in data stand out.
+/// Any color with a channel above one is emissive.
+#[derive(Debug, Clone, PartialEq, Gui, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Bloom {
+    pub enabled: bool,
+
+    /// The brightness above which colors glow
+    pub threshold: f32,
+
+    /// How strongly the glow is added to the scene
+    pub intensity: f32,
+}
+
+impl Default for Bloom {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            threshold: 1.0,
+            intensity: 0.6,
+        }
+    }
+}
+
+/// Outlines the lines, quads and meshes of the selected entity and its descendants
+#[derive(Debug, Clone, PartialEq, Gui, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Outline {
+    pub enabled: bool,
+    pub color: Vec4,
+
+    /// The width of the outline in pixels
+    pub width: f32,
+}
+
+impl Default for Outline {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            color: [0.98, 0.57, 0.24, 1.0].into(),
+            width: 2.0,
+        }
+    }
+}
+
+#[derive(Default, Debug, Clone, Gui, EnumStr, Serialize, Deserialize)]
+pub enum RecordCommand {
+    #[default]
+    Empty,
+
+    /// Writes every rendered frame of a pane or camera to numbered PNG files in a directory.
+    /// Time advances by one frame at the given rate per rendered frame, independent of real time,
+    /// until `frame_count` frames are written or, when it is zero, until the recording is stopped.
+    Start {
+        id: u64,
+        target: CaptureTarget,
+        directory: String,
+        frames_per_second: f32,
+        frame_count: u32,
+    },
+    Stop,
+}
+
+/// A model file to import, in a format chosen by its extension
+#[derive(Default, Debug, Clone, Gui, EnumStr, Serialize, Deserialize)]
+pub enum ImportSource {
+    #[default]
+    Empty,
+
+    /// A file on the machine running the engine
+    File { path: String },
+
+    /// The contents of a file sent along with the command, named with the file's extension
+    Bytes { name: String, bytes: FileBytes },
+}
+
+/// The contents of a file.
+/// The bytes are shared, so cloning a command carrying them is cheap.
+#[derive(Default, Clone, Serialize, Deserialize)]
+pub struct FileBytes(pub std::sync::Arc<[u8]>);
+
+/// Shared bytes are equal without comparing them, which keeps comparing settings cheap
+impl PartialEq for FileBytes {
+    fn eq(&self, other: &Self) -> bool {
+        std::sync::Arc::ptr_eq(&self.0, &other.0) || self.0 == other.0
+    }
+}
+
+impl std::fmt::Debug for FileBytes {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "FileBytes {{ bytes: {} }}", self.0.len())
+    }
+}
+
+impl GuiInspect for FileBytes {
+    fn ui(&self, ui: &mut egui::Ui) {
+        ui.label(format!("{} bytes", self.0.len()));
+    }
+
+    fn ui_mut(&mut self, ui: &mut egui::Ui) {
+        self.ui(ui);
+    }
+}
+
+#[derive(Default, Debug, Clone, Gui, EnumStr, Serialize, Deserialize)]
+pub enum CaptureOutput {
+    /// Reports the encoded PNG
+    #[default]
+    Bytes,
+
+    /// Writes the PNG to a file on the machine running the engine and reports its path
+    File { path: String },
+}
+
+#[derive(Default, Debug, Clone, Gui, EnumStr, Serialize, Deserialize)]
+pub enum QueryCommand {
+    #[default]
+    Empty,
+    ListCameras,
+    ListEntities,
+    GetTransform {
+        entity_id: EntityId,
+    },
+    GetLineBuffer {
+        entity_id: EntityId,
+    },
+    GetQuadBuffer {
+        entity_id: EntityId,
+    },
+    GetMesh {
+        entity_id: EntityId,
+    },
+    GetPoints {
+        entity_id: EntityId,
+    },
+    GetPolyline {
+        entity_id: EntityId,
+    },
+    GetLabel {
+        entity_id: EntityId,
+    },
+}
+
+#[derive(Default, Debug, Clone, Gui, EnumStr, Serialize, Deserialize)]
+pub enum SpawnCommand {
+    #[default]
+    Empty,
+    Cube {
+        position: Vec3,
+        size: f32,
+        name: String,
+    },
+    Camera {
+        position: Vec3,
+        name: String,
+    },
+    Entity {
+        position: Vec3,
+        name: String,
+    },
+}
+
+#[derive(Default, Debug, Clone, Gui, Serialize, Deserialize)]
+pub struct Vec3 {
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
+}
+
+impl From<Vec3> for nalgebra_glm::Vec3 {
+    fn from(val: Vec3) -> Self {
+        nalgebra_glm::vec3(val.x, val.y, val.z)
+    }
+}
+
+#[derive(Default, Debug, Clone, Gui, Serialize, Deserialize)]
+pub struct Vec2 {
+    pub x: f32,
+    pub y: f32,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Gui, Serialize, Deserialize)]
+pub struct Vec4 {
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
+    pub w: f32,
+}
+
+#[derive(Debug, Clone, Gui, Serialize, Deserialize)]
+pub struct Quat {
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
+    pub w: f32,
+}
+
+impl Default for Quat {
+    fn default() -> Self {
+        Self {
+            x: 0.0,
+            y: 0.0,
+            z: 0.0,
+            w: 1.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Gui, Serialize, Deserialize)]
+pub struct Transform {
+    pub translation: Vec3,
+    pub rotation: Quat,
+    pub scale: Vec3,
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Self {
+            translation: Vec3::default(),
+            rotation: Quat::default(),
+            scale: [1.0, 1.0, 1.0].into(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Gui, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Line {
+    pub start: Vec3,
+    pub end: Vec3,
+    pub color: Vec4,
+
+    /// The width in pixels
+    pub width: f32,
+
+    /// The lengths of each dash and the gap after it in pixels, or `None` for a solid line
+    pub dash: Option<Vec2>,
+    pub cap: LineCap,
+}
+
+impl Default for Line {
+    fn default() -> Self {
+        Self {
+            start: Default::default(),
+            end: Default::default(),
+            color: Default::default(),
+            width: 1.0,
+            dash: None,
+            cap: LineCap::Butt,
+        }
+    }
+}
+
+/// How the ends of a line, and of each of its dashes, are drawn
+#[derive(Default, Debug, Copy, Clone, PartialEq, Gui, EnumStr, Serialize, Deserialize)]
+pub enum LineCap {
+    /// Ends exactly at its end points
+    #[default]
+    Butt,
+
+    /// Extends past its end points by half its width
+    Square,
+
+    /// Ends in a half circle around its end points
+    Round,
+}
+
+#[derive(Default, Debug, Clone, Gui, Serialize, Deserialize)]
+pub struct Quad {
+    pub offset: Vec3,
+    pub size: Vec2,
+    pub color: Vec4,
+}
+
+/// Text anchored at a point of an entity
+#[derive(Debug, Clone, Gui, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Label {
+    pub text: String,
+
+    /// The point the text is centered above, in the local space of the entity
+    pub offset: Vec3,
+    pub mode: LabelMode,
+
+    /// The height of a line of text, in pixels for billboards or world units otherwise
+    pub size: f32,
+    pub color: Vec4,
+
+    /// The color of the panel drawn behind the text, if any
+    pub background: Option<Vec4>,
+
+    /// The color of the line drawn from the origin of the entity to the text, if any
+    pub leader: Option<Vec4>,
+}
+
+impl Default for Label {
+    fn default() -> Self {
+        (&crate::context::Label::default()).into()
+    }
+}
+
+/// How a label is oriented
+#[derive(Default, Debug, Copy, Clone, PartialEq, Gui, EnumStr, Serialize, Deserialize)]
+pub enum LabelMode {
+    /// Faces the camera and keeps the same size in pixels at any distance
+    #[default]
+    Billboard,
+
+    /// Lies in the XY plane of its entity and is sized in world units, shrinking with distance
+    World,
+}
+
+/// Lines packed into flat arrays, so bulk producers such as NumPy
+/// can fill them without building a `Line` per segment.
+/// Buffers are shared, so cloning a command carrying them is cheap.
+#[derive(Default, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LineBuffer {
+    /// Start points, three floats per line
+    pub starts: std::sync::Arc<[f32]>,
+
+    /// End points, three floats per line
+    pub ends: std::sync::Arc<[f32]>,
+
+    /// RGBA colors, four floats per line or four floats shared by every line
+    pub colors: std::sync::Arc<[f32]>,
+
+    /// Widths in pixels, one float per line or one float shared by every line.
+    /// Lines are one pixel wide when empty.
+    pub widths: std::sync::Arc<[f32]>,
+
+    /// Dash and gap lengths in pixels, two floats per line or two floats shared by every line.
+    /// Lines with a dash length of zero are solid, as are all lines when empty.
+    pub dashes: std::sync::Arc<[f32]>,
+
+    /// The caps shared by every line
+    pub cap: LineCap,
+}
+
+impl LineBuffer {
+    pub fn len(&self) -> usize {
+        self.starts.len() / 3
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Quads packed into flat arrays, so bulk producers such as NumPy
+/// can fill them without building a `Quad` per element.
+/// Buffers are shared, so cloning a command carrying them is cheap.
+#[derive(Default, Clone, Serialize, Deserialize)]
+pub struct QuadBuffer {
+    /// Centers, three floats per quad
+    pub offsets: std::sync::Arc<[f32]>,
+
+    /// Widths and heights, two floats per quad or two floats shared by every quad
+    pub sizes: std::sync::Arc<[f32]>,
+
+    /// RGBA colors, four floats per quad or four floats shared by every quad
+    pub colors: std::sync::Arc<[f32]>,
+}
+
+impl QuadBuffer {
+    pub fn len(&self) -> usize {
+        self.offsets.len() / 3
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// An indexed triangle mesh packed into flat arrays.
+/// Optional vertex attributes are left empty when absent.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MeshBuffer {
+    /// Vertex positions, three floats per vertex
+    pub positions: std::sync::Arc<[f32]>,
+
+    /// Vertex normals, three floats per vertex, computed from the triangles when empty
+    pub normals: std::sync::Arc<[f32]>,
+
+    /// Texture coordinates, two floats per vertex
+    pub uvs: std::sync::Arc<[f32]>,
+
+    /// RGBA vertex colors, four floats per vertex, multiplied with the mesh color
+    pub colors: std::sync::Arc<[f32]>,
+
+    /// Vertex indices, three per triangle
+    pub indices: std::sync::Arc<[u32]>,
+
+    pub color: Vec4,
+
+    /// Draws the edges of every triangle over the surface in this color
+    pub wireframe: Option<Vec4>,
+}
+
+impl Default for MeshBuffer {
+    fn default() -> Self {
+        Self {
+            positions: Default::default(),
+            normals: Default::default(),
+            uvs: Default::default(),
+            colors: Default::default(),
+            indices: Default::default(),
+            color: [1.0, 1.0, 1.0, 1.0].into(),
+            wireframe: None,
+        }
+    }
+}
+
+impl MeshBuffer {
+    pub fn vertex_count(&self) -> usize {
+        self.positions.len() / 3
+    }
+
+    pub fn triangle_count(&self) -> usize {
+        self.indices.len() / 3
+    }
+}
+
+/// A point cloud packed into flat arrays.
+/// Points are colored by their colors, then their scalars, then the point color alone.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PointBuffer {
+    /// Point positions, three floats per point
+    pub positions: std::sync::Arc<[f32]>,
+
+    /// RGBA colors, four floats per point, multiplied with the point color
+    pub colors: std::sync::Arc<[f32]>,
+
+    /// One value per point, mapped through a colormap when there are no colors
+    pub scalars: std::sync::Arc<[f32]>,
+
+    /// The scalars mapped to the ends of the colormap, or the range of the scalars when absent
+    pub scalar_range: Option<Vec2>,
+
+    pub color: Vec4,
+
+    /// The diameter of a point, in pixels or world units depending on the sizing
+    pub size: f32,
+    pub sizing: PointSizing,
+    pub shape: PointShape,
+}
+
+impl Default for PointBuffer {
+    fn default() -> Self {
+        let points = crate::context::Points::default();
+        Self {
+            positions: Default::default(),
+            colors: Default::default(),
+            scalars: Default::default(),
+            scalar_range: None,
+            color: points.color.into(),
+            size: points.size,
+            sizing: points.sizing,
+            shape: points.shape,
+        }
+    }
+}
+
+impl PointBuffer {
+    pub fn len(&self) -> usize {
+        self.positions.len() / 3
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// A polyline packed into flat arrays, drawn as a connected strip through its points
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PolylineBuffer {
+    /// Point positions, three floats per point
+    pub points: std::sync::Arc<[f32]>,
+
+    /// RGBA colors, four floats per point, multiplied with the polyline color
+    pub colors: std::sync::Arc<[f32]>,
+    pub color: Vec4,
+
+    /// The width in pixels
+    pub width: f32,
+}
+
+impl Default for PolylineBuffer {
+    fn default() -> Self {
+        let polyline = crate::context::Polyline::default();
+        Self {
+            points: Default::default(),
+            colors: Default::default(),
+            color: polyline.color.into(),
+            width: polyline.width,
+        }
+    }
+}
+
+impl PolylineBuffer {
+    pub fn len(&self) -> usize {
+        self.points.len() / 3
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// How the size of a point is measured
+#[derive(Default, Debug, Copy, Clone, PartialEq, Gui, EnumStr, Serialize, Deserialize)]
+pub enum PointSizing {
+    /// Points keep the same size in pixels at any distance
+    #[default]
+    Screen,
+
+    /// Points are sized in world units, shrinking with distance
+    World,
+}
+
+#[derive(Default, Debug, Copy, Clone, PartialEq, Gui, EnumStr, Serialize, Deserialize)]
+pub enum PointShape {
+    #[default]
+    Round,
+    Square,
+}
+
+// Packed buffers can hold millions of elements,
+// so they are summarized instead of listed element by element
+impl std::fmt::Debug for LineBuffer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "LineBuffer {{ lines: {} }}", self.len())
+    }
+}
+
+impl std::fmt::Debug for QuadBuffer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "QuadBuffer {{ quads: {} }}", self.len())
+    }
+}
+
+impl std::fmt::Debug for MeshBuffer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "MeshBuffer {{ vertices: {}, triangles: {} }}",
+            self.vertex_count(),
+            self.triangle_count()
+        )
+    }
+}
+
+impl GuiInspect for LineBuffer {
+    fn ui(&self, ui: &mut egui::Ui) {
+        ui.label(format!("{} lines", self.len()));
+    }
+
+    fn ui_mut(&mut self, ui: &mut egui::Ui) {
+        self.ui(ui);
+    }
+}
+
+impl GuiInspect for QuadBuffer {
+    fn ui(&self, ui: &mut egui::Ui) {
+        ui.label(format!("{} quads", self.len()));
+    }
+
+    fn ui_mut(&mut self, ui: &mut egui::Ui) {
+        self.ui(ui);
+    }
+}
+
+impl std::fmt::Debug for PointBuffer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "PointBuffer {{ points: {} }}", self.len())
+    }
+}
+
+impl GuiInspect for PointBuffer {
+    fn ui(&self, ui: &mut egui::Ui) {
+        ui.label(format!("{} points", self.len()));
+    }
+
+    fn ui_mut(&mut self, ui: &mut egui::Ui) {
+        self.ui(ui);
+    }
+}
+
+impl std::fmt::Debug for PolylineBuffer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "PolylineBuffer {{ points: {} }}", self.len())
+    }
+}
+
+impl GuiInspect for PolylineBuffer {
+    fn ui(&self, ui: &mut egui::Ui) {
+        ui.label(format!("{} points", self.len()));
+    }
+
+    fn ui_mut(&mut self, ui: &mut egui::Ui) {
+        self.ui(ui);
+    }
+}
+
+impl GuiInspect for MeshBuffer {
+    fn ui(&self, ui: &mut egui::Ui) {
+        ui.label(format!(
+            "{} vertices, {} triangles",
+            self.vertex_count(),
+            self.triangle_count()
+        ));
+    }
+
+    fn ui_mut(&mut self, ui: &mut egui::Ui) {
+        self.ui(ui);
+    }
+}
+
+impl From<&Lines> for LineBuffer {
+    fn from(Lines(lines): &Lines) -> Self {
+        let dashed = lines.iter().any(|line| line.dash.is_some());
+        Self {
+            starts: lines.iter().flat_map(|line| line.start.data.0[0]).collect(),
+            ends: lines.iter().flat_map(|line| line.end.data.0[0]).collect(),
+            colors: lines.iter().flat_map(|line| line.color.data.0[0]).collect(),
+            widths: lines.iter().map(|line| line.width).collect(),
+            dashes: if dashed {
+                lines
+                    .iter()
+                    .flat_map(|line| line.dash.unwrap_or_default().data.0[0])
+                    .collect()
+            } else {
+                std::sync::Arc::new([])
+            },
+            cap: lines.first().map(|line| line.cap).unwrap_or_default(),
+        }
+    }
+}
+
+impl From<&Quads> for QuadBuffer {
+    fn from(Quads(quads): &Quads) -> Self {
+        Self {
+            offsets: quads
+                .iter()
+                .flat_map(|quad| quad.offset.data.0[0])
+                .collect(),
+            sizes: quads.iter().flat_map(|quad| quad.size.data.0[0]).collect(),
+            colors: quads.iter().flat_map(|quad| quad.color.data.0[0]).collect(),
+        }
+    }
+}
+
+impl From<&LineBuffer> for Lines {
+    fn from(buffer: &LineBuffer) -> Self {
+        Self(
+            buffer
+                .starts
+                .chunks_exact(3)
+                .zip(buffer.ends.chunks_exact(3))
+                .enumerate()
+                .map(|(index, (start, end))| crate::context::Line {
+                    start: nalgebra_glm::make_vec3(start),
+                    end: nalgebra_glm::make_vec3(end),
+                    color: packed_element(&buffer.colors, index),
+                    width: packed_element::<1>(&buffer.widths, index).x,
+                    dash: (!buffer.dashes.is_empty())
+                        .then(|| packed_element::<2>(&buffer.dashes, index))
+                        .filter(|dash| dash.x > 0.0),
+                    cap: buffer.cap,
+                })
+                .collect(),
+        )
+    }
+}
+
+impl From<&QuadBuffer> for Quads {
+    fn from(buffer: &QuadBuffer) -> Self {
+        Self(
+            buffer
+                .offsets
+                .chunks_exact(3)
+                .enumerate()
+                .map(|(index, offset)| crate::context::Quad {
+                    offset: nalgebra_glm::make_vec3(offset),
+                    size: packed_element(&buffer.sizes, index),
+                    color: packed_element(&buffer.colors, index),
+                })
+                .collect(),
+        )
+    }
+}
+
+impl From<&crate::context::Mesh> for MeshBuffer {
+    fn from(mesh: &crate::context::Mesh) -> Self {
+        Self {
+            positions: mesh.positions.iter().flat_map(|v| v.data.0[0]).collect(),
+            normals: mesh.normals.iter().flat_map(|v| v.data.0[0]).collect(),
+            uvs: mesh.uvs.iter().flat_map(|v| v.data.0[0]).collect(),
+            colors: mesh.colors.iter().flat_map(|v| v.data.0[0]).collect(),
+            indices: mesh.indices.iter().copied().collect(),
+            color: mesh.color.into(),
+            wireframe: mesh.wireframe.map(Into::into),
+        }
+    }
+}
+
+impl From<&MeshBuffer> for crate::context::Mesh {
+    fn from(buffer: &MeshBuffer) -> Self {
+        let mut mesh = Self {
+            positions: buffer
+                .positions
+                .chunks_exact(3)
+                .map(nalgebra_glm::make_vec3)
+                .collect(),
+            normals: buffer
+                .normals
+                .chunks_exact(3)
+                .map(nalgebra_glm::make_vec3)
+                .collect(),
+            uvs: buffer
+                .uvs
+                .chunks_exact(2)
+                .map(nalgebra_glm::make_vec2)
+                .collect(),
+            colors: buffer
+                .colors
+                .chunks_exact(4)
+                .map(nalgebra_glm::make_vec4)
+                .collect(),
+            indices: buffer.indices.to_vec(),
+            color: buffer.color.clone().into(),
+            wireframe: buffer.wireframe.clone().map(Into::into),
+        };
+        // Normals are computed once here rather than every frame
+        if mesh.normals.len() != mesh.positions.len() {
+            mesh.normals = mesh.smooth_normals();
+        }
+        mesh
+    }
+}
+
+impl From<&crate::context::Points> for PointBuffer {
+    fn from(points: &crate::context::Points) -> Self {
+        Self {
+            positions: points.positions.iter().flat_map(|v| v.data.0[0]).collect(),
+            colors: points.colors.iter().flat_map(|v| v.data.0[0]).collect(),
+            scalars: points.scalars.clone(),
+            scalar_range: points.scalar_range.map(|range| [range.x, range.y].into()),
+            color: points.color.into(),
+            size: points.size,
+            sizing: points.sizing,
+            shape: points.shape,
+        }
+    }
+}
+
+impl From<&PointBuffer> for crate::context::Points {
+    fn from(buffer: &PointBuffer) -> Self {
+        Self {
+            positions: buffer
+                .positions
+                .chunks_exact(3)
+                .map(nalgebra_glm::make_vec3)
+                .collect(),
+            colors: buffer
+                .colors
+                .chunks_exact(4)
+                .map(nalgebra_glm::make_vec4)
+                .collect(),
+            scalars: buffer.scalars.clone(),
+            scalar_range: buffer.scalar_range.clone().map(Into::into),
+            color: buffer.color.clone().into(),
+            size: buffer.size,
+            sizing: buffer.sizing,
+            shape: buffer.shape,
+        }
+    }
+}
+
+impl From<&crate::context::Polyline> for PolylineBuffer {
+    fn from(polyline: &crate::context::Polyline) -> Self {
+        Self {
+            points: polyline.points.iter().flat_map(|v| v.data.0[0]).collect(),
+            colors: polyline.colors.iter().flat_map(|v| v.data.0[0]).collect(),
+            color: polyline.color.into(),
+            width: polyline.width,
+        }
+    }
+}
+
+impl From<&PolylineBuffer> for crate::context::Polyline {
+    fn from(buffer: &PolylineBuffer) -> Self {
+        Self {
+            points: buffer
+                .points
+                .chunks_exact(3)
+                .map(nalgebra_glm::make_vec3)
+                .collect(),
+            colors: buffer
+                .colors
+                .chunks_exact(4)
+                .map(nalgebra_glm::make_vec4)
+                .collect(),
+            color: buffer.color.clone().into(),
+            width: buffer.width,
+        }
+    }
+}
+
+impl From<&crate::context::Label> for Label {
+    fn from(label: &crate::context::Label) -> Self {
+        Self {
+            text: label.text.clone(),
+            offset: label.offset.into(),
+            mode: label.mode,
+            size: label.size,
+            color: label.color.into(),
+            background: label.background.map(Into::into),
+            leader: label.leader.map(Into::into),
+        }
+    }
+}
+
+impl From<Label> for crate::context::Label {
+    fn from(label: Label) -> Self {
+        Self {
+            text: label.text,
+            offset: label.offset.into(),
+            mode: label.mode,
+            size: label.size,
+            color: label.color.into(),
+            background: label.background.map(Into::into),
+            leader: label.leader.map(Into::into),
+        }
+    }
+}
+
+/// Reads the element at an index from a packed array,
+/// which holds either one element per item or a single element shared by all items.
+/// Missing elements are filled with ones, so absent colors are opaque white.
+fn packed_element<const N: usize>(values: &[f32], index: usize) -> nalgebra_glm::TVec<f32, N> {
+    let element = values
+        .get(index * N..(index + 1) * N)
+        .or_else(|| values.get(..N))
+        .unwrap_or(&[1.0; N]);
+    nalgebra_glm::TVec::<f32, N>::from_column_slice(element)
+}
+
+#[derive(Default, Debug, Clone, Gui, Serialize, Deserialize)]
+pub struct EntitySummary {
+    pub entity_id: EntityId,
+    pub name: String,
+    pub parent: Option<EntityId>,
+}
+
+impl From<[f32; 3]> for Vec3 {
+    fn from([x, y, z]: [f32; 3]) -> Self {
+        Self { x, y, z }
+    }
+}
+
+impl From<nalgebra_glm::Vec3> for Vec3 {
+    fn from(val: nalgebra_glm::Vec3) -> Self {
+        Self {
+            x: val.x,
+            y: val.y,
+            z: val.z,
+        }
+    }
+}
+
+impl From<[f32; 2]> for Vec2 {
+    fn from([x, y]: [f32; 2]) -> Self {
+        Self { x, y }
+    }
+}
+
+impl From<Vec2> for nalgebra_glm::Vec2 {
+    fn from(val: Vec2) -> Self {
+        nalgebra_glm::vec2(val.x, val.y)
+    }
+}
+
+impl From<[f32; 4]> for Vec4 {
+    fn from([x, y, z, w]: [f32; 4]) -> Self {
+        Self { x, y, z, w }
+    }
+}
+
+impl From<nalgebra_glm::Vec4> for Vec4 {
+    fn from(val: nalgebra_glm::Vec4) -> Self {
+        Self {
+            x: val.x,
+            y: val.y,
+            z: val.z,
+            w: val.w,
+        }
+    }
+}
+
+impl From<Vec4> for nalgebra_glm::Vec4 {
+    fn from(val: Vec4) -> Self {
+        nalgebra_glm::vec4(val.x, val.y, val.z, val.w)
+    }
 }
 
-#[derive(Default, Debug, Clone, Gui, EnumStr)]
-pub enum QueryCommand {
-    #[default]
-    Empty,
-    ListCameras,
+impl From<[f32; 4]> for Quat {
+    fn from([x, y, z, w]: [f32; 4]) -> Self {
+        Self { x, y, z, w }
+    }
 }
 
-#[derive(Default, Debug, Clone, Gui, EnumStr)]
-pub enum SpawnCommand {
-    #[default]
-    Empty,
-    Cube {
-        position: Vec3,
-        size: f32,
-        name: String,
-    },
-    Camera {
-        position: Vec3,
-        name: String,
-    },
+impl From<Quat> for nalgebra_glm::Quat {
+    fn from(val: Quat) -> Self {
+        nalgebra_glm::quat(val.x, val.y, val.z, val.w)
+    }
 }
 
-#[derive(Default, Debug, Clone, Gui)]
-pub struct Vec3 {
-    pub x: f32,
-    pub y: f32,
-    pub z: f32,
+impl From<nalgebra_glm::Quat> for Quat {
+    fn from(val: nalgebra_glm::Quat) -> Self {
+        Self {
+            x: val.i,
+            y: val.j,
+            z: val.k,
+            w: val.w,
+        }
+    }
 }
 
-impl From<Vec3> for nalgebra_glm::Vec3 {
-    fn from(val: Vec3) -> Self {
-        nalgebra_glm::vec3(val.x, val.y, val.z)
+impl From<Transform> for LocalTransform {
+    fn from(val: Transform) -> Self {
+        Self {
+            translation: val.translation.into(),
+            rotation: val.rotation.into(),
+            scale: val.scale.into(),
+        }
     }
 }
 
-impl From<nalgebra_glm::Vec3> for Vec3 {
-    fn from(val: nalgebra_glm::Vec3) -> Self {
+impl From<LocalTransform> for Transform {
+    fn from(val: LocalTransform) -> Self {
         Self {
-            x: val.x,
-            y: val.y,
-            z: val.z,
+            translation: val.translation.into(),
+            rotation: val.rotation.into(),
+            scale: val.scale.into(),
+        }
+    }
+}
+
+impl From<Line> for crate::context::Line {
+    fn from(val: Line) -> Self {
+        Self {
+            start: val.start.into(),
+            end: val.end.into(),
+            color: val.color.into(),
+            width: val.width,
+            dash: val.dash.map(Into::into),
+            cap: val.cap,
+        }
+    }
+}
+
+impl From<Quad> for crate::context::Quad {
+    fn from(val: Quad) -> Self {
+        Self {
+            offset: val.offset.into(),
+            size: val.size.into(),
+            color: val.color.into(),
         }
     }
 }
 
 // Events - Output from engine
-#[derive(Default, Debug, Clone, Gui, EnumStr)]
+#[derive(Default, Debug, Clone, Gui, EnumStr, Serialize, Deserialize)]
 pub enum Event {
     #[default]
     Empty,
@@ -81,25 +1216,142 @@ pub enum Event {
     },
 }
 
-#[derive(Default, Debug, Clone, Gui, EnumStr)]
+#[derive(Default, Debug, Clone, Gui, EnumStr, Serialize, Deserialize)]
 pub enum QueryResult {
     #[default]
     Empty,
     CameraList {
         cameras: Vec<EntityId>,
     },
+    EntityList {
+        entities: Vec<EntitySummary>,
+    },
+    Transform {
+        entity_id: EntityId,
+        transform: Transform,
+    },
+    LineBuffer {
+        entity_id: EntityId,
+        buffer: LineBuffer,
+    },
+    QuadBuffer {
+        entity_id: EntityId,
+        buffer: QuadBuffer,
+    },
+    Mesh {
+        entity_id: EntityId,
+        mesh: MeshBuffer,
+    },
+    Points {
+        entity_id: EntityId,
+        points: PointBuffer,
+    },
+    Polyline {
+        entity_id: EntityId,
+        polyline: PolylineBuffer,
+    },
+    Label {
+        entity_id: EntityId,
+        label: Label,
+    },
+    NotFound {
+        entity_id: EntityId,
+    },
 }
 
-#[derive(Default, Debug, Clone, Gui, EnumStr)]
+#[derive(Default, Debug, Clone, Gui, EnumStr, Serialize, Deserialize)]
 pub enum Report {
     #[default]
     Empty,
     EntityCreated {
+        id: u64,
         entity_id: EntityId,
     },
+    EntityDespawned {
+        entity_id: EntityId,
+    },
+    EntityNotFound {
+        entity_id: EntityId,
+    },
+    PaneNotFound {
+        index: usize,
+    },
+    EnvironmentFailed {
+        index: usize,
+        error: String,
+    },
+    CaptureSaved {
+        id: u64,
+        path: String,
+    },
+    CaptureEncoded {
+        id: u64,
+        image: PngImage,
+    },
+    CaptureFailed {
+        id: u64,
+        error: String,
+    },
+    RecordingStarted {
+        id: u64,
+        directory: String,
+    },
+    RecordingFinished {
+        id: u64,
+        directory: String,
+        frames: u32,
+    },
+    RecordingFailed {
+        id: u64,
+        error: String,
+    },
+    Imported {
+        id: u64,
+        root: EntityId,
+    },
+    ImportFailed {
+        id: u64,
+        error: String,
+    },
+}
+
+/// A PNG encoded image.
+/// The bytes are shared, so cloning an event carrying them is cheap.
+#[derive(Default, Clone, Serialize, Deserialize)]
+pub struct PngImage {
+    pub width: u32,
+    pub height: u32,
+    pub bytes: std::sync::Arc<[u8]>,
+}
+
+impl std::fmt::Debug for PngImage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "PngImage {{ width: {}, height: {}, bytes: {} }}",
+            self.width,
+            self.height,
+            self.bytes.len()
+        )
+    }
+}
+
+impl GuiInspect for PngImage {
+    fn ui(&self, ui: &mut egui::Ui) {
+        ui.label(format!(
+            "{}x{} PNG, {} bytes",
+            self.width,
+            self.height,
+            self.bytes.len()
+        ));
+    }
+
+    fn ui_mut(&mut self, ui: &mut egui::Ui) {
+        self.ui(ui);
+    }
 }
 
-#[derive(Default, Debug, Clone, Gui, EnumStr)]
+#[derive(Default, Debug, Clone, Gui, EnumStr, Serialize, Deserialize)]
 pub enum WebsocketEvent {
     #[default]
     Empty,
@@ -111,15 +1363,64 @@ pub enum WebsocketEvent {
     Error {
         error: String,
     },
+    /// A command was refused, identified by the id its answer would have carried
+    Rejected {
+        id: u64,
+        error: String,
+    },
 }
 
 // Update the Message enum definition
-#[derive(Debug, Clone, Gui, EnumStr)]
+#[derive(Debug, Clone, Gui, EnumStr, Serialize, Deserialize)]
 pub enum Message {
     Command { command: Command },
     Event { event: Event },
 }
 
+impl Message {
+    /// Messages carrying packed buffers are sent as binary frames,
+    /// which are far smaller and faster to decode than JSON
+    pub fn is_bulk(&self) -> bool {
+        matches!(
+            self,
+            Message::Command {
+                command: Command::SetLineBuffer { .. }
+                    | Command::SetQuadBuffer { .. }
+                    | Command::SetMesh { .. }
+                    | Command::SetPoints { .. }
+                    | Command::SetPolyline { .. }
+                    | Command::Import {
+                        source: ImportSource::Bytes { .. },
+                        ..
+                    },
+            } | Message::Event {
+                event: Event::QueryResult {
+                    result: QueryResult::LineBuffer { .. }
+                        | QueryResult::QuadBuffer { .. }
+                        | QueryResult::Mesh { .. }
+                        | QueryResult::Points { .. }
+                        | QueryResult::Polyline { .. },
+                    ..
+                },
+            } | Message::Event {
+                event: Event::Report {
+                    report: Report::CaptureEncoded { .. },
+                },
+            }
+        )
+    }
+
+    /// Encodes the message for a binary frame
+    pub fn to_bytes(&self) -> Result<Vec<u8>, bincode::Error> {
+        bincode::serialize(self)
+    }
+
+    /// Decodes a message from a binary frame
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, bincode::Error> {
+        bincode::deserialize(bytes)
+    }
+}
+
 impl Default for Message {
     fn default() -> Self {
         Self::Command {
@@ -128,60 +1429,442 @@ impl Default for Message {
     }
 }
 
+// Queued commands and events are tagged with the name of the RPC connection
+// they came from or are addressed to, and are local to this engine when untagged
+#[derive(Debug, Clone)]
+pub struct QueuedCommand {
+    pub command: Command,
+    pub connection: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct QueuedEvent {
+    pub event: Event,
+    pub connection: Option<String>,
+}
+
 // Event storage in Resources
 #[derive(Default)]
 pub struct EventQueues {
-    pub events: Vec<Event>,
+    pub events: Vec<QueuedEvent>,
+
+    /// Events received from remote peers, which are never forwarded back to the server
+    pub remote_events: Vec<QueuedEvent>,
+
+    /// The connection that sent the command being executed.
+    /// Events published while it executes are addressed back to that connection.
+    pub connection: Option<String>,
 }
 
-// Public API - Just two functions
+// Public API
 pub fn publish_command(context: &mut Context, command: Command) {
-    context.resources.commands.push(command);
+    context.resources.commands.push(QueuedCommand {
+        command,
+        connection: None,
+    });
 }
 
 pub fn publish_event(context: &mut Context, event: Event) {
-    context.resources.events.events.push(event);
+    let connection = context.resources.events.connection.clone();
+    context
+        .resources
+        .events
+        .events
+        .push(QueuedEvent { event, connection });
+}
+
+/// Queues a command received from a named RPC connection
+pub fn publish_remote_command(context: &mut Context, connection: &str, command: Command) {
+    context.resources.commands.push(QueuedCommand {
+        command,
+        connection: Some(connection.to_string()),
+    });
+}
+
+/// Publishes an event addressed to the connection that caused it,
+/// for work that finishes after the command that started it
+pub fn publish_event_to(context: &mut Context, connection: Option<String>, event: Event) {
+    context
+        .resources
+        .events
+        .events
+        .push(QueuedEvent { event, connection });
+}
+
+/// Publishes an event concerning a named RPC connection
+pub fn publish_connection_event(context: &mut Context, connection: &str, event: Event) {
+    context.resources.events.events.push(QueuedEvent {
+        event,
+        connection: Some(connection.to_string()),
+    });
 }
 
 // System for processing commands
 pub fn execute_commands_system(context: &mut Context) {
     let commands = std::mem::take(&mut context.resources.commands);
-    for command in commands {
+    for QueuedCommand {
+        command,
+        connection,
+    } in commands
+    {
         log::info!("[Command] {command:?}");
-        context.resources.user_interface.api_log.push(ApiLogEntry {
-            message: Message::Command {
-                command: command.clone(),
+        push_api_log_entry(
+            context,
+            ApiLogEntry {
+                message: Message::Command {
+                    command: command.clone(),
+                },
+                connection: connection.clone(),
             },
-        });
+        );
+        context.resources.events.connection = connection;
         execute_command(context, command);
     }
+    context.resources.events.connection = None;
 }
 
 // System for processing events
 pub fn process_events_system(context: &mut Context) {
     let events = std::mem::take(&mut context.resources.events.events);
-    events.into_iter().for_each(|event| {
-        log::info!("[Event] {event:?}");
-        context.resources.user_interface.api_log.push(ApiLogEntry {
-            message: Message::Event {
-                event: event.clone(),
-            },
+    forward_events(context, &events);
+    let remote_events = std::mem::take(&mut context.resources.events.remote_events);
+    crate::run::forward_host_events(context, &events);
+    crate::run::forward_host_events(context, &remote_events);
+    events
+        .into_iter()
+        .chain(remote_events)
+        .for_each(|QueuedEvent { event, connection }| {
+            log::info!("[Event] {event:?}");
+            push_api_log_entry(
+                context,
+                ApiLogEntry {
+                    message: Message::Event { event },
+                    connection,
+                },
+            );
         });
-    });
+}
+
+/// The API log only keeps the most recent entries, since messages can carry large buffers
+const MAX_API_LOG_ENTRIES: usize = 1024;
+
+fn push_api_log_entry(context: &mut Context, entry: ApiLogEntry) {
+    let api_log = &mut context.resources.user_interface.api_log;
+    api_log.push(entry);
+    if api_log.len() > MAX_API_LOG_ENTRIES {
+        let excess = api_log.len() - MAX_API_LOG_ENTRIES;
+        api_log.drain(..excess);
+    }
 }
 
 fn execute_command(context: &mut Context, command: Command) {
     match command {
-        Command::Spawn { command } => execute_spawn_command(context, command),
+        Command::Spawn { id, command } => execute_spawn_command(context, id, command),
+        Command::Despawn { entity_id } => {
+            if component_mask(context, entity_id).is_none() {
+                publish_entity_not_found(context, entity_id);
+                return;
+            }
+            despawn_hierarchy(context, entity_id);
+            publish_event(
+                context,
+                Event::Report {
+                    report: Report::EntityDespawned { entity_id },
+                },
+            );
+        }
+        Command::SetTransform {
+            entity_id,
+            transform,
+        } => {
+            if !add_components(context, entity_id, LOCAL_TRANSFORM | GLOBAL_TRANSFORM) {
+                publish_entity_not_found(context, entity_id);
+                return;
+            }
+            if let Some(local_transform) =
+                get_component_mut::<LocalTransform>(context, entity_id, LOCAL_TRANSFORM)
+            {
+                *local_transform = transform.into();
+            }
+        }
+        Command::SetLines { entity_id, lines } => {
+            if !add_components(context, entity_id, LINES) {
+                publish_entity_not_found(context, entity_id);
+                return;
+            }
+            if let Some(Lines(entity_lines)) = get_component_mut::<Lines>(context, entity_id, LINES)
+            {
+                *entity_lines = lines.into_iter().map(Into::into).collect();
+            }
+        }
+        Command::SetQuads { entity_id, quads } => {
+            if !add_components(context, entity_id, QUADS) {
+                publish_entity_not_found(context, entity_id);
+                return;
+            }
+            if let Some(Quads(entity_quads)) = get_component_mut::<Quads>(context, entity_id, QUADS)
+            {
+                *entity_quads = quads.into_iter().map(Into::into).collect();
+            }
+        }
+        Command::SetLineBuffer { entity_id, buffer } => {
+            if !add_components(context, entity_id, LINES) {
+                publish_entity_not_found(context, entity_id);
+                return;
+            }
+            if let Some(lines) = get_component_mut::<Lines>(context, entity_id, LINES) {
+                *lines = (&buffer).into();
+            }
+        }
+        Command::SetQuadBuffer { entity_id, buffer } => {
+            if !add_components(context, entity_id, QUADS) {
+                publish_entity_not_found(context, entity_id);
+                return;
+            }
+            if let Some(quads) = get_component_mut::<Quads>(context, entity_id, QUADS) {
+                *quads = (&buffer).into();
+            }
+        }
+        Command::SetMesh { entity_id, mesh } => {
+            if !add_components(context, entity_id, MESH) {
+                publish_entity_not_found(context, entity_id);
+                return;
+            }
+            if let Some(entity_mesh) = get_component_mut::<Mesh>(context, entity_id, MESH) {
+                *entity_mesh = (&mesh).into();
+            }
+        }
+        Command::SetPoints { entity_id, points } => {
+            if !add_components(context, entity_id, POINTS) {
+                publish_entity_not_found(context, entity_id);
+                return;
+            }
+            if let Some(entity_points) = get_component_mut::<Points>(context, entity_id, POINTS) {
+                *entity_points = (&points).into();
+            }
+        }
+        Command::SetPolyline {
+            entity_id,
+            polyline,
+        } => {
+            if !add_components(context, entity_id, POLYLINE) {
+                publish_entity_not_found(context, entity_id);
+                return;
+            }
+            if let Some(entity_polyline) =
+                get_component_mut::<Polyline>(context, entity_id, POLYLINE)
+            {
+                *entity_polyline = (&polyline).into();
+            }
+        }
+        Command::SetLabel { entity_id, label } => {
+            if !add_components(context, entity_id, LABEL) {
+                publish_entity_not_found(context, entity_id);
+                return;
+            }
+            if let Some(entity_label) =
+                get_component_mut::<crate::context::Label>(context, entity_id, LABEL)
+            {
+                *entity_label = label.into();
+            }
+        }
         Command::Rpc { command } => {
             execute_rpc_command(context, command);
         }
         Command::Query { id, command } => execute_query_command(context, id, command),
+        Command::Capture { id, target, output } => request_capture(context, id, target, output),
+        Command::Record { command } => execute_record_command(context, command),
+        Command::SetPaneSettings { index, settings } => {
+            if !crate::ui::set_pane_settings(context, index, settings) {
+                publish_event(
+                    context,
+                    Event::Report {
+                        report: Report::PaneNotFound { index },
+                    },
+                );
+            }
+        }
+        Command::Import { id, source } => execute_import_command(context, id, source),
         Command::Empty => {}
     }
 }
 
-fn execute_spawn_command(context: &mut Context, spawn_command: SpawnCommand) {
+fn execute_import_command(context: &mut Context, id: u64, source: ImportSource) {
+    let result = match source {
+        ImportSource::Empty => return,
+        #[cfg(not(target_arch = "wasm32"))]
+        ImportSource::File { path } => crate::import::import_file(context, path),
+        #[cfg(target_arch = "wasm32")]
+        ImportSource::File { .. } => {
+            Err("Files cannot be read in a browser, send their bytes instead".to_string())
+        }
+        ImportSource::Bytes { name, bytes } => {
+            crate::import::import_bytes(context, &name, &bytes.0, None)
+        }
+    };
+    let report = match result {
+        Ok(root) => {
+            // Imports from a connection join its scene, while local imports are new scenes
+            if context.resources.events.connection.is_some() {
+                attach_to_connection(context, root);
+            } else {
+                crate::import::add_framing_camera(context, root);
+            }
+            Report::Imported { id, root }
+        }
+        Err(error) => {
+            log::error!("Failed to import a model: {error}");
+            Report::ImportFailed { id, error }
+        }
+    };
+    publish_event(context, Event::Report { report });
+}
+
+/// Camera captures are rendered offscreen right away,
+/// while window and pane captures are read back once the next frame is rendered
+fn request_capture(context: &mut Context, id: u64, target: CaptureTarget, output: CaptureOutput) {
+    let connection = context.resources.events.connection.clone();
+    if cfg!(target_arch = "wasm32") {
+        let error = "Captures are not supported on the web".to_string();
+        publish_capture(context, id, output, connection, Err(error));
+        return;
+    }
+    match target {
+        #[cfg(not(target_arch = "wasm32"))]
+        CaptureTarget::Camera {
+            camera_entity,
+            width,
+            height,
+        } => {
+            let image =
+                crate::graphics::render_camera_offscreen(context, camera_entity, width, height);
+            publish_capture(context, id, output, connection, image);
+        }
+        _ if context
+            .resources
+            .graphics
+            .renderer
+            .as_ref()
+            .is_some_and(|renderer| renderer.gpu.surface.is_none()) =>
+        {
+            let error = "Headless renderers can only capture cameras".to_string();
+            publish_capture(context, id, output, connection, Err(error));
+        }
+        target => {
+            context
+                .resources
+                .graphics
+                .pending_captures
+                .push(crate::graphics::PendingCapture {
+                    id,
+                    target,
+                    output,
+                    connection,
+                })
+        }
+    }
+}
+
+/// Encodes a captured image and reports it to the connection that requested it
+pub fn publish_capture(
+    context: &mut Context,
+    id: u64,
+    output: CaptureOutput,
+    connection: Option<String>,
+    image: Result<crate::graphics::CapturedImage, String>,
+) {
+    let report = image
+        .and_then(|image| match output {
+            CaptureOutput::Bytes => Ok(Report::CaptureEncoded {
+                id,
+                image: PngImage {
+                    width: image.width,
+                    height: image.height,
+                    bytes: image.to_png()?.into(),
+                },
+            }),
+            CaptureOutput::File { path } => {
+                image.save_png(&path)?;
+                Ok(Report::CaptureSaved { id, path })
+            }
+        })
+        .unwrap_or_else(|error| Report::CaptureFailed { id, error });
+    publish_event_to(context, connection, Event::Report { report });
+}
+
+fn execute_record_command(context: &mut Context, command: RecordCommand) {
+    match command {
+        RecordCommand::Start {
+            id,
+            target,
+            directory,
+            frames_per_second,
+            frame_count,
+        } => {
+            let connection = context.resources.events.connection.clone();
+            let result = if cfg!(target_arch = "wasm32") {
+                Err("Recording is not supported on the web".to_string())
+            } else if context.resources.graphics.recording.is_some() {
+                Err("A recording is already in progress".to_string())
+            } else if matches!(target, CaptureTarget::Window) {
+                Err("Recordings capture a pane or a camera".to_string())
+            } else if !(frames_per_second.is_finite() && frames_per_second > 0.0) {
+                Err("Recordings need a positive frame rate".to_string())
+            } else {
+                std::fs::create_dir_all(&directory)
+                    .map_err(|error| format!("Failed to create {directory}: {error}"))
+            };
+            if let Err(error) = result {
+                let report = Report::RecordingFailed { id, error };
+                publish_event_to(context, connection, Event::Report { report });
+                return;
+            }
+            context.resources.graphics.recording = Some(crate::graphics::Recording {
+                id,
+                target,
+                directory: directory.clone(),
+                frames: 0,
+                frame_count,
+                connection,
+            });
+            context.resources.window.fixed_delta_time = Some(1.0 / frames_per_second);
+            publish_event(
+                context,
+                Event::Report {
+                    report: Report::RecordingStarted { id, directory },
+                },
+            );
+        }
+        RecordCommand::Stop => finish_recording(context, Ok(())),
+        RecordCommand::Empty => {}
+    }
+}
+
+/// Ends the active recording, if there is one, and returns to real time
+pub fn finish_recording(context: &mut Context, result: Result<(), String>) {
+    let Some(crate::graphics::Recording {
+        id,
+        directory,
+        frames,
+        connection,
+        ..
+    }) = context.resources.graphics.recording.take()
+    else {
+        return;
+    };
+    context.resources.window.fixed_delta_time = None;
+    let report = match result {
+        Ok(()) => Report::RecordingFinished {
+            id,
+            directory,
+            frames,
+        },
+        Err(error) => Report::RecordingFailed { id, error },
+    };
+    publish_event_to(context, connection, Event::Report { report });
+}
+
+fn execute_spawn_command(context: &mut Context, id: u64, spawn_command: SpawnCommand) {
     match spawn_command {
         SpawnCommand::Empty => {}
         SpawnCommand::Cube {
@@ -190,19 +1873,40 @@ fn execute_spawn_command(context: &mut Context, spawn_command: SpawnCommand) {
             name,
         } => {
             let entity = spawn_cube(context, position.into(), size, name);
+            attach_to_connection(context, entity);
             publish_event(
                 context,
                 Event::Report {
-                    report: Report::EntityCreated { entity_id: entity },
+                    report: Report::EntityCreated {
+                        id,
+                        entity_id: entity,
+                    },
                 },
             );
         }
         SpawnCommand::Camera { position, name } => {
             let entity = spawn_camera(context, position.into(), name);
+            attach_to_connection(context, entity);
+            publish_event(
+                context,
+                Event::Report {
+                    report: Report::EntityCreated {
+                        id,
+                        entity_id: entity,
+                    },
+                },
+            );
+        }
+        SpawnCommand::Entity { position, name } => {
+            let entity = spawn_node(context, position.into(), name);
+            attach_to_connection(context, entity);
             publish_event(
                 context,
                 Event::Report {
-                    report: Report::EntityCreated { entity_id: entity },
+                    report: Report::EntityCreated {
+                        id,
+                        entity_id: entity,
+                    },
                 },
             );
         }
@@ -221,11 +1925,110 @@ fn execute_query_command(context: &mut Context, id: u64, query_command: QueryCom
                 },
             );
         }
+        QueryCommand::ListEntities => {
+            let entities = query_entities(context, NAME)
+                .into_iter()
+                .map(|entity_id| EntitySummary {
+                    entity_id,
+                    name: get_component::<Name>(context, entity_id, NAME)
+                        .map(|Name(name)| name.clone())
+                        .unwrap_or_default(),
+                    parent: get_component::<Parent>(context, entity_id, PARENT)
+                        .map(|Parent(parent)| *parent),
+                })
+                .collect();
+            publish_event(
+                context,
+                Event::QueryResult {
+                    id,
+                    result: QueryResult::EntityList { entities },
+                },
+            );
+        }
+        QueryCommand::GetTransform { entity_id } => {
+            let result = match get_component::<LocalTransform>(context, entity_id, LOCAL_TRANSFORM)
+            {
+                Some(local_transform) => QueryResult::Transform {
+                    entity_id,
+                    transform: (*local_transform).into(),
+                },
+                None => QueryResult::NotFound { entity_id },
+            };
+            publish_event(context, Event::QueryResult { id, result });
+        }
+        QueryCommand::GetLineBuffer { entity_id } => {
+            let result = match get_component::<Lines>(context, entity_id, LINES) {
+                Some(lines) => QueryResult::LineBuffer {
+                    entity_id,
+                    buffer: lines.into(),
+                },
+                None => QueryResult::NotFound { entity_id },
+            };
+            publish_event(context, Event::QueryResult { id, result });
+        }
+        QueryCommand::GetQuadBuffer { entity_id } => {
+            let result = match get_component::<Quads>(context, entity_id, QUADS) {
+                Some(quads) => QueryResult::QuadBuffer {
+                    entity_id,
+                    buffer: quads.into(),
+                },
+                None => QueryResult::NotFound { entity_id },
+            };
+            publish_event(context, Event::QueryResult { id, result });
+        }
+        QueryCommand::GetMesh { entity_id } => {
+            let result = match get_component::<Mesh>(context, entity_id, MESH) {
+                Some(mesh) => QueryResult::Mesh {
+                    entity_id,
+                    mesh: mesh.into(),
+                },
+                None => QueryResult::NotFound { entity_id },
+            };
+            publish_event(context, Event::QueryResult { id, result });
+        }
+        QueryCommand::GetPoints { entity_id } => {
+            let result = match get_component::<Points>(context, entity_id, POINTS) {
+                Some(points) => QueryResult::Points {
+                    entity_id,
+                    points: points.into(),
+                },
+                None => QueryResult::NotFound { entity_id },
+            };
+            publish_event(context, Event::QueryResult { id, result });
+        }
+        QueryCommand::GetPolyline { entity_id } => {
+            let result = match get_component::<Polyline>(context, entity_id, POLYLINE) {
+                Some(polyline) => QueryResult::Polyline {
+                    entity_id,
+                    polyline: polyline.into(),
+                },
+                None => QueryResult::NotFound { entity_id },
+            };
+            publish_event(context, Event::QueryResult { id, result });
+        }
+        QueryCommand::GetLabel { entity_id } => {
+            let result = match get_component::<crate::context::Label>(context, entity_id, LABEL) {
+                Some(label) => QueryResult::Label {
+                    entity_id,
+                    label: label.into(),
+                },
+                None => QueryResult::NotFound { entity_id },
+            };
+            publish_event(context, Event::QueryResult { id, result });
+        }
         QueryCommand::Empty => {}
     }
 }
 
 // Helper functions
+
+/// Entities spawned by a remote command go into the scene root of the connection that sent it
+fn attach_to_connection(context: &mut Context, entity: EntityId) {
+    if let Some(connection) = context.resources.events.connection.clone() {
+        attach_to_scene_root(context, &connection, entity);
+    }
+}
+
 fn spawn_cube(
     context: &mut Context,
     position: nalgebra_glm::Vec3,
@@ -259,6 +2062,44 @@ fn spawn_cube(
     entity
 }
 
+fn spawn_node(context: &mut Context, position: nalgebra_glm::Vec3, name: String) -> EntityId {
+    let entity = spawn_entities(context, LOCAL_TRANSFORM | GLOBAL_TRANSFORM | NAME, 1)[0];
+
+    if let Some(name_comp) = get_component_mut::<Name>(context, entity, NAME) {
+        *name_comp = Name(name);
+    }
+
+    if let Some(transform) = get_component_mut::<LocalTransform>(context, entity, LOCAL_TRANSFORM) {
+        transform.translation = position;
+    }
+
+    entity
+}
+
+fn publish_entity_not_found(context: &mut Context, entity_id: EntityId) {
+    publish_event(
+        context,
+        Event::Report {
+            report: Report::EntityNotFound { entity_id },
+        },
+    );
+}
+
+fn despawn_hierarchy(context: &mut Context, entity: EntityId) {
+    let descendents = query_descendents(context, entity);
+    despawn_entities(context, &descendents);
+    if let Some(camera) = context.resources.active_camera_entity {
+        if descendents.contains(&camera) {
+            context.resources.active_camera_entity = None;
+        }
+    }
+    if let Some(selected) = context.resources.user_interface.selected_entity {
+        if descendents.contains(&selected) {
+            context.resources.user_interface.selected_entity = None;
+        }
+    }
+}
+
 fn spawn_camera(context: &mut Context, position: nalgebra_glm::Vec3, name: String) -> EntityId {
     let entity = spawn_entities(
         context,
@@ -281,3 +2122,90 @@ fn spawn_camera(context: &mut Context, position: nalgebra_glm::Vec3, name: Strin
 
     entity
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entity(id: u32) -> EntityId {
+        EntityId { id, generation: 1 }
+    }
+
+    #[test]
+    fn line_buffer_commands_round_trip_as_binary() {
+        let message = Message::Command {
+            command: Command::SetLineBuffer {
+                entity_id: entity(3),
+                buffer: LineBuffer {
+                    starts: [0.0, 1.0, 2.0, 3.0, 4.0, 5.0].into(),
+                    ends: [6.0, 7.0, 8.0, 9.0, 10.0, 11.0].into(),
+                    colors: [1.0, 0.5, 0.25, 1.0].into(),
+                    widths: [2.0, 3.0].into(),
+                    dashes: [].into(),
+                    cap: LineCap::Round,
+                },
+            },
+        };
+        assert!(message.is_bulk());
+        let bytes = message.to_bytes().unwrap();
+        let decoded = Message::from_bytes(&bytes).unwrap();
+        let Message::Command {
+            command: Command::SetLineBuffer { entity_id, buffer },
+        } = &decoded
+        else {
+            panic!("Decoded the wrong message: {decoded:?}");
+        };
+        assert_eq!(*entity_id, entity(3));
+        assert_eq!(&*buffer.starts, &[0.0, 1.0, 2.0, 3.0, 4.0, 5.0]);
+        assert_eq!(&*buffer.ends, &[6.0, 7.0, 8.0, 9.0, 10.0, 11.0]);
+        assert_eq!(&*buffer.colors, &[1.0, 0.5, 0.25, 1.0]);
+        assert_eq!(&*buffer.widths, &[2.0, 3.0]);
+        assert!(buffer.dashes.is_empty());
+        assert_eq!(buffer.cap, LineCap::Round);
+        assert_eq!(decoded.to_bytes().unwrap(), bytes);
+    }
+
+    #[test]
+    fn query_results_round_trip_as_binary() {
+        let message = Message::Event {
+            event: Event::QueryResult {
+                id: 42,
+                result: QueryResult::Points {
+                    entity_id: entity(7),
+                    points: PointBuffer {
+                        positions: [0.0, 0.5, 1.0].into(),
+                        scalars: [0.25].into(),
+                        scalar_range: Some(Vec2 { x: 0.0, y: 1.0 }),
+                        size: 3.0,
+                        sizing: PointSizing::Screen,
+                        shape: PointShape::Square,
+                        ..Default::default()
+                    },
+                },
+            },
+        };
+        assert!(message.is_bulk());
+        let bytes = message.to_bytes().unwrap();
+        let decoded = Message::from_bytes(&bytes).unwrap();
+        let Message::Event {
+            event:
+                Event::QueryResult {
+                    id,
+                    result: QueryResult::Points { entity_id, points },
+                },
+        } = &decoded
+        else {
+            panic!("Decoded the wrong message: {decoded:?}");
+        };
+        assert_eq!(*id, 42);
+        assert_eq!(*entity_id, entity(7));
+        assert_eq!(&*points.positions, &[0.0, 0.5, 1.0]);
+        assert!(points.colors.is_empty());
+        assert_eq!(&*points.scalars, &[0.25]);
+        assert!(matches!(points.scalar_range, Some(Vec2 { x: 0.0, y: 1.0 })));
+        assert_eq!(points.size, 3.0);
+        assert_eq!(points.sizing, PointSizing::Screen);
+        assert_eq!(points.shape, PointShape::Square);
+        assert_eq!(decoded.to_bytes().unwrap(), bytes);
+    }
+}
@@ -1,5 +1,4 @@
-use crate::api::EventQueues;
-use crate::prelude::*;
+use crate::api::{EventQueues, QueuedCommand};
 use crate::*;
 
 crate::ecs! {
@@ -9,6 +8,10 @@ crate::ecs! {
         local_transform: LocalTransform => LOCAL_TRANSFORM,
         lines: Lines => LINES,
         quads: Quads => QUADS,
+        mesh: Mesh => MESH,
+        points: Points => POINTS,
+        polyline: Polyline => POLYLINE,
+        label: Label => LABEL,
         name: Name => NAME,
         parent: Parent => PARENT,
     }
@@ -19,8 +22,9 @@ crate::ecs! {
         input: input::Input,
         user_interface: ui::UserInterface,
         active_camera_entity: Option<EntityId>,
-        commands: Vec<Command>,
+        commands: Vec<QueuedCommand>,
         events: EventQueues,
+        host: run::Host,
     }
 }
 
@@ -107,6 +111,26 @@ pub struct Line {
     pub start: nalgebra_glm::Vec3,
     pub end: nalgebra_glm::Vec3,
     pub color: nalgebra_glm::Vec4,
+
+    /// The width in pixels, which stays the same at any distance
+    pub width: f32,
+
+    /// The lengths of each dash and the gap after it in pixels, or `None` for a solid line
+    pub dash: Option<nalgebra_glm::Vec2>,
+    pub cap: crate::api::LineCap,
+}
+
+impl Default for Line {
+    fn default() -> Self {
+        Self {
+            start: nalgebra_glm::Vec3::zeros(),
+            end: nalgebra_glm::Vec3::zeros(),
+            color: nalgebra_glm::vec4(1.0, 1.0, 1.0, 1.0),
+            width: 1.0,
+            dash: None,
+            cap: crate::api::LineCap::Butt,
+        }
+    }
 }
 
 #[derive(Default, Debug, Clone)]
@@ -119,6 +143,223 @@ pub struct Quad {
     pub color: nalgebra_glm::Vec4,
 }
 
+/// A triangle mesh in the local space of its entity.
+/// Normals, UVs and colors each hold one element per position, or are empty when absent.
+#[derive(Debug, Clone)]
+pub struct Mesh {
+    pub positions: Vec<nalgebra_glm::Vec3>,
+    pub normals: Vec<nalgebra_glm::Vec3>,
+    pub uvs: Vec<nalgebra_glm::Vec2>,
+    pub colors: Vec<nalgebra_glm::Vec4>,
+
+    /// Three indices into the vertex attributes per triangle
+    pub indices: Vec<u32>,
+
+    /// Multiplied with the vertex colors, or used alone when there are none
+    pub color: nalgebra_glm::Vec4,
+
+    /// The color of the triangle edges drawn over the shaded surface, if any
+    pub wireframe: Option<nalgebra_glm::Vec4>,
+}
+
+impl Default for Mesh {
+    fn default() -> Self {
+        Self {
+            positions: Vec::new(),
+            normals: Vec::new(),
+            uvs: Vec::new(),
+            colors: Vec::new(),
+            indices: Vec::new(),
+            color: nalgebra_glm::vec4(1.0, 1.0, 1.0, 1.0),
+            wireframe: None,
+        }
+    }
+}
+
+impl Mesh {
+    /// The triangles of the mesh, skipping any that index past the positions
+    pub fn triangles(&self) -> impl Iterator<Item = [usize; 3]> + '_ {
+        self.indices
+            .chunks_exact(3)
+            .map(|triangle| {
+                [
+                    triangle[0] as usize,
+                    triangle[1] as usize,
+                    triangle[2] as usize,
+                ]
+            })
+            .filter(|triangle| triangle.iter().all(|index| *index < self.positions.len()))
+    }
+
+    /// Computes smooth vertex normals, weighting each triangle by its area
+    pub fn smooth_normals(&self) -> Vec<nalgebra_glm::Vec3> {
+        let mut normals = vec![nalgebra_glm::Vec3::zeros(); self.positions.len()];
+        self.triangles().for_each(|[a, b, c]| {
+            let normal = (self.positions[b] - self.positions[a])
+                .cross(&(self.positions[c] - self.positions[a]));
+            [a, b, c].iter().for_each(|index| normals[*index] += normal);
+        });
+        normals
+            .into_iter()
+            .map(|normal| normal.try_normalize(f32::EPSILON).unwrap_or(normal))
+            .collect()
+    }
+}
+
+/// A connected strip of line segments through shared vertices, in the local space of its entity.
+/// The arrays are shared, so the renderer only uploads them again after they are replaced.
+#[derive(Debug, Clone)]
+pub struct Polyline {
+    pub points: std::sync::Arc<[nalgebra_glm::Vec3]>,
+
+    /// One color per point, blended along each segment, or empty to use the polyline color alone
+    pub colors: std::sync::Arc<[nalgebra_glm::Vec4]>,
+
+    /// Multiplied with the point colors
+    pub color: nalgebra_glm::Vec4,
+
+    /// The width in pixels, which stays the same at any distance
+    pub width: f32,
+}
+
+impl Default for Polyline {
+    fn default() -> Self {
+        Self {
+            points: std::sync::Arc::new([]),
+            colors: std::sync::Arc::new([]),
+            color: nalgebra_glm::vec4(1.0, 1.0, 1.0, 1.0),
+            width: 1.0,
+        }
+    }
+}
+
+/// Text anchored at a point of its entity
+#[derive(Debug, Clone)]
+pub struct Label {
+    pub text: String,
+
+    /// The point the text is centered above, in the local space of its entity
+    pub offset: nalgebra_glm::Vec3,
+    pub mode: crate::api::LabelMode,
+
+    /// The height of a line of text, in pixels for billboards or world units otherwise
+    pub size: f32,
+    pub color: nalgebra_glm::Vec4,
+
+    /// The color of the panel drawn behind the text, if any
+    pub background: Option<nalgebra_glm::Vec4>,
+
+    /// The color of the line drawn from the origin of the entity to the text, if any
+    pub leader: Option<nalgebra_glm::Vec4>,
+}
+
+impl Default for Label {
+    fn default() -> Self {
+        Self {
+            text: String::new(),
+            offset: nalgebra_glm::Vec3::zeros(),
+            mode: crate::api::LabelMode::Billboard,
+            size: 16.0,
+            color: nalgebra_glm::vec4(1.0, 1.0, 1.0, 1.0),
+            background: None,
+            leader: None,
+        }
+    }
+}
+
+/// Points drawn as camera facing sprites, in the local space of their entity.
+/// The arrays are shared, so the renderer only uploads them again after they are replaced.
+#[derive(Debug, Clone)]
+pub struct Points {
+    pub positions: std::sync::Arc<[nalgebra_glm::Vec3]>,
+
+    /// One color per point, or empty to color the points by their scalars
+    pub colors: std::sync::Arc<[nalgebra_glm::Vec4]>,
+
+    /// One value per point mapped through a colormap, used when there are no colors
+    pub scalars: std::sync::Arc<[f32]>,
+
+    /// The scalars mapped to the ends of the colormap, or the range of the scalars when absent
+    pub scalar_range: Option<nalgebra_glm::Vec2>,
+
+    /// Multiplied with the point colors, or used alone when there are none
+    pub color: nalgebra_glm::Vec4,
+
+    /// The diameter of a point, in pixels or world units depending on the sizing
+    pub size: f32,
+    pub sizing: crate::api::PointSizing,
+    pub shape: crate::api::PointShape,
+}
+
+impl Default for Points {
+    fn default() -> Self {
+        Self {
+            positions: std::sync::Arc::new([]),
+            colors: std::sync::Arc::new([]),
+            scalars: std::sync::Arc::new([]),
+            scalar_range: None,
+            color: nalgebra_glm::vec4(1.0, 1.0, 1.0, 1.0),
+            size: 4.0,
+            sizing: crate::api::PointSizing::Screen,
+            shape: crate::api::PointShape::Round,
+        }
+    }
+}
+
+impl Points {
+    /// The color of every point before the point color is applied,
+    /// taken from the colors or the scalars, or white when there are neither
+    pub fn point_colors(&self) -> Vec<nalgebra_glm::Vec4> {
+        let white = nalgebra_glm::vec4(1.0, 1.0, 1.0, 1.0);
+        if self.colors.len() == self.positions.len() {
+            return self.colors.to_vec();
+        }
+        if self.scalars.len() != self.positions.len() {
+            return vec![white; self.positions.len()];
+        }
+        let range = self.scalar_range.unwrap_or_else(|| {
+            let (min, max) = self
+                .scalars
+                .iter()
+                .filter(|value| value.is_finite())
+                .fold((f32::MAX, f32::MIN), |(min, max), value| {
+                    (min.min(*value), max.max(*value))
+                });
+            nalgebra_glm::vec2(min, max)
+        });
+        let extent = range.y - range.x;
+        self.scalars
+            .iter()
+            .map(|value| {
+                let t = if extent > 0.0 {
+                    (value - range.x) / extent
+                } else {
+                    0.5
+                };
+                colormap(t)
+            })
+            .collect()
+    }
+}
+
+/// Maps a value between zero and one onto a perceptually uniform blue to yellow colormap
+pub fn colormap(t: f32) -> nalgebra_glm::Vec4 {
+    const STOPS: [[f32; 3]; 5] = [
+        [0.267, 0.005, 0.329],
+        [0.229, 0.322, 0.546],
+        [0.128, 0.567, 0.551],
+        [0.369, 0.789, 0.383],
+        [0.993, 0.906, 0.144],
+    ];
+    let t = if t.is_nan() { 0.0 } else { t.clamp(0.0, 1.0) };
+    let position = t * (STOPS.len() - 1) as f32;
+    let index = (position as usize).min(STOPS.len() - 2);
+    let start = nalgebra_glm::make_vec3(&STOPS[index]);
+    let end = nalgebra_glm::make_vec3(&STOPS[index + 1]);
+    let color = nalgebra_glm::lerp(&start, &end, position - index as f32);
+    nalgebra_glm::vec4(color.x, color.y, color.z, 1.0)
+}
+
 #[derive(Debug, Clone)]
 pub struct Camera {
     pub projection: Projection,
@@ -301,19 +542,17 @@ pub fn query_active_camera_matrices(context: &Context) -> Option<CameraMatrices>
 }
 
 pub fn query_camera_matrices(context: &Context, camera_entity: EntityId) -> Option<CameraMatrices> {
-    let (Some(camera), Some(local_transform), Some(global_transform)) = (
+    let (Some(camera), Some(global_transform)) = (
         get_component::<Camera>(context, camera_entity, CAMERA),
-        get_component::<LocalTransform>(context, camera_entity, LOCAL_TRANSFORM),
         get_component::<GlobalTransform>(context, camera_entity, GLOBAL_TRANSFORM),
     ) else {
         return None;
     };
 
-    let normalized_rotation = local_transform.rotation.normalize();
+    // The global orientation is used, since cameras may be nested under rotated parents
     let camera_translation = global_transform.0.column(3).xyz();
-    let target = camera_translation
-        + nalgebra_glm::quat_rotate_vec3(&normalized_rotation, &(-nalgebra_glm::Vec3::z()));
-    let up = nalgebra_glm::quat_rotate_vec3(&normalized_rotation, &nalgebra_glm::Vec3::y());
+    let target = camera_translation + global_transform.forward_vector().normalize();
+    let up = global_transform.up_vector().normalize();
 
     let aspect_ratio = window::query_viewport_aspect_ratio(context).unwrap_or(4.0 / 3.0);
 
@@ -334,28 +573,34 @@ pub fn initialize_camera_transform(context: &mut Context, camera_entity: EntityI
     if let Some(local_transform) =
         get_component_mut::<LocalTransform>(context, camera_entity, LOCAL_TRANSFORM)
     {
-        // Set a default position offset from origin
+        // Set a default position offset from origin, looking at the origin
         local_transform.translation = nalgebra_glm::vec3(0.0, 4.0, 5.0);
-
-        // Ensure rotation is looking at origin with proper up vector
-        let camera_pos = local_transform.translation;
-        let target = nalgebra_glm::Vec3::zeros();
-        let up = nalgebra_glm::Vec3::y();
-
-        // Calculate rotation to look at target
-        let forward = nalgebra_glm::normalize(&(target - camera_pos));
-        let right = nalgebra_glm::normalize(&nalgebra_glm::cross(&up, &forward));
-        let new_up = nalgebra_glm::cross(&forward, &right);
-
-        // Convert to quaternion
-        let rotation_mat = nalgebra_glm::mat3(
-            right.x, new_up.x, -forward.x, right.y, new_up.y, -forward.y, right.z, new_up.z,
-            -forward.z,
-        );
-        local_transform.rotation = nalgebra_glm::mat3_to_quat(&rotation_mat);
+        local_transform.rotation =
+            look_at_rotation(&local_transform.translation, &nalgebra_glm::Vec3::zeros());
     }
 }
 
+/// The rotation that points the forward vector of an entity at a target, keeping +Y up
+pub fn look_at_rotation(
+    position: &nalgebra_glm::Vec3,
+    target: &nalgebra_glm::Vec3,
+) -> nalgebra_glm::Quat {
+    let forward = nalgebra_glm::normalize(&(target - position));
+
+    // Looking straight up or down leaves the right vector undefined, so +X is used instead
+    let right = forward
+        .cross(&nalgebra_glm::Vec3::y())
+        .try_normalize(f32::EPSILON)
+        .unwrap_or_else(nalgebra_glm::Vec3::x);
+    let new_up = right.cross(&forward);
+
+    // Convert to quaternion
+    let rotation_mat = nalgebra_glm::mat3(
+        right.x, new_up.x, -forward.x, right.y, new_up.y, -forward.y, right.z, new_up.z, -forward.z,
+    );
+    nalgebra_glm::mat3_to_quat(&rotation_mat)
+}
+
 /// System that ensures all cameras have proper initialization
 pub fn ensure_camera_transform_system(context: &mut Context) {
     let camera_entities: Vec<_> = query_entities(context, CAMERA)
@@ -37,7 +37,18 @@ macro_rules! ecs {
 
         use enum2egui::GuiInspect;
         /// Entity ID, an index into storage and a generation counter to prevent stale references
-        #[derive(Default, Clone, Copy, Debug, Eq, PartialEq, Hash, enum2egui::Gui)]
+        #[derive(
+            Default,
+            Clone,
+            Copy,
+            Debug,
+            Eq,
+            PartialEq,
+            Hash,
+            enum2egui::Gui,
+            serde::Serialize,
+            serde::Deserialize,
+        )]
         pub struct EntityId {
             pub id: u32,
             pub generation: u32,
@@ -79,6 +90,9 @@ macro_rules! ecs {
             pub allocator: EntityAllocator,
             pub resources: $resources,
             table_edges: Vec<TableEdges>,
+
+            /// Counts the changes to each component, indexed like the component masks
+            change_ticks: [u64; COMPONENT_COUNT],
         }
 
         /// Resources
@@ -92,6 +106,9 @@ macro_rules! ecs {
         pub struct ComponentArrays {
             $(pub $name: Vec<$type>,)*
             pub entity_indices: Vec<EntityId>,
+
+            /// The change ticks of each entity's components when they last changed
+            pub change_ticks: Vec<[u64; COMPONENT_COUNT]>,
             pub mask: u32,
         }
 
@@ -101,6 +118,42 @@ macro_rules! ecs {
             remove_edges: [Option<usize>; COMPONENT_COUNT],
         }
 
+        /// Records a change to every component in the mask
+        fn mark_changed(context: &mut $context, mask: u32) {
+            for (index, ticks) in context.change_ticks.iter_mut().enumerate() {
+                if mask & (1 << index) != 0 {
+                    *ticks = ticks.wrapping_add(1);
+                }
+            }
+        }
+
+        #[allow(dead_code)]
+        /// A counter that changes whenever any component in the mask may have changed,
+        /// through mutable access, spawning, despawning, or adding and removing components.
+        /// Caches derived from components compare it to the value they were built from.
+        pub fn component_changes(context: &$context, mask: u32) -> u64 {
+            context
+                .change_ticks
+                .iter()
+                .enumerate()
+                .filter(|(index, _)| mask & (1 << index) != 0)
+                .fold(0, |sum, (_, ticks)| sum.wrapping_add(*ticks))
+        }
+
+        #[allow(dead_code)]
+        /// Like `component_changes`, but only changes when the components of one entity may have changed,
+        /// so caches built from many entities can tell which of them to rebuild
+        pub fn entity_changes(context: &$context, entity: EntityId, mask: u32) -> u64 {
+            let Some((table_index, array_index)) = get_location(&context.entity_locations, entity) else {
+                return 0;
+            };
+            context.tables[table_index].change_ticks[array_index]
+                .iter()
+                .enumerate()
+                .filter(|(index, _)| mask & (1 << index) != 0)
+                .fold(0, |sum, (_, ticks)| sum.wrapping_add(*ticks))
+        }
+
         fn get_component_index(mask: u32) -> Option<usize> {
             match mask {
                 $($mask => Some(Component::$mask as _),)*
@@ -113,6 +166,8 @@ macro_rules! ecs {
         pub fn spawn_entities(context: &mut $context, mask: u32, count: usize) -> Vec<EntityId> {
             let mut entities = Vec::with_capacity(count);
             let table_index = get_or_create_table(context, mask);
+            mark_changed(context, mask);
+            let change_ticks = context.change_ticks;
 
             context.tables[table_index].entity_indices.reserve(count);
 
@@ -128,6 +183,7 @@ macro_rules! ecs {
                 add_to_table(
                     &mut context.tables[table_index],
                     entity,
+                    change_ticks,
                     (
                         $(
                         if mask & $mask != 0 {
@@ -232,11 +288,40 @@ macro_rules! ecs {
         #[allow(dead_code)]
         /// Get a mutable reference to a specific component for an entity
         pub fn get_component_mut<T: 'static>(context: &mut $context, entity: EntityId, mask: u32) -> Option<&mut T> {
-            let (table_index, array_index) = get_location(&context.entity_locations, entity)?;
+            mark_component_changed(context, entity, mask);
+            get_component_mut_untracked(context, entity, mask)
+        }
+
+        #[allow(dead_code)]
+        /// Records a change to components of an entity that were edited
+        /// through `get_component_mut_untracked`
+        pub fn mark_component_changed(context: &mut $context, entity: EntityId, mask: u32) {
+            let Some((table_index, array_index)) = get_location(&context.entity_locations, entity) else {
+                return;
+            };
+            if context.tables[table_index].mask & mask == 0 {
+                return;
+            }
+            mark_changed(context, mask);
+            let change_ticks = context.change_ticks;
             let table = &mut context.tables[table_index];
-            if table.mask & mask == 0 {
+            for (index, ticks) in table.change_ticks[array_index].iter_mut().enumerate() {
+                if mask & (1 << index) != 0 {
+                    *ticks = change_ticks[index];
+                }
+            }
+        }
+
+        #[allow(dead_code)]
+        /// Like `get_component_mut`, but without recording a change,
+        /// for editors that usually leave the component as it is.
+        /// Callers must call `mark_component_changed` when they do change it.
+        pub fn get_component_mut_untracked<T: 'static>(context: &mut $context, entity: EntityId, mask: u32) -> Option<&mut T> {
+            let (table_index, array_index) = get_location(&context.entity_locations, entity)?;
+            if context.tables[table_index].mask & mask == 0 {
                 return None;
             }
+            let table = &mut context.tables[table_index];
 
             $(
                 if mask == $mask && std::any::TypeId::of::<T>() == std::any::TypeId::of::<$type>() {
@@ -289,6 +374,8 @@ macro_rules! ecs {
                     continue;
                 }
 
+                let table_mask = context.tables[table_idx].mask;
+                mark_changed(context, table_mask);
                 let table = &mut context.tables[table_idx];
                 let last_idx = table.entity_indices.len() - 1;
 
@@ -309,6 +396,7 @@ macro_rules! ecs {
                     }
                 )*
                 table.entity_indices.swap_remove(array_idx);
+                table.change_ticks.swap_remove(array_idx);
             }
 
             despawned
@@ -386,6 +474,7 @@ macro_rules! ecs {
                 }
             )*
             arrays.entity_indices.swap_remove(index);
+            arrays.change_ticks.swap_remove(index);
 
             swapped_entity
         }
@@ -397,8 +486,11 @@ macro_rules! ecs {
             from_index: usize,
             to_table: usize,
         ) {
+            let changed_mask = context.tables[from_table].mask | context.tables[to_table].mask;
+            mark_changed(context, changed_mask);
             let components = get_components(&context.tables[from_table], from_index);
-            add_to_table(&mut context.tables[to_table], entity, components);
+            let change_ticks = context.change_ticks;
+            add_to_table(&mut context.tables[to_table], entity, change_ticks, components);
             let new_index = context.tables[to_table].entity_indices.len() - 1;
             insert_location(&mut context.entity_locations, entity, (to_table, new_index));
 
@@ -491,6 +583,7 @@ macro_rules! ecs {
         fn add_to_table(
             arrays: &mut ComponentArrays,
             entity: EntityId,
+            change_ticks: [u64; COMPONENT_COUNT],
             components: ( $(Option<$type>,)* ),
         ) {
             let ($($name,)*) = components;
@@ -502,6 +595,7 @@ macro_rules! ecs {
                 }
             )*
             arrays.entity_indices.push(entity);
+            arrays.change_ticks.push(change_ticks);
         }
 
         fn get_or_create_table(context: &mut $context, mask: u32) -> usize {
@@ -0,0 +1,312 @@
+use crate::graphics::CapturedImage;
+
+/// Scenes rendered from fixed cameras and compared against reference images,
+/// loaded from a TOML file. Paths are relative to the directory of the suite file.
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GoldenSuite {
+    /// The largest difference in any color channel for a pixel to still match its reference
+    pub tolerance: u8,
+
+    /// The fraction of pixels allowed to differ before a case fails,
+    /// which absorbs small rasterization differences between drivers
+    pub max_mismatched_fraction: f32,
+
+    /// The directory holding the reference images, named after their case
+    pub references: std::path::PathBuf,
+
+    /// How every case is rendered, such as its environment and antialiasing
+    pub settings: crate::api::PaneSettings,
+
+    pub cases: Vec<GoldenCase>,
+}
+
+impl Default for GoldenSuite {
+    fn default() -> Self {
+        Self {
+            tolerance: 8,
+            max_mismatched_fraction: 0.001,
+            references: "reference".into(),
+            settings: crate::api::PaneSettings::default(),
+            cases: Vec::new(),
+        }
+    }
+}
+
+/// A camera view of a scene, rendered at a fixed size
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct GoldenCase {
+    /// Names the reference image and the images written for the case
+    pub name: String,
+
+    /// A JSON file containing an array of commands that builds the scene
+    pub scene: std::path::PathBuf,
+
+    /// The camera to render from, or the first camera when absent
+    pub camera: Option<String>,
+
+    pub width: u32,
+    pub height: u32,
+
+    /// Overrides the tolerance of the suite
+    pub tolerance: Option<u8>,
+}
+
+/// Reads a golden image suite from a TOML file
+pub fn load_golden_suite(
+    path: impl AsRef<std::path::Path>,
+) -> Result<GoldenSuite, Box<dyn std::error::Error>> {
+    let contents = std::fs::read_to_string(path)?;
+    Ok(toml::from_str(&contents)?)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum GoldenOutcome {
+    /// The render matched its reference within the tolerance
+    Passed { mismatched_pixels: usize },
+
+    /// The render did not match its reference, or could not be made
+    Failed { reason: String },
+
+    /// The render was stored as the new reference
+    Updated,
+}
+
+#[derive(Debug, Clone)]
+pub struct GoldenResult {
+    pub name: String,
+    pub outcome: GoldenOutcome,
+}
+
+/// Renders every case of a suite and compares it against its reference image.
+/// Renders are written to the output directory, along with a diff image for each failing case,
+/// and replace the references instead when updating.
+/// Cases always render with the software fallback adapter, so references match on any machine,
+/// and the suite fails when that adapter is not available.
+pub fn run_golden_suite(
+    path: impl AsRef<std::path::Path>,
+    output: impl AsRef<std::path::Path>,
+    update: bool,
+) -> Result<Vec<GoldenResult>, String> {
+    let path = path.as_ref();
+    let output = output.as_ref();
+    let suite = load_golden_suite(path)
+        .map_err(|error| format!("Failed to load {}: {error}", path.display()))?;
+    let directory = path.parent().unwrap_or(std::path::Path::new(""));
+    std::fs::create_dir_all(output)
+        .map_err(|error| format!("Failed to create {}: {error}", output.display()))?;
+
+    let mut results = Vec::new();
+    for case in &suite.cases {
+        if case.width == 0 || case.height == 0 {
+            results.push(GoldenResult {
+                name: case.name.clone(),
+                outcome: GoldenOutcome::Failed {
+                    reason: "Images must be at least one pixel wide and tall".to_string(),
+                },
+            });
+            continue;
+        }
+
+        // Each case gets a fresh context, so entity ids in scenes do not depend on earlier cases
+        let mut context = crate::run::create_software_context(case.width, case.height)
+            .map_err(|error| format!("Failed to create a software renderer: {error}"))?;
+
+        let outcome = run_golden_case(&mut context, &suite, case, directory, output, update)
+            .unwrap_or_else(|reason| GoldenOutcome::Failed { reason });
+        results.push(GoldenResult {
+            name: case.name.clone(),
+            outcome,
+        });
+    }
+    Ok(results)
+}
+
+fn run_golden_case(
+    context: &mut crate::context::Context,
+    suite: &GoldenSuite,
+    case: &GoldenCase,
+    directory: &std::path::Path,
+    output: &std::path::Path,
+    update: bool,
+) -> Result<GoldenOutcome, String> {
+    let scene_path = directory.join(&case.scene);
+    let scene = std::fs::read_to_string(&scene_path)
+        .map_err(|error| format!("Failed to read {}: {error}", scene_path.display()))?;
+    let commands = serde_json::from_str::<Vec<crate::api::Command>>(&scene)
+        .map_err(|error| format!("Failed to parse {}: {error}", scene_path.display()))?;
+    crate::run::load_scene(context, "scene", commands);
+
+    let camera = crate::run::find_camera(context, case.camera.as_deref())
+        .ok_or("The scene has no matching camera")?;
+    let image = crate::graphics::render_camera_offscreen_with_settings(
+        context,
+        camera,
+        case.width,
+        case.height,
+        &suite.settings,
+    )?;
+    image.save_png(output.join(format!("{}.png", case.name)))?;
+
+    let reference_path = directory
+        .join(&suite.references)
+        .join(format!("{}.png", case.name));
+    if update {
+        if let Some(references) = reference_path.parent() {
+            std::fs::create_dir_all(references)
+                .map_err(|error| format!("Failed to create {}: {error}", references.display()))?;
+        }
+        image.save_png(&reference_path)?;
+        return Ok(GoldenOutcome::Updated);
+    }
+
+    // Diff images from earlier runs would be mistaken for failures of this one
+    let diff_path = output.join(format!("{}.diff.png", case.name));
+    let _ = std::fs::remove_file(&diff_path);
+
+    let reference = CapturedImage::load_png(&reference_path)
+        .map_err(|error| format!("{error}. Run with --update to record the reference"))?;
+    let tolerance = case.tolerance.unwrap_or(suite.tolerance);
+    let comparison = compare_images(&reference, &image, tolerance)?;
+    let pixel_count = (case.width * case.height) as usize;
+    if comparison.passes(suite.max_mismatched_fraction) {
+        return Ok(GoldenOutcome::Passed {
+            mismatched_pixels: comparison.mismatched_pixels,
+        });
+    }
+
+    comparison.diff.save_png(&diff_path)?;
+    Ok(GoldenOutcome::Failed {
+        reason: format!(
+            "{} of {pixel_count} pixels differ by more than {tolerance}, by up to {}. See {}",
+            comparison.mismatched_pixels,
+            comparison.max_difference,
+            diff_path.display()
+        ),
+    })
+}
+
+/// The differences between a render and its reference
+#[derive(Debug, Clone)]
+pub struct ImageComparison {
+    /// The number of pixels differing by more than the tolerance in any channel
+    pub mismatched_pixels: usize,
+
+    /// The largest difference in any channel of any pixel
+    pub max_difference: u8,
+
+    /// The render dimmed to grayscale, with mismatched pixels highlighted in magenta
+    pub diff: CapturedImage,
+}
+
+impl ImageComparison {
+    /// Whether at most the given fraction of the pixels are mismatched
+    pub fn passes(&self, max_mismatched_fraction: f32) -> bool {
+        let pixel_count = (self.diff.width * self.diff.height) as usize;
+        self.mismatched_pixels as f32 <= max_mismatched_fraction * pixel_count as f32
+    }
+}
+
+/// Compares two images of the same size pixel by pixel
+pub fn compare_images(
+    reference: &CapturedImage,
+    image: &CapturedImage,
+    tolerance: u8,
+) -> Result<ImageComparison, String> {
+    if (reference.width, reference.height) != (image.width, image.height) {
+        return Err(format!(
+            "The render is {}x{} but the reference is {}x{}",
+            image.width, image.height, reference.width, reference.height
+        ));
+    }
+
+    let mut mismatched_pixels = 0;
+    let mut max_difference = 0;
+    let mut pixels = Vec::with_capacity(image.pixels.len());
+    for (expected, actual) in reference
+        .pixels
+        .chunks_exact(4)
+        .zip(image.pixels.chunks_exact(4))
+    {
+        let difference = expected
+            .iter()
+            .zip(actual)
+            .map(|(expected, actual)| expected.abs_diff(*actual))
+            .max()
+            .unwrap_or_default();
+        max_difference = max_difference.max(difference);
+        if difference > tolerance {
+            mismatched_pixels += 1;
+            pixels.extend_from_slice(&[255, 0, 255, 255]);
+        } else {
+            let luminance = (actual[0] as u16 + actual[1] as u16 + actual[2] as u16) / 3;
+            let gray = (luminance / 3) as u8;
+            pixels.extend_from_slice(&[gray, gray, gray, 255]);
+        }
+    }
+
+    Ok(ImageComparison {
+        mismatched_pixels,
+        max_difference,
+        diff: CapturedImage {
+            width: image.width,
+            height: image.height,
+            pixels,
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn image(width: u32, height: u32, color: [u8; 4]) -> CapturedImage {
+        CapturedImage {
+            width,
+            height,
+            pixels: color.repeat((width * height) as usize),
+        }
+    }
+
+    #[test]
+    fn identical_images_match() {
+        let reference = image(4, 4, [10, 20, 30, 255]);
+        let comparison = compare_images(&reference, &reference.clone(), 0).unwrap();
+        assert_eq!(comparison.mismatched_pixels, 0);
+        assert_eq!(comparison.max_difference, 0);
+        assert!(comparison.passes(0.0));
+    }
+
+    #[test]
+    fn differences_within_the_tolerance_match() {
+        let reference = image(4, 4, [10, 20, 30, 255]);
+        let render = image(4, 4, [15, 20, 27, 255]);
+        let comparison = compare_images(&reference, &render, 8).unwrap();
+        assert_eq!(comparison.mismatched_pixels, 0);
+        assert_eq!(comparison.max_difference, 5);
+        assert!(comparison.passes(0.0));
+    }
+
+    #[test]
+    fn mismatches_above_the_fraction_fail_and_are_highlighted() {
+        let reference = image(2, 2, [10, 20, 30, 255]);
+        let mut render = reference.clone();
+        render.pixels[4..8].copy_from_slice(&[200, 20, 30, 255]);
+        let comparison = compare_images(&reference, &render, 8).unwrap();
+        assert_eq!(comparison.mismatched_pixels, 1);
+        assert_eq!(comparison.max_difference, 190);
+        assert_eq!(&comparison.diff.pixels[4..8], &[255, 0, 255, 255]);
+        assert_ne!(&comparison.diff.pixels[0..4], &[255, 0, 255, 255]);
+        assert!(!comparison.passes(0.2));
+        assert!(comparison.passes(0.25));
+    }
+
+    #[test]
+    fn images_of_different_sizes_are_refused() {
+        let reference = image(4, 4, [0, 0, 0, 255]);
+        let render = image(4, 2, [0, 0, 0, 255]);
+        let error = compare_images(&reference, &render, 8).unwrap_err();
+        assert_eq!(error, "The render is 4x2 but the reference is 4x4");
+    }
+}
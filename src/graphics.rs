@@ -1,6 +1,10 @@
 use crate::prelude::{get_component, EntityId, Parent, PARENT};
 
-use crate::graphics::{lines::Lines as RendererLines, quads::Quads as RendererQuads};
+use crate::graphics::{
+    labels::Labels as RendererLabels, lines::Lines as RendererLines,
+    meshes::Meshes as RendererMeshes, points::Points as RendererPoints,
+    polylines::Polylines as RendererPolylines, quads::Quads as RendererQuads,
+};
 
 /// A resource for graphics state
 #[derive(Default)]
@@ -14,6 +18,38 @@ pub struct Graphics {
 
     /// The size of the display viewport
     pub viewport_size: (u32, u32),
+
+    /// Window and pane captures waiting for the next frame to be rendered
+    pub pending_captures: Vec<PendingCapture>,
+
+    /// The image sequence being recorded, if any
+    pub recording: Option<Recording>,
+}
+
+/// A capture of the window or a pane, requested by a command
+pub struct PendingCapture {
+    pub id: u64,
+    pub target: crate::api::CaptureTarget,
+    pub output: crate::api::CaptureOutput,
+
+    /// The connection that requested the capture, which its report is addressed to
+    pub connection: Option<String>,
+}
+
+/// An image sequence written one numbered PNG per rendered frame
+pub struct Recording {
+    pub id: u64,
+    pub target: crate::api::CaptureTarget,
+    pub directory: String,
+
+    /// The number of frames written so far
+    pub frames: u32,
+
+    /// The number of frames to write, or zero to record until stopped
+    pub frame_count: u32,
+
+    /// The connection that started the recording, which its reports are addressed to
+    pub connection: Option<String>,
 }
 
 /// Contains all resources required for rendering
@@ -22,25 +58,76 @@ pub struct Renderer {
     pub ui_depth_texture_view: wgpu::TextureView,
     pub ui: egui_wgpu::Renderer,
     pub targets: Vec<RenderTarget>,
+
+    /// The target offscreen renders reuse while their size stays the same
+    pub offscreen_target: Option<RenderTarget>,
+
+    /// The uploaded points of every entity, shared by all targets
+    pub point_clouds: std::collections::HashMap<EntityId, PointCloud>,
+
+    /// The uploaded levels of detail of every polyline, shared by all targets
+    pub polylines: std::collections::HashMap<EntityId, PolylineLevels>,
+
+    /// The render list of every scene shown, shared by all targets showing the scene
+    pub scenes: std::collections::HashMap<EntityId, SceneRenderList>,
+
+    /// The glyphs of every label, shared by all targets
+    pub glyph_atlas: GlyphAtlas,
+
+    /// The cubemaps of the environments shown, converted once for all targets showing them
+    pub environment_maps: EnvironmentMaps,
 }
 
 pub struct RenderTarget {
     pub color_texture: wgpu::Texture,
     pub color_texture_view: wgpu::TextureView,
+
+    /// The multisampled texture scenes are drawn into and resolved from, when multisampling
+    pub msaa_texture_view: Option<wgpu::TextureView>,
+
+    /// The number of samples per pixel of the target's attachments and pipelines
+    pub sample_count: u32,
     pub depth_texture: wgpu::Texture,
     pub depth_texture_view: wgpu::TextureView,
+    pub post_process: PostProcess,
     pub grid: Grid,
     pub sky: Sky,
     pub lines: RendererLines,
     pub quads: RendererQuads,
+    pub meshes: RendererMeshes,
+    pub points: RendererPoints,
+    pub polylines: RendererPolylines,
+    pub labels: RendererLabels,
+
+    /// The scene drawn by the target, whose render list holds its lines, quads and meshes
+    pub scene_root: Option<EntityId>,
+
+    /// The entities outlined in the target's scene
+    pub outlined: Vec<EntityId>,
 }
 
 /// Low-level wgpu handles
 pub struct Gpu {
-    pub surface: wgpu::Surface<'static>,
+    /// The window surface, absent when rendering headless
+    pub surface: Option<wgpu::Surface<'static>>,
     pub device: wgpu::Device,
     pub queue: wgpu::Queue,
     pub surface_config: wgpu::SurfaceConfiguration,
+
+    /// The multisample counts render targets can use, in increasing order
+    pub sample_counts: Vec<u32>,
+}
+
+impl Gpu {
+    /// The largest supported sample count that does not exceed the requested count
+    pub fn supported_sample_count(&self, requested: u32) -> u32 {
+        self.sample_counts
+            .iter()
+            .copied()
+            .filter(|sample_count| *sample_count <= requested)
+            .max()
+            .unwrap_or(1)
+    }
 }
 
 /// Receives the renderer from the async task that creates it on wasm, injecting it as a resource
@@ -65,10 +152,9 @@ pub fn resize_renderer(context: &mut crate::context::Context, width: u32, height
     // Update surface config
     renderer.gpu.surface_config.width = width;
     renderer.gpu.surface_config.height = height;
-    renderer
-        .gpu
-        .surface
-        .configure(&renderer.gpu.device, &renderer.gpu.surface_config);
+    if let Some(surface) = renderer.gpu.surface.as_ref() {
+        surface.configure(&renderer.gpu.device, &renderer.gpu.surface_config);
+    }
 
     let ui_depth_view = {
         let device: &wgpu::Device = &renderer.gpu.device;
@@ -103,98 +189,139 @@ pub fn resize_renderer(context: &mut crate::context::Context, width: u32, height
     };
     renderer.ui_depth_texture_view = ui_depth_view;
 
-    let mut targets = Vec::new();
-    (0..renderer.targets.len()).for_each(|_| {
-        let color_texture = renderer
-            .gpu
-            .device
-            .create_texture(&wgpu::TextureDescriptor {
-                label: Some("Viewport Texture"),
-                size: wgpu::Extent3d {
-                    width: renderer.gpu.surface_config.width,
-                    height: renderer.gpu.surface_config.height,
-                    depth_or_array_layers: 1,
-                },
-                mip_level_count: 1,
-                sample_count: 1,
-                dimension: wgpu::TextureDimension::D2,
-                format: renderer.gpu.surface_config.format,
-                usage: wgpu::TextureUsages::RENDER_ATTACHMENT
-                    | wgpu::TextureUsages::TEXTURE_BINDING
-                    | wgpu::TextureUsages::COPY_SRC,
-                view_formats: &[renderer.gpu.surface_config.format],
-            });
-        let color_texture_view = color_texture.create_view(&wgpu::TextureViewDescriptor::default());
+    renderer.targets = renderer
+        .targets
+        .iter()
+        .map(|target| create_render_target(&renderer.gpu, width, height, target.sample_count))
+        .collect();
 
-        let device: &wgpu::Device = &renderer.gpu.device;
-        let width = renderer.gpu.surface_config.width;
-        let height = renderer.gpu.surface_config.height;
-        let depth_texture = device.create_texture(
-            &(wgpu::TextureDescriptor {
-                label: Some("Depth Texture"),
+    context.resources.graphics.viewport_size = (width, height);
+}
+
+/// Creates the textures and pipelines a pane renders into, at a supported sample count
+fn create_render_target(gpu: &Gpu, width: u32, height: u32, sample_count: u32) -> RenderTarget {
+    let color_texture = gpu.device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("Viewport Texture"),
+        size: wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: gpu.surface_config.format,
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT
+            | wgpu::TextureUsages::TEXTURE_BINDING
+            | wgpu::TextureUsages::COPY_SRC,
+        view_formats: &[gpu.surface_config.format],
+    });
+    let color_texture_view = color_texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+    let msaa_texture_view = (sample_count > 1).then(|| {
+        gpu.device
+            .create_texture(&wgpu::TextureDescriptor {
+                label: Some("Multisampled Viewport Texture"),
                 size: wgpu::Extent3d {
                     width,
                     height,
                     depth_or_array_layers: 1,
                 },
                 mip_level_count: 1,
-                sample_count: 1,
+                sample_count,
                 dimension: wgpu::TextureDimension::D2,
-                format: wgpu::TextureFormat::Depth32Float,
-                usage: wgpu::TextureUsages::RENDER_ATTACHMENT
-                    | wgpu::TextureUsages::TEXTURE_BINDING,
+                format: SCENE_FORMAT,
+                usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
                 view_formats: &[],
-            }),
-        );
-        let depth_texture_view = depth_texture.create_view(&wgpu::TextureViewDescriptor {
-            label: None,
-            format: Some(wgpu::TextureFormat::Depth32Float),
-            dimension: Some(wgpu::TextureViewDimension::D2),
-            aspect: wgpu::TextureAspect::All,
-            base_mip_level: 0,
-            base_array_layer: 0,
-            array_layer_count: None,
-            mip_level_count: None,
-            usage: None,
-        });
-        let grid = create_grid(
-            &renderer.gpu.device,
-            renderer.gpu.surface_config.format,
-            DEPTH_FORMAT,
-        );
-        let sky = create_sky(
-            &renderer.gpu.device,
-            &renderer.gpu.queue,
-            renderer.gpu.surface_config.format,
-            DEPTH_FORMAT,
-        );
-        let lines = create_line_renderer(&renderer.gpu.device, renderer.gpu.surface_config.format);
-        let quads = create_quad_renderer(
-            &renderer.gpu.device,
-            renderer.gpu.surface_config.format,
-            DEPTH_FORMAT,
-        );
-        let target = RenderTarget {
-            color_texture,
-            color_texture_view,
-            depth_texture,
-            depth_texture_view,
-            grid,
-            sky,
-            lines,
-            quads,
-        };
-        targets.push(target);
+            })
+            .create_view(&wgpu::TextureViewDescriptor::default())
     });
-    renderer.targets = targets;
 
-    context.resources.graphics.viewport_size = (width, height);
+    let depth_texture = gpu.device.create_texture(
+        &(wgpu::TextureDescriptor {
+            label: Some("Depth Texture"),
+            size: wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Depth32Float,
+            // Only attached, so GL backends allocate it like the multisampled color attachment
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            view_formats: &[],
+        }),
+    );
+    let depth_texture_view = depth_texture.create_view(&wgpu::TextureViewDescriptor {
+        label: None,
+        format: Some(wgpu::TextureFormat::Depth32Float),
+        dimension: Some(wgpu::TextureViewDimension::D2),
+        aspect: wgpu::TextureAspect::All,
+        base_mip_level: 0,
+        base_array_layer: 0,
+        array_layer_count: None,
+        mip_level_count: None,
+        usage: None,
+    });
+    let format = SCENE_FORMAT;
+    let grid = create_grid(&gpu.device, format, DEPTH_FORMAT, sample_count);
+    let sky = create_sky(&gpu.device, format, DEPTH_FORMAT, sample_count);
+    let lines = create_line_renderer(&gpu.device, format, sample_count);
+    let quads = create_quad_renderer(&gpu.device, format, DEPTH_FORMAT, sample_count);
+    let meshes = create_mesh_renderer(&gpu.device, format, DEPTH_FORMAT, sample_count);
+    let points = create_point_renderer(&gpu.device, format, DEPTH_FORMAT, sample_count);
+    let polylines = create_polyline_renderer(&gpu.device, format, DEPTH_FORMAT, sample_count);
+    let labels = create_label_renderer(&gpu.device, format, DEPTH_FORMAT, sample_count);
+    let post_process = create_post_process(&gpu.device, gpu.surface_config.format, width, height);
+    RenderTarget {
+        color_texture,
+        color_texture_view,
+        msaa_texture_view,
+        sample_count,
+        depth_texture,
+        depth_texture_view,
+        post_process,
+        grid,
+        sky,
+        lines,
+        quads,
+        meshes,
+        points,
+        polylines,
+        labels,
+        scene_root: None,
+        outlined: Vec::new(),
+    }
 }
 
-/// This synchronizes the viewport uniforms with the world
-fn update_panes_system(context: &mut crate::context::Context) {
-    use crate::context::*;
+/// What a pane shows and how, gathered from the world before its target is updated
+struct PaneView {
+    matrices: crate::context::CameraMatrices,
+    viewport_size: (u32, u32),
+    scene_root: Option<EntityId>,
+    settings: crate::api::PaneSettings,
+
+    /// The selected entity and its descendants, when the pane outlines them
+    outlined: Vec<EntityId>,
+}
+
+/// The entities outlined by panes, which are the selected entity and its descendants
+fn query_outlined_entities(context: &crate::context::Context) -> Vec<EntityId> {
+    context
+        .resources
+        .user_interface
+        .selected_entity
+        .map(|entity| crate::context::query_descendents(context, entity))
+        .unwrap_or_default()
+}
 
+/// This synchronizes the viewport uniforms with the world
+fn update_panes_system(
+    context: &mut crate::context::Context,
+    pane_settings: &[crate::api::PaneSettings],
+) {
     // First collect all viewport and camera data
     let viewports = context
         .resources
@@ -205,288 +332,798 @@ fn update_panes_system(context: &mut crate::context::Context) {
         .copied()
         .collect::<Vec<_>>();
 
-    // Collect camera matrices
-    let mut camera_matrices = Vec::new();
-    for (kind, viewport) in &viewports {
-        let matrices = if let crate::ui::PaneKind::Scene {
-            camera_entity: Some(camera_entity),
-            ..
-        } = kind
-        {
-            if let (Some(camera), Some(transform)) = (
-                get_component::<Camera>(context, *camera_entity, CAMERA),
-                get_component::<GlobalTransform>(context, *camera_entity, GLOBAL_TRANSFORM),
-            ) {
-                let view = nalgebra_glm::inverse(&transform.0);
-                let projection = camera.projection_matrix(viewport.width() / viewport.height());
-
-                Some(CameraMatrices {
-                    view,
-                    projection,
-                    camera_position: transform.0.column(3).xyz(),
-                })
-            } else {
-                None
-            }
-        } else {
-            None
-        };
-        camera_matrices.push(matrices);
-    }
-
-    // Collect scene data for each viewport
-    let scene_data: Vec<_> = viewports
+    // Collect camera matrices and the scene shown by each viewport
+    let outlined = query_outlined_entities(context);
+    let panes = viewports
         .iter()
-        .map(|(kind, _)| {
-            if let crate::ui::PaneKind::Scene {
-                scene_entity: _,
-                camera_entity,
+        .zip(pane_settings)
+        .map(|((kind, viewport), settings)| {
+            let crate::ui::PaneKind::Scene {
+                camera_entity: Some(camera_entity),
+                ..
             } = kind
-            {
-                // Find the scene this camera belongs to by traversing up
-                let actual_scene = if let Some(camera) = camera_entity {
-                    let mut current = *camera;
-                    let mut found_scene = None;
-                    // Keep traversing up until we find a root node
-                    while let Some(Parent(parent)) =
-                        get_component::<Parent>(context, current, PARENT)
-                    {
-                        current = *parent;
-                        // If current is a root node (no parent), this is our scene
-                        if get_component::<Parent>(context, current, PARENT).is_none() {
-                            found_scene = Some(current);
-                            break;
-                        }
-                    }
-                    found_scene
+            else {
+                return None;
+            };
+            let matrices = query_pane_camera_matrices(
+                context,
+                *camera_entity,
+                viewport.width() / viewport.height(),
+            )?;
+            Some(PaneView {
+                matrices,
+                viewport_size: (viewport.width() as u32, viewport.height() as u32),
+                scene_root: query_scene_root(context, *camera_entity),
+                settings: settings.clone(),
+                outlined: if settings.outline.enabled {
+                    outlined.clone()
                 } else {
-                    None
-                };
-
-                // Use the actual scene entity for rendering
-                if let Some(actual_scene) = actual_scene {
-                    // Get all entities in this scene's hierarchy
-                    let scene_entities = query_entities(context, LOCAL_TRANSFORM)
-                        .into_iter()
-                        .filter(|entity| is_descendant_of(context, *entity, actual_scene))
-                        .collect::<Vec<_>>();
+                    Vec::new()
+                },
+            })
+        })
+        .collect::<Vec<_>>();
 
-                    // Process lines for this scene's entities only
-                    let scene_lines: Vec<_> = scene_entities
-                        .iter()
-                        .filter_map(|entity| {
-                            let Lines(lines) = get_component::<Lines>(context, *entity, LINES)?;
-                            let global_transform = get_component::<GlobalTransform>(
-                                context,
-                                *entity,
-                                GLOBAL_TRANSFORM,
-                            )?;
-
-                            Some(
-                                lines
-                                    .iter()
-                                    .map(|line| {
-                                        // Transform line to world space
-                                        let start_world = (global_transform.0
-                                            * nalgebra_glm::vec4(
-                                                line.start.x,
-                                                line.start.y,
-                                                line.start.z,
-                                                1.0,
-                                            ))
-                                        .xyz();
-                                        let end_world = (global_transform.0
-                                            * nalgebra_glm::vec4(
-                                                line.end.x, line.end.y, line.end.z, 1.0,
-                                            ))
-                                        .xyz();
-
-                                        LineInstance {
-                                            start: nalgebra_glm::vec4(
-                                                start_world.x,
-                                                start_world.y,
-                                                start_world.z,
-                                                1.0,
-                                            ),
-                                            end: nalgebra_glm::vec4(
-                                                end_world.x,
-                                                end_world.y,
-                                                end_world.z,
-                                                1.0,
-                                            ),
-                                            color: line.color,
-                                        }
-                                    })
-                                    .collect::<Vec<_>>(),
-                            )
-                        })
-                        .flatten()
-                        .collect();
-
-                    // Process quads for this scene's entities only
-                    let scene_quads: Vec<_> = scene_entities
-                        .iter()
-                        .filter_map(|entity| {
-                            let Quads(quads) = get_component::<Quads>(context, *entity, QUADS)?;
-                            let global_transform = get_component::<GlobalTransform>(
-                                context,
-                                *entity,
-                                GLOBAL_TRANSFORM,
-                            )?;
-                            Some(
-                                quads
-                                    .iter()
-                                    .map(|quad| {
-                                        let scale = nalgebra_glm::scaling(&nalgebra_glm::vec3(
-                                            quad.size.x,
-                                            quad.size.y,
-                                            1.0,
-                                        ));
-                                        let offset =
-                                            nalgebra_glm::translation(&nalgebra_glm::vec3(
-                                                quad.offset.x,
-                                                quad.offset.y,
-                                                quad.offset.z,
-                                            ));
-                                        let final_transform = global_transform.0 * offset * scale;
-                                        QuadInstance {
-                                            model_matrix_0: final_transform.column(0).into(),
-                                            model_matrix_1: final_transform.column(1).into(),
-                                            model_matrix_2: final_transform.column(2).into(),
-                                            model_matrix_3: final_transform.column(3).into(),
-                                            color: quad.color,
-                                        }
-                                    })
-                                    .collect::<Vec<_>>(),
-                            )
-                        })
-                        .flatten()
-                        .collect();
+    update_point_clouds(context);
+    update_polylines(context);
+    update_glyph_atlas(context);
 
-                    Some((scene_lines, scene_quads))
-                } else {
-                    None
-                }
-            } else {
-                None
-            }
-        })
-        .collect();
+    // Panes showing the same scene share its render list
+    let scene_roots = panes
+        .iter()
+        .flatten()
+        .filter_map(|pane| pane.scene_root)
+        .collect::<Vec<_>>();
+    update_scene_render_lists(context, &scene_roots);
 
     // Now update renderer with collected data
     let Some(renderer) = context.resources.graphics.renderer.as_mut() else {
         return;
     };
 
-    for (((target, (kind, _)), matrices), scene_data) in renderer
-        .targets
-        .iter_mut()
-        .zip(viewports.iter())
-        .zip(camera_matrices.iter())
-        .zip(scene_data.iter())
-    {
-        match kind {
-            crate::ui::PaneKind::Scene { .. } => {
-                if let Some(matrices) = matrices {
-                    update_grid(matrices, &renderer.gpu.queue, &target.grid);
-                    update_sky(matrices, &renderer.gpu.queue, &target.sky);
-
-                    if let Some((scene_lines, scene_quads)) = scene_data {
-                        update_lines_uniform(
-                            matrices,
-                            &renderer.gpu.device,
-                            &renderer.gpu.queue,
-                            &mut target.lines,
-                            scene_lines.clone(),
-                        );
-                        update_quads_uniform(
-                            matrices,
-                            &renderer.gpu.device,
-                            &renderer.gpu.queue,
-                            &mut target.quads,
-                            scene_quads.clone(),
-                        );
-                    }
-                }
-            }
-            crate::ui::PaneKind::Color(_color) => {}
-            crate::ui::PaneKind::Empty => {}
-            crate::ui::PaneKind::ApiLog => {}
+    for (target, pane) in renderer.targets.iter_mut().zip(panes) {
+        if let Some(pane) = pane {
+            update_render_target(
+                &renderer.gpu,
+                target,
+                pane,
+                &renderer.scenes,
+                &renderer.polylines,
+            );
         }
     }
 }
 
-/// This system renders and presents the next frame
-pub fn render_frame_system(context: &mut crate::context::Context) {
-    update_panes_system(context);
+/// Uploads the points of entities whose point arrays changed since they were last uploaded,
+/// and frees the buffers of points that were removed. Unchanged points are never uploaded again.
+fn update_point_clouds(context: &mut crate::context::Context) {
+    use crate::context::*;
+    let entity_points = query_entities(context, POINTS)
+        .into_iter()
+        .filter_map(|entity| {
+            let points = get_component::<Points>(context, entity, POINTS)?;
+            Some((entity, points.clone()))
+        })
+        .collect::<Vec<_>>();
 
-    let viewports = context
-        .resources
-        .user_interface
-        .tile_tree_context
-        .viewport_tiles
-        .values()
-        .copied()
+    let Some(renderer) = context.resources.graphics.renderer.as_mut() else {
+        return;
+    };
+    let mut previous_clouds = std::mem::take(&mut renderer.point_clouds);
+    renderer.point_clouds = entity_points
+        .into_iter()
+        .map(|(entity, points)| {
+            let cloud = match previous_clouds.remove(&entity) {
+                Some(cloud) if cloud.is_uploaded_from(&points) => cloud,
+                _ => create_point_cloud(&renderer.gpu.device, points),
+            };
+            (entity, cloud)
+        })
+        .collect();
+}
+
+/// Uploads the levels of detail of polylines whose arrays changed since they were last uploaded,
+/// and frees the buffers of polylines that were removed
+fn update_polylines(context: &mut crate::context::Context) {
+    use crate::context::*;
+    let entity_polylines = query_entities(context, POLYLINE)
+        .into_iter()
+        .filter_map(|entity| {
+            let polyline = get_component::<Polyline>(context, entity, POLYLINE)?;
+            Some((entity, polyline.clone()))
+        })
         .collect::<Vec<_>>();
 
-    let Some((egui::FullOutput { textures_delta, .. }, paint_jobs)) =
-        context.resources.user_interface.frame_output.take()
-    else {
+    let Some(renderer) = context.resources.graphics.renderer.as_mut() else {
         return;
     };
+    let mut previous_polylines = std::mem::take(&mut renderer.polylines);
+    renderer.polylines = entity_polylines
+        .into_iter()
+        .map(|(entity, polyline)| {
+            let levels = match previous_polylines.remove(&entity) {
+                Some(levels) if levels.is_uploaded_from(&polyline) => levels,
+                _ => create_polyline_levels(&renderer.gpu.device, polyline),
+            };
+            (entity, levels)
+        })
+        .collect();
+}
 
-    let Some(window_handle) = context.resources.window.handle.as_ref() else {
+/// Rasterizes the glyphs of labels that are not in the glyph atlas yet
+fn update_glyph_atlas(context: &mut crate::context::Context) {
+    use crate::context::*;
+    let changes = component_changes(context, LABEL);
+    match context.resources.graphics.renderer.as_ref() {
+        Some(renderer) if renderer.glyph_atlas.changes != changes => {}
+        _ => return,
+    }
+    let texts = query_entities(context, LABEL)
+        .into_iter()
+        .filter_map(|entity| {
+            let label = get_component::<Label>(context, entity, LABEL)?;
+            Some(label.text.clone())
+        })
+        .collect::<Vec<_>>();
+
+    let Some(renderer) = context.resources.graphics.renderer.as_mut() else {
         return;
     };
+    texts
+        .iter()
+        .for_each(|text| renderer.glyph_atlas.insert_text(&renderer.gpu.queue, text));
+    renderer.glyph_atlas.changes = changes;
+}
 
-    let screen_descriptor = {
-        let (width, height) = context.resources.graphics.viewport_size;
-        egui_wgpu::ScreenDescriptor {
-            size_in_pixels: [width, height],
-            pixels_per_point: window_handle.scale_factor() as f32,
+/// Computes the matrices a camera renders a pane with, using the aspect ratio of the pane
+fn query_pane_camera_matrices(
+    context: &crate::context::Context,
+    camera_entity: EntityId,
+    aspect_ratio: f32,
+) -> Option<crate::context::CameraMatrices> {
+    use crate::context::*;
+    let camera = get_component::<Camera>(context, camera_entity, CAMERA)?;
+    let transform = get_component::<GlobalTransform>(context, camera_entity, GLOBAL_TRANSFORM)?;
+    Some(CameraMatrices {
+        view: nalgebra_glm::inverse(&transform.0),
+        projection: camera.projection_matrix(aspect_ratio),
+        camera_position: transform.0.column(3).xyz(),
+    })
+}
+
+/// Finds the scene a camera belongs to, the root of the hierarchy above it
+fn query_scene_root(
+    context: &crate::context::Context,
+    camera_entity: EntityId,
+) -> Option<EntityId> {
+    // Keep traversing up until we find a root node
+    let mut current = camera_entity;
+    while let Some(Parent(parent)) = get_component::<Parent>(context, current, PARENT) {
+        current = *parent;
+        // If current is a root node (no parent), this is our scene
+        if get_component::<Parent>(context, current, PARENT).is_none() {
+            return Some(current);
         }
+    }
+    None
+}
+
+/// The components that are drawn from a scene render list
+const SCENE_DRAWABLES: u32 = crate::context::LINES
+    | crate::context::QUADS
+    | crate::context::MESH
+    | crate::context::POINTS
+    | crate::context::POLYLINE
+    | crate::context::LABEL
+    | crate::context::PARENT;
+
+/// The components drawn from instances that are rewritten in place when only some entities change.
+/// Meshes are merged into shared buffers that index each other's vertices,
+/// so a changed mesh rebuilds the whole list instead.
+const SCENE_INSTANCES: u32 = crate::context::LINES | crate::context::QUADS | crate::context::LABEL;
+
+/// Updates the render lists of scenes whose drawables changed since they were built,
+/// and uploads the model matrices of their entities, which move without rebuilding the list.
+/// Lists are patched where only the instances of some entities changed, and rebuilt otherwise.
+/// The lists of scenes that were despawned are freed.
+fn update_scene_render_lists(context: &mut crate::context::Context, scene_roots: &[EntityId]) {
+    use crate::context::*;
+    let changes = component_changes(context, SCENE_DRAWABLES);
+    let Some(renderer) = context.resources.graphics.renderer.as_ref() else {
+        return;
     };
 
-    ensure_viewports(context, viewports.len());
+    // Everything needed from the world is gathered before the renderer is borrowed mutably
+    let mut visited = std::collections::HashSet::new();
+    let updates = scene_roots
+        .iter()
+        .filter(|scene_root| visited.insert(**scene_root))
+        .filter_map(|scene_root| {
+            let update = match renderer.scenes.get(scene_root) {
+                Some(list) if list.changes == changes => None,
+                Some(list) => Some(plan_scene_update(
+                    context,
+                    *scene_root,
+                    list,
+                    &renderer.glyph_atlas,
+                )),
+                None => Some(SceneUpdate::Rebuild(collect_scene_drawables(
+                    context,
+                    *scene_root,
+                    &renderer.glyph_atlas,
+                ))),
+            };
+            let members = match &update {
+                Some(SceneUpdate::Rebuild(drawables)) => &drawables.members,
+                _ => &renderer.scenes.get(scene_root)?.members,
+            };
+            let global_transform = |entity: &EntityId| {
+                get_component::<GlobalTransform>(context, *entity, GLOBAL_TRANSFORM)
+                    .map_or_else(nalgebra_glm::Mat4::identity, |transform| transform.0)
+            };
+            let models = members
+                .entities
+                .iter()
+                .map(|entity| ModelUniform::new(&global_transform(entity)))
+                .collect::<Vec<_>>();
+            let points = members
+                .points
+                .iter()
+                .filter_map(|entity| {
+                    let points = get_component::<Points>(context, *entity, POINTS)?;
+                    Some((
+                        *entity,
+                        PointCloudUniform::new(points, global_transform(entity)),
+                    ))
+                })
+                .collect::<Vec<_>>();
+            let polylines = members
+                .polylines
+                .iter()
+                .filter_map(|entity| {
+                    let polyline = get_component::<Polyline>(context, *entity, POLYLINE)?;
+                    Some((
+                        *entity,
+                        PolylineUniform::new(polyline, global_transform(entity)),
+                    ))
+                })
+                .collect::<Vec<_>>();
+            Some((*scene_root, update, models, points, polylines))
+        })
+        .collect::<Vec<_>>();
+    let despawned = renderer
+        .scenes
+        .keys()
+        .filter(|scene_root| component_mask(context, **scene_root).is_none())
+        .copied()
+        .collect::<Vec<_>>();
 
     let Some(renderer) = context.resources.graphics.renderer.as_mut() else {
         return;
     };
-
-    for (id, image_delta) in &textures_delta.set {
-        renderer
-            .ui
-            .update_texture(&renderer.gpu.device, &renderer.gpu.queue, *id, image_delta);
+    for scene_root in despawned {
+        renderer.scenes.remove(&scene_root);
     }
-
-    for id in &textures_delta.free {
-        renderer.ui.free_texture(id);
+    for (scene_root, update, models, points, polylines) in updates {
+        let gpu = &renderer.gpu;
+        let list = renderer
+            .scenes
+            .entry(scene_root)
+            .or_insert_with(|| SceneRenderList::new(&gpu.device));
+        match update {
+            Some(SceneUpdate::Rebuild(drawables)) => {
+                list.update_drawables(&gpu.device, &gpu.queue, drawables, changes)
+            }
+            Some(SceneUpdate::Patch(patch)) => list.apply_patch(&gpu.queue, patch, changes),
+            None => {}
+        }
+        list.update_models(&gpu.device, &gpu.queue, &models);
+        list.points = points;
+        list.polylines = polylines;
     }
+}
 
-    let mut encoder = renderer
-        .gpu
-        .device
-        .create_command_encoder(&wgpu::CommandEncoderDescriptor {
-            label: Some("Render Encoder"),
-        });
+/// The changes a render list tracks for each of its entities,
+/// first to its instanced components and then to its mesh
+fn member_changes(context: &crate::context::Context, entity: EntityId) -> (u64, u64) {
+    use crate::context::*;
+    (
+        entity_changes(context, entity, SCENE_INSTANCES),
+        entity_changes(context, entity, MESH),
+    )
+}
 
-    renderer.ui.update_buffers(
-        &renderer.gpu.device,
-        &renderer.gpu.queue,
-        &mut encoder,
-        &paint_jobs,
-        &screen_descriptor,
-    );
+/// Decides how a render list catches up with the world. Only the instances of entities
+/// that changed are gathered, unless the scene's entities or meshes changed,
+/// or the instances no longer fit in their buffers, which rebuilds the list.
+fn plan_scene_update(
+    context: &crate::context::Context,
+    scene_root: EntityId,
+    list: &SceneRenderList,
+    glyph_atlas: &GlyphAtlas,
+) -> SceneUpdate {
+    let rebuild =
+        || SceneUpdate::Rebuild(collect_scene_drawables(context, scene_root, glyph_atlas));
+    if collect_scene_members(context, scene_root) != list.members {
+        return rebuild();
+    }
+    let member_changes = list
+        .members
+        .entities
+        .iter()
+        .map(|entity| member_changes(context, *entity))
+        .collect::<Vec<_>>();
+    let meshes_changed = member_changes
+        .iter()
+        .zip(&list.member_changes)
+        .any(|(new, old)| new.1 != old.1);
+    if meshes_changed {
+        return rebuild();
+    }
 
-    let surface_texture = renderer
-        .gpu
-        .surface
-        .get_current_texture()
-        .expect("Failed to get surface texture!");
+    let changed = member_changes
+        .iter()
+        .zip(&list.member_changes)
+        .enumerate()
+        .filter(|(_, (new, old))| new.0 != old.0)
+        .map(|(model, _)| {
+            let entity = list.members.entities[model];
+            (
+                model,
+                collect_entity_instances(context, entity, glyph_atlas),
+            )
+        })
+        .collect::<std::collections::HashMap<_, _>>();
+    let collect =
+        |model: usize| collect_entity_instances(context, list.members.entities[model], glyph_atlas);
+    let model_count = list.members.entities.len();
+    let lines = plan_instance_patch(
+        &list.lines,
+        &list.line_batches,
+        model_count,
+        |model| {
+            changed
+                .get(&model)
+                .map(|instances| instances.lines.as_slice())
+        },
+        |model| collect(model).lines,
+    );
+    let quads = plan_instance_patch(
+        &list.quads,
+        &list.quad_batches,
+        model_count,
+        |model| {
+            changed
+                .get(&model)
+                .map(|instances| instances.quads.as_slice())
+        },
+        |model| collect(model).quads,
+    );
+    let glyphs = plan_instance_patch(
+        &list.glyphs,
+        &list.glyph_batches,
+        model_count,
+        |model| {
+            changed
+                .get(&model)
+                .map(|instances| instances.glyphs.as_slice())
+        },
+        |model| collect(model).glyphs,
+    );
+    match (lines, quads, glyphs) {
+        (Some(lines), Some(quads), Some(glyphs)) => SceneUpdate::Patch(ScenePatch {
+            member_changes,
+            lines,
+            quads,
+            glyphs,
+        }),
+        _ => rebuild(),
+    }
+}
 
-    let surface_texture_view = surface_texture
-        .texture
-        .create_view(&wgpu::TextureViewDescriptor::default());
+/// Plans the rewrite of one instance buffer of a render list, from the first changed entity
+/// through the last one, or through the end when an entity's instance count changed.
+/// Returns `None` when the instances outgrow the buffer.
+fn plan_instance_patch<'a, T: bytemuck::Pod + 'a>(
+    buffer: &InstanceBuffer<T>,
+    batches: &[Batch],
+    model_count: usize,
+    changed: impl Fn(usize) -> Option<&'a [T]>,
+    collect: impl Fn(usize) -> Vec<T>,
+) -> Option<InstancePatch<T>> {
+    let old_counts = batch_counts(batches, model_count);
+    let new_counts = (0..model_count)
+        .map(|model| changed(model).map_or(old_counts[model], |instances| instances.len() as u32))
+        .collect::<Vec<_>>();
+    let len = new_counts.iter().sum::<u32>() as usize;
+    if len > buffer.capacity() {
+        return None;
+    }
+    let changed_models = (0..model_count)
+        .map(|model| changed(model).is_some())
+        .collect::<Vec<_>>();
+    let range = changed_range(&old_counts, &new_counts, &changed_models).unwrap_or_default();
+
+    let mut instances = Vec::with_capacity(range.len());
+    let mut offset = 0;
+    for (model, count) in new_counts.iter().enumerate() {
+        let model_range = offset..offset + *count as usize;
+        offset = model_range.end;
+        if model_range.is_empty() || model_range.end <= range.start {
+            continue;
+        }
+        if model_range.start >= range.end {
+            break;
+        }
+        match changed(model) {
+            Some(changed) => instances.extend_from_slice(changed),
+            None => instances.extend(collect(model)),
+        }
+    }
+    Some(InstancePatch {
+        start: range.start,
+        instances,
+        len,
+        batches: counted_batches(&new_counts),
+    })
+}
+
+/// Finds the entities of a scene that are drawn
+fn collect_scene_members(context: &crate::context::Context, scene_root: EntityId) -> SceneMembers {
+    use crate::context::*;
+    let mut members = SceneMembers::default();
+    query_entities(context, GLOBAL_TRANSFORM)
+        .into_iter()
+        .filter(|entity| is_descendant_of(context, *entity, scene_root))
+        .for_each(|entity| {
+            let mask = component_mask(context, entity).unwrap_or_default();
+            if mask & POINTS != 0 {
+                members.points.push(entity);
+            }
+            if mask & POLYLINE != 0 {
+                members.polylines.push(entity);
+            }
+            if mask & (LINES | QUADS | MESH | LABEL) != 0 {
+                members.entities.push(entity);
+            }
+        });
+    members
+}
+
+/// Gathers the drawables of a scene in the local space of their entities,
+/// batched by entity so each batch is drawn with the model matrix of its entity
+fn collect_scene_drawables(
+    context: &crate::context::Context,
+    scene_root: EntityId,
+    glyph_atlas: &GlyphAtlas,
+) -> SceneDrawables {
+    use crate::context::*;
+    let members = collect_scene_members(context, scene_root);
+    let mut drawables = SceneDrawables::default();
+    for (model, entity) in members.entities.iter().enumerate() {
+        let model = model as u32;
+        if let Some(mesh) = get_component::<Mesh>(context, *entity, MESH) {
+            append_mesh(&mut drawables, mesh, model);
+        }
+        let EntityInstances {
+            lines,
+            quads,
+            glyphs,
+        } = collect_entity_instances(context, *entity, glyph_atlas);
+        push_batch(
+            &mut drawables.line_batches,
+            model,
+            &mut drawables.lines,
+            lines,
+        );
+        push_batch(
+            &mut drawables.quad_batches,
+            model,
+            &mut drawables.quads,
+            quads,
+        );
+        push_batch(
+            &mut drawables.glyph_batches,
+            model,
+            &mut drawables.glyphs,
+            glyphs,
+        );
+        drawables
+            .member_changes
+            .push(member_changes(context, *entity));
+    }
+    drawables.members = members;
+    drawables
+}
+
+/// The instances drawn for one entity, in its local space
+#[derive(Default)]
+struct EntityInstances {
+    lines: Vec<LineInstance>,
+    quads: Vec<QuadInstance>,
+    glyphs: Vec<GlyphInstance>,
+}
+
+fn collect_entity_instances(
+    context: &crate::context::Context,
+    entity: EntityId,
+    glyph_atlas: &GlyphAtlas,
+) -> EntityInstances {
+    use crate::context::*;
+    let mut instances = EntityInstances::default();
+    let label = get_component::<Label>(context, entity, LABEL);
+
+    if let Some(Lines(lines)) = get_component::<Lines>(context, entity, LINES) {
+        instances
+            .lines
+            .extend(lines.iter().map(|line| LineInstance {
+                start: nalgebra_glm::vec4(line.start.x, line.start.y, line.start.z, 1.0),
+                end: nalgebra_glm::vec4(line.end.x, line.end.y, line.end.z, 1.0),
+                color: line.color,
+                style: LineInstance::style(line),
+            }));
+    }
+    if let Some(mesh) = get_component::<Mesh>(context, entity, MESH) {
+        push_wireframe(&mut instances.lines, mesh);
+    }
+    if let Some(Label {
+        offset,
+        leader: Some(color),
+        ..
+    }) = label
+    {
+        instances.lines.push(LineInstance {
+            start: nalgebra_glm::vec4(0.0, 0.0, 0.0, 1.0),
+            end: nalgebra_glm::vec4(offset.x, offset.y, offset.z, 1.0),
+            color: *color,
+            style: LineInstance::THIN,
+        });
+    }
+
+    if let Some(Quads(quads)) = get_component::<Quads>(context, entity, QUADS) {
+        instances.quads.extend(quads.iter().map(|quad| {
+            let offset = nalgebra_glm::translation(&quad.offset);
+            let scale = nalgebra_glm::scaling(&nalgebra_glm::vec3(quad.size.x, quad.size.y, 1.0));
+            let transform = offset * scale;
+            QuadInstance {
+                model_matrix_0: transform.column(0).into(),
+                model_matrix_1: transform.column(1).into(),
+                model_matrix_2: transform.column(2).into(),
+                model_matrix_3: transform.column(3).into(),
+                color: quad.color,
+            }
+        }));
+    }
+
+    if let Some(label) = label {
+        glyph_atlas.layout_label(label, &mut instances.glyphs);
+    }
+    instances
+}
+
+/// Appends the instances of an entity, batched with its model matrix
+fn push_batch<T>(batches: &mut Vec<Batch>, model: u32, instances: &mut Vec<T>, added: Vec<T>) {
+    let first = instances.len();
+    instances.extend(added);
+    if instances.len() > first {
+        batches.push(Batch {
+            model,
+            range: first as u32..instances.len() as u32,
+        });
+    }
+}
+
+/// Appends a mesh to the merged mesh buffers of a scene, in the local space of its entity
+fn append_mesh(drawables: &mut SceneDrawables, mesh: &crate::context::Mesh, model: u32) {
+    let first_vertex = drawables.mesh_vertices.len();
+    let first_index = drawables.mesh_indices.len();
+    let smooth_normals;
+    let normals = if mesh.normals.len() == mesh.positions.len() {
+        &mesh.normals
+    } else {
+        smooth_normals = mesh.smooth_normals();
+        &smooth_normals
+    };
+    let white = nalgebra_glm::vec4(1.0, 1.0, 1.0, 1.0);
+    drawables
+        .mesh_vertices
+        .extend(mesh.positions.iter().zip(normals).enumerate().map(
+            |(index, (position, normal))| {
+                MeshVertex {
+                    position: *position,
+                    normal: *normal,
+                    color: mesh
+                        .color
+                        .component_mul(mesh.colors.get(index).unwrap_or(&white)),
+                }
+            },
+        ));
+    drawables.mesh_indices.extend(
+        mesh.triangles()
+            .flatten()
+            .map(|index| (first_vertex + index) as u32),
+    );
+    if drawables.mesh_indices.len() > first_index {
+        drawables.mesh_batches.push(Batch {
+            model,
+            range: first_index as u32..drawables.mesh_indices.len() as u32,
+        });
+    }
+}
+
+/// Appends the edges of a mesh's wireframe as lines,
+/// so they share the depth bias that keeps lines on top of surfaces
+fn push_wireframe(lines: &mut Vec<LineInstance>, mesh: &crate::context::Mesh) {
+    let Some(color) = mesh.wireframe else {
+        return;
+    };
+    let mut edges = std::collections::HashSet::new();
+    for [a, b, c] in mesh.triangles() {
+        for (start, end) in [(a, b), (b, c), (c, a)] {
+            // Edges shared by neighboring triangles are only drawn once
+            if !edges.insert((start.min(end), start.max(end))) {
+                continue;
+            }
+            let (start, end) = (mesh.positions[start], mesh.positions[end]);
+            lines.push(LineInstance {
+                start: nalgebra_glm::vec4(start.x, start.y, start.z, 1.0),
+                end: nalgebra_glm::vec4(end.x, end.y, end.z, 1.0),
+                color,
+                style: LineInstance::THIN,
+            });
+        }
+    }
+}
+
+/// Uploads camera uniforms to the pipelines of a render target,
+/// along with the uniforms of the point clouds and polylines in the scene it shows
+fn update_render_target(
+    gpu: &Gpu,
+    target: &mut RenderTarget,
+    pane: PaneView,
+    scenes: &std::collections::HashMap<EntityId, SceneRenderList>,
+    polyline_levels: &std::collections::HashMap<EntityId, PolylineLevels>,
+) {
+    let PaneView {
+        matrices,
+        viewport_size,
+        scene_root,
+        settings,
+        outlined,
+    } = pane;
+    let matrices = &matrices;
+    update_grid(matrices, &gpu.queue, &target.grid);
+    update_sky(matrices, &settings, &gpu.queue, &target.sky);
+    update_lines_uniform(matrices, viewport_size, &gpu.queue, &target.lines);
+    update_quads_uniform(matrices, &gpu.queue, &target.quads);
+    update_meshes_uniform(matrices, &gpu.queue, &target.meshes);
+    update_labels_uniform(matrices, viewport_size, &gpu.queue, &target.labels);
+    update_post_process(viewport_size, &settings, &gpu.queue, &target.post_process);
+
+    target.scene_root = scene_root.filter(|scene_root| scenes.contains_key(scene_root));
+    target.outlined = outlined;
+    let (points, polylines) = match target.scene_root.and_then(|root| scenes.get(&root)) {
+        Some(list) => (list.points.clone(), list.polylines.clone()),
+        None => (Vec::new(), Vec::new()),
+    };
+    update_points_uniform(
+        matrices,
+        viewport_size,
+        &gpu.device,
+        &gpu.queue,
+        &mut target.points,
+        points,
+    );
+    update_polylines_uniform(
+        matrices,
+        viewport_size,
+        &gpu.device,
+        &gpu.queue,
+        &mut target.polylines,
+        polylines,
+        polyline_levels,
+    );
+}
+
+/// This system renders and presents the next frame
+pub fn render_frame_system(context: &mut crate::context::Context) {
+    let tile_tree_context = &context.resources.user_interface.tile_tree_context;
+    let viewports = tile_tree_context
+        .viewport_tiles
+        .iter()
+        .map(|(tile_id, (kind, viewport))| (*tile_id, *kind, *viewport))
+        .collect::<Vec<_>>();
+    let pane_settings = viewports
+        .iter()
+        .map(|(tile_id, ..)| {
+            tile_tree_context
+                .pane_settings
+                .get(tile_id)
+                .cloned()
+                .unwrap_or_default()
+        })
+        .collect::<Vec<_>>();
+
+    // Targets are created before their uniforms are updated, so they are never drawn empty
+    let failures = ensure_viewports(context, &pane_settings);
+    for (position, error) in failures {
+        let Some(index) = context
+            .resources
+            .user_interface
+            .tile_tree_context
+            .tile_mapping
+            .get(&viewports[position].0)
+            .copied()
+        else {
+            continue;
+        };
+        crate::api::publish_event(
+            context,
+            crate::api::Event::Report {
+                report: crate::api::Report::EnvironmentFailed { index, error },
+            },
+        );
+    }
+    update_panes_system(context, &pane_settings);
+
+    let Some((egui::FullOutput { textures_delta, .. }, paint_jobs)) =
+        context.resources.user_interface.frame_output.take()
+    else {
+        return;
+    };
+
+    let Some(window_handle) = context.resources.window.handle.as_ref() else {
+        return;
+    };
+
+    let screen_descriptor = {
+        let (width, height) = context.resources.graphics.viewport_size;
+        egui_wgpu::ScreenDescriptor {
+            size_in_pixels: [width, height],
+            pixels_per_point: window_handle.scale_factor() as f32,
+        }
+    };
+
+    let Some(renderer) = context.resources.graphics.renderer.as_mut() else {
+        return;
+    };
+
+    for (id, image_delta) in &textures_delta.set {
+        renderer
+            .ui
+            .update_texture(&renderer.gpu.device, &renderer.gpu.queue, *id, image_delta);
+    }
+
+    for id in &textures_delta.free {
+        renderer.ui.free_texture(id);
+    }
+
+    let mut encoder = renderer
+        .gpu
+        .device
+        .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Render Encoder"),
+        });
+
+    renderer.ui.update_buffers(
+        &renderer.gpu.device,
+        &renderer.gpu.queue,
+        &mut encoder,
+        &paint_jobs,
+        &screen_descriptor,
+    );
+
+    // Headless renderers only render offscreen
+    let Some(surface) = renderer.gpu.surface.as_ref() else {
+        return;
+    };
+    let surface_texture = surface
+        .get_current_texture()
+        .expect("Failed to get surface texture!");
+
+    let surface_texture_view = surface_texture
+        .texture
+        .create_view(&wgpu::TextureViewDescriptor::default());
 
     {
         encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
@@ -519,10 +1156,18 @@ pub fn render_frame_system(context: &mut crate::context::Context) {
 
     viewports
         .iter()
+        .zip(pane_settings.iter())
         .zip(renderer.targets.iter())
-        .for_each(|((kind, viewport), target)| {
+        .for_each(|(((_, kind, viewport), settings), target)| {
             let viewport_size = (viewport.width() as u32, viewport.height() as u32);
-            render_pane(&mut encoder, kind, target, viewport_size);
+            render_pane(
+                &mut encoder,
+                kind,
+                settings,
+                target,
+                renderer,
+                viewport_size,
+            );
 
             let source_origin = wgpu::Origin3d { x: 0, y: 0, z: 0 };
             let destination_origin = wgpu::Origin3d {
@@ -582,17 +1227,157 @@ pub fn render_frame_system(context: &mut crate::context::Context) {
     }
 
     renderer.gpu.queue.submit(std::iter::once(encoder.finish()));
+
+    // Captures are read back before presenting, while the surface texture is still available
+    #[cfg(not(target_arch = "wasm32"))]
+    let captures = std::mem::take(&mut context.resources.graphics.pending_captures)
+        .into_iter()
+        .map(|capture| {
+            let image = capture_frame(
+                renderer,
+                &surface_texture.texture,
+                &viewports,
+                &context
+                    .resources
+                    .user_interface
+                    .tile_tree_context
+                    .tile_mapping,
+                &capture.target,
+            );
+            (capture, image)
+        })
+        .collect::<Vec<_>>();
+
     surface_texture.present();
+
+    #[cfg(not(target_arch = "wasm32"))]
+    captures.into_iter().for_each(|(capture, image)| {
+        crate::api::publish_capture(
+            context,
+            capture.id,
+            capture.output,
+            capture.connection,
+            image,
+        );
+    });
+}
+
+/// Reads back the window or a pane from the frame that was just rendered
+#[cfg(not(target_arch = "wasm32"))]
+fn capture_frame(
+    renderer: &Renderer,
+    surface_texture: &wgpu::Texture,
+    viewports: &[(egui_tiles::TileId, crate::ui::PaneKind, egui::Rect)],
+    tile_mapping: &std::collections::HashMap<egui_tiles::TileId, usize>,
+    target: &crate::api::CaptureTarget,
+) -> Result<CapturedImage, String> {
+    match target {
+        crate::api::CaptureTarget::Window => {
+            if !surface_texture
+                .usage()
+                .contains(wgpu::TextureUsages::COPY_SRC)
+            {
+                return Err("The window surface does not support captures".to_string());
+            }
+            read_texture(
+                &renderer.gpu,
+                surface_texture,
+                surface_texture.width(),
+                surface_texture.height(),
+            )
+        }
+        crate::api::CaptureTarget::Pane { index } => {
+            read_pane(renderer, viewports, tile_mapping, *index)
+        }
+        crate::api::CaptureTarget::Camera { .. } => {
+            Err("Cameras are captured offscreen, not from the window".to_string())
+        }
+    }
+}
+
+/// Reads back the target a pane was last rendered into, cropped to the size of the pane
+#[cfg(not(target_arch = "wasm32"))]
+fn read_pane(
+    renderer: &Renderer,
+    viewports: &[(egui_tiles::TileId, crate::ui::PaneKind, egui::Rect)],
+    tile_mapping: &std::collections::HashMap<egui_tiles::TileId, usize>,
+    index: usize,
+) -> Result<CapturedImage, String> {
+    let (viewport, target) = viewports
+        .iter()
+        .zip(renderer.targets.iter())
+        .find(|((tile_id, ..), _)| tile_mapping.get(tile_id) == Some(&index))
+        .map(|((_, _, viewport), target)| (viewport, target))
+        .ok_or_else(|| format!("Pane {index} is not a visible scene or color pane"))?;
+    let width = (viewport.width() as u32).min(target.color_texture.width());
+    let height = (viewport.height() as u32).min(target.color_texture.height());
+    if width == 0 || height == 0 {
+        return Err(format!("Pane {index} has no area"));
+    }
+    read_texture(&renderer.gpu, &target.color_texture, width, height)
+}
+
+/// Writes the frame that was just rendered to the active recording,
+/// finishing it once the requested number of frames is written
+#[cfg(not(target_arch = "wasm32"))]
+pub fn record_frame_system(context: &mut crate::context::Context) {
+    let Some(recording) = context.resources.graphics.recording.as_ref() else {
+        return;
+    };
+    let path = std::path::Path::new(&recording.directory)
+        .join(format!("frame-{:06}.png", recording.frames));
+    let image = match recording.target {
+        crate::api::CaptureTarget::Camera {
+            camera_entity,
+            width,
+            height,
+        } => render_camera_offscreen(context, camera_entity, width, height),
+        crate::api::CaptureTarget::Pane { index } => {
+            let tile_tree_context = &context.resources.user_interface.tile_tree_context;
+            let viewports = tile_tree_context
+                .viewport_tiles
+                .iter()
+                .map(|(tile_id, (kind, viewport))| (*tile_id, *kind, *viewport))
+                .collect::<Vec<_>>();
+            match context.resources.graphics.renderer.as_ref() {
+                Some(renderer) if renderer.gpu.surface.is_some() => {
+                    read_pane(renderer, &viewports, &tile_tree_context.tile_mapping, index)
+                }
+                _ => Err("Panes can only be recorded from a window".to_string()),
+            }
+        }
+        crate::api::CaptureTarget::Window => {
+            Err("Recordings capture a pane or a camera".to_string())
+        }
+    };
+    if let Err(error) = image.and_then(|image| image.save_png(&path)) {
+        crate::api::finish_recording(context, Err(error));
+        return;
+    }
+    let Some(recording) = context.resources.graphics.recording.as_mut() else {
+        return;
+    };
+    recording.frames += 1;
+    if recording.frames == recording.frame_count {
+        crate::api::finish_recording(context, Ok(()));
+    }
 }
 
+/// Renders a pane into its target, drawing from the GPU resources the renderer shares between targets.
+/// Scenes are drawn into the post process input and put through the pane's effects.
 fn render_pane(
     encoder: &mut wgpu::CommandEncoder,
     pane_kind: &crate::prelude::PaneKind,
+    settings: &crate::api::PaneSettings,
     target: &RenderTarget,
+    renderer: &Renderer,
     viewport_size: (u32, u32),
 ) {
     let clear_color = match pane_kind {
-        crate::ui::PaneKind::Scene { .. } => wgpu::Color::BLACK,
+        crate::ui::PaneKind::Scene { .. } => {
+            render_scene(encoder, settings, target, renderer, viewport_size);
+            return;
+        }
         crate::ui::PaneKind::Color(color) => wgpu::Color {
             r: (color.r() as f64 / 255.0),
             g: (color.g() as f64 / 255.0),
@@ -612,10 +1397,8 @@ fn render_pane(
             a: 1.0,
         },
     };
-
-    // Create viewport-sized render pass
-    let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-        label: Some("Viewport Render Pass"),
+    encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+        label: Some("Viewport Clear Pass"),
         color_attachments: &[Some(wgpu::RenderPassColorAttachment {
             view: &target.color_texture_view,
             resolve_target: None,
@@ -624,6 +1407,45 @@ fn render_pane(
                 store: wgpu::StoreOp::Store,
             },
         })],
+        depth_stencil_attachment: None,
+        timestamp_writes: None,
+        occlusion_query_set: None,
+    });
+}
+
+/// Draws the scene a target shows, then its outlines and effects
+fn render_scene(
+    encoder: &mut wgpu::CommandEncoder,
+    settings: &crate::api::PaneSettings,
+    target: &RenderTarget,
+    renderer: &Renderer,
+    viewport_size: (u32, u32),
+) {
+    if viewport_size.0 == 0 || viewport_size.1 == 0 {
+        return;
+    }
+    let scene_texture_view = &target.post_process.scene_texture_view;
+
+    // Multisampled passes resolve into the view and discard their samples
+    let (view, resolve_target, store) = match target.msaa_texture_view.as_ref() {
+        Some(msaa_texture_view) => (
+            msaa_texture_view,
+            Some(scene_texture_view),
+            wgpu::StoreOp::Discard,
+        ),
+        None => (scene_texture_view, None, wgpu::StoreOp::Store),
+    };
+
+    let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+        label: Some("Viewport Render Pass"),
+        color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+            view,
+            resolve_target,
+            ops: wgpu::Operations {
+                load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                store,
+            },
+        })],
         depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
             view: &target.depth_texture_view,
             depth_ops: Some(wgpu::Operations {
@@ -635,123 +1457,363 @@ fn render_pane(
         timestamp_writes: None,
         occlusion_query_set: None,
     });
+    set_pane_viewport(&mut render_pass, viewport_size);
+
+    let scene = target
+        .scene_root
+        .and_then(|scene_root| renderer.scenes.get(&scene_root));
+    render_sky(&mut render_pass, &target.sky);
+    if let Some(scene) = scene {
+        render_meshes(&mut render_pass, &target.meshes, scene);
+    }
+    render_points(&mut render_pass, &target.points, &renderer.point_clouds);
+    render_polylines(&mut render_pass, &target.polylines, &renderer.polylines);
+    if let Some(scene) = scene {
+        render_lines(&mut render_pass, &target.lines, scene);
+        render_quads(&mut render_pass, &target.quads, scene);
+    }
+    render_grid(&mut render_pass, &target.grid);
+
+    // Labels are drawn last, since they blend over everything without writing depth
+    if let Some(scene) = scene {
+        render_labels(
+            &mut render_pass,
+            &target.labels,
+            &renderer.glyph_atlas,
+            scene,
+        );
+    }
+    drop(render_pass);
 
-    // Set viewport to match pane size
-    let viewport = egui::Rect::from_min_size(
-        egui::pos2(0.0, 0.0),
-        egui::vec2(viewport_size.0 as f32, viewport_size.1 as f32),
-    );
-
-    if viewport.width() <= 0.0 || viewport.height() <= 0.0 {
-        return;
+    // The mask covers outlined entities whether they are hidden or not,
+    // so outlines show selections behind other entities
+    if settings.outline.enabled {
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Outline Mask Pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: &target.post_process.outline_mask_view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+        set_pane_viewport(&mut render_pass, viewport_size);
+        if let Some(scene) = scene.filter(|_| !target.outlined.is_empty()) {
+            render_meshes_outline(&mut render_pass, &target.meshes, scene, &target.outlined);
+            render_lines_outline(&mut render_pass, &target.lines, scene, &target.outlined);
+            render_quads_outline(&mut render_pass, &target.quads, scene, &target.outlined);
+        }
     }
 
+    render_post_process(
+        encoder,
+        &target.post_process,
+        settings,
+        &target.color_texture_view,
+        viewport_size,
+    );
+}
+
+/// Limits a pass to the top left of its target, where panes are drawn
+fn set_pane_viewport(render_pass: &mut wgpu::RenderPass<'_>, viewport_size: (u32, u32)) {
     render_pass.set_viewport(
-        viewport.min.x,
-        viewport.min.y,
-        viewport.width().max(1.0),
-        viewport.height().max(1.0),
+        0.0,
+        0.0,
+        viewport_size.0 as f32,
+        viewport_size.1 as f32,
         0.0,
         1.0,
     );
-
-    if matches!(pane_kind, crate::ui::PaneKind::Scene { .. }) {
-        render_sky(&mut render_pass, &target.sky);
-        render_lines(&mut render_pass, &target.lines);
-        render_quads(&mut render_pass, &target.quads);
-        render_grid(&mut render_pass, &target.grid);
-    }
 }
 
-fn ensure_viewports(context: &mut crate::Context, viewport_count: usize) {
+/// Creates a target for every pane that has none, recreates the targets of panes
+/// whose sample count changed and sets the environment of each target.
+/// Returns the position and error of each pane whose environment failed to load.
+fn ensure_viewports(
+    context: &mut crate::Context,
+    pane_settings: &[crate::api::PaneSettings],
+) -> Vec<(usize, String)> {
     let Some(renderer) = context.resources.graphics.renderer.as_mut() else {
-        return;
+        return Vec::new();
     };
 
-    if renderer.targets.len() >= viewport_count {
-        return;
+    let (width, height) = (
+        renderer.gpu.surface_config.width,
+        renderer.gpu.surface_config.height,
+    );
+    for (index, settings) in pane_settings.iter().enumerate() {
+        let sample_count = renderer.gpu.supported_sample_count(settings.msaa_samples);
+        match renderer.targets.get(index) {
+            Some(target) if target.sample_count == sample_count => {}
+            Some(_) => {
+                renderer.targets[index] =
+                    create_render_target(&renderer.gpu, width, height, sample_count);
+            }
+            None => {
+                let target = create_render_target(&renderer.gpu, width, height, sample_count);
+                renderer.targets.push(target);
+            }
+        }
     }
-    (0..(viewport_count - renderer.targets.len())).for_each(|_| {
-        let color_texture = renderer
-            .gpu
-            .device
-            .create_texture(&wgpu::TextureDescriptor {
-                label: Some("Viewport Texture"),
-                size: wgpu::Extent3d {
-                    width: renderer.gpu.surface_config.width,
-                    height: renderer.gpu.surface_config.height,
-                    depth_or_array_layers: 1,
-                },
-                mip_level_count: 1,
-                sample_count: 1,
-                dimension: wgpu::TextureDimension::D2,
-                format: renderer.gpu.surface_config.format,
-                usage: wgpu::TextureUsages::RENDER_ATTACHMENT
-                    | wgpu::TextureUsages::TEXTURE_BINDING
-                    | wgpu::TextureUsages::COPY_SRC,
-                view_formats: &[renderer.gpu.surface_config.format],
-            });
-        let color_texture_view = color_texture.create_view(&wgpu::TextureViewDescriptor::default());
 
-        let device: &wgpu::Device = &renderer.gpu.device;
-        let width = renderer.gpu.surface_config.width;
-        let height = renderer.gpu.surface_config.height;
-        let depth_texture = device.create_texture(
-            &(wgpu::TextureDescriptor {
-                label: Some("Depth Texture"),
-                size: wgpu::Extent3d {
-                    width,
-                    height,
-                    depth_or_array_layers: 1,
-                },
-                mip_level_count: 1,
-                sample_count: 1,
-                dimension: wgpu::TextureDimension::D2,
-                format: wgpu::TextureFormat::Depth32Float,
-                usage: wgpu::TextureUsages::RENDER_ATTACHMENT
-                    | wgpu::TextureUsages::TEXTURE_BINDING,
-                view_formats: &[],
-            }),
-        );
-        let depth_texture_view = depth_texture.create_view(&wgpu::TextureViewDescriptor {
-            label: None,
-            format: Some(wgpu::TextureFormat::Depth32Float),
-            dimension: Some(wgpu::TextureViewDimension::D2),
-            aspect: wgpu::TextureAspect::All,
-            base_mip_level: 0,
-            base_array_layer: 0,
-            array_layer_count: None,
-            mip_level_count: None,
-            usage: None,
-        });
-        let grid = create_grid(
-            &renderer.gpu.device,
-            renderer.gpu.surface_config.format,
-            DEPTH_FORMAT,
-        );
-        let sky = create_sky(
-            &renderer.gpu.device,
-            &renderer.gpu.queue,
-            renderer.gpu.surface_config.format,
-            DEPTH_FORMAT,
-        );
-        let lines = create_line_renderer(&renderer.gpu.device, renderer.gpu.surface_config.format);
-        let quads = create_quad_renderer(
-            &renderer.gpu.device,
-            renderer.gpu.surface_config.format,
-            DEPTH_FORMAT,
-        );
-        renderer.targets.push(RenderTarget {
-            color_texture,
-            color_texture_view,
-            depth_texture,
-            depth_texture_view,
-            grid,
-            sky,
-            lines,
-            quads,
+    let failures = renderer
+        .targets
+        .iter_mut()
+        .zip(pane_settings)
+        .enumerate()
+        .filter_map(|(position, (target, settings))| {
+            update_environment(
+                &renderer.gpu.device,
+                &renderer.gpu.queue,
+                &mut renderer.environment_maps,
+                &mut target.sky,
+                &settings.environment,
+            )
+            .err()
+            .map(|error| (position, error))
+        })
+        .collect();
+
+    // Maps no target shows anymore are freed
+    let shown = renderer
+        .targets
+        .iter()
+        .chain(renderer.offscreen_target.as_ref())
+        .filter_map(|target| target.sky.environment_map.clone())
+        .collect::<Vec<_>>();
+    renderer
+        .environment_maps
+        .retain(|key, _| shown.contains(key));
+    failures
+}
+
+/// An RGBA image read back from the GPU, with rows stored top to bottom
+#[derive(Debug, Clone)]
+pub struct CapturedImage {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<u8>,
+}
+
+impl CapturedImage {
+    /// Encodes the image as a PNG file in memory
+    pub fn to_png(&self) -> Result<Vec<u8>, String> {
+        let image = image::RgbaImage::from_raw(self.width, self.height, self.pixels.clone())
+            .ok_or("The image size does not match its pixels")?;
+        let mut bytes = std::io::Cursor::new(Vec::new());
+        image
+            .write_to(&mut bytes, image::ImageOutputFormat::Png)
+            .map_err(|error| format!("Failed to encode PNG: {error}"))?;
+        Ok(bytes.into_inner())
+    }
+
+    pub fn save_png(&self, path: impl AsRef<std::path::Path>) -> Result<(), String> {
+        let path = path.as_ref();
+        std::fs::write(path, self.to_png()?)
+            .map_err(|error| format!("Failed to write {}: {error}", path.display()))
+    }
+
+    /// Reads a PNG file, converting it to RGBA
+    pub fn load_png(path: impl AsRef<std::path::Path>) -> Result<Self, String> {
+        let path = path.as_ref();
+        let image = image::open(path)
+            .map_err(|error| format!("Failed to read {}: {error}", path.display()))?
+            .to_rgba8();
+        Ok(Self {
+            width: image.width(),
+            height: image.height(),
+            pixels: image.into_raw(),
+        })
+    }
+}
+
+/// Copies the top left corner of a texture into CPU memory.
+/// Blocks until the GPU has finished all submitted work.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn read_texture(
+    gpu: &Gpu,
+    texture: &wgpu::Texture,
+    width: u32,
+    height: u32,
+) -> Result<CapturedImage, String> {
+    let is_bgra = match texture.format() {
+        wgpu::TextureFormat::Rgba8Unorm | wgpu::TextureFormat::Rgba8UnormSrgb => false,
+        wgpu::TextureFormat::Bgra8Unorm | wgpu::TextureFormat::Bgra8UnormSrgb => true,
+        format => return Err(format!("Reading back {format:?} textures is not supported")),
+    };
+
+    // Rows of a texture copy must be aligned, so the padding is stripped after reading
+    let unpadded_bytes_per_row = width * 4;
+    let padded_bytes_per_row = unpadded_bytes_per_row.div_ceil(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT)
+        * wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+    let buffer = gpu.device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Readback Buffer"),
+        size: u64::from(padded_bytes_per_row) * u64::from(height),
+        usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+        mapped_at_creation: false,
+    });
+
+    let mut encoder = gpu
+        .device
+        .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Readback Encoder"),
         });
+    encoder.copy_texture_to_buffer(
+        wgpu::TexelCopyTextureInfo {
+            texture,
+            mip_level: 0,
+            origin: wgpu::Origin3d::ZERO,
+            aspect: wgpu::TextureAspect::All,
+        },
+        wgpu::TexelCopyBufferInfo {
+            buffer: &buffer,
+            layout: wgpu::TexelCopyBufferLayout {
+                offset: 0,
+                bytes_per_row: Some(padded_bytes_per_row),
+                rows_per_image: Some(height),
+            },
+        },
+        wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        },
+    );
+    gpu.queue.submit(std::iter::once(encoder.finish()));
+
+    let slice = buffer.slice(..);
+    let (sender, receiver) = std::sync::mpsc::channel();
+    slice.map_async(wgpu::MapMode::Read, move |result| {
+        let _ = sender.send(result);
     });
+    gpu.device.poll(wgpu::Maintain::Wait);
+    receiver
+        .recv()
+        .map_err(|_| "The readback buffer was never mapped".to_string())?
+        .map_err(|error| format!("Failed to map the readback buffer: {error}"))?;
+
+    let mut pixels = Vec::with_capacity((unpadded_bytes_per_row * height) as usize);
+    {
+        let data = slice.get_mapped_range();
+        data.chunks_exact(padded_bytes_per_row as usize)
+            .for_each(|row| pixels.extend_from_slice(&row[..unpadded_bytes_per_row as usize]));
+    }
+    buffer.unmap();
+
+    if is_bgra {
+        pixels
+            .chunks_exact_mut(4)
+            .for_each(|pixel| pixel.swap(0, 2));
+    }
+
+    Ok(CapturedImage {
+        width,
+        height,
+        pixels,
+    })
+}
+
+/// Renders the scene a camera belongs to into an offscreen texture and reads it back.
+/// This works with or without a window, so it backs both headless rendering and captures.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn render_camera_offscreen(
+    context: &mut crate::context::Context,
+    camera_entity: EntityId,
+    width: u32,
+    height: u32,
+) -> Result<CapturedImage, String> {
+    let settings = crate::api::PaneSettings::default();
+    render_camera_offscreen_with_settings(context, camera_entity, width, height, &settings)
+}
+
+/// Renders a camera offscreen the way a scene pane with the given settings would
+#[cfg(not(target_arch = "wasm32"))]
+pub fn render_camera_offscreen_with_settings(
+    context: &mut crate::context::Context,
+    camera_entity: EntityId,
+    width: u32,
+    height: u32,
+    settings: &crate::api::PaneSettings,
+) -> Result<CapturedImage, String> {
+    if width == 0 || height == 0 {
+        return Err("Images must be at least one pixel wide and tall".to_string());
+    }
+    let matrices = query_pane_camera_matrices(context, camera_entity, width as f32 / height as f32)
+        .ok_or_else(|| format!("Entity {} is not a camera", camera_entity.id))?;
+    let scene_root = query_scene_root(context, camera_entity);
+    update_point_clouds(context);
+    update_polylines(context);
+    update_glyph_atlas(context);
+    update_scene_render_lists(context, scene_root.as_slice());
+
+    let Some(renderer) = context.resources.graphics.renderer.as_mut() else {
+        return Err("The renderer is not available".to_string());
+    };
+
+    // Creating a target builds its pipelines, so the target is kept for renders of the same size
+    let sample_count = renderer.gpu.supported_sample_count(settings.msaa_samples);
+    let mut target = match renderer.offscreen_target.take() {
+        Some(target)
+            if target.color_texture.width() == width
+                && target.color_texture.height() == height
+                && target.sample_count == sample_count =>
+        {
+            target
+        }
+        _ => create_render_target(&renderer.gpu, width, height, sample_count),
+    };
+    if let Err(error) = update_environment(
+        &renderer.gpu.device,
+        &renderer.gpu.queue,
+        &mut renderer.environment_maps,
+        &mut target.sky,
+        &settings.environment,
+    ) {
+        log::error!("Failed to load the environment of an offscreen render: {error}");
+    }
+    let pane = PaneView {
+        matrices,
+        viewport_size: (width, height),
+        scene_root,
+        settings: settings.clone(),
+        outlined: Vec::new(),
+    };
+    update_render_target(
+        &renderer.gpu,
+        &mut target,
+        pane,
+        &renderer.scenes,
+        &renderer.polylines,
+    );
+
+    let mut encoder = renderer
+        .gpu
+        .device
+        .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Offscreen Render Encoder"),
+        });
+    let pane_kind = crate::ui::PaneKind::Scene {
+        scene_entity: camera_entity,
+        camera_entity: Some(camera_entity),
+    };
+    render_pane(
+        &mut encoder,
+        &pane_kind,
+        settings,
+        &target,
+        renderer,
+        (width, height),
+    );
+    renderer.gpu.queue.submit(std::iter::once(encoder.finish()));
+
+    let image = read_texture(&renderer.gpu, &target.color_texture, width, height);
+    renderer.offscreen_target = Some(target);
+    image
 }
 
 const DEPTH_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Depth32Float;
@@ -762,6 +1824,80 @@ pub async fn create_renderer_async(
     height: u32,
 ) -> crate::graphics::Renderer {
     let gpu = create_gpu_async(window, width, height).await;
+    create_renderer_with_gpu(gpu, width, height)
+}
+
+/// Creates a renderer without a window or surface, which renders into offscreen textures only.
+/// Software adapters such as llvmpipe are used when no GPU is present,
+/// or always when `force_fallback_adapter` is set.
+#[cfg(not(target_arch = "wasm32"))]
+pub async fn create_headless_renderer_async(
+    width: u32,
+    height: u32,
+    force_fallback_adapter: bool,
+) -> Result<crate::graphics::Renderer, String> {
+    let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor {
+        backends: wgpu::Backends::from_env().unwrap_or(wgpu::Backends::all()),
+        ..Default::default()
+    });
+
+    let attempts: &[bool] = if force_fallback_adapter {
+        &[true]
+    } else {
+        &[false, true]
+    };
+    let mut adapter = None;
+    for &force_fallback_adapter in attempts {
+        adapter = instance
+            .request_adapter(&wgpu::RequestAdapterOptions {
+                power_preference: wgpu::PowerPreference::default(),
+                compatible_surface: None,
+                force_fallback_adapter,
+            })
+            .await;
+        if adapter.is_some() {
+            break;
+        }
+    }
+    let adapter = adapter.ok_or(if force_fallback_adapter {
+        "No software graphics adapter is available"
+    } else {
+        "No graphics adapter is available, including software adapters"
+    })?;
+    log::info!("Headless adapter: {:?}", adapter.get_info());
+
+    let (device, queue) = request_device_async(&adapter)
+        .await
+        .map_err(|error| format!("Failed to request a device: {error}"))?;
+
+    // Offscreen targets use the same non-srgb format egui expects from a surface
+    let surface_config = wgpu::SurfaceConfiguration {
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+        format: wgpu::TextureFormat::Rgba8Unorm,
+        width,
+        height,
+        present_mode: wgpu::PresentMode::Fifo,
+        alpha_mode: wgpu::CompositeAlphaMode::Opaque,
+        view_formats: vec![],
+        desired_maximum_frame_latency: 2,
+    };
+
+    let sample_counts = supported_sample_counts(&adapter, SCENE_FORMAT);
+    let gpu = crate::graphics::Gpu {
+        surface: None,
+        device,
+        queue,
+        surface_config,
+        sample_counts,
+    };
+    Ok(create_renderer_with_gpu(gpu, width, height))
+}
+
+fn create_renderer_with_gpu(
+    gpu: crate::graphics::Gpu,
+    width: u32,
+    height: u32,
+) -> crate::graphics::Renderer {
     let ui_depth_texture_view = {
         let device: &wgpu::Device = &gpu.device;
         let texture = device.create_texture(
@@ -793,6 +1929,7 @@ pub async fn create_renderer_async(
             usage: None,
         })
     };
+    let glyph_atlas = GlyphAtlas::new(&gpu.device, &gpu.queue);
     let egui_renderer = egui_wgpu::Renderer::new(
         &gpu.device,
         gpu.surface_config.format,
@@ -806,6 +1943,12 @@ pub async fn create_renderer_async(
         ui_depth_texture_view,
         ui: egui_renderer,
         targets: Vec::new(),
+        offscreen_target: None,
+        point_clouds: std::collections::HashMap::new(),
+        polylines: std::collections::HashMap::new(),
+        scenes: std::collections::HashMap::new(),
+        glyph_atlas,
+        environment_maps: EnvironmentMaps::default(),
     }
 }
 
@@ -830,21 +1973,9 @@ async fn create_gpu_async(
         })
         .await
         .expect("Failed to request adapter!");
-    let (device, queue) = {
-        log::info!("WGPU Adapter Features: {:#?}", adapter.features());
-        adapter
-            .request_device(
-                &wgpu::DeviceDescriptor {
-                    label: Some("WGPU Device"),
-                    memory_hints: wgpu::MemoryHints::default(),
-                    required_features: adapter.features(),
-                    required_limits: wgpu::Limits::default().using_resolution(adapter.limits()),
-                },
-                None,
-            )
-            .await
-            .expect("Failed to request a device!")
-    };
+    let (device, queue) = request_device_async(&adapter)
+        .await
+        .expect("Failed to request a device!");
 
     let surface_capabilities = surface.get_capabilities(&adapter);
 
@@ -855,8 +1986,11 @@ async fn create_gpu_async(
         .find(|f| !f.is_srgb()) // egui wants a non-srgb surface texture
         .unwrap_or(surface_capabilities.formats[0]);
 
+    // Surfaces that allow it are also copied from, so the window can be captured
     let surface_config = wgpu::SurfaceConfiguration {
-        usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_DST,
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT
+            | wgpu::TextureUsages::COPY_DST
+            | (surface_capabilities.usages & wgpu::TextureUsages::COPY_SRC),
         format: surface_format,
         width,
         height,
@@ -869,24 +2003,91 @@ async fn create_gpu_async(
     surface.configure(&device, &surface_config);
 
     crate::graphics::Gpu {
-        surface,
+        surface: Some(surface),
+        sample_counts: supported_sample_counts(&adapter, SCENE_FORMAT),
         device,
         queue,
         surface_config,
     }
 }
 
+/// The sample counts that both the color format and the depth format can be multisampled
+/// and resolved with. Counts beyond those every device supports need adapter specific features.
+fn supported_sample_counts(adapter: &wgpu::Adapter, color_format: wgpu::TextureFormat) -> Vec<u32> {
+    let features = adapter.features();
+    let format_flags = |format: wgpu::TextureFormat| {
+        if features.contains(wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES) {
+            adapter.get_texture_format_features(format).flags
+        } else {
+            format.guaranteed_format_features(features).flags
+        }
+    };
+    let color_flags = format_flags(color_format);
+    let depth_flags = format_flags(DEPTH_FORMAT);
+    [1, 2, 4, 8, 16]
+        .into_iter()
+        .filter(|sample_count| {
+            *sample_count == 1
+                || (color_flags.sample_count_supported(*sample_count)
+                    && color_flags.contains(wgpu::TextureFormatFeatureFlags::MULTISAMPLE_RESOLVE)
+                    && depth_flags.sample_count_supported(*sample_count))
+        })
+        .collect()
+}
+
+async fn request_device_async(
+    adapter: &wgpu::Adapter,
+) -> Result<(wgpu::Device, wgpu::Queue), wgpu::RequestDeviceError> {
+    log::info!("WGPU Adapter Features: {:#?}", adapter.features());
+    adapter
+        .request_device(
+            &wgpu::DeviceDescriptor {
+                label: Some("WGPU Device"),
+                memory_hints: wgpu::MemoryHints::default(),
+                required_features: adapter.features(),
+                required_limits: wgpu::Limits::default().using_resolution(adapter.limits()),
+            },
+            None,
+        )
+        .await
+}
+
 pub use sky::*;
 mod sky {
+    use crate::api::Environment;
+
     pub struct Sky {
         pub uniform_buffer: wgpu::Buffer,
-        pub texture: wgpu::Texture,
-        pub texture_view: wgpu::TextureView,
         pub sampler: wgpu::Sampler,
-        pub bind_group: wgpu::BindGroup,
+        pub bind_group_layout: wgpu::BindGroupLayout,
+
+        /// Binds the environment map, absent while no environment is drawn
+        pub bind_group: Option<wgpu::BindGroup>,
         pub pipeline: wgpu::RenderPipeline,
+
+        /// The environment the bind group was created for
+        pub environment: Option<Environment>,
+
+        /// The cached map the bind group samples
+        pub environment_map: Option<EnvironmentMapKey>,
+    }
+
+    /// Identifies a cubemap converted from an equirectangular image
+    #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+    pub enum EnvironmentMapKey {
+        Builtin,
+        File(String),
+
+        /// Images sent as bytes are identified by a hash of their contents
+        Bytes(u64),
+
+        /// A black map bound by environments that are not drawn from one
+        Placeholder,
     }
 
+    /// The cubemaps of the environments shown, shared by all targets showing them
+    pub type EnvironmentMaps = std::collections::HashMap<EnvironmentMapKey, wgpu::TextureView>;
+
     #[repr(C)]
     #[derive(Default, Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
     struct SkyUniform {
@@ -894,13 +2095,23 @@ mod sky {
         proj_inv: nalgebra_glm::Mat4,
         view: nalgebra_glm::Mat4,
         cam_pos: nalgebra_glm::Vec4,
+
+        /// The operator, exposure and gamma, with an operator of zero leaving the sky linear
+        tonemapping: nalgebra_glm::Vec4,
+
+        /// Zero to sample the environment map, one for a gradient and two for a solid color
+        background: nalgebra_glm::Vec4,
+        top: nalgebra_glm::Vec4,
+        bottom: nalgebra_glm::Vec4,
     }
 
+    /// Creates the pipeline a target draws its environment with.
+    /// The environment map is bound once the environment is set with `update_environment`.
     pub fn create_sky(
         device: &wgpu::Device,
-        queue: &wgpu::Queue,
         surface_format: wgpu::TextureFormat,
         depth_format: wgpu::TextureFormat,
+        sample_count: u32,
     ) -> Sky {
         let sky_uniform_buffer = device.create_buffer(&wgpu::BufferDescriptor {
             label: Some("Sky Uniform Buffer"),
@@ -909,20 +2120,15 @@ mod sky {
             mapped_at_creation: false,
         });
 
-        let sky_texture = load_sky_texture(device, queue);
-        let sky_texture_view = sky_texture.create_view(&wgpu::TextureViewDescriptor {
-            dimension: Some(wgpu::TextureViewDimension::Cube),
-            usage: None,
-            ..Default::default()
-        });
+        let (filterable, filter_mode, sampler_binding_type) = float32_sampling(device);
 
         let sky_sampler = device.create_sampler(&wgpu::SamplerDescriptor {
             address_mode_u: wgpu::AddressMode::ClampToEdge,
             address_mode_v: wgpu::AddressMode::ClampToEdge,
             address_mode_w: wgpu::AddressMode::ClampToEdge,
-            mag_filter: wgpu::FilterMode::Linear,
-            min_filter: wgpu::FilterMode::Linear,
-            mipmap_filter: wgpu::FilterMode::Linear,
+            mag_filter: filter_mode,
+            min_filter: filter_mode,
+            mipmap_filter: filter_mode,
             ..Default::default()
         });
 
@@ -944,7 +2150,7 @@ mod sky {
                         binding: 1,
                         visibility: wgpu::ShaderStages::FRAGMENT,
                         ty: wgpu::BindingType::Texture {
-                            sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                            sample_type: wgpu::TextureSampleType::Float { filterable },
                             view_dimension: wgpu::TextureViewDimension::Cube,
                             multisampled: false,
                         },
@@ -953,31 +2159,12 @@ mod sky {
                     wgpu::BindGroupLayoutEntry {
                         binding: 2,
                         visibility: wgpu::ShaderStages::FRAGMENT,
-                        ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                        ty: wgpu::BindingType::Sampler(sampler_binding_type),
                         count: None,
                     },
                 ],
             });
 
-        let sky_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
-            layout: &sky_bind_group_layout,
-            entries: &[
-                wgpu::BindGroupEntry {
-                    binding: 0,
-                    resource: sky_uniform_buffer.as_entire_binding(),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 1,
-                    resource: wgpu::BindingResource::TextureView(&sky_texture_view),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 2,
-                    resource: wgpu::BindingResource::Sampler(&sky_sampler),
-                },
-            ],
-            label: Some("Sky Bind Group"),
-        });
-
         let sky_shader = device.create_shader_module(wgpu::include_wgsl!("shaders/sky.wgsl"));
 
         let sky_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
@@ -1018,29 +2205,176 @@ mod sky {
                 stencil: wgpu::StencilState::default(),
                 bias: wgpu::DepthBiasState::default(),
             }),
-            multisample: wgpu::MultisampleState::default(),
+            multisample: wgpu::MultisampleState {
+                count: sample_count,
+                ..Default::default()
+            },
             multiview: None,
             cache: None,
         });
         Sky {
             uniform_buffer: sky_uniform_buffer,
-            texture: sky_texture,
-            texture_view: sky_texture_view,
             sampler: sky_sampler,
-            bind_group: sky_bind_group,
+            bind_group_layout: sky_bind_group_layout,
+            bind_group: None,
             pipeline: sky_pipeline,
+            environment: None,
+            environment_map: None,
+        }
+    }
+
+    /// Binds the map of an environment when it differs from the one the sky was last set to,
+    /// converting it into a cubemap only when no target has it cached.
+    /// Environments that fail to load are not retried until the environment changes.
+    pub fn update_environment(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        environment_maps: &mut EnvironmentMaps,
+        sky: &mut Sky,
+        environment: &Environment,
+    ) -> Result<(), String> {
+        if sky.environment.as_ref() == Some(environment) {
+            return Ok(());
+        }
+        sky.environment = Some(environment.clone());
+        sky.bind_group = None;
+        sky.environment_map = None;
+
+        let key = match environment {
+            Environment::Sky => EnvironmentMapKey::Builtin,
+            Environment::HdrFile { path } => EnvironmentMapKey::File(path.clone()),
+            Environment::HdrBytes { bytes } => {
+                use std::hash::{Hash as _, Hasher as _};
+                let mut hasher = std::collections::hash_map::DefaultHasher::new();
+                bytes.0.hash(&mut hasher);
+                EnvironmentMapKey::Bytes(hasher.finish())
+            }
+            Environment::Gradient { .. } | Environment::Solid { .. } => {
+                EnvironmentMapKey::Placeholder
+            }
+            Environment::None => return Ok(()),
+        };
+        let texture_view = match environment_maps.get(&key) {
+            Some(texture_view) => texture_view.clone(),
+            None => {
+                let texture_view = load_environment_map(device, queue, environment)?.create_view(
+                    &wgpu::TextureViewDescriptor {
+                        dimension: Some(wgpu::TextureViewDimension::Cube),
+                        usage: None,
+                        ..Default::default()
+                    },
+                );
+                environment_maps.insert(key.clone(), texture_view.clone());
+                texture_view
+            }
+        };
+
+        sky.bind_group = Some(device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &sky.bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: sky.uniform_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::TextureView(&texture_view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: wgpu::BindingResource::Sampler(&sky.sampler),
+                },
+            ],
+            label: Some("Sky Bind Group"),
+        }));
+        sky.environment_map = Some(key);
+        Ok(())
+    }
+
+    fn load_environment_map(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        environment: &Environment,
+    ) -> Result<wgpu::Texture, String> {
+        match environment {
+            Environment::Sky => {
+                equirect_to_cube(device, queue, include_bytes!("hdr/restaurant.hdr"))
+            }
+            #[cfg(not(target_arch = "wasm32"))]
+            Environment::HdrFile { path } => {
+                let bytes = std::fs::read(path)
+                    .map_err(|error| format!("Failed to read {path}: {error}"))?;
+                equirect_to_cube(device, queue, &bytes)
+                    .map_err(|error| format!("Failed to load {path}: {error}"))
+            }
+            #[cfg(target_arch = "wasm32")]
+            Environment::HdrFile { .. } => {
+                Err("Files cannot be read in a browser, send their bytes instead".to_string())
+            }
+            Environment::HdrBytes { bytes } => equirect_to_cube(device, queue, &bytes.0),
+            Environment::Gradient { .. } | Environment::Solid { .. } | Environment::None => {
+                Ok(device.create_texture(&wgpu::TextureDescriptor {
+                    label: Some("Placeholder Cubemap Texture"),
+                    size: wgpu::Extent3d {
+                        width: 1,
+                        height: 1,
+                        depth_or_array_layers: 6,
+                    },
+                    mip_level_count: 1,
+                    sample_count: 1,
+                    dimension: wgpu::TextureDimension::D2,
+                    format: wgpu::TextureFormat::Rgba32Float,
+                    usage: wgpu::TextureUsages::TEXTURE_BINDING,
+                    view_formats: &[],
+                }))
+            }
+        }
+    }
+
+    /// Float32 textures can only be sampled with filtering when the device supports it,
+    /// which software adapters such as llvmpipe often do not
+    fn float32_sampling(
+        device: &wgpu::Device,
+    ) -> (bool, wgpu::FilterMode, wgpu::SamplerBindingType) {
+        if device
+            .features()
+            .contains(wgpu::Features::FLOAT32_FILTERABLE)
+        {
+            (
+                true,
+                wgpu::FilterMode::Linear,
+                wgpu::SamplerBindingType::Filtering,
+            )
+        } else {
+            (
+                false,
+                wgpu::FilterMode::Nearest,
+                wgpu::SamplerBindingType::NonFiltering,
+            )
         }
     }
 
-    fn load_sky_texture(device: &wgpu::Device, queue: &wgpu::Queue) -> wgpu::Texture {
-        let hdr_data = include_bytes!("hdr/restaurant.hdr");
+    /// Decodes an equirectangular `.hdr` image and converts it into a cubemap on the GPU
+    fn equirect_to_cube(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        hdr_data: &[u8],
+    ) -> Result<wgpu::Texture, String> {
+        let (filterable, filter_mode, sampler_binding_type) = float32_sampling(device);
         let cursor = std::io::Cursor::new(hdr_data);
-        let decoder =
-            image::codecs::hdr::HdrDecoder::new(cursor).expect("Failed to create HDR decoder");
+        let decoder = image::codecs::hdr::HdrDecoder::new(cursor)
+            .map_err(|error| format!("Failed to read HDR header: {error}"))?;
         let metadata = decoder.metadata();
+        let max_size = device.limits().max_texture_dimension_2d;
+        if metadata.width > max_size || metadata.height > max_size {
+            return Err(format!(
+                "The image is {}x{}, larger than the {max_size} pixels the GPU supports",
+                metadata.width, metadata.height
+            ));
+        }
         let decoded = decoder
             .read_image_hdr()
-            .expect("Failed to decode HDR image");
+            .map_err(|error| format!("Failed to decode HDR image: {error}"))?;
 
         // Create source texture for equirectangular image
         let equirect_texture = device.create_texture(&wgpu::TextureDescriptor {
@@ -1111,7 +2445,7 @@ mod sky {
                     binding: 0,
                     visibility: wgpu::ShaderStages::COMPUTE,
                     ty: wgpu::BindingType::Texture {
-                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        sample_type: wgpu::TextureSampleType::Float { filterable },
                         view_dimension: wgpu::TextureViewDimension::D2,
                         multisampled: false,
                     },
@@ -1120,7 +2454,7 @@ mod sky {
                 wgpu::BindGroupLayoutEntry {
                     binding: 1,
                     visibility: wgpu::ShaderStages::COMPUTE,
-                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    ty: wgpu::BindingType::Sampler(sampler_binding_type),
                     count: None,
                 },
                 wgpu::BindGroupLayoutEntry {
@@ -1169,9 +2503,9 @@ mod sky {
                             address_mode_u: wgpu::AddressMode::ClampToEdge,
                             address_mode_v: wgpu::AddressMode::ClampToEdge,
                             address_mode_w: wgpu::AddressMode::ClampToEdge,
-                            mag_filter: wgpu::FilterMode::Linear,
-                            min_filter: wgpu::FilterMode::Linear,
-                            mipmap_filter: wgpu::FilterMode::Linear,
+                            mag_filter: filter_mode,
+                            min_filter: filter_mode,
+                            mipmap_filter: filter_mode,
                             ..Default::default()
                         },
                     )),
@@ -1205,10 +2539,22 @@ mod sky {
 
         queue.submit(Some(encoder.finish()));
 
-        cubemap
+        Ok(cubemap)
     }
 
-    pub fn update_sky(matrices: &crate::context::CameraMatrices, queue: &wgpu::Queue, sky: &Sky) {
+    pub fn update_sky(
+        matrices: &crate::context::CameraMatrices,
+        settings: &crate::api::PaneSettings,
+        queue: &wgpu::Queue,
+        sky: &Sky,
+    ) {
+        let tonemapping = &settings.tonemapping;
+        let operator = match tonemapping.operator {
+            _ if !tonemapping.enabled => 0.0,
+            crate::api::TonemapOperator::Exponential => 1.0,
+            crate::api::TonemapOperator::Reinhard => 2.0,
+            crate::api::TonemapOperator::Aces => 3.0,
+        };
         let uniform = SkyUniform {
             proj: matrices.projection,
             proj_inv: nalgebra_glm::inverse(&matrices.projection),
@@ -1219,13 +2565,32 @@ mod sky {
                 matrices.camera_position.z,
                 1.0,
             ),
+            tonemapping: nalgebra_glm::vec4(operator, tonemapping.exposure, tonemapping.gamma, 0.0),
+            ..Default::default()
+        };
+        let uniform = match &settings.environment {
+            Environment::Gradient { top, bottom } => SkyUniform {
+                background: nalgebra_glm::vec4(1.0, 0.0, 0.0, 0.0),
+                top: top.clone().into(),
+                bottom: bottom.clone().into(),
+                ..uniform
+            },
+            Environment::Solid { color } => SkyUniform {
+                background: nalgebra_glm::vec4(2.0, 0.0, 0.0, 0.0),
+                top: color.clone().into(),
+                ..uniform
+            },
+            _ => uniform,
         };
         queue.write_buffer(&sky.uniform_buffer, 0, bytemuck::cast_slice(&[uniform]));
     }
 
     pub fn render_sky(render_pass: &mut wgpu::RenderPass<'_>, sky: &Sky) {
+        let Some(bind_group) = sky.bind_group.as_ref() else {
+            return;
+        };
         render_pass.set_pipeline(&sky.pipeline);
-        render_pass.set_bind_group(0, &sky.bind_group, &[]);
+        render_pass.set_bind_group(0, bind_group, &[]);
         render_pass.draw(0..3, 0..1);
     }
 }
@@ -1253,6 +2618,7 @@ mod grid {
         device: &wgpu::Device,
         color_format: wgpu::TextureFormat,
         depth_format: wgpu::TextureFormat,
+        sample_count: u32,
     ) -> Grid {
         use wgpu::util::DeviceExt;
 
@@ -1344,7 +2710,10 @@ mod grid {
                 stencil: wgpu::StencilState::default(),
                 bias: wgpu::DepthBiasState::default(),
             }),
-            multisample: wgpu::MultisampleState::default(),
+            multisample: wgpu::MultisampleState {
+                count: sample_count,
+                ..Default::default()
+            },
             multiview: None,
             cache: None,
         });
@@ -1379,70 +2748,1889 @@ mod grid {
     }
 }
 
-pub use lines::*;
-mod lines {
-    use super::*;
+pub use post_process::*;
+mod post_process {
+    use crate::api::PaneSettings;
 
-    pub struct Lines {
-        pub vertex_buffer: wgpu::Buffer,
-        pub instance_buffer: wgpu::Buffer,
-        pub uniform_buffer: wgpu::Buffer,
-        pub bind_group: wgpu::BindGroup,
-        pub pipeline: wgpu::RenderPipeline,
-    }
+    /// The format scenes are drawn in, which keeps colors brighter than white for bloom
+    pub const SCENE_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba16Float;
 
-    #[repr(C)]
-    #[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
-    pub struct LineVertex {
-        pub position: nalgebra_glm::Vec3,
+    /// The format of the mask outlined entities are drawn into
+    pub const OUTLINE_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::R8Unorm;
+
+    /// The most times bloom halves the scene, which sets how far the glow spreads
+    const BLOOM_LEVELS: u32 = 6;
+
+    /// The passes a pane's scene is put through after it is drawn and resolved,
+    /// before it is shown. Each pass reads the previous one's texture.
+    pub struct PostProcess {
+        /// The resolved scene
+        pub scene_texture: wgpu::Texture,
+        pub scene_texture_view: wgpu::TextureView,
+
+        /// Covers the outlined entities, drawn without depth at one sample
+        pub outline_mask_view: wgpu::TextureView,
+
+        /// The scene with bloom and outlines, read by FXAA
+        composite_texture_view: wgpu::TextureView,
+
+        /// A view of each level of the bloom texture, from the largest to the smallest
+        bloom_views: Vec<wgpu::TextureView>,
+
+        /// The inputs of the passes reading each bloom level
+        bloom_inputs: Vec<PassInput>,
+        prefilter_input: PassInput,
+        composite_input: PassInput,
+        fxaa_input: PassInput,
+
+        prefilter_pipeline: wgpu::RenderPipeline,
+        downsample_pipeline: wgpu::RenderPipeline,
+        upsample_pipeline: wgpu::RenderPipeline,
+        composite_pipeline: wgpu::RenderPipeline,
+        fxaa_pipeline: wgpu::RenderPipeline,
     }
 
-    #[repr(C)]
-    #[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
-    pub struct LineInstance {
-        pub start: nalgebra_glm::Vec4,
-        pub end: nalgebra_glm::Vec4,
-        pub color: nalgebra_glm::Vec4,
+    /// The texture a pass reads, along with its own uniforms
+    struct PassInput {
+        uniform_buffer: wgpu::Buffer,
+        bind_group: wgpu::BindGroup,
+
+        /// The size of the texture read
+        size: (u32, u32),
     }
 
     #[repr(C)]
-    #[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
-    pub struct LineUniform {
+    #[derive(Default, Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+    struct PostProcessUniform {
+        texel_size: nalgebra_glm::Vec2,
+
+        /// The fraction of the texture read that is covered by the pane
+        uv_scale: nalgebra_glm::Vec2,
+        bloom_texel_size: nalgebra_glm::Vec2,
+        bloom_uv_scale: nalgebra_glm::Vec2,
+
+        /// The threshold and intensity of bloom, with an intensity of zero without bloom
+        bloom: nalgebra_glm::Vec4,
+        outline_color: nalgebra_glm::Vec4,
+
+        /// The width of outlines in pixels, which is zero without outlines
+        outline: nalgebra_glm::Vec4,
+    }
+
+    /// The fragment target of pipelines drawing into an outline mask,
+    /// which keeps the greatest coverage of each pixel
+    pub fn outline_mask_target() -> wgpu::ColorTargetState {
+        let max = wgpu::BlendComponent {
+            src_factor: wgpu::BlendFactor::One,
+            dst_factor: wgpu::BlendFactor::One,
+            operation: wgpu::BlendOperation::Max,
+        };
+        wgpu::ColorTargetState {
+            format: OUTLINE_FORMAT,
+            blend: Some(wgpu::BlendState {
+                color: max,
+                alpha: max,
+            }),
+            write_mask: wgpu::ColorWrites::ALL,
+        }
+    }
+
+    pub fn create_post_process(
+        device: &wgpu::Device,
+        output_format: wgpu::TextureFormat,
+        width: u32,
+        height: u32,
+    ) -> PostProcess {
+        let create_texture = |label, format, size: (u32, u32), mip_level_count| {
+            device.create_texture(&wgpu::TextureDescriptor {
+                label: Some(label),
+                size: wgpu::Extent3d {
+                    width: size.0,
+                    height: size.1,
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format,
+                usage: wgpu::TextureUsages::RENDER_ATTACHMENT
+                    | wgpu::TextureUsages::TEXTURE_BINDING,
+                view_formats: &[],
+            })
+        };
+        let scene_texture = create_texture(
+            "Post Process Scene Texture",
+            SCENE_FORMAT,
+            (width, height),
+            1,
+        );
+        let scene_texture_view = scene_texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let outline_mask_view =
+            create_texture("Outline Mask Texture", OUTLINE_FORMAT, (width, height), 1)
+                .create_view(&wgpu::TextureViewDescriptor::default());
+        let composite_texture_view = create_texture(
+            "Post Process Composite Texture",
+            output_format,
+            (width, height),
+            1,
+        )
+        .create_view(&wgpu::TextureViewDescriptor::default());
+
+        // Bloom starts at half the size of the scene, halving until the smallest level is a pixel
+        let bloom_size = ((width / 2).max(1), (height / 2).max(1));
+        let bloom_levels = BLOOM_LEVELS.min(bloom_size.0.min(bloom_size.1).ilog2() + 1);
+        let bloom_texture = create_texture("Bloom Texture", SCENE_FORMAT, bloom_size, bloom_levels);
+        let bloom_views = (0..bloom_levels)
+            .map(|level| {
+                bloom_texture.create_view(&wgpu::TextureViewDescriptor {
+                    base_mip_level: level,
+                    mip_level_count: Some(1),
+                    ..Default::default()
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Post Process Sampler"),
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+
+        let texture_entry = |binding| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::FRAGMENT,
+            ty: wgpu::BindingType::Texture {
+                sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                view_dimension: wgpu::TextureViewDimension::D2,
+                multisampled: false,
+            },
+            count: None,
+        };
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            entries: &[
+                texture_entry(0),
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                texture_entry(3),
+                texture_entry(4),
+            ],
+            label: Some("Post Process Layout"),
+        });
+
+        // Every pass binds the bloom and outline textures, but only compositing reads them.
+        // The others bind the scene in their place, which is never written while they run.
+        let create_input = |label, source: &wgpu::TextureView, size, bloom, outline_mask| {
+            let uniform_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some(label),
+                size: std::mem::size_of::<PostProcessUniform>() as u64,
+                usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            });
+            let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+                layout: &bind_group_layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: wgpu::BindingResource::TextureView(source),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: wgpu::BindingResource::Sampler(&sampler),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 2,
+                        resource: uniform_buffer.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 3,
+                        resource: wgpu::BindingResource::TextureView(bloom),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 4,
+                        resource: wgpu::BindingResource::TextureView(outline_mask),
+                    },
+                ],
+                label: Some(label),
+            });
+            PassInput {
+                uniform_buffer,
+                bind_group,
+                size,
+            }
+        };
+        let prefilter_input = create_input(
+            "Bloom Prefilter Input",
+            &scene_texture_view,
+            (width, height),
+            &scene_texture_view,
+            &scene_texture_view,
+        );
+        let bloom_inputs = bloom_views
+            .iter()
+            .enumerate()
+            .map(|(level, view)| {
+                create_input(
+                    "Bloom Input",
+                    view,
+                    (
+                        (bloom_size.0 >> level).max(1),
+                        (bloom_size.1 >> level).max(1),
+                    ),
+                    &scene_texture_view,
+                    &scene_texture_view,
+                )
+            })
+            .collect::<Vec<_>>();
+        let composite_input = create_input(
+            "Composite Input",
+            &scene_texture_view,
+            (width, height),
+            &bloom_views[0],
+            &outline_mask_view,
+        );
+        let fxaa_input = create_input(
+            "FXAA Input",
+            &composite_texture_view,
+            (width, height),
+            &scene_texture_view,
+            &scene_texture_view,
+        );
+
+        let shader = device.create_shader_module(wgpu::include_wgsl!("shaders/post_process.wgsl"));
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Post Process Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let create_pipeline = |label, entry_point, format, blend| {
+            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some(label),
+                layout: Some(&pipeline_layout),
+                vertex: wgpu::VertexState {
+                    module: &shader,
+                    entry_point: Some("vs_main"),
+                    buffers: &[],
+                    compilation_options: Default::default(),
+                },
+                fragment: Some(wgpu::FragmentState {
+                    module: &shader,
+                    entry_point: Some(entry_point),
+                    targets: &[Some(wgpu::ColorTargetState {
+                        format,
+                        blend,
+                        write_mask: wgpu::ColorWrites::ALL,
+                    })],
+                    compilation_options: Default::default(),
+                }),
+                primitive: wgpu::PrimitiveState::default(),
+                depth_stencil: None,
+                multisample: wgpu::MultisampleState::default(),
+                multiview: None,
+                cache: None,
+            })
+        };
+
+        // Upsampled levels are added onto the level above, which already holds its own glow
+        let additive = wgpu::BlendComponent {
+            src_factor: wgpu::BlendFactor::One,
+            dst_factor: wgpu::BlendFactor::One,
+            operation: wgpu::BlendOperation::Add,
+        };
+        let prefilter_pipeline = create_pipeline(
+            "Bloom Prefilter Pipeline",
+            "fs_prefilter",
+            SCENE_FORMAT,
+            None,
+        );
+        let downsample_pipeline = create_pipeline(
+            "Bloom Downsample Pipeline",
+            "fs_downsample",
+            SCENE_FORMAT,
+            None,
+        );
+        let upsample_pipeline = create_pipeline(
+            "Bloom Upsample Pipeline",
+            "fs_upsample",
+            SCENE_FORMAT,
+            Some(wgpu::BlendState {
+                color: additive,
+                alpha: additive,
+            }),
+        );
+        let composite_pipeline =
+            create_pipeline("Composite Pipeline", "fs_composite", output_format, None);
+        let fxaa_pipeline = create_pipeline("FXAA Pipeline", "fs_fxaa", output_format, None);
+
+        PostProcess {
+            scene_texture,
+            scene_texture_view,
+            outline_mask_view,
+            composite_texture_view,
+            bloom_views,
+            bloom_inputs,
+            prefilter_input,
+            composite_input,
+            fxaa_input,
+            prefilter_pipeline,
+            downsample_pipeline,
+            upsample_pipeline,
+            composite_pipeline,
+            fxaa_pipeline,
+        }
+    }
+
+    /// The size of the pane in a texture the size of the target or a level of bloom,
+    /// which is rounded up at each level
+    fn pane_size(
+        viewport_size: (u32, u32),
+        target_size: (u32, u32),
+        size: (u32, u32),
+    ) -> (u32, u32) {
+        let scale = |pane: u32, target: u32, size: u32| {
+            let levels = (target / size).max(1).ilog2();
+            pane.div_ceil(1 << levels).clamp(1, size)
+        };
+        (
+            scale(viewport_size.0, target_size.0, size.0),
+            scale(viewport_size.1, target_size.1, size.1),
+        )
+    }
+
+    pub fn update_post_process(
+        viewport_size: (u32, u32),
+        settings: &PaneSettings,
+        queue: &wgpu::Queue,
+        post_process: &PostProcess,
+    ) {
+        let target_size = post_process.prefilter_input.size;
+        let scale = |size: (u32, u32)| {
+            let pane = pane_size(viewport_size, target_size, size);
+            (
+                nalgebra_glm::vec2(1.0 / size.0 as f32, 1.0 / size.1 as f32),
+                nalgebra_glm::vec2(pane.0 as f32 / size.0 as f32, pane.1 as f32 / size.1 as f32),
+            )
+        };
+        let (bloom_texel_size, bloom_uv_scale) = scale(post_process.bloom_inputs[0].size);
+        let bloom = &settings.bloom;
+        let outline = &settings.outline;
+        let uniform = PostProcessUniform {
+            bloom_texel_size,
+            bloom_uv_scale,
+            bloom: nalgebra_glm::vec4(
+                bloom.threshold,
+                if bloom.enabled { bloom.intensity } else { 0.0 },
+                0.0,
+                0.0,
+            ),
+            outline_color: outline.color.clone().into(),
+            outline: nalgebra_glm::vec4(
+                if outline.enabled { outline.width } else { 0.0 },
+                0.0,
+                0.0,
+                0.0,
+            ),
+            ..Default::default()
+        };
+        std::iter::once(&post_process.prefilter_input)
+            .chain(&post_process.bloom_inputs)
+            .chain([&post_process.composite_input, &post_process.fxaa_input])
+            .for_each(|input| {
+                let (texel_size, uv_scale) = scale(input.size);
+                let uniform = PostProcessUniform {
+                    texel_size,
+                    uv_scale,
+                    ..uniform
+                };
+                queue.write_buffer(&input.uniform_buffer, 0, bytemuck::cast_slice(&[uniform]));
+            });
+    }
+
+    /// Draws a pass over the pane's area of a texture
+    fn render_pass(
+        encoder: &mut wgpu::CommandEncoder,
+        label: &str,
+        pipeline: &wgpu::RenderPipeline,
+        input: &PassInput,
+        target: &wgpu::TextureView,
+        load: wgpu::LoadOp<wgpu::Color>,
+        viewport_size: (u32, u32),
+    ) {
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some(label),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: target,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load,
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+        render_pass.set_viewport(
+            0.0,
+            0.0,
+            viewport_size.0 as f32,
+            viewport_size.1 as f32,
+            0.0,
+            1.0,
+        );
+        render_pass.set_pipeline(pipeline);
+        render_pass.set_bind_group(0, &input.bind_group, &[]);
+        render_pass.draw(0..3, 0..1);
+    }
+
+    /// Puts the resolved scene through bloom, outlines and FXAA as the settings enable them,
+    /// writing the result to the target's color texture
+    pub fn render_post_process(
+        encoder: &mut wgpu::CommandEncoder,
+        post_process: &PostProcess,
+        settings: &PaneSettings,
+        output: &wgpu::TextureView,
+        viewport_size: (u32, u32),
+    ) {
+        let target_size = post_process.prefilter_input.size;
+        let level_size = |level: usize| {
+            pane_size(
+                viewport_size,
+                target_size,
+                post_process.bloom_inputs[level].size,
+            )
+        };
+
+        // Bright colors are halved into the first level, then down the levels and back up,
+        // blurring them further at each level
+        if settings.bloom.enabled {
+            render_pass(
+                encoder,
+                "Bloom Prefilter Pass",
+                &post_process.prefilter_pipeline,
+                &post_process.prefilter_input,
+                &post_process.bloom_views[0],
+                wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                level_size(0),
+            );
+            for level in 1..post_process.bloom_views.len() {
+                render_pass(
+                    encoder,
+                    "Bloom Downsample Pass",
+                    &post_process.downsample_pipeline,
+                    &post_process.bloom_inputs[level - 1],
+                    &post_process.bloom_views[level],
+                    wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                    level_size(level),
+                );
+            }
+            for level in (1..post_process.bloom_views.len()).rev() {
+                render_pass(
+                    encoder,
+                    "Bloom Upsample Pass",
+                    &post_process.upsample_pipeline,
+                    &post_process.bloom_inputs[level],
+                    &post_process.bloom_views[level - 1],
+                    wgpu::LoadOp::Load,
+                    level_size(level - 1),
+                );
+            }
+        }
+
+        let composite_output = if settings.fxaa {
+            &post_process.composite_texture_view
+        } else {
+            output
+        };
+        render_pass(
+            encoder,
+            "Composite Pass",
+            &post_process.composite_pipeline,
+            &post_process.composite_input,
+            composite_output,
+            wgpu::LoadOp::Load,
+            viewport_size,
+        );
+
+        if settings.fxaa {
+            render_pass(
+                encoder,
+                "FXAA Pass",
+                &post_process.fxaa_pipeline,
+                &post_process.fxaa_input,
+                output,
+                wgpu::LoadOp::Load,
+                viewport_size,
+            );
+        }
+    }
+}
+
+pub use instance_buffer::*;
+mod instance_buffer {
+    /// A vertex buffer of instances that persists across frames.
+    /// It grows geometrically when the instances outgrow it and is otherwise updated in place.
+    /// No copy of the instances is kept on the CPU, so callers tell it which range changed.
+    pub struct InstanceBuffer<T: bytemuck::Pod> {
+        pub buffer: wgpu::Buffer,
+        label: &'static str,
+        len: usize,
+        _instance: std::marker::PhantomData<T>,
+    }
+
+    impl<T: bytemuck::Pod> InstanceBuffer<T> {
+        const INITIAL_CAPACITY: usize = 1024;
+
+        pub fn new(device: &wgpu::Device, label: &'static str) -> Self {
+            Self {
+                buffer: create_instance_buffer::<T>(device, label, Self::INITIAL_CAPACITY),
+                label,
+                len: 0,
+                _instance: std::marker::PhantomData,
+            }
+        }
+
+        /// The number of instances to draw
+        pub fn len(&self) -> u32 {
+            self.len as u32
+        }
+
+        pub fn is_empty(&self) -> bool {
+            self.len == 0
+        }
+
+        pub fn capacity(&self) -> usize {
+            self.buffer.size() as usize / std::mem::size_of::<T>()
+        }
+
+        /// Replaces every instance
+        pub fn update(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, instances: &[T]) {
+            if instances.len() > self.capacity() {
+                let capacity = instances.len().next_power_of_two();
+                self.buffer = create_instance_buffer::<T>(device, self.label, capacity);
+            }
+            queue.write_buffer(&self.buffer, 0, bytemuck::cast_slice(instances));
+            self.len = instances.len();
+        }
+
+        /// Rewrites the instances from `start` onward and sets the number of instances drawn,
+        /// keeping the instances before `start`. The instances must fit in the buffer.
+        pub fn patch(&mut self, queue: &wgpu::Queue, start: usize, instances: &[T], len: usize) {
+            debug_assert!(len <= self.capacity() && start + instances.len() <= len);
+            if !instances.is_empty() {
+                let offset = (start * std::mem::size_of::<T>()) as wgpu::BufferAddress;
+                queue.write_buffer(&self.buffer, offset, bytemuck::cast_slice(instances));
+            }
+            self.len = len;
+        }
+
+        pub fn slice(&self) -> wgpu::BufferSlice<'_> {
+            let size = (self.len * std::mem::size_of::<T>()) as wgpu::BufferAddress;
+            self.buffer.slice(..size)
+        }
+    }
+
+    fn create_instance_buffer<T>(
+        device: &wgpu::Device,
+        label: &'static str,
+        capacity: usize,
+    ) -> wgpu::Buffer {
+        device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some(label),
+            size: (std::mem::size_of::<T>() * capacity) as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        })
+    }
+
+    /// The range of instances to rewrite after some entities changed, given how many instances
+    /// each entity had and has now, in the order they are stored.
+    /// Instances before the first changed entity stay where they are. When any entity's count
+    /// changed, every instance after it moves, so the range runs to the end.
+    pub fn changed_range(
+        old_counts: &[u32],
+        new_counts: &[u32],
+        changed: &[bool],
+    ) -> Option<std::ops::Range<usize>> {
+        let first = changed.iter().position(|changed| *changed)?;
+        let last = changed.iter().rposition(|changed| *changed)?;
+        let start = new_counts[..first].iter().sum::<u32>() as usize;
+        let resized = old_counts != new_counts;
+        let end = if resized {
+            new_counts.iter().sum::<u32>()
+        } else {
+            new_counts[..=last].iter().sum::<u32>()
+        } as usize;
+        (start < end).then_some(start..end)
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn changed_range_is_empty_without_changes() {
+            assert_eq!(changed_range(&[], &[], &[]), None);
+            assert_eq!(changed_range(&[2, 3], &[2, 3], &[false, false]), None);
+            assert_eq!(changed_range(&[2, 0], &[2, 0], &[false, true]), None);
+        }
+
+        #[test]
+        fn changed_range_covers_a_changed_entity_in_the_middle() {
+            assert_eq!(
+                changed_range(&[2, 3, 4], &[2, 3, 4], &[false, true, false]),
+                Some(2..5)
+            );
+            assert_eq!(
+                changed_range(&[2, 3, 4, 1], &[2, 3, 4, 1], &[true, false, true, false]),
+                Some(0..9)
+            );
+        }
+
+        #[test]
+        fn changed_range_runs_to_the_end_when_an_entity_grows() {
+            assert_eq!(
+                changed_range(&[2, 3, 4], &[2, 5, 4], &[false, true, false]),
+                Some(2..11)
+            );
+            assert_eq!(
+                changed_range(&[2, 3, 0], &[2, 3, 6], &[false, false, true]),
+                Some(5..11)
+            );
+        }
+
+        #[test]
+        fn changed_range_runs_to_the_end_when_an_entity_shrinks() {
+            assert_eq!(
+                changed_range(&[2, 3, 4], &[2, 1, 4], &[false, true, false]),
+                Some(2..7)
+            );
+            assert_eq!(
+                changed_range(&[2, 3, 4], &[2, 3, 0], &[false, false, true]),
+                None
+            );
+        }
+    }
+}
+
+pub use scenes::*;
+mod scenes {
+    use super::{
+        GlyphInstance, InstanceBuffer, LineInstance, MeshVertex, PointCloudUniform, PolylineUniform,
+    };
+    use crate::prelude::EntityId;
+    use wgpu::util::DeviceExt as _;
+
+    /// A range of instances or indices drawn with the model matrix of one entity
+    pub struct Batch {
+        /// The index of the model matrix in the scene's model uniforms
+        pub model: u32,
+        pub range: std::ops::Range<u32>,
+    }
+
+    #[repr(C)]
+    #[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+    pub struct ModelUniform {
+        pub model: nalgebra_glm::Mat4,
+
+        /// The inverse transpose of the model matrix, which keeps normals perpendicular to surfaces
+        pub normal: nalgebra_glm::Mat4,
+    }
+
+    impl ModelUniform {
+        pub fn new(model: &nalgebra_glm::Mat4) -> Self {
+            let normal = nalgebra_glm::inverse_transpose(nalgebra_glm::mat4_to_mat3(model));
+            Self {
+                model: *model,
+                normal: nalgebra_glm::mat3_to_mat4(&normal),
+            }
+        }
+    }
+
+    /// The entities of a scene that are drawn, which is all a list needs from the world every frame
+    #[derive(Default, Clone, PartialEq)]
+    pub struct SceneMembers {
+        /// The entities with lines, quads, meshes or labels, in the order of their model matrices
+        pub entities: Vec<EntityId>,
+
+        /// The point clouds and polylines, whose uniforms hold their own model matrices
+        pub points: Vec<EntityId>,
+        pub polylines: Vec<EntityId>,
+    }
+
+    /// The drawables of a scene gathered on the CPU, in the local space of their entities
+    #[derive(Default)]
+    pub struct SceneDrawables {
+        pub members: SceneMembers,
+
+        /// The changes of each entity the drawables were gathered from, in the order of the members
+        pub member_changes: Vec<(u64, u64)>,
+        pub lines: Vec<LineInstance>,
+        pub line_batches: Vec<Batch>,
+        pub quads: Vec<super::QuadInstance>,
+        pub quad_batches: Vec<Batch>,
+
+        /// Every mesh of the scene, merged into one vertex and index buffer
+        pub mesh_vertices: Vec<MeshVertex>,
+        pub mesh_indices: Vec<u32>,
+        pub mesh_batches: Vec<Batch>,
+
+        /// The backgrounds and glyphs of every label
+        pub glyphs: Vec<GlyphInstance>,
+        pub glyph_batches: Vec<Batch>,
+    }
+
+    /// How a render list catches up with the world
+    pub enum SceneUpdate {
+        /// Replaces every buffer of the list
+        Rebuild(SceneDrawables),
+
+        /// Rewrites the instances of the entities that changed
+        Patch(ScenePatch),
+    }
+
+    pub struct ScenePatch {
+        pub member_changes: Vec<(u64, u64)>,
+        pub lines: InstancePatch<LineInstance>,
+        pub quads: InstancePatch<super::QuadInstance>,
+        pub glyphs: InstancePatch<GlyphInstance>,
+    }
+
+    /// The instances of one buffer rewritten from `start`, along with the new number of instances
+    /// and the batches they are drawn in
+    pub struct InstancePatch<T> {
+        pub start: usize,
+        pub instances: Vec<T>,
+        pub len: usize,
+        pub batches: Vec<Batch>,
+    }
+
+    impl<T: bytemuck::Pod> InstancePatch<T> {
+        fn apply(self, queue: &wgpu::Queue, buffer: &mut InstanceBuffer<T>) -> Vec<Batch> {
+            buffer.patch(queue, self.start, &self.instances, self.len);
+            self.batches
+        }
+    }
+
+    /// The number of instances each model draws, including models that draw none
+    pub fn batch_counts(batches: &[Batch], model_count: usize) -> Vec<u32> {
+        let mut counts = vec![0; model_count];
+        batches
+            .iter()
+            .for_each(|batch| counts[batch.model as usize] += batch.range.len() as u32);
+        counts
+    }
+
+    /// The batches of instances stored model after model, skipping models that draw none
+    pub fn counted_batches(counts: &[u32]) -> Vec<Batch> {
+        let mut first = 0;
+        counts
+            .iter()
+            .enumerate()
+            .filter_map(|(model, count)| {
+                let range = first..first + count;
+                first = range.end;
+                (!range.is_empty()).then_some(Batch {
+                    model: model as u32,
+                    range,
+                })
+            })
+            .collect()
+    }
+
+    /// The GPU buffers of a scene, shared by every pane showing it.
+    /// They are only rebuilt when drawable components change, while the model matrices
+    /// of the entities are uploaded every frame and applied on the GPU.
+    pub struct SceneRenderList {
+        /// The component changes the list was built from
+        pub changes: u64,
+        pub members: SceneMembers,
+
+        /// The changes of each entity when its instances were last gathered
+        pub member_changes: Vec<(u64, u64)>,
+
+        pub lines: InstanceBuffer<LineInstance>,
+        pub line_batches: Vec<Batch>,
+        pub quads: InstanceBuffer<super::QuadInstance>,
+        pub quad_batches: Vec<Batch>,
+        pub mesh_vertex_buffer: wgpu::Buffer,
+        pub mesh_index_buffer: wgpu::Buffer,
+        pub mesh_batches: Vec<Batch>,
+        pub glyphs: InstanceBuffer<GlyphInstance>,
+        pub glyph_batches: Vec<Batch>,
+
+        /// Holds the model matrix of every entity, each at its own offset
+        pub model_buffer: wgpu::Buffer,
+        pub model_bind_group_layout: wgpu::BindGroupLayout,
+        pub model_bind_group: wgpu::BindGroup,
+        pub model_stride: u64,
+
+        /// The uniforms of the scene's point clouds and polylines, refreshed every frame
+        pub points: Vec<(EntityId, PointCloudUniform)>,
+        pub polylines: Vec<(EntityId, PolylineUniform)>,
+    }
+
+    /// The layout of the model matrices bound by every pipeline drawing from a render list
+    pub fn create_model_bind_group_layout(device: &wgpu::Device) -> wgpu::BindGroupLayout {
+        device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::VERTEX,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: true,
+                    min_binding_size: wgpu::BufferSize::new(
+                        std::mem::size_of::<ModelUniform>() as u64
+                    ),
+                },
+                count: None,
+            }],
+            label: Some("Model Bind Group Layout"),
+        })
+    }
+
+    fn create_model_uniforms(
+        device: &wgpu::Device,
+        layout: &wgpu::BindGroupLayout,
+        stride: u64,
+        capacity: u64,
+    ) -> (wgpu::Buffer, wgpu::BindGroup) {
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Model Uniform Buffer"),
+            size: stride * capacity,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+                    buffer: &buffer,
+                    offset: 0,
+                    size: wgpu::BufferSize::new(std::mem::size_of::<ModelUniform>() as u64),
+                }),
+            }],
+            label: Some("Model Bind Group"),
+        });
+        (buffer, bind_group)
+    }
+
+    /// Buffers may not be empty, so empty meshes get a placeholder that is never drawn
+    fn create_mesh_buffer(
+        device: &wgpu::Device,
+        label: &str,
+        contents: &[u8],
+        usage: wgpu::BufferUsages,
+    ) -> wgpu::Buffer {
+        device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some(label),
+            contents: if contents.is_empty() {
+                &[0; 16]
+            } else {
+                contents
+            },
+            usage,
+        })
+    }
+
+    impl SceneRenderList {
+        pub fn new(device: &wgpu::Device) -> Self {
+            let model_bind_group_layout = create_model_bind_group_layout(device);
+            let alignment = u64::from(device.limits().min_uniform_buffer_offset_alignment);
+            let model_stride =
+                (std::mem::size_of::<ModelUniform>() as u64).div_ceil(alignment) * alignment;
+            let (model_buffer, model_bind_group) =
+                create_model_uniforms(device, &model_bind_group_layout, model_stride, 1);
+            Self {
+                changes: 0,
+                members: SceneMembers::default(),
+                member_changes: Vec::new(),
+                lines: InstanceBuffer::new(device, "Line Instance Buffer"),
+                line_batches: Vec::new(),
+                quads: InstanceBuffer::new(device, "Quad Instance Buffer"),
+                quad_batches: Vec::new(),
+                mesh_vertex_buffer: create_mesh_buffer(
+                    device,
+                    "Mesh Vertex Buffer",
+                    &[],
+                    wgpu::BufferUsages::VERTEX,
+                ),
+                mesh_index_buffer: create_mesh_buffer(
+                    device,
+                    "Mesh Index Buffer",
+                    &[],
+                    wgpu::BufferUsages::INDEX,
+                ),
+                mesh_batches: Vec::new(),
+                glyphs: InstanceBuffer::new(device, "Glyph Instance Buffer"),
+                glyph_batches: Vec::new(),
+                model_buffer,
+                model_bind_group_layout,
+                model_bind_group,
+                model_stride,
+                points: Vec::new(),
+                polylines: Vec::new(),
+            }
+        }
+
+        /// Replaces the drawables of the list with ones gathered from the world
+        pub fn update_drawables(
+            &mut self,
+            device: &wgpu::Device,
+            queue: &wgpu::Queue,
+            drawables: SceneDrawables,
+            changes: u64,
+        ) {
+            self.changes = changes;
+            self.members = drawables.members;
+            self.member_changes = drawables.member_changes;
+            self.lines.update(device, queue, &drawables.lines);
+            self.line_batches = drawables.line_batches;
+            self.quads.update(device, queue, &drawables.quads);
+            self.quad_batches = drawables.quad_batches;
+            self.mesh_vertex_buffer = create_mesh_buffer(
+                device,
+                "Mesh Vertex Buffer",
+                bytemuck::cast_slice(&drawables.mesh_vertices),
+                wgpu::BufferUsages::VERTEX,
+            );
+            self.mesh_index_buffer = create_mesh_buffer(
+                device,
+                "Mesh Index Buffer",
+                bytemuck::cast_slice(&drawables.mesh_indices),
+                wgpu::BufferUsages::INDEX,
+            );
+            self.mesh_batches = drawables.mesh_batches;
+            self.glyphs.update(device, queue, &drawables.glyphs);
+            self.glyph_batches = drawables.glyph_batches;
+        }
+
+        /// Rewrites the instances of the entities that changed, keeping everything else
+        pub fn apply_patch(&mut self, queue: &wgpu::Queue, patch: ScenePatch, changes: u64) {
+            self.changes = changes;
+            self.member_changes = patch.member_changes;
+            self.line_batches = patch.lines.apply(queue, &mut self.lines);
+            self.quad_batches = patch.quads.apply(queue, &mut self.quads);
+            self.glyph_batches = patch.glyphs.apply(queue, &mut self.glyphs);
+        }
+
+        /// Uploads the model matrices of the list's entities, in the order of its members
+        pub fn update_models(
+            &mut self,
+            device: &wgpu::Device,
+            queue: &wgpu::Queue,
+            models: &[ModelUniform],
+        ) {
+            if models.is_empty() {
+                return;
+            }
+
+            // The uniform buffer only grows, doubling so that adding entities rarely recreates it
+            let stride = self.model_stride;
+            let required_size = stride * models.len() as u64;
+            if self.model_buffer.size() < required_size {
+                let capacity = (models.len() as u64).next_power_of_two();
+                (self.model_buffer, self.model_bind_group) =
+                    create_model_uniforms(device, &self.model_bind_group_layout, stride, capacity);
+            }
+
+            let mut data = vec![0_u8; required_size as usize];
+            models.iter().enumerate().for_each(|(index, model)| {
+                let offset = index * stride as usize;
+                let bytes = bytemuck::bytes_of(model);
+                data[offset..offset + bytes.len()].copy_from_slice(bytes);
+            });
+            queue.write_buffer(&self.model_buffer, 0, &data);
+        }
+
+        /// Binds the model matrix a batch is drawn with
+        pub fn bind_model(&self, render_pass: &mut wgpu::RenderPass, index: u32, batch: &Batch) {
+            let offset = (u64::from(batch.model) * self.model_stride) as u32;
+            render_pass.set_bind_group(index, &self.model_bind_group, &[offset]);
+        }
+
+        /// The batches drawn for any of the outlined entities
+        pub fn outlined_batches<'a>(
+            &'a self,
+            batches: &'a [Batch],
+            outlined: &'a [EntityId],
+        ) -> impl Iterator<Item = &'a Batch> {
+            batches
+                .iter()
+                .filter(|batch| outlined.contains(&self.members.entities[batch.model as usize]))
+        }
+    }
+}
+
+pub use lines::*;
+mod lines {
+    use super::*;
+
+    pub struct Lines {
+        pub vertex_buffer: wgpu::Buffer,
+        pub uniform_buffer: wgpu::Buffer,
+        pub bind_group: wgpu::BindGroup,
+        pub pipeline: wgpu::RenderPipeline,
+        pub outline_pipeline: wgpu::RenderPipeline,
+    }
+
+    #[repr(C)]
+    #[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+    pub struct LineVertex {
+        pub position: nalgebra_glm::Vec3,
+    }
+
+    #[repr(C)]
+    #[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+    pub struct LineInstance {
+        pub start: nalgebra_glm::Vec4,
+        pub end: nalgebra_glm::Vec4,
+        pub color: nalgebra_glm::Vec4,
+
+        /// The width, dash length, gap length and cap of the line
+        pub style: nalgebra_glm::Vec4,
+    }
+
+    impl LineInstance {
+        /// The style of solid one pixel lines, such as mesh wireframes
+        pub const THIN: nalgebra_glm::Vec4 = nalgebra_glm::Vec4::new(1.0, 0.0, 0.0, 0.0);
+
+        pub fn style(line: &crate::context::Line) -> nalgebra_glm::Vec4 {
+            let dash = line.dash.unwrap_or_default();
+            let cap = match line.cap {
+                crate::api::LineCap::Butt => 0.0,
+                crate::api::LineCap::Square => 1.0,
+                crate::api::LineCap::Round => 2.0,
+            };
+            nalgebra_glm::vec4(line.width, dash.x, dash.y, cap)
+        }
+    }
+
+    #[repr(C)]
+    #[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+    pub struct LineUniform {
+        pub view_proj: nalgebra_glm::Mat4,
+        pub viewport_size: nalgebra_glm::Vec4,
+    }
+
+    pub fn create_line_renderer(
+        device: &wgpu::Device,
+        format: wgpu::TextureFormat,
+        sample_count: u32,
+    ) -> Lines {
+        // Two triangles spanning the line from its start (x = 0) to its end (x = 1),
+        // and across it from one edge (y = -1) to the other (y = 1)
+        let vertices = [
+            [0.0, -1.0],
+            [1.0, -1.0],
+            [1.0, 1.0],
+            [0.0, -1.0],
+            [1.0, 1.0],
+            [0.0, 1.0],
+        ]
+        .map(|[x, y]| LineVertex {
+            position: nalgebra_glm::vec3(x, y, 0.0),
+        });
+
+        let vertex_buffer = wgpu::util::DeviceExt::create_buffer_init(
+            device,
+            &wgpu::util::BufferInitDescriptor {
+                label: Some("Line Vertex Buffer"),
+                contents: bytemuck::cast_slice(&vertices),
+                usage: wgpu::BufferUsages::VERTEX,
+            },
+        );
+
+        let uniform_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Line Uniform Buffer"),
+            size: std::mem::size_of::<LineUniform>() as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::VERTEX,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+            label: Some("Line Bind Group Layout"),
+        });
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: uniform_buffer.as_entire_binding(),
+            }],
+            label: Some("Line Bind Group"),
+        });
+
+        let shader = device.create_shader_module(wgpu::include_wgsl!("shaders/line.wgsl"));
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Line Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout, &create_model_bind_group_layout(device)],
+            push_constant_ranges: &[],
+        });
+
+        let buffers = [
+            wgpu::VertexBufferLayout {
+                array_stride: std::mem::size_of::<LineVertex>() as wgpu::BufferAddress,
+                step_mode: wgpu::VertexStepMode::Vertex,
+                attributes: &wgpu::vertex_attr_array![0 => Float32x3],
+            },
+            wgpu::VertexBufferLayout {
+                array_stride: std::mem::size_of::<LineInstance>() as wgpu::BufferAddress,
+                step_mode: wgpu::VertexStepMode::Instance,
+                attributes: &wgpu::vertex_attr_array![
+                    1 => Float32x4,
+                    2 => Float32x4,
+                    3 => Float32x4,
+                    4 => Float32x4
+                ],
+            },
+        ];
+        let primitive = wgpu::PrimitiveState {
+            topology: wgpu::PrimitiveTopology::TriangleList,
+            strip_index_format: None,
+            front_face: wgpu::FrontFace::Ccw,
+            cull_mode: None,
+            unclipped_depth: false,
+            polygon_mode: wgpu::PolygonMode::Fill,
+            conservative: false,
+        };
+
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Line Pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                buffers: &buffers,
+                compilation_options: Default::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("fs_main"),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format,
+                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+                compilation_options: Default::default(),
+            }),
+            primitive,
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: DEPTH_FORMAT,
+                depth_write_enabled: true,
+                depth_compare: wgpu::CompareFunction::LessEqual,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState {
+                    constant: -1, // Small negative bias to avoid z-fighting
+                    slope_scale: 0.0,
+                    clamp: 0.0,
+                },
+            }),
+            multisample: wgpu::MultisampleState {
+                count: sample_count,
+                ..Default::default()
+            },
+            multiview: None,
+            cache: None,
+        });
+
+        // Outlined entities are drawn into a mask at one sample, whatever their depth
+        let outline_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Line Outline Pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                buffers: &buffers,
+                compilation_options: Default::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("fs_outline"),
+                targets: &[Some(outline_mask_target())],
+                compilation_options: Default::default(),
+            }),
+            primitive,
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+            cache: None,
+        });
+
+        Lines {
+            vertex_buffer,
+            uniform_buffer,
+            bind_group,
+            pipeline,
+            outline_pipeline,
+        }
+    }
+
+    pub fn update_lines_uniform(
+        matrices: &crate::context::CameraMatrices,
+        viewport_size: (u32, u32),
+        queue: &wgpu::Queue,
+        lines: &Lines,
+    ) {
+        let uniform = LineUniform {
+            view_proj: matrices.projection * matrices.view,
+            viewport_size: nalgebra_glm::vec4(
+                viewport_size.0.max(1) as f32,
+                viewport_size.1.max(1) as f32,
+                0.0,
+                0.0,
+            ),
+        };
+
+        queue.write_buffer(&lines.uniform_buffer, 0, bytemuck::cast_slice(&[uniform]));
+    }
+
+    pub fn render_lines(
+        render_pass: &mut wgpu::RenderPass,
+        lines: &Lines,
+        scene: &SceneRenderList,
+    ) {
+        if scene.line_batches.is_empty() {
+            return;
+        }
+        render_pass.set_pipeline(&lines.pipeline);
+        render_pass.set_bind_group(0, &lines.bind_group, &[]);
+        render_pass.set_vertex_buffer(0, lines.vertex_buffer.slice(..));
+        render_pass.set_vertex_buffer(1, scene.lines.slice());
+        scene.line_batches.iter().for_each(|batch| {
+            scene.bind_model(render_pass, 1, batch);
+            render_pass.draw(0..6, batch.range.clone());
+        });
+    }
+
+    /// Draws the lines of outlined entities into the outline mask
+    pub fn render_lines_outline(
+        render_pass: &mut wgpu::RenderPass,
+        lines: &Lines,
+        scene: &SceneRenderList,
+        outlined: &[EntityId],
+    ) {
+        if scene.line_batches.is_empty() {
+            return;
+        }
+        render_pass.set_pipeline(&lines.outline_pipeline);
+        render_pass.set_bind_group(0, &lines.bind_group, &[]);
+        render_pass.set_vertex_buffer(0, lines.vertex_buffer.slice(..));
+        render_pass.set_vertex_buffer(1, scene.lines.slice());
+        scene
+            .outlined_batches(&scene.line_batches, outlined)
+            .for_each(|batch| {
+                scene.bind_model(render_pass, 1, batch);
+                render_pass.draw(0..6, batch.range.clone());
+            });
+    }
+}
+
+pub use quads::*;
+mod quads {
+    use super::{create_model_bind_group_layout, outline_mask_target, SceneRenderList};
+    use crate::prelude::EntityId;
+    use wgpu::util::DeviceExt as _;
+
+    pub struct Quads {
+        pub vertex_buffer: wgpu::Buffer,
+        pub index_buffer: wgpu::Buffer,
+        pub uniform_buffer: wgpu::Buffer,
+        pub bind_group: wgpu::BindGroup,
+        pub pipeline: wgpu::RenderPipeline,
+        pub outline_pipeline: wgpu::RenderPipeline,
+    }
+
+    #[repr(C)]
+    #[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+    pub struct QuadVertex {
+        pub position: nalgebra_glm::Vec3,
+    }
+
+    #[repr(C)]
+    #[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+    pub struct QuadInstance {
+        pub model_matrix_0: nalgebra_glm::Vec4,
+        pub model_matrix_1: nalgebra_glm::Vec4,
+        pub model_matrix_2: nalgebra_glm::Vec4,
+        pub model_matrix_3: nalgebra_glm::Vec4,
+        pub color: nalgebra_glm::Vec4,
+    }
+
+    #[repr(C)]
+    #[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+    pub struct QuadUniform {
+        pub view_proj: nalgebra_glm::Mat4,
+    }
+
+    pub fn create_quad_renderer(
+        device: &wgpu::Device,
+        surface_format: wgpu::TextureFormat,
+        depth_format: wgpu::TextureFormat,
+        sample_count: u32,
+    ) -> Quads {
+        // Create a unit quad centered at origin in XY plane
+        let vertices = [
+            QuadVertex {
+                position: nalgebra_glm::vec3(-0.5, -0.5, 0.0),
+            },
+            QuadVertex {
+                position: nalgebra_glm::vec3(0.5, -0.5, 0.0),
+            },
+            QuadVertex {
+                position: nalgebra_glm::vec3(0.5, 0.5, 0.0),
+            },
+            QuadVertex {
+                position: nalgebra_glm::vec3(-0.5, 0.5, 0.0),
+            },
+        ];
+
+        let indices: &[u16] = &[0, 1, 2, 2, 3, 0];
+
+        let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Quad Vertex Buffer"),
+            contents: bytemuck::cast_slice(&vertices),
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+
+        let index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Quad Index Buffer"),
+            contents: bytemuck::cast_slice(indices),
+            usage: wgpu::BufferUsages::INDEX,
+        });
+
+        let uniform_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Quad Uniform Buffer"),
+            size: std::mem::size_of::<QuadUniform>() as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::VERTEX,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+            label: Some("Quad Bind Group Layout"),
+        });
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: uniform_buffer.as_entire_binding(),
+            }],
+            label: Some("Quad Bind Group"),
+        });
+
+        let shader = device.create_shader_module(wgpu::include_wgsl!("shaders/quad.wgsl"));
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Quad Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout, &create_model_bind_group_layout(device)],
+            push_constant_ranges: &[],
+        });
+
+        let buffers = [
+            // Vertex buffer
+            wgpu::VertexBufferLayout {
+                array_stride: std::mem::size_of::<QuadVertex>() as wgpu::BufferAddress,
+                step_mode: wgpu::VertexStepMode::Vertex,
+                attributes: &wgpu::vertex_attr_array![0 => Float32x3],
+            },
+            // Instance buffer
+            wgpu::VertexBufferLayout {
+                array_stride: std::mem::size_of::<QuadInstance>() as wgpu::BufferAddress,
+                step_mode: wgpu::VertexStepMode::Instance,
+                attributes: &wgpu::vertex_attr_array![
+                    1 => Float32x4,
+                    2 => Float32x4,
+                    3 => Float32x4,
+                    4 => Float32x4,
+                    5 => Float32x4
+                ],
+            },
+        ];
+        let primitive = wgpu::PrimitiveState {
+            topology: wgpu::PrimitiveTopology::TriangleList,
+            strip_index_format: None,
+            front_face: wgpu::FrontFace::Ccw,
+            cull_mode: None,
+            unclipped_depth: false,
+            polygon_mode: wgpu::PolygonMode::Fill,
+            conservative: false,
+        };
+
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Quad Pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                buffers: &buffers,
+                compilation_options: Default::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("fs_main"),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: surface_format,
+                    blend: Some(wgpu::BlendState {
+                        color: wgpu::BlendComponent {
+                            src_factor: wgpu::BlendFactor::SrcAlpha,
+                            dst_factor: wgpu::BlendFactor::OneMinusSrcAlpha,
+                            operation: wgpu::BlendOperation::Add,
+                        },
+                        alpha: wgpu::BlendComponent::OVER,
+                    }),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+                compilation_options: Default::default(),
+            }),
+            primitive,
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: depth_format,
+                depth_write_enabled: true,
+                depth_compare: wgpu::CompareFunction::LessEqual,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
+            multisample: wgpu::MultisampleState {
+                count: sample_count,
+                ..Default::default()
+            },
+            multiview: None,
+            cache: None,
+        });
+
+        // Outlined entities are drawn into a mask at one sample, whatever their depth
+        let outline_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Quad Outline Pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                buffers: &buffers,
+                compilation_options: Default::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("fs_outline"),
+                targets: &[Some(outline_mask_target())],
+                compilation_options: Default::default(),
+            }),
+            primitive,
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+            cache: None,
+        });
+
+        Quads {
+            vertex_buffer,
+            index_buffer,
+            uniform_buffer,
+            bind_group,
+            pipeline,
+            outline_pipeline,
+        }
+    }
+
+    pub fn update_quads_uniform(
+        matrices: &crate::context::CameraMatrices,
+        queue: &wgpu::Queue,
+        quads: &Quads,
+    ) {
+        let uniform = QuadUniform {
+            view_proj: matrices.projection * matrices.view,
+        };
+
+        queue.write_buffer(&quads.uniform_buffer, 0, bytemuck::cast_slice(&[uniform]));
+    }
+
+    pub fn render_quads(
+        render_pass: &mut wgpu::RenderPass,
+        quads: &Quads,
+        scene: &SceneRenderList,
+    ) {
+        if scene.quad_batches.is_empty() {
+            return;
+        }
+        render_pass.set_pipeline(&quads.pipeline);
+        render_pass.set_bind_group(0, &quads.bind_group, &[]);
+        render_pass.set_vertex_buffer(0, quads.vertex_buffer.slice(..));
+        render_pass.set_vertex_buffer(1, scene.quads.slice());
+        render_pass.set_index_buffer(quads.index_buffer.slice(..), wgpu::IndexFormat::Uint16);
+        scene.quad_batches.iter().for_each(|batch| {
+            scene.bind_model(render_pass, 1, batch);
+            render_pass.draw_indexed(0..6, 0, batch.range.clone());
+        });
+    }
+
+    /// Draws the quads of outlined entities into the outline mask
+    pub fn render_quads_outline(
+        render_pass: &mut wgpu::RenderPass,
+        quads: &Quads,
+        scene: &SceneRenderList,
+        outlined: &[EntityId],
+    ) {
+        if scene.quad_batches.is_empty() {
+            return;
+        }
+        render_pass.set_pipeline(&quads.outline_pipeline);
+        render_pass.set_bind_group(0, &quads.bind_group, &[]);
+        render_pass.set_vertex_buffer(0, quads.vertex_buffer.slice(..));
+        render_pass.set_vertex_buffer(1, scene.quads.slice());
+        render_pass.set_index_buffer(quads.index_buffer.slice(..), wgpu::IndexFormat::Uint16);
+        scene
+            .outlined_batches(&scene.quad_batches, outlined)
+            .for_each(|batch| {
+                scene.bind_model(render_pass, 1, batch);
+                render_pass.draw_indexed(0..6, 0, batch.range.clone());
+            });
+    }
+}
+
+pub use meshes::*;
+mod meshes {
+    use super::{create_model_bind_group_layout, outline_mask_target, SceneRenderList};
+    use crate::prelude::EntityId;
+
+    pub struct Meshes {
+        pub uniform_buffer: wgpu::Buffer,
+        pub bind_group: wgpu::BindGroup,
+        pub pipeline: wgpu::RenderPipeline,
+        pub outline_pipeline: wgpu::RenderPipeline,
+    }
+
+    #[repr(C)]
+    #[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+    pub struct MeshVertex {
+        pub position: nalgebra_glm::Vec3,
+        pub normal: nalgebra_glm::Vec3,
+        pub color: nalgebra_glm::Vec4,
+    }
+
+    #[repr(C)]
+    #[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+    pub struct MeshUniform {
         pub view_proj: nalgebra_glm::Mat4,
+        pub camera_position: nalgebra_glm::Vec4,
     }
 
-    pub fn create_line_renderer(device: &wgpu::Device, format: wgpu::TextureFormat) -> Lines {
-        let vertices = [
-            LineVertex {
-                position: nalgebra_glm::vec3(0.0, 0.0, 0.0),
+    pub fn create_mesh_renderer(
+        device: &wgpu::Device,
+        surface_format: wgpu::TextureFormat,
+        depth_format: wgpu::TextureFormat,
+        sample_count: u32,
+    ) -> Meshes {
+        let uniform_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Mesh Uniform Buffer"),
+            size: std::mem::size_of::<MeshUniform>() as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+            label: Some("Mesh Bind Group Layout"),
+        });
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: uniform_buffer.as_entire_binding(),
+            }],
+            label: Some("Mesh Bind Group"),
+        });
+
+        let shader = device.create_shader_module(wgpu::include_wgsl!("shaders/mesh.wgsl"));
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Mesh Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout, &create_model_bind_group_layout(device)],
+            push_constant_ranges: &[],
+        });
+
+        let buffers = [wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<MeshVertex>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: &wgpu::vertex_attr_array![
+                0 => Float32x3,
+                1 => Float32x3,
+                2 => Float32x4
+            ],
+        }];
+        let primitive = wgpu::PrimitiveState {
+            topology: wgpu::PrimitiveTopology::TriangleList,
+            strip_index_format: None,
+            front_face: wgpu::FrontFace::Ccw,
+            // Imported meshes often have inconsistent winding, so both sides are drawn
+            cull_mode: None,
+            unclipped_depth: false,
+            polygon_mode: wgpu::PolygonMode::Fill,
+            conservative: false,
+        };
+
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Mesh Pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                buffers: &buffers,
+                compilation_options: Default::default(),
             },
-            LineVertex {
-                position: nalgebra_glm::vec3(1.0, 0.0, 0.0),
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("fs_main"),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: surface_format,
+                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+                compilation_options: Default::default(),
+            }),
+            primitive,
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: depth_format,
+                depth_write_enabled: true,
+                depth_compare: wgpu::CompareFunction::LessEqual,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
+            multisample: wgpu::MultisampleState {
+                count: sample_count,
+                ..Default::default()
             },
-        ];
+            multiview: None,
+            cache: None,
+        });
 
-        let vertex_buffer = wgpu::util::DeviceExt::create_buffer_init(
-            device,
-            &wgpu::util::BufferInitDescriptor {
-                label: Some("Line Vertex Buffer"),
-                contents: bytemuck::cast_slice(&vertices),
-                usage: wgpu::BufferUsages::VERTEX,
+        // Outlined entities are drawn into a mask at one sample, whatever their depth
+        let outline_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Mesh Outline Pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                buffers: &buffers,
+                compilation_options: Default::default(),
             },
-        );
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("fs_outline"),
+                targets: &[Some(outline_mask_target())],
+                compilation_options: Default::default(),
+            }),
+            primitive,
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+            cache: None,
+        });
+
+        Meshes {
+            uniform_buffer,
+            bind_group,
+            pipeline,
+            outline_pipeline,
+        }
+    }
+
+    pub fn update_meshes_uniform(
+        matrices: &crate::context::CameraMatrices,
+        queue: &wgpu::Queue,
+        meshes: &Meshes,
+    ) {
+        let uniform = MeshUniform {
+            view_proj: matrices.projection * matrices.view,
+            camera_position: nalgebra_glm::vec4(
+                matrices.camera_position.x,
+                matrices.camera_position.y,
+                matrices.camera_position.z,
+                1.0,
+            ),
+        };
+
+        queue.write_buffer(&meshes.uniform_buffer, 0, bytemuck::cast_slice(&[uniform]));
+    }
+
+    pub fn render_meshes(
+        render_pass: &mut wgpu::RenderPass,
+        meshes: &Meshes,
+        scene: &SceneRenderList,
+    ) {
+        if scene.mesh_batches.is_empty() {
+            return;
+        }
+        render_pass.set_pipeline(&meshes.pipeline);
+        render_pass.set_bind_group(0, &meshes.bind_group, &[]);
+        render_pass.set_vertex_buffer(0, scene.mesh_vertex_buffer.slice(..));
+        render_pass.set_index_buffer(scene.mesh_index_buffer.slice(..), wgpu::IndexFormat::Uint32);
+        scene.mesh_batches.iter().for_each(|batch| {
+            scene.bind_model(render_pass, 1, batch);
+            render_pass.draw_indexed(batch.range.clone(), 0, 0..1);
+        });
+    }
+
+    /// Draws the meshes of outlined entities into the outline mask
+    pub fn render_meshes_outline(
+        render_pass: &mut wgpu::RenderPass,
+        meshes: &Meshes,
+        scene: &SceneRenderList,
+        outlined: &[EntityId],
+    ) {
+        if scene.mesh_batches.is_empty() {
+            return;
+        }
+        render_pass.set_pipeline(&meshes.outline_pipeline);
+        render_pass.set_bind_group(0, &meshes.bind_group, &[]);
+        render_pass.set_vertex_buffer(0, scene.mesh_vertex_buffer.slice(..));
+        render_pass.set_index_buffer(scene.mesh_index_buffer.slice(..), wgpu::IndexFormat::Uint32);
+        scene
+            .outlined_batches(&scene.mesh_batches, outlined)
+            .for_each(|batch| {
+                scene.bind_model(render_pass, 1, batch);
+                render_pass.draw_indexed(batch.range.clone(), 0, 0..1);
+            });
+    }
+}
+
+pub use points::*;
+mod points {
+    use wgpu::util::DeviceExt as _;
+
+    use crate::prelude::EntityId;
+
+    pub struct Points {
+        pub uniform_buffer: wgpu::Buffer,
+        pub bind_group: wgpu::BindGroup,
+
+        /// Holds the uniforms of every point cloud drawn by the pane, each at its own offset
+        pub cloud_uniform_buffer: wgpu::Buffer,
+        pub cloud_bind_group_layout: wgpu::BindGroupLayout,
+        pub cloud_bind_group: wgpu::BindGroup,
+        pub cloud_uniform_stride: u64,
+
+        /// The point clouds drawn by the pane, in the order of their uniforms
+        pub clouds: Vec<EntityId>,
+        pub pipeline: wgpu::RenderPipeline,
+    }
+
+    /// The GPU buffers of a point cloud, shared by every pane that draws it.
+    /// The points they were uploaded from are kept to tell when they need uploading again.
+    pub struct PointCloud {
+        pub source: crate::context::Points,
+        pub position_buffer: wgpu::Buffer,
+        pub color_buffer: wgpu::Buffer,
+        pub count: u32,
+    }
+
+    impl PointCloud {
+        /// Whether the buffers still hold these points, which is the case
+        /// until their arrays are replaced, as only the arrays are uploaded
+        pub fn is_uploaded_from(&self, points: &crate::context::Points) -> bool {
+            std::sync::Arc::ptr_eq(&self.source.positions, &points.positions)
+                && std::sync::Arc::ptr_eq(&self.source.colors, &points.colors)
+                && std::sync::Arc::ptr_eq(&self.source.scalars, &points.scalars)
+                && self.source.scalar_range == points.scalar_range
+        }
+    }
+
+    #[repr(C)]
+    #[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+    pub struct PointsUniform {
+        pub view: nalgebra_glm::Mat4,
+        pub projection: nalgebra_glm::Mat4,
+        pub viewport_size: nalgebra_glm::Vec4,
+    }
 
-        let initial_instance_capacity = 1024;
-        let instance_buffer_size = std::mem::size_of::<LineInstance>() * initial_instance_capacity;
+    #[repr(C)]
+    #[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+    pub struct PointCloudUniform {
+        pub model: nalgebra_glm::Mat4,
+        pub color: nalgebra_glm::Vec4,
+        pub size: f32,
+        pub sizing: u32,
+        pub shape: u32,
+        pub padding: u32,
+    }
 
-        let instance_buffer = device.create_buffer(&wgpu::BufferDescriptor {
-            label: Some("Line Instance Buffer"),
-            size: instance_buffer_size as u64,
-            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
-            mapped_at_creation: false,
-        });
+    impl PointCloudUniform {
+        pub fn new(points: &crate::context::Points, model: nalgebra_glm::Mat4) -> Self {
+            Self {
+                model,
+                color: points.color,
+                size: points.size,
+                sizing: match points.sizing {
+                    crate::api::PointSizing::Screen => 0,
+                    crate::api::PointSizing::World => 1,
+                },
+                shape: match points.shape {
+                    crate::api::PointShape::Round => 0,
+                    crate::api::PointShape::Square => 1,
+                },
+                padding: 0,
+            }
+        }
+    }
 
+    pub fn create_point_renderer(
+        device: &wgpu::Device,
+        surface_format: wgpu::TextureFormat,
+        depth_format: wgpu::TextureFormat,
+        sample_count: u32,
+    ) -> Points {
         let uniform_buffer = device.create_buffer(&wgpu::BufferDescriptor {
-            label: Some("Line Uniform Buffer"),
-            size: std::mem::size_of::<LineUniform>() as u64,
+            label: Some("Points Uniform Buffer"),
+            size: std::mem::size_of::<PointsUniform>() as u64,
             usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
             mapped_at_creation: false,
         });
@@ -1458,7 +4646,7 @@ mod lines {
                 },
                 count: None,
             }],
-            label: Some("Line Bind Group Layout"),
+            label: Some("Points Bind Group Layout"),
         });
 
         let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
@@ -1467,37 +4655,59 @@ mod lines {
                 binding: 0,
                 resource: uniform_buffer.as_entire_binding(),
             }],
-            label: Some("Line Bind Group"),
+            label: Some("Points Bind Group"),
         });
 
-        let shader = device.create_shader_module(wgpu::include_wgsl!("shaders/line.wgsl"));
+        // Every point cloud binds its uniforms at a dynamic offset into one shared buffer
+        let cloud_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                entries: &[wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: true,
+                        min_binding_size: wgpu::BufferSize::new(
+                            std::mem::size_of::<PointCloudUniform>() as u64,
+                        ),
+                    },
+                    count: None,
+                }],
+                label: Some("Point Cloud Bind Group Layout"),
+            });
+
+        let alignment = u64::from(device.limits().min_uniform_buffer_offset_alignment);
+        let cloud_uniform_stride =
+            (std::mem::size_of::<PointCloudUniform>() as u64).div_ceil(alignment) * alignment;
+        let (cloud_uniform_buffer, cloud_bind_group) =
+            create_cloud_uniforms(device, &cloud_bind_group_layout, cloud_uniform_stride, 1);
+
+        let shader = device.create_shader_module(wgpu::include_wgsl!("shaders/points.wgsl"));
 
         let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
-            label: Some("Line Pipeline Layout"),
-            bind_group_layouts: &[&bind_group_layout],
+            label: Some("Points Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout, &cloud_bind_group_layout],
             push_constant_ranges: &[],
         });
 
         let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-            label: Some("Line Pipeline"),
+            label: Some("Points Pipeline"),
             layout: Some(&pipeline_layout),
             vertex: wgpu::VertexState {
                 module: &shader,
                 entry_point: Some("vs_main"),
                 buffers: &[
                     wgpu::VertexBufferLayout {
-                        array_stride: std::mem::size_of::<LineVertex>() as wgpu::BufferAddress,
-                        step_mode: wgpu::VertexStepMode::Vertex,
+                        array_stride: std::mem::size_of::<nalgebra_glm::Vec3>()
+                            as wgpu::BufferAddress,
+                        step_mode: wgpu::VertexStepMode::Instance,
                         attributes: &wgpu::vertex_attr_array![0 => Float32x3],
                     },
                     wgpu::VertexBufferLayout {
-                        array_stride: std::mem::size_of::<LineInstance>() as wgpu::BufferAddress,
+                        array_stride: std::mem::size_of::<nalgebra_glm::Vec4>()
+                            as wgpu::BufferAddress,
                         step_mode: wgpu::VertexStepMode::Instance,
-                        attributes: &wgpu::vertex_attr_array![
-                            1 => Float32x4,
-                            2 => Float32x4,
-                            3 => Float32x4
-                        ],
+                        attributes: &wgpu::vertex_attr_array![1 => Float32x4],
                     },
                 ],
                 compilation_options: Default::default(),
@@ -1506,14 +4716,14 @@ mod lines {
                 module: &shader,
                 entry_point: Some("fs_main"),
                 targets: &[Some(wgpu::ColorTargetState {
-                    format,
+                    format: surface_format,
                     blend: Some(wgpu::BlendState::ALPHA_BLENDING),
                     write_mask: wgpu::ColorWrites::ALL,
                 })],
                 compilation_options: Default::default(),
             }),
             primitive: wgpu::PrimitiveState {
-                topology: wgpu::PrimitiveTopology::LineList,
+                topology: wgpu::PrimitiveTopology::TriangleList,
                 strip_index_format: None,
                 front_face: wgpu::FrontFace::Ccw,
                 cull_mode: None,
@@ -1522,162 +4732,1217 @@ mod lines {
                 conservative: false,
             },
             depth_stencil: Some(wgpu::DepthStencilState {
-                format: DEPTH_FORMAT,
+                format: depth_format,
+                depth_write_enabled: true,
+                depth_compare: wgpu::CompareFunction::LessEqual,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
+            multisample: wgpu::MultisampleState {
+                count: sample_count,
+                ..Default::default()
+            },
+            multiview: None,
+            cache: None,
+        });
+
+        Points {
+            uniform_buffer,
+            bind_group,
+            cloud_uniform_buffer,
+            cloud_bind_group_layout,
+            cloud_bind_group,
+            cloud_uniform_stride,
+            clouds: Vec::new(),
+            pipeline,
+        }
+    }
+
+    fn create_cloud_uniforms(
+        device: &wgpu::Device,
+        layout: &wgpu::BindGroupLayout,
+        stride: u64,
+        capacity: u64,
+    ) -> (wgpu::Buffer, wgpu::BindGroup) {
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Point Cloud Uniform Buffer"),
+            size: stride * capacity,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+                    buffer: &buffer,
+                    offset: 0,
+                    size: wgpu::BufferSize::new(std::mem::size_of::<PointCloudUniform>() as u64),
+                }),
+            }],
+            label: Some("Point Cloud Bind Group"),
+        });
+        (buffer, bind_group)
+    }
+
+    /// Uploads the positions and colors of a point cloud, resolving scalars through the colormap
+    pub fn create_point_cloud(device: &wgpu::Device, points: crate::context::Points) -> PointCloud {
+        let colors = if points.colors.len() == points.positions.len() {
+            std::borrow::Cow::Borrowed(&points.colors[..])
+        } else {
+            std::borrow::Cow::Owned(points.point_colors())
+        };
+        // Buffers may not be empty, so empty clouds get a placeholder that is never drawn
+        let create_buffer = |label, contents: &[u8]| {
+            device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some(label),
+                contents: if contents.is_empty() {
+                    &[0; 16]
+                } else {
+                    contents
+                },
+                usage: wgpu::BufferUsages::VERTEX,
+            })
+        };
+        PointCloud {
+            position_buffer: create_buffer(
+                "Point Position Buffer",
+                bytemuck::cast_slice(&points.positions),
+            ),
+            color_buffer: create_buffer("Point Color Buffer", bytemuck::cast_slice(&colors)),
+            count: points.positions.len() as u32,
+            source: points,
+        }
+    }
+
+    pub fn update_points_uniform(
+        matrices: &crate::context::CameraMatrices,
+        viewport_size: (u32, u32),
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        points: &mut Points,
+        clouds: Vec<(EntityId, PointCloudUniform)>,
+    ) {
+        let uniform = PointsUniform {
+            view: matrices.view,
+            projection: matrices.projection,
+            viewport_size: nalgebra_glm::vec4(
+                viewport_size.0.max(1) as f32,
+                viewport_size.1.max(1) as f32,
+                0.0,
+                0.0,
+            ),
+        };
+        queue.write_buffer(&points.uniform_buffer, 0, bytemuck::cast_slice(&[uniform]));
+
+        points.clouds = clouds.iter().map(|(entity, _)| *entity).collect();
+        if clouds.is_empty() {
+            return;
+        }
+
+        // The uniform buffer only grows, doubling so that adding clouds rarely recreates it
+        let stride = points.cloud_uniform_stride;
+        let required_size = stride * clouds.len() as u64;
+        if points.cloud_uniform_buffer.size() < required_size {
+            let capacity = (clouds.len() as u64).next_power_of_two();
+            (points.cloud_uniform_buffer, points.cloud_bind_group) =
+                create_cloud_uniforms(device, &points.cloud_bind_group_layout, stride, capacity);
+        }
+
+        let mut data = vec![0_u8; required_size as usize];
+        clouds.iter().enumerate().for_each(|(index, (_, uniform))| {
+            let offset = index * stride as usize;
+            let bytes = bytemuck::bytes_of(uniform);
+            data[offset..offset + bytes.len()].copy_from_slice(bytes);
+        });
+        queue.write_buffer(&points.cloud_uniform_buffer, 0, &data);
+    }
+
+    pub fn render_points(
+        render_pass: &mut wgpu::RenderPass,
+        points: &Points,
+        point_clouds: &std::collections::HashMap<EntityId, PointCloud>,
+    ) {
+        if points.clouds.is_empty() {
+            return;
+        }
+        render_pass.set_pipeline(&points.pipeline);
+        render_pass.set_bind_group(0, &points.bind_group, &[]);
+        points
+            .clouds
+            .iter()
+            .enumerate()
+            .for_each(|(index, entity)| {
+                let Some(cloud) = point_clouds.get(entity) else {
+                    return;
+                };
+                if cloud.count == 0 {
+                    return;
+                }
+                let offset = (index as u64 * points.cloud_uniform_stride) as u32;
+                render_pass.set_bind_group(1, &points.cloud_bind_group, &[offset]);
+                render_pass.set_vertex_buffer(0, cloud.position_buffer.slice(..));
+                render_pass.set_vertex_buffer(1, cloud.color_buffer.slice(..));
+                render_pass.draw(0..6, 0..cloud.count);
+            });
+    }
+}
+
+pub use polylines::*;
+mod polylines {
+    use wgpu::util::DeviceExt as _;
+
+    use crate::prelude::EntityId;
+
+    /// Levels are halved until they have no more than this many points
+    const MIN_LEVEL_POINTS: usize = 1024;
+
+    /// Coarser levels are drawn while they still have this many points per pixel of their extent
+    const POINTS_PER_PIXEL: f32 = 2.0;
+
+    pub struct Polylines {
+        pub uniform_buffer: wgpu::Buffer,
+        pub bind_group: wgpu::BindGroup,
+        pub corner_buffer: wgpu::Buffer,
+
+        /// Holds the uniforms of every polyline drawn by the pane, each at its own offset
+        pub polyline_uniform_buffer: wgpu::Buffer,
+        pub polyline_bind_group_layout: wgpu::BindGroupLayout,
+        pub polyline_bind_group: wgpu::BindGroup,
+        pub polyline_uniform_stride: u64,
+
+        /// The polylines drawn by the pane and the level of detail each is drawn at,
+        /// in the order of their uniforms
+        pub draws: Vec<(EntityId, usize)>,
+        pub pipeline: wgpu::RenderPipeline,
+    }
+
+    /// The GPU buffers of a polyline at every level of detail, shared by every pane that draws it.
+    /// The polyline they were uploaded from is kept to tell when they need uploading again.
+    pub struct PolylineLevels {
+        pub source: crate::context::Polyline,
+
+        /// The full polyline followed by decimated copies, each with about half the points
+        pub levels: Vec<PolylineLevel>,
+        pub bounds: (nalgebra_glm::Vec3, nalgebra_glm::Vec3),
+    }
+
+    pub struct PolylineLevel {
+        /// The points with the first and last repeated, so every segment can read its neighbors
+        pub position_buffer: wgpu::Buffer,
+        pub color_buffer: wgpu::Buffer,
+        pub point_count: usize,
+    }
+
+    impl PolylineLevels {
+        /// Whether the buffers still hold this polyline, which is the case
+        /// until its arrays are replaced, as only the arrays are uploaded
+        pub fn is_uploaded_from(&self, polyline: &crate::context::Polyline) -> bool {
+            std::sync::Arc::ptr_eq(&self.source.points, &polyline.points)
+                && std::sync::Arc::ptr_eq(&self.source.colors, &polyline.colors)
+        }
+
+        /// Picks the coarsest level that still has a couple of points per pixel the polyline spans.
+        /// Polylines reaching behind the camera are always drawn in full.
+        pub fn level_of_detail(
+            &self,
+            model: &nalgebra_glm::Mat4,
+            view_proj: &nalgebra_glm::Mat4,
+            viewport_size: (u32, u32),
+        ) -> usize {
+            let (min, max) = self.bounds;
+            let viewport = nalgebra_glm::vec2(viewport_size.0 as f32, viewport_size.1 as f32);
+            let mut screen_min = nalgebra_glm::Vec2::repeat(f32::MAX);
+            let mut screen_max = nalgebra_glm::Vec2::repeat(f32::MIN);
+            for corner in 0..8 {
+                let position = nalgebra_glm::vec4(
+                    if corner & 1 == 0 { min.x } else { max.x },
+                    if corner & 2 == 0 { min.y } else { max.y },
+                    if corner & 4 == 0 { min.z } else { max.z },
+                    1.0,
+                );
+                let clip = view_proj * model * position;
+                if clip.w <= 0.0 {
+                    return 0;
+                }
+                let pixels = (clip.xy() / clip.w * 0.5)
+                    .add_scalar(0.5)
+                    .component_mul(&viewport);
+                screen_min = screen_min.inf(&pixels);
+                screen_max = screen_max.sup(&pixels);
+            }
+            let extent = screen_max - screen_min;
+            let target = (extent.x + extent.y).max(1.0) * POINTS_PER_PIXEL;
+            self.levels
+                .iter()
+                .rposition(|level| level.point_count as f32 >= target)
+                .unwrap_or(0)
+        }
+    }
+
+    #[repr(C)]
+    #[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+    pub struct PolylinesUniform {
+        pub view_proj: nalgebra_glm::Mat4,
+        pub viewport_size: nalgebra_glm::Vec4,
+    }
+
+    #[repr(C)]
+    #[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+    pub struct PolylineUniform {
+        pub model: nalgebra_glm::Mat4,
+        pub color: nalgebra_glm::Vec4,
+        pub width: f32,
+        pub padding: [f32; 3],
+    }
+
+    impl PolylineUniform {
+        pub fn new(polyline: &crate::context::Polyline, model: nalgebra_glm::Mat4) -> Self {
+            Self {
+                model,
+                color: polyline.color,
+                width: polyline.width,
+                padding: [0.0; 3],
+            }
+        }
+    }
+
+    pub fn create_polyline_renderer(
+        device: &wgpu::Device,
+        surface_format: wgpu::TextureFormat,
+        depth_format: wgpu::TextureFormat,
+        sample_count: u32,
+    ) -> Polylines {
+        let uniform_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Polylines Uniform Buffer"),
+            size: std::mem::size_of::<PolylinesUniform>() as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        // Two triangles spanning a segment from its start (x = 0) to its end (x = 1),
+        // and across it from one edge (y = -1) to the other (y = 1)
+        let corners: [[f32; 2]; 6] = [
+            [0.0, -1.0],
+            [1.0, -1.0],
+            [1.0, 1.0],
+            [0.0, -1.0],
+            [1.0, 1.0],
+            [0.0, 1.0],
+        ];
+        let corner_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Polyline Corner Buffer"),
+            contents: bytemuck::cast_slice(&corners),
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::VERTEX,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+            label: Some("Polylines Bind Group Layout"),
+        });
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: uniform_buffer.as_entire_binding(),
+            }],
+            label: Some("Polylines Bind Group"),
+        });
+
+        // Every polyline binds its uniforms at a dynamic offset into one shared buffer
+        let polyline_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                entries: &[wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: true,
+                        min_binding_size: wgpu::BufferSize::new(
+                            std::mem::size_of::<PolylineUniform>() as u64,
+                        ),
+                    },
+                    count: None,
+                }],
+                label: Some("Polyline Bind Group Layout"),
+            });
+
+        let alignment = u64::from(device.limits().min_uniform_buffer_offset_alignment);
+        let polyline_uniform_stride =
+            (std::mem::size_of::<PolylineUniform>() as u64).div_ceil(alignment) * alignment;
+        let (polyline_uniform_buffer, polyline_bind_group) = create_polyline_uniforms(
+            device,
+            &polyline_bind_group_layout,
+            polyline_uniform_stride,
+            1,
+        );
+
+        let shader = device.create_shader_module(wgpu::include_wgsl!("shaders/polyline.wgsl"));
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Polyline Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout, &polyline_bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        // The points and colors are each bound several times at increasing offsets,
+        // so that every segment instance reads its neighbors from the same shared vertices
+        let position_stride = std::mem::size_of::<nalgebra_glm::Vec3>() as wgpu::BufferAddress;
+        let color_stride = std::mem::size_of::<[u8; 4]>() as wgpu::BufferAddress;
+        let position_attributes = [
+            wgpu::vertex_attr_array![1 => Float32x3],
+            wgpu::vertex_attr_array![2 => Float32x3],
+            wgpu::vertex_attr_array![3 => Float32x3],
+            wgpu::vertex_attr_array![4 => Float32x3],
+        ];
+        let color_attributes = [
+            wgpu::vertex_attr_array![5 => Unorm8x4],
+            wgpu::vertex_attr_array![6 => Unorm8x4],
+        ];
+        let corner_attributes = wgpu::vertex_attr_array![0 => Float32x2];
+        let mut buffers = vec![wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<[f32; 2]>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: &corner_attributes,
+        }];
+        buffers.extend(
+            position_attributes
+                .iter()
+                .map(|attributes| wgpu::VertexBufferLayout {
+                    array_stride: position_stride,
+                    step_mode: wgpu::VertexStepMode::Instance,
+                    attributes,
+                }),
+        );
+        buffers.extend(
+            color_attributes
+                .iter()
+                .map(|attributes| wgpu::VertexBufferLayout {
+                    array_stride: color_stride,
+                    step_mode: wgpu::VertexStepMode::Instance,
+                    attributes,
+                }),
+        );
+
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Polyline Pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                buffers: &buffers,
+                compilation_options: Default::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("fs_main"),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: surface_format,
+                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+                compilation_options: Default::default(),
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                strip_index_format: None,
+                front_face: wgpu::FrontFace::Ccw,
+                cull_mode: None,
+                unclipped_depth: false,
+                polygon_mode: wgpu::PolygonMode::Fill,
+                conservative: false,
+            },
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: depth_format,
                 depth_write_enabled: true,
                 depth_compare: wgpu::CompareFunction::LessEqual,
                 stencil: wgpu::StencilState::default(),
                 bias: wgpu::DepthBiasState {
-                    constant: -1, // Small negative bias to avoid z-fighting
+                    constant: -1, // Small negative bias to avoid z-fighting, like lines
                     slope_scale: 0.0,
                     clamp: 0.0,
                 },
-            }),
-            multisample: wgpu::MultisampleState::default(),
-            multiview: None,
-            cache: None,
-        });
+            }),
+            multisample: wgpu::MultisampleState {
+                count: sample_count,
+                ..Default::default()
+            },
+            multiview: None,
+            cache: None,
+        });
+
+        Polylines {
+            uniform_buffer,
+            bind_group,
+            corner_buffer,
+            polyline_uniform_buffer,
+            polyline_bind_group_layout,
+            polyline_bind_group,
+            polyline_uniform_stride,
+            draws: Vec::new(),
+            pipeline,
+        }
+    }
+
+    fn create_polyline_uniforms(
+        device: &wgpu::Device,
+        layout: &wgpu::BindGroupLayout,
+        stride: u64,
+        capacity: u64,
+    ) -> (wgpu::Buffer, wgpu::BindGroup) {
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Polyline Uniform Buffer"),
+            size: stride * capacity,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+                    buffer: &buffer,
+                    offset: 0,
+                    size: wgpu::BufferSize::new(std::mem::size_of::<PolylineUniform>() as u64),
+                }),
+            }],
+            label: Some("Polyline Bind Group"),
+        });
+        (buffer, bind_group)
+    }
+
+    /// Uploads a polyline along with decimated copies of it, each about half the size of the last
+    pub fn create_polyline_levels(
+        device: &wgpu::Device,
+        polyline: crate::context::Polyline,
+    ) -> PolylineLevels {
+        let white = nalgebra_glm::vec4(1.0, 1.0, 1.0, 1.0);
+        let mut points = polyline
+            .points
+            .iter()
+            .enumerate()
+            .map(|(index, point)| {
+                let color = if polyline.colors.len() == polyline.points.len() {
+                    polyline.colors[index]
+                } else {
+                    white
+                };
+                (*point, color)
+            })
+            .collect::<Vec<_>>();
+        let bounds = polyline.points.iter().fold(
+            (
+                nalgebra_glm::Vec3::repeat(f32::MAX),
+                nalgebra_glm::Vec3::repeat(f32::MIN),
+            ),
+            |(min, max), point| (min.inf(point), max.sup(point)),
+        );
+
+        let mut levels = vec![create_polyline_level(device, &points)];
+        while points.len() > MIN_LEVEL_POINTS {
+            points = decimate(&points);
+            levels.push(create_polyline_level(device, &points));
+        }
+        PolylineLevels {
+            source: polyline,
+            levels,
+            bounds,
+        }
+    }
+
+    fn create_polyline_level(
+        device: &wgpu::Device,
+        points: &[(nalgebra_glm::Vec3, nalgebra_glm::Vec4)],
+    ) -> PolylineLevel {
+        let mut positions = Vec::with_capacity(points.len() + 2);
+        positions.extend(points.first().map(|(position, _)| *position));
+        positions.extend(points.iter().map(|(position, _)| *position));
+        positions.extend(points.last().map(|(position, _)| *position));
+        // Colors are packed into bytes, as huge polylines would otherwise be mostly colors
+        let colors = points
+            .iter()
+            .map(|(_, color)| color.map(|channel| (channel.clamp(0.0, 1.0) * 255.0).round() as u8))
+            .map(|color| [color.x, color.y, color.z, color.w])
+            .collect::<Vec<_>>();
+
+        // Buffers may not be empty, so empty polylines get a placeholder that is never drawn
+        let create_buffer = |label, contents: &[u8]| {
+            device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some(label),
+                contents: if contents.is_empty() {
+                    &[0; 16]
+                } else {
+                    contents
+                },
+                usage: wgpu::BufferUsages::VERTEX,
+            })
+        };
+        PolylineLevel {
+            position_buffer: create_buffer(
+                "Polyline Position Buffer",
+                bytemuck::cast_slice(&positions),
+            ),
+            color_buffer: create_buffer("Polyline Color Buffer", bytemuck::cast_slice(&colors)),
+            point_count: points.len(),
+        }
+    }
 
-        Lines {
-            vertex_buffer,
-            instance_buffer,
-            uniform_buffer,
-            bind_group,
-            pipeline,
+    /// Halves a polyline, keeping its ends and, from each pair of points between them,
+    /// the one that bends the polyline the most, so that peaks survive decimation
+    fn decimate(
+        points: &[(nalgebra_glm::Vec3, nalgebra_glm::Vec4)],
+    ) -> Vec<(nalgebra_glm::Vec3, nalgebra_glm::Vec4)> {
+        let (Some(first), Some(last)) = (points.first(), points.last()) else {
+            return Vec::new();
+        };
+        let mut decimated = Vec::with_capacity(points.len() / 2 + 2);
+        decimated.push(*first);
+        let last_index = points.len() - 1;
+        let mut index = 1;
+        while index < last_index {
+            let previous = decimated[decimated.len() - 1].0;
+            let next = points[(index + 2).min(last_index)].0;
+            let candidates = [points[index], points[(index + 1).min(last_index - 1)]];
+            let kept = candidates
+                .into_iter()
+                .max_by(|(a, _), (b, _)| {
+                    deviation(a, &previous, &next).total_cmp(&deviation(b, &previous, &next))
+                })
+                .unwrap_or(candidates[0]);
+            decimated.push(kept);
+            index += 2;
         }
+        if points.len() > 1 {
+            decimated.push(*last);
+        }
+        decimated
     }
 
-    pub fn update_lines_uniform(
+    /// The distance from a point to the line through two others
+    fn deviation(
+        point: &nalgebra_glm::Vec3,
+        start: &nalgebra_glm::Vec3,
+        end: &nalgebra_glm::Vec3,
+    ) -> f32 {
+        let direction = end - start;
+        let length = direction.norm();
+        if length <= f32::EPSILON {
+            return (point - start).norm();
+        }
+        (point - start).cross(&direction).norm() / length
+    }
+
+    pub fn update_polylines_uniform(
         matrices: &crate::context::CameraMatrices,
+        viewport_size: (u32, u32),
         device: &wgpu::Device,
         queue: &wgpu::Queue,
-        lines: &mut Lines,
-        instances: Vec<LineInstance>,
+        polylines: &mut Polylines,
+        instances: Vec<(EntityId, PolylineUniform)>,
+        polyline_levels: &std::collections::HashMap<EntityId, PolylineLevels>,
     ) {
-        // Create the data that will be sent to the GPU
-        let gpu_data = if instances.is_empty() {
-            vec![LineInstance {
-                start: nalgebra_glm::vec4(0.0, 0.0, 0.0, 0.0),
-                end: nalgebra_glm::vec4(0.0, 0.0, 0.0, 0.0),
-                color: nalgebra_glm::vec4(0.0, 0.0, 0.0, 0.0),
-            }]
-        } else {
-            instances
+        let view_proj = matrices.projection * matrices.view;
+        let uniform = PolylinesUniform {
+            view_proj,
+            viewport_size: nalgebra_glm::vec4(
+                viewport_size.0.max(1) as f32,
+                viewport_size.1.max(1) as f32,
+                0.0,
+                0.0,
+            ),
         };
+        queue.write_buffer(
+            &polylines.uniform_buffer,
+            0,
+            bytemuck::cast_slice(&[uniform]),
+        );
 
-        let uniform = LineUniform {
-            view_proj: matrices.projection * matrices.view,
-        };
+        polylines.draws = instances
+            .iter()
+            .map(|(entity, uniform)| {
+                let level = polyline_levels.get(entity).map_or(0, |levels| {
+                    levels.level_of_detail(&uniform.model, &view_proj, viewport_size)
+                });
+                (*entity, level)
+            })
+            .collect();
+        if instances.is_empty() {
+            return;
+        }
 
-        queue.write_buffer(&lines.uniform_buffer, 0, bytemuck::cast_slice(&[uniform]));
+        // The uniform buffer only grows, doubling so that adding polylines rarely recreates it
+        let stride = polylines.polyline_uniform_stride;
+        let required_size = stride * instances.len() as u64;
+        if polylines.polyline_uniform_buffer.size() < required_size {
+            let capacity = (instances.len() as u64).next_power_of_two();
+            (
+                polylines.polyline_uniform_buffer,
+                polylines.polyline_bind_group,
+            ) = create_polyline_uniforms(
+                device,
+                &polylines.polyline_bind_group_layout,
+                stride,
+                capacity,
+            );
+        }
 
-        // Always recreate the buffer with the exact size needed
-        lines.instance_buffer = wgpu::util::DeviceExt::create_buffer_init(
-            device,
-            &wgpu::util::BufferInitDescriptor {
-                label: Some("Debug Line Instance Buffer"),
-                contents: bytemuck::cast_slice(&gpu_data),
-                usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
-            },
-        );
+        let mut data = vec![0_u8; required_size as usize];
+        instances
+            .iter()
+            .enumerate()
+            .for_each(|(index, (_, uniform))| {
+                let offset = index * stride as usize;
+                let bytes = bytemuck::bytes_of(uniform);
+                data[offset..offset + bytes.len()].copy_from_slice(bytes);
+            });
+        queue.write_buffer(&polylines.polyline_uniform_buffer, 0, &data);
+    }
+
+    pub fn render_polylines(
+        render_pass: &mut wgpu::RenderPass,
+        polylines: &Polylines,
+        polyline_levels: &std::collections::HashMap<EntityId, PolylineLevels>,
+    ) {
+        if polylines.draws.is_empty() {
+            return;
+        }
+        render_pass.set_pipeline(&polylines.pipeline);
+        render_pass.set_bind_group(0, &polylines.bind_group, &[]);
+        render_pass.set_vertex_buffer(0, polylines.corner_buffer.slice(..));
+        let position_stride = std::mem::size_of::<nalgebra_glm::Vec3>() as u64;
+        let color_stride = std::mem::size_of::<[u8; 4]>() as u64;
+        polylines
+            .draws
+            .iter()
+            .enumerate()
+            .for_each(|(index, (entity, level))| {
+                let Some(level) = polyline_levels
+                    .get(entity)
+                    .and_then(|levels| levels.levels.get(*level))
+                else {
+                    return;
+                };
+                if level.point_count < 2 {
+                    return;
+                }
+                let offset = (index as u64 * polylines.polyline_uniform_stride) as u32;
+                render_pass.set_bind_group(1, &polylines.polyline_bind_group, &[offset]);
+                (0..4).for_each(|slot| {
+                    let start = slot as u64 * position_stride;
+                    render_pass.set_vertex_buffer(1 + slot, level.position_buffer.slice(start..));
+                });
+                (0..2).for_each(|slot| {
+                    let start = slot as u64 * color_stride;
+                    render_pass.set_vertex_buffer(5 + slot, level.color_buffer.slice(start..));
+                });
+                render_pass.draw(0..6, 0..(level.point_count - 1) as u32);
+            });
     }
 
-    pub fn render_lines(render_pass: &mut wgpu::RenderPass, lines: &Lines) {
-        let instance_size = std::mem::size_of::<LineInstance>();
-        let debug_line_instance_count =
-            (lines.instance_buffer.size() as usize / instance_size) as u32;
-        if debug_line_instance_count > 0 {
-            render_pass.set_pipeline(&lines.pipeline);
-            render_pass.set_bind_group(0, &lines.bind_group, &[]);
-            render_pass.set_vertex_buffer(0, lines.vertex_buffer.slice(..));
-            render_pass.set_vertex_buffer(1, lines.instance_buffer.slice(..));
-            render_pass.draw(0..2, 0..debug_line_instance_count);
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        fn points(heights: &[f32]) -> Vec<(nalgebra_glm::Vec3, nalgebra_glm::Vec4)> {
+            heights
+                .iter()
+                .enumerate()
+                .map(|(index, height)| {
+                    (
+                        nalgebra_glm::vec3(index as f32, *height, 0.0),
+                        nalgebra_glm::vec4(1.0, 1.0, 1.0, 1.0),
+                    )
+                })
+                .collect()
+        }
+
+        fn heights(points: &[(nalgebra_glm::Vec3, nalgebra_glm::Vec4)]) -> Vec<f32> {
+            points.iter().map(|(position, _)| position.y).collect()
+        }
+
+        #[test]
+        fn decimate_handles_tiny_polylines() {
+            assert!(decimate(&[]).is_empty());
+            assert_eq!(heights(&decimate(&points(&[1.0]))), [1.0]);
+            assert_eq!(heights(&decimate(&points(&[1.0, 2.0]))), [1.0, 2.0]);
+        }
+
+        #[test]
+        fn decimate_keeps_the_ends() {
+            for count in [3, 6, 7, 100, 101] {
+                let input = points(&(0..count).map(|index| index as f32).collect::<Vec<_>>());
+                let decimated = decimate(&input);
+                assert_eq!(decimated.first().unwrap().0, input.first().unwrap().0);
+                assert_eq!(decimated.last().unwrap().0, input.last().unwrap().0);
+            }
+        }
+
+        #[test]
+        fn decimate_keeps_the_biggest_bend_of_each_pair() {
+            // Pairs are (1, 2), (3, 4) and the lone point 5 before the end
+            let decimated = decimate(&points(&[0.0, 0.0, 5.0, -3.0, 0.0, 4.0, 0.0]));
+            assert_eq!(heights(&decimated), [0.0, 5.0, -3.0, 4.0, 0.0]);
+        }
+
+        #[test]
+        fn decimate_halves_each_level() {
+            let mut level = points(
+                &(0..10_000)
+                    .map(|index| (index % 7) as f32)
+                    .collect::<Vec<_>>(),
+            );
+            let mut lengths = vec![level.len()];
+            while level.len() > MIN_LEVEL_POINTS {
+                let decimated = decimate(&level);
+                assert_eq!(decimated.len(), (level.len() - 2).div_ceil(2) + 2);
+                level = decimated;
+                lengths.push(level.len());
+            }
+            assert_eq!(lengths, [10_000, 5_001, 2_502, 1_252, 627]);
         }
     }
 }
 
-pub use quads::*;
-mod quads {
+pub use labels::*;
+mod labels {
+    use ab_glyph::{Font as _, ScaleFont as _};
     use wgpu::util::DeviceExt as _;
 
-    pub struct Quads {
-        pub vertex_buffer: wgpu::Buffer,
-        pub index_buffer: wgpu::Buffer,
-        pub instance_buffer: wgpu::Buffer,
-        pub uniform_buffer: wgpu::Buffer,
+    use super::{create_model_bind_group_layout, SceneRenderList};
+
+    /// The height of a line of text in the atlas, so labels up to about this many pixels stay sharp
+    const GLYPH_PIXELS: f32 = 48.0;
+
+    const ATLAS_SIZE: u32 = 1024;
+
+    /// Smaller labels sample the mipmaps of the atlas, which are built glyph by glyph.
+    /// Glyphs are placed at multiples of the smallest mip's texel so each of their levels lines up.
+    const ATLAS_MIP_LEVELS: u32 = 4;
+    const GLYPH_ALIGNMENT: u32 = 1 << (ATLAS_MIP_LEVELS - 1);
+
+    /// Empty pixels around each glyph, which keep filtering from bleeding in its neighbors
+    const GLYPH_PADDING: u32 = 4;
+
+    /// The space between text and the edges of its background, in lines of text
+    const BACKGROUND_PADDING: f32 = 0.2;
+
+    /// Glyphs rasterized on demand into one texture shared by every pane.
+    /// Each is rasterized once, at a fixed size, the first time a label uses it.
+    pub struct GlyphAtlas {
+        font: ab_glyph::FontArc,
+        texture: wgpu::Texture,
         pub bind_group: wgpu::BindGroup,
-        pub pipeline: wgpu::RenderPipeline,
+
+        /// Glyphs without an outline, such as spaces, or that no longer fit are `None`
+        glyphs: std::collections::HashMap<char, Option<AtlasGlyph>>,
+
+        /// Where the next glyph goes on the current shelf, and the height of the shelf
+        cursor: (u32, u32),
+        shelf_height: u32,
+
+        /// Texture coordinates inside a solid cell, which backgrounds are drawn with
+        solid: nalgebra_glm::Vec2,
+
+        /// The label changes the atlas holds the glyphs of
+        pub changes: u64,
     }
 
-    #[repr(C)]
-    #[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
-    pub struct QuadVertex {
-        pub position: nalgebra_glm::Vec3,
+    #[derive(Copy, Clone)]
+    struct AtlasGlyph {
+        /// The corners of the glyph relative to its origin on the baseline, in lines of text
+        min: nalgebra_glm::Vec2,
+        max: nalgebra_glm::Vec2,
+
+        /// The texture coordinates of the top left and bottom right of the glyph
+        uv_min: nalgebra_glm::Vec2,
+        uv_max: nalgebra_glm::Vec2,
     }
 
     #[repr(C)]
     #[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
-    pub struct QuadInstance {
-        pub model_matrix_0: nalgebra_glm::Vec4,
-        pub model_matrix_1: nalgebra_glm::Vec4,
-        pub model_matrix_2: nalgebra_glm::Vec4,
-        pub model_matrix_3: nalgebra_glm::Vec4,
+    pub struct GlyphInstance {
+        /// The point the label is centered above, in the local space of its entity,
+        /// with the height of a line of text in w
+        pub anchor: nalgebra_glm::Vec4,
+
+        /// The bottom left and top right corners relative to the anchor, in lines of text
+        pub rect: nalgebra_glm::Vec4,
+
+        /// The texture coordinates at those corners
+        pub uv: nalgebra_glm::Vec4,
         pub color: nalgebra_glm::Vec4,
+
+        /// One for labels facing the camera, zero for labels lying in the plane of their entity
+        pub billboard: f32,
+    }
+
+    pub struct Labels {
+        pub vertex_buffer: wgpu::Buffer,
+        pub uniform_buffer: wgpu::Buffer,
+        pub bind_group: wgpu::BindGroup,
+        pub pipeline: wgpu::RenderPipeline,
     }
 
     #[repr(C)]
     #[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
-    pub struct QuadUniform {
+    pub struct LabelsUniform {
         pub view_proj: nalgebra_glm::Mat4,
+        pub viewport_size: nalgebra_glm::Vec4,
     }
 
-    pub fn create_quad_renderer(
+    /// The layout of the glyph atlas bound by the label pipeline
+    fn create_glyph_atlas_bind_group_layout(device: &wgpu::Device) -> wgpu::BindGroupLayout {
+        device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        multisampled: false,
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+            label: Some("Glyph Atlas Bind Group Layout"),
+        })
+    }
+
+    impl GlyphAtlas {
+        /// Creates an empty atlas for the proportional font of the user interface
+        pub fn new(device: &wgpu::Device, queue: &wgpu::Queue) -> Self {
+            let font_data = egui::FontDefinitions::default()
+                .font_data
+                .remove("Ubuntu-Light")
+                .expect("The default fonts include Ubuntu-Light");
+            let font = ab_glyph::FontArc::try_from_vec(font_data.font.to_vec())
+                .expect("The default font is a valid font");
+
+            let texture = device.create_texture(&wgpu::TextureDescriptor {
+                label: Some("Glyph Atlas Texture"),
+                size: wgpu::Extent3d {
+                    width: ATLAS_SIZE,
+                    height: ATLAS_SIZE,
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: ATLAS_MIP_LEVELS,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: wgpu::TextureFormat::R8Unorm,
+                usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+                view_formats: &[],
+            });
+            let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+            let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+                label: Some("Glyph Atlas Sampler"),
+                address_mode_u: wgpu::AddressMode::ClampToEdge,
+                address_mode_v: wgpu::AddressMode::ClampToEdge,
+                mag_filter: wgpu::FilterMode::Linear,
+                min_filter: wgpu::FilterMode::Linear,
+                mipmap_filter: wgpu::FilterMode::Linear,
+                ..Default::default()
+            });
+            let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+                layout: &create_glyph_atlas_bind_group_layout(device),
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: wgpu::BindingResource::TextureView(&view),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: wgpu::BindingResource::Sampler(&sampler),
+                    },
+                ],
+                label: Some("Glyph Atlas Bind Group"),
+            });
+
+            let mut atlas = Self {
+                font,
+                texture,
+                bind_group,
+                glyphs: std::collections::HashMap::new(),
+                cursor: (0, 0),
+                shelf_height: 0,
+                solid: nalgebra_glm::Vec2::zeros(),
+                changes: 0,
+            };
+
+            // The first cell is solid, so backgrounds sample full coverage at every mip level
+            let size = GLYPH_ALIGNMENT;
+            if let Some((x, y)) = atlas.allocate(size, size) {
+                write_cell(
+                    queue,
+                    &atlas.texture,
+                    (x, y),
+                    (size, size),
+                    vec![255; (size * size) as usize],
+                );
+                let center = nalgebra_glm::vec2((x + size / 2) as f32, (y + size / 2) as f32);
+                atlas.solid = center / ATLAS_SIZE as f32;
+            }
+            atlas
+        }
+
+        /// Rasterizes the glyphs of a text that are not in the atlas yet
+        pub fn insert_text(&mut self, queue: &wgpu::Queue, text: &str) {
+            text.chars()
+                .filter(|character| !character.is_control())
+                .for_each(|character| {
+                    if self.glyphs.contains_key(&character) {
+                        return;
+                    }
+                    let glyph = self.rasterize(queue, character);
+                    self.glyphs.insert(character, glyph);
+                });
+        }
+
+        fn rasterize(&mut self, queue: &wgpu::Queue, character: char) -> Option<AtlasGlyph> {
+            let glyph = self
+                .font
+                .glyph_id(character)
+                .with_scale(ab_glyph::PxScale::from(GLYPH_PIXELS));
+            let outline = self.font.outline_glyph(glyph)?;
+            let bounds = outline.px_bounds();
+            let (width, height) = (bounds.width() as u32, bounds.height() as u32);
+            let cell_width = (width + 2 * GLYPH_PADDING).next_multiple_of(GLYPH_ALIGNMENT);
+            let cell_height = (height + 2 * GLYPH_PADDING).next_multiple_of(GLYPH_ALIGNMENT);
+            let Some((x, y)) = self.allocate(cell_width, cell_height) else {
+                log::warn!("The glyph atlas is full, {character:?} will not be drawn");
+                return None;
+            };
+
+            let mut coverage = vec![0; (cell_width * cell_height) as usize];
+            outline.draw(|column, row, value| {
+                let index = (row + GLYPH_PADDING) * cell_width + column + GLYPH_PADDING;
+                coverage[index as usize] = (value.clamp(0.0, 1.0) * 255.0) as u8;
+            });
+            write_cell(
+                queue,
+                &self.texture,
+                (x, y),
+                (cell_width, cell_height),
+                coverage,
+            );
+
+            let line_height = self.font.as_scaled(GLYPH_PIXELS).height();
+            let (left, top) = ((x + GLYPH_PADDING) as f32, (y + GLYPH_PADDING) as f32);
+            Some(AtlasGlyph {
+                min: nalgebra_glm::vec2(bounds.min.x, -bounds.max.y) / line_height,
+                max: nalgebra_glm::vec2(bounds.max.x, -bounds.min.y) / line_height,
+                uv_min: nalgebra_glm::vec2(left, top) / ATLAS_SIZE as f32,
+                uv_max: nalgebra_glm::vec2(left + width as f32, top + height as f32)
+                    / ATLAS_SIZE as f32,
+            })
+        }
+
+        /// Finds room for a cell, filling shelves from left to right and top to bottom
+        fn allocate(&mut self, width: u32, height: u32) -> Option<(u32, u32)> {
+            if self.cursor.0 + width > ATLAS_SIZE {
+                self.cursor = (0, self.cursor.1 + self.shelf_height);
+                self.shelf_height = 0;
+            }
+            if self.cursor.0 + width > ATLAS_SIZE || self.cursor.1 + height > ATLAS_SIZE {
+                return None;
+            }
+            let position = self.cursor;
+            self.cursor.0 += width;
+            self.shelf_height = self.shelf_height.max(height);
+            Some(position)
+        }
+
+        /// Lays out the lines of a label centered above its offset, so a leader line
+        /// meets the bottom edge of its background, appending a quad for the background
+        /// followed by one for each glyph
+        pub fn layout_label(
+            &self,
+            label: &crate::context::Label,
+            instances: &mut Vec<GlyphInstance>,
+        ) {
+            let font = self.font.as_scaled(GLYPH_PIXELS);
+            let line_height = font.height();
+            let line_advance = (font.height() + font.line_gap()) / line_height;
+            let ascent = font.ascent() / line_height;
+
+            // Widths are measured first, so each line can be centered
+            let lines = label
+                .text
+                .lines()
+                .map(|line| {
+                    let mut pen = 0.0;
+                    let mut previous = None;
+                    let glyphs = line
+                        .chars()
+                        .filter(|character| !character.is_control())
+                        .map(|character| {
+                            let id = font.glyph_id(character);
+                            if let Some(previous) = previous {
+                                pen += font.kern(previous, id) / line_height;
+                            }
+                            previous = Some(id);
+                            let origin = pen;
+                            pen += font.h_advance(id) / line_height;
+                            (character, origin)
+                        })
+                        .collect::<Vec<_>>();
+                    (glyphs, pen)
+                })
+                .collect::<Vec<_>>();
+            if lines.is_empty() {
+                return;
+            }
+            let width = lines
+                .iter()
+                .fold(0.0_f32, |width, (_, line)| width.max(*line));
+            let height = 1.0 + (lines.len() - 1) as f32 * line_advance;
+
+            let anchor =
+                nalgebra_glm::vec4(label.offset.x, label.offset.y, label.offset.z, label.size);
+            let billboard = match label.mode {
+                crate::api::LabelMode::Billboard => 1.0,
+                crate::api::LabelMode::World => 0.0,
+            };
+            if let Some(background) = label.background {
+                let extent = nalgebra_glm::vec2(width * 0.5, height)
+                    + nalgebra_glm::Vec2::repeat(BACKGROUND_PADDING);
+                instances.push(GlyphInstance {
+                    anchor,
+                    rect: nalgebra_glm::vec4(
+                        -extent.x,
+                        0.0,
+                        extent.x,
+                        extent.y + BACKGROUND_PADDING,
+                    ),
+                    uv: nalgebra_glm::vec4(self.solid.x, self.solid.y, self.solid.x, self.solid.y),
+                    color: background,
+                    billboard,
+                });
+            }
+
+            lines
+                .iter()
+                .enumerate()
+                .for_each(|(index, (glyphs, line_width))| {
+                    let baseline =
+                        BACKGROUND_PADDING + height - ascent - index as f32 * line_advance;
+                    let start = -line_width * 0.5;
+                    glyphs.iter().for_each(|(character, origin)| {
+                        let Some(Some(glyph)) = self.glyphs.get(character) else {
+                            return;
+                        };
+                        let origin = nalgebra_glm::vec2(start + origin, baseline);
+                        let (min, max) = (origin + glyph.min, origin + glyph.max);
+
+                        // Texture rows run downwards, so the bottom of the glyph is at its largest v
+                        instances.push(GlyphInstance {
+                            anchor,
+                            rect: nalgebra_glm::vec4(min.x, min.y, max.x, max.y),
+                            uv: nalgebra_glm::vec4(
+                                glyph.uv_min.x,
+                                glyph.uv_max.y,
+                                glyph.uv_max.x,
+                                glyph.uv_min.y,
+                            ),
+                            color: label.color,
+                            billboard,
+                        });
+                    });
+                });
+        }
+    }
+
+    /// Writes a cell of the atlas and each of its mip levels, halving it with a box filter
+    fn write_cell(
+        queue: &wgpu::Queue,
+        texture: &wgpu::Texture,
+        (x, y): (u32, u32),
+        (width, height): (u32, u32),
+        mut coverage: Vec<u8>,
+    ) {
+        for level in 0..ATLAS_MIP_LEVELS {
+            let (level_width, level_height) = (width >> level, height >> level);
+            if level > 0 {
+                let previous_width = (level_width * 2) as usize;
+                coverage = (0..level_height as usize)
+                    .flat_map(|row| (0..level_width as usize).map(move |column| (row, column)))
+                    .map(|(row, column)| {
+                        let top = 2 * row * previous_width + 2 * column;
+                        let bottom = top + previous_width;
+                        let sum = coverage[top] as u32
+                            + coverage[top + 1] as u32
+                            + coverage[bottom] as u32
+                            + coverage[bottom + 1] as u32;
+                        (sum / 4) as u8
+                    })
+                    .collect();
+            }
+            queue.write_texture(
+                wgpu::TexelCopyTextureInfo {
+                    texture,
+                    mip_level: level,
+                    origin: wgpu::Origin3d {
+                        x: x >> level,
+                        y: y >> level,
+                        z: 0,
+                    },
+                    aspect: wgpu::TextureAspect::All,
+                },
+                &coverage,
+                wgpu::TexelCopyBufferLayout {
+                    offset: 0,
+                    bytes_per_row: Some(level_width),
+                    rows_per_image: Some(level_height),
+                },
+                wgpu::Extent3d {
+                    width: level_width,
+                    height: level_height,
+                    depth_or_array_layers: 1,
+                },
+            );
+        }
+    }
+
+    pub fn create_label_renderer(
         device: &wgpu::Device,
         surface_format: wgpu::TextureFormat,
         depth_format: wgpu::TextureFormat,
-    ) -> Quads {
-        // Create a unit quad centered at origin in XY plane
-        let vertices = [
-            QuadVertex {
-                position: nalgebra_glm::vec3(-0.5, -0.5, 0.0),
-            },
-            QuadVertex {
-                position: nalgebra_glm::vec3(0.5, -0.5, 0.0),
-            },
-            QuadVertex {
-                position: nalgebra_glm::vec3(0.5, 0.5, 0.0),
-            },
-            QuadVertex {
-                position: nalgebra_glm::vec3(-0.5, 0.5, 0.0),
-            },
+        sample_count: u32,
+    ) -> Labels {
+        // Two triangles spanning a glyph from its bottom left (0, 0) to its top right (1, 1)
+        let corners: [[f32; 2]; 6] = [
+            [0.0, 0.0],
+            [1.0, 0.0],
+            [1.0, 1.0],
+            [0.0, 0.0],
+            [1.0, 1.0],
+            [0.0, 1.0],
         ];
-
-        let indices: &[u16] = &[0, 1, 2, 2, 3, 0];
-
         let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some("Quad Vertex Buffer"),
-            contents: bytemuck::cast_slice(&vertices),
+            label: Some("Label Vertex Buffer"),
+            contents: bytemuck::cast_slice(&corners),
             usage: wgpu::BufferUsages::VERTEX,
         });
 
-        let index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some("Quad Index Buffer"),
-            contents: bytemuck::cast_slice(indices),
-            usage: wgpu::BufferUsages::INDEX,
-        });
-
-        let initial_instance_capacity = 1024;
-        let instance_buffer_size = std::mem::size_of::<QuadInstance>() * initial_instance_capacity;
-
-        let instance_buffer = device.create_buffer(&wgpu::BufferDescriptor {
-            label: Some("Quad Instance Buffer"),
-            size: instance_buffer_size as u64,
-            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
-            mapped_at_creation: false,
-        });
-
         let uniform_buffer = device.create_buffer(&wgpu::BufferDescriptor {
-            label: Some("Quad Uniform Buffer"),
-            size: std::mem::size_of::<QuadUniform>() as u64,
+            label: Some("Labels Uniform Buffer"),
+            size: std::mem::size_of::<LabelsUniform>() as u64,
             usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
             mapped_at_creation: false,
         });
@@ -1693,7 +5958,7 @@ mod quads {
                 },
                 count: None,
             }],
-            label: Some("Quad Bind Group Layout"),
+            label: Some("Labels Bind Group Layout"),
         });
 
         let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
@@ -1702,40 +5967,42 @@ mod quads {
                 binding: 0,
                 resource: uniform_buffer.as_entire_binding(),
             }],
-            label: Some("Quad Bind Group"),
+            label: Some("Labels Bind Group"),
         });
 
-        let shader = device.create_shader_module(wgpu::include_wgsl!("shaders/quad.wgsl"));
+        let shader = device.create_shader_module(wgpu::include_wgsl!("shaders/label.wgsl"));
 
         let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
-            label: Some("Quad Pipeline Layout"),
-            bind_group_layouts: &[&bind_group_layout],
+            label: Some("Label Pipeline Layout"),
+            bind_group_layouts: &[
+                &bind_group_layout,
+                &create_model_bind_group_layout(device),
+                &create_glyph_atlas_bind_group_layout(device),
+            ],
             push_constant_ranges: &[],
         });
 
         let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-            label: Some("Quad Pipeline"),
+            label: Some("Label Pipeline"),
             layout: Some(&pipeline_layout),
             vertex: wgpu::VertexState {
                 module: &shader,
                 entry_point: Some("vs_main"),
                 buffers: &[
-                    // Vertex buffer
                     wgpu::VertexBufferLayout {
-                        array_stride: std::mem::size_of::<QuadVertex>() as wgpu::BufferAddress,
+                        array_stride: std::mem::size_of::<[f32; 2]>() as wgpu::BufferAddress,
                         step_mode: wgpu::VertexStepMode::Vertex,
-                        attributes: &wgpu::vertex_attr_array![0 => Float32x3],
+                        attributes: &wgpu::vertex_attr_array![0 => Float32x2],
                     },
-                    // Instance buffer
                     wgpu::VertexBufferLayout {
-                        array_stride: std::mem::size_of::<QuadInstance>() as wgpu::BufferAddress,
+                        array_stride: std::mem::size_of::<GlyphInstance>() as wgpu::BufferAddress,
                         step_mode: wgpu::VertexStepMode::Instance,
                         attributes: &wgpu::vertex_attr_array![
                             1 => Float32x4,
                             2 => Float32x4,
                             3 => Float32x4,
                             4 => Float32x4,
-                            5 => Float32x4
+                            5 => Float32
                         ],
                     },
                 ],
@@ -1746,14 +6013,7 @@ mod quads {
                 entry_point: Some("fs_main"),
                 targets: &[Some(wgpu::ColorTargetState {
                     format: surface_format,
-                    blend: Some(wgpu::BlendState {
-                        color: wgpu::BlendComponent {
-                            src_factor: wgpu::BlendFactor::SrcAlpha,
-                            dst_factor: wgpu::BlendFactor::OneMinusSrcAlpha,
-                            operation: wgpu::BlendOperation::Add,
-                        },
-                        alpha: wgpu::BlendComponent::OVER,
-                    }),
+                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
                     write_mask: wgpu::ColorWrites::ALL,
                 })],
                 compilation_options: Default::default(),
@@ -1767,73 +6027,67 @@ mod quads {
                 polygon_mode: wgpu::PolygonMode::Fill,
                 conservative: false,
             },
+            // Labels are hidden behind geometry but do not hide each other,
+            // so glyphs blend over their own background
             depth_stencil: Some(wgpu::DepthStencilState {
                 format: depth_format,
-                depth_write_enabled: true,
+                depth_write_enabled: false,
                 depth_compare: wgpu::CompareFunction::LessEqual,
                 stencil: wgpu::StencilState::default(),
                 bias: wgpu::DepthBiasState::default(),
             }),
-            multisample: wgpu::MultisampleState::default(),
+            multisample: wgpu::MultisampleState {
+                count: sample_count,
+                ..Default::default()
+            },
             multiview: None,
             cache: None,
         });
 
-        Quads {
+        Labels {
             vertex_buffer,
-            index_buffer,
-            instance_buffer,
             uniform_buffer,
             bind_group,
             pipeline,
         }
     }
 
-    pub fn update_quads_uniform(
+    pub fn update_labels_uniform(
         matrices: &crate::context::CameraMatrices,
-        device: &wgpu::Device,
+        viewport_size: (u32, u32),
         queue: &wgpu::Queue,
-        quads: &mut Quads,
-        instances: Vec<QuadInstance>,
+        labels: &Labels,
     ) {
-        let uniform = QuadUniform {
+        let uniform = LabelsUniform {
             view_proj: matrices.projection * matrices.view,
+            viewport_size: nalgebra_glm::vec4(
+                viewport_size.0.max(1) as f32,
+                viewport_size.1.max(1) as f32,
+                0.0,
+                0.0,
+            ),
         };
-
-        queue.write_buffer(&quads.uniform_buffer, 0, bytemuck::cast_slice(&[uniform]));
-
-        // Create the data that will be sent to the GPU
-        let gpu_data = if instances.is_empty() {
-            vec![QuadInstance {
-                model_matrix_0: nalgebra_glm::vec4(0.0, 0.0, 0.0, 0.0),
-                model_matrix_1: nalgebra_glm::vec4(0.0, 0.0, 0.0, 0.0),
-                model_matrix_2: nalgebra_glm::vec4(0.0, 0.0, 0.0, 0.0),
-                model_matrix_3: nalgebra_glm::vec4(0.0, 0.0, 0.0, 0.0),
-                color: nalgebra_glm::vec4(0.0, 0.0, 0.0, 0.0),
-            }]
-        } else {
-            instances
-        };
-
-        // Always recreate the buffer with the exact size needed
-        quads.instance_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some("Quad Instance Buffer"),
-            contents: bytemuck::cast_slice(&gpu_data),
-            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
-        });
+        queue.write_buffer(&labels.uniform_buffer, 0, bytemuck::cast_slice(&[uniform]));
     }
 
-    pub fn render_quads(render_pass: &mut wgpu::RenderPass, quads: &Quads) {
-        let instance_size = std::mem::size_of::<QuadInstance>();
-        let instance_count = (quads.instance_buffer.size() as usize / instance_size) as u32;
-        if instance_count > 0 {
-            render_pass.set_pipeline(&quads.pipeline);
-            render_pass.set_bind_group(0, &quads.bind_group, &[]);
-            render_pass.set_vertex_buffer(0, quads.vertex_buffer.slice(..));
-            render_pass.set_vertex_buffer(1, quads.instance_buffer.slice(..));
-            render_pass.set_index_buffer(quads.index_buffer.slice(..), wgpu::IndexFormat::Uint16);
-            render_pass.draw_indexed(0..6, 0, 0..instance_count);
+    pub fn render_labels(
+        render_pass: &mut wgpu::RenderPass,
+        labels: &Labels,
+        glyph_atlas: &GlyphAtlas,
+        scene: &SceneRenderList,
+    ) {
+        if scene.glyph_batches.is_empty() {
+            return;
         }
+        render_pass.set_pipeline(&labels.pipeline);
+        render_pass.set_bind_group(0, &labels.bind_group, &[]);
+        render_pass.set_bind_group(2, &glyph_atlas.bind_group, &[]);
+        render_pass.set_vertex_buffer(0, labels.vertex_buffer.slice(..));
+        render_pass.set_vertex_buffer(1, scene.glyphs.slice());
+        scene.glyph_batches.iter().for_each(|batch| {
+            scene.bind_model(render_pass, 1, batch);
+            render_pass.draw(0..6, batch.range.clone());
+        });
     }
 }
 
@@ -0,0 +1,1163 @@
+use crate::context::*;
+
+/// Imports a model file into the world under a new entity named after the file.
+/// The format is chosen from the file extension.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn import_file(
+    context: &mut Context,
+    path: impl AsRef<std::path::Path>,
+) -> Result<EntityId, String> {
+    let path = path.as_ref();
+    let bytes = std::fs::read(path)
+        .map_err(|error| format!("Failed to read {}: {error}", path.display()))?;
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    import_bytes(context, &name, &bytes, path.parent())
+}
+
+/// Imports the contents of a model file, named with its extension so the format can be chosen.
+/// Resources the file refers to, such as the buffers of a `.gltf` file,
+/// are read relative to the directory when one is given.
+pub fn import_bytes(
+    context: &mut Context,
+    name: &str,
+    bytes: &[u8],
+    directory: Option<&std::path::Path>,
+) -> Result<EntityId, String> {
+    let path = std::path::Path::new(name);
+    let extension = path
+        .extension()
+        .map(|extension| extension.to_string_lossy().to_ascii_lowercase())
+        .unwrap_or_default();
+    let stem = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_else(|| name.to_string());
+    let geometries = match extension.as_str() {
+        "gltf" | "glb" => return import_gltf(context, &stem, bytes, directory),
+        "obj" => parse_obj(&stem, bytes),
+        "ply" => parse_ply(&stem, bytes),
+        "stl" => parse_stl(&stem, bytes),
+        _ => return Err(format!("Unsupported file format: {name}")),
+    }
+    .map_err(|error| format!("Failed to import {name}: {error}"))?;
+
+    let root = spawn_imported_node(context, stem, LocalTransform::default(), None);
+    for (name, geometry) in geometries {
+        let entity = spawn_imported_node(context, name, LocalTransform::default(), Some(root));
+        match geometry {
+            Geometry::Mesh(mesh) => {
+                add_components(context, entity, MESH);
+                if let Some(entity_mesh) = get_component_mut::<Mesh>(context, entity, MESH) {
+                    *entity_mesh = mesh;
+                }
+            }
+            Geometry::Points(points) => {
+                add_components(context, entity, POINTS);
+                if let Some(entity_points) = get_component_mut::<Points>(context, entity, POINTS) {
+                    *entity_points = points;
+                }
+            }
+        }
+    }
+    Ok(root)
+}
+
+/// Adds a camera framing everything under an imported root that has no camera of its own,
+/// since a scene can only be viewed through one of its cameras
+pub fn add_framing_camera(context: &mut Context, root: EntityId) {
+    let descendents = query_descendents(context, root);
+    if descendents
+        .iter()
+        .any(|entity| get_component::<Camera>(context, *entity, CAMERA).is_some())
+    {
+        return;
+    }
+
+    update_global_transforms_system(context);
+    let mut bounds: Option<(nalgebra_glm::Vec3, nalgebra_glm::Vec3)> = None;
+    for entity in &descendents {
+        let Some(GlobalTransform(transform)) =
+            get_component::<GlobalTransform>(context, *entity, GLOBAL_TRANSFORM)
+        else {
+            continue;
+        };
+        let mesh_positions = get_component::<Mesh>(context, *entity, MESH)
+            .into_iter()
+            .flat_map(|mesh| mesh.positions.iter().copied());
+        let quad_positions = get_component::<Quads>(context, *entity, QUADS)
+            .into_iter()
+            .flat_map(|Quads(quads)| quads.iter().map(|quad| quad.offset));
+        let point_positions = get_component::<Points>(context, *entity, POINTS)
+            .into_iter()
+            .flat_map(|points| points.positions.iter().copied());
+        for position in mesh_positions.chain(quad_positions).chain(point_positions) {
+            let position = (transform * position.push(1.0)).xyz();
+            bounds = Some(match bounds {
+                Some((min, max)) => (min.inf(&position), max.sup(&position)),
+                None => (position, position),
+            });
+        }
+    }
+    let (min, max) = bounds.unwrap_or_default();
+    let center = (min + max) * 0.5;
+    let radius = (nalgebra_glm::distance(&min, &max) * 0.5).max(0.01);
+
+    // Far enough away for the bounding sphere to fit in the default field of view
+    let eye = center + nalgebra_glm::vec3(0.5, 0.4, 1.0).normalize() * radius * 3.0;
+    let transform = LocalTransform {
+        translation: eye,
+        rotation: look_at_rotation(&eye, &center),
+        ..Default::default()
+    };
+    let camera = spawn_imported_node(context, "Camera".to_string(), transform, Some(root));
+    add_components(context, camera, CAMERA);
+    if context.resources.active_camera_entity.is_none() {
+        context.resources.active_camera_entity = Some(camera);
+    }
+}
+
+/// Geometry read from a model file, before it is spawned
+enum Geometry {
+    Mesh(Mesh),
+
+    /// Points without faces, such as scans
+    Points(Points),
+}
+
+/// Splits geometry into a mesh when it has faces, or points when it has none
+fn mesh_or_points(mut mesh: Mesh) -> Geometry {
+    if mesh.indices.is_empty() {
+        return Geometry::Points(Points {
+            positions: mesh.positions.into(),
+            colors: mesh.colors.into(),
+            ..Default::default()
+        });
+    }
+    if mesh.normals.len() != mesh.positions.len() {
+        mesh.normals = mesh.smooth_normals();
+    }
+    Geometry::Mesh(mesh)
+}
+
+/// Appends a polygon as a fan of triangles
+fn triangulate(indices: &mut Vec<u32>, polygon: &[u32]) {
+    for corner in 1..polygon.len().saturating_sub(1) {
+        indices.extend([polygon[0], polygon[corner], polygon[corner + 1]]);
+    }
+}
+
+/// Reads a Wavefront OBJ file. Every object or group becomes a separate mesh,
+/// and polygons are split into triangles. Materials are not imported.
+fn parse_obj(name: &str, bytes: &[u8]) -> Result<Vec<(String, Geometry)>, String> {
+    let text = std::str::from_utf8(bytes).map_err(|error| format!("Not a text file: {error}"))?;
+
+    // Positions, texture coordinates and normals are shared by every object in the file
+    let mut positions = Vec::new();
+    let mut position_colors = Vec::new();
+    let mut uvs = Vec::new();
+    let mut normals = Vec::new();
+
+    let mut objects = Vec::new();
+    let mut object_name = name.to_string();
+    let mut mesh = Mesh::default();
+    let mut vertices = std::collections::HashMap::new();
+
+    for (line_index, line) in text.lines().enumerate() {
+        let line_number = line_index + 1;
+        let line = line.split('#').next().unwrap_or_default();
+        let mut tokens = line.split_whitespace();
+        let Some(keyword) = tokens.next() else {
+            continue;
+        };
+        let values = tokens.collect::<Vec<_>>();
+        match keyword {
+            "v" => {
+                let coordinates = parse_floats(&values, line_number)?;
+                match coordinates.len() {
+                    // Some tools append a weight or an RGB vertex color to positions
+                    3 | 4 => {}
+                    6 | 7 => position_colors.resize(positions.len(), None),
+                    _ => {
+                        return Err(format!(
+                            "line {line_number}: expected 3 coordinates, found {}",
+                            coordinates.len()
+                        ))
+                    }
+                }
+                if coordinates.len() >= 6 {
+                    position_colors.push(Some(nalgebra_glm::vec4(
+                        coordinates[3],
+                        coordinates[4],
+                        coordinates[5],
+                        1.0,
+                    )));
+                }
+                positions.push(nalgebra_glm::make_vec3(&coordinates[..3]));
+            }
+            "vt" => {
+                let coordinates = parse_floats(&values, line_number)?;
+                if coordinates.is_empty() {
+                    return Err(format!("line {line_number}: expected texture coordinates"));
+                }
+                uvs.push(nalgebra_glm::vec2(
+                    coordinates[0],
+                    coordinates.get(1).copied().unwrap_or_default(),
+                ));
+            }
+            "vn" => {
+                let coordinates = parse_floats(&values, line_number)?;
+                if coordinates.len() != 3 {
+                    return Err(format!(
+                        "line {line_number}: expected 3 normal components, found {}",
+                        coordinates.len()
+                    ));
+                }
+                normals.push(nalgebra_glm::make_vec3(&coordinates));
+            }
+            "f" => {
+                if values.len() < 3 {
+                    return Err(format!(
+                        "line {line_number}: a face needs at least 3 vertices, found {}",
+                        values.len()
+                    ));
+                }
+                let mut polygon = Vec::with_capacity(values.len());
+                for value in values {
+                    let mut references = value.split('/');
+                    let position =
+                        resolve_obj_index(references.next(), positions.len(), line_number)?
+                            .ok_or_else(|| {
+                                format!("line {line_number}: a face vertex has no position")
+                            })?;
+                    let uv = resolve_obj_index(references.next(), uvs.len(), line_number)?;
+                    let normal = resolve_obj_index(references.next(), normals.len(), line_number)?;
+
+                    // Each distinct combination of attributes becomes one vertex
+                    let vertex = *vertices.entry((position, uv, normal)).or_insert_with(|| {
+                        mesh.positions.push(positions[position]);
+                        mesh.uvs.push(uv.map(|uv| uvs[uv]).unwrap_or_default());
+                        mesh.normals
+                            .push(normal.map(|normal| normals[normal]).unwrap_or_default());
+                        mesh.colors.push(
+                            position_colors
+                                .get(position)
+                                .copied()
+                                .flatten()
+                                .unwrap_or(nalgebra_glm::vec4(1.0, 1.0, 1.0, 1.0)),
+                        );
+                        mesh.positions.len() as u32 - 1
+                    });
+                    polygon.push(vertex);
+                }
+                triangulate(&mut mesh.indices, &polygon);
+            }
+            "o" | "g" => {
+                if !mesh.indices.is_empty() {
+                    objects.push((object_name.clone(), std::mem::take(&mut mesh)));
+                    vertices.clear();
+                }
+                if !values.is_empty() {
+                    object_name = values.join(" ");
+                }
+            }
+            // Materials, smoothing groups, lines and other statements are not imported
+            _ => {}
+        }
+    }
+    if !mesh.indices.is_empty() {
+        objects.push((object_name, mesh));
+    }
+
+    if objects.is_empty() {
+        if positions.is_empty() {
+            return Err("The file has no vertices".to_string());
+        }
+        let colors = if position_colors.is_empty() {
+            Vec::new()
+        } else {
+            position_colors.resize(positions.len(), None);
+            position_colors
+                .into_iter()
+                .map(|color| color.unwrap_or(nalgebra_glm::vec4(1.0, 1.0, 1.0, 1.0)))
+                .collect()
+        };
+        return Ok(vec![(
+            name.to_string(),
+            Geometry::Points(Points {
+                positions: positions.into(),
+                colors: colors.into(),
+                ..Default::default()
+            }),
+        )]);
+    }
+
+    let has_normals = !normals.is_empty();
+    let has_uvs = !uvs.is_empty();
+    let has_colors = !position_colors.is_empty();
+    Ok(objects
+        .into_iter()
+        .map(|(name, mut mesh)| {
+            if !has_normals {
+                mesh.normals.clear();
+            }
+            if !has_uvs {
+                mesh.uvs.clear();
+            }
+            if !has_colors {
+                mesh.colors.clear();
+            }
+            (name, mesh_or_points(mesh))
+        })
+        .collect())
+}
+
+fn parse_floats(values: &[&str], line_number: usize) -> Result<Vec<f32>, String> {
+    values
+        .iter()
+        .map(|value| {
+            value
+                .parse::<f32>()
+                .map_err(|_| format!("line {line_number}: {value} is not a number"))
+        })
+        .collect()
+}
+
+/// Resolves a one-based or negative relative OBJ index to a zero-based index
+fn resolve_obj_index(
+    reference: Option<&str>,
+    count: usize,
+    line_number: usize,
+) -> Result<Option<usize>, String> {
+    let Some(reference) = reference.filter(|reference| !reference.is_empty()) else {
+        return Ok(None);
+    };
+    let index = reference
+        .parse::<i64>()
+        .map_err(|_| format!("line {line_number}: {reference} is not an index"))?;
+    let resolved = match index {
+        1.. => index - 1,
+        ..=-1 => count as i64 + index,
+        0 => -1,
+    };
+    if resolved < 0 || resolved >= count as i64 {
+        return Err(format!(
+            "line {line_number}: index {index} is out of range, there are {count} elements"
+        ));
+    }
+    Ok(Some(resolved as usize))
+}
+
+/// Reads a PLY file in ASCII or binary encoding.
+/// Vertices with faces become a mesh and vertices without faces become points,
+/// using any vertex normals and colors in the file.
+fn parse_ply(name: &str, bytes: &[u8]) -> Result<Vec<(String, Geometry)>, String> {
+    const END_HEADER: &[u8] = b"end_header";
+    let header_end = bytes
+        .windows(END_HEADER.len())
+        .position(|window| window == END_HEADER)
+        .ok_or("The header has no end_header line")?;
+    let body_start = bytes[header_end..]
+        .iter()
+        .position(|byte| *byte == b'\n')
+        .map(|offset| header_end + offset + 1)
+        .unwrap_or(bytes.len());
+    let header = std::str::from_utf8(&bytes[..header_end])
+        .map_err(|_| "The header is not text".to_string())?;
+
+    let mut lines = header.lines().map(str::trim);
+    if lines.next() != Some("ply") {
+        return Err("The file does not start with ply".to_string());
+    }
+    let mut format = None;
+    let mut elements: Vec<PlyElement> = Vec::new();
+    for line in lines {
+        let tokens = line.split_whitespace().collect::<Vec<_>>();
+        match tokens.as_slice() {
+            ["format", encoding, _] => {
+                format = Some(match *encoding {
+                    "ascii" => PlyFormat::Ascii,
+                    "binary_little_endian" => PlyFormat::BinaryLittleEndian,
+                    "binary_big_endian" => PlyFormat::BinaryBigEndian,
+                    _ => return Err(format!("Unsupported format {encoding}")),
+                })
+            }
+            ["element", element_name, count] => elements.push(PlyElement {
+                name: element_name.to_string(),
+                count: count
+                    .parse()
+                    .map_err(|_| format!("The element count {count} is not a number"))?,
+                properties: Vec::new(),
+            }),
+            ["property", "list", count_type, item_type, property_name] => elements
+                .last_mut()
+                .ok_or("A property is declared before any element")?
+                .properties
+                .push(PlyProperty {
+                    name: property_name.to_string(),
+                    kind: PlyPropertyKind::List(
+                        PlyScalar::parse(count_type)?,
+                        PlyScalar::parse(item_type)?,
+                    ),
+                }),
+            ["property", scalar, property_name] => elements
+                .last_mut()
+                .ok_or("A property is declared before any element")?
+                .properties
+                .push(PlyProperty {
+                    name: property_name.to_string(),
+                    kind: PlyPropertyKind::Scalar(PlyScalar::parse(scalar)?),
+                }),
+            ["comment", ..] | ["obj_info", ..] | [] => {}
+            _ => return Err(format!("Unexpected header line: {line}")),
+        }
+    }
+    let format = format.ok_or("The header has no format line")?;
+
+    let mut reader = PlyReader {
+        format,
+        bytes: &bytes[body_start..],
+        position: 0,
+        tokens: Vec::new(),
+    };
+    if format == PlyFormat::Ascii {
+        let body = std::str::from_utf8(reader.bytes)
+            .map_err(|_| "The body of an ASCII file is not text".to_string())?;
+        reader.tokens = body.split_whitespace().rev().collect();
+    }
+
+    let mut mesh = Mesh::default();
+    let mut has_normals = false;
+    let mut has_colors = false;
+    for element in &elements {
+        match element.name.as_str() {
+            "vertex" => {
+                let property = |names: &[&str]| {
+                    element
+                        .properties
+                        .iter()
+                        .position(|property| names.contains(&property.name.as_str()))
+                };
+                let coordinates = [property(&["x"]), property(&["y"]), property(&["z"])];
+                let [Some(x), Some(y), Some(z)] = coordinates else {
+                    return Err("Vertices have no x, y and z properties".to_string());
+                };
+                let normal = [property(&["nx"]), property(&["ny"]), property(&["nz"])];
+                let color = [
+                    property(&["red", "r", "diffuse_red"]),
+                    property(&["green", "g", "diffuse_green"]),
+                    property(&["blue", "b", "diffuse_blue"]),
+                ];
+                let alpha = property(&["alpha", "a"]);
+                has_normals = normal.iter().all(Option::is_some);
+                has_colors = color.iter().all(Option::is_some);
+
+                for vertex in 0..element.count {
+                    let mut values = Vec::with_capacity(element.properties.len());
+                    for property in &element.properties {
+                        let PlyPropertyKind::Scalar(scalar) = property.kind else {
+                            reader.skip_list(&property.kind, vertex)?;
+                            values.push(0.0);
+                            continue;
+                        };
+                        values.push(
+                            reader.read(scalar).map_err(|error| {
+                                format!("Failed to read vertex {vertex}: {error}")
+                            })?,
+                        );
+                    }
+                    mesh.positions
+                        .push(nalgebra_glm::vec3(values[x], values[y], values[z]));
+                    if let [Some(nx), Some(ny), Some(nz)] = normal {
+                        mesh.normals
+                            .push(nalgebra_glm::vec3(values[nx], values[ny], values[nz]));
+                    }
+                    if let [Some(red), Some(green), Some(blue)] = color {
+                        let channel = |index: usize| {
+                            let PlyPropertyKind::Scalar(scalar) = element.properties[index].kind
+                            else {
+                                return 1.0;
+                            };
+                            scalar.normalize(values[index])
+                        };
+                        mesh.colors.push(nalgebra_glm::vec4(
+                            channel(red),
+                            channel(green),
+                            channel(blue),
+                            alpha.map(channel).unwrap_or(1.0),
+                        ));
+                    }
+                }
+            }
+            "face" => {
+                let indices = element
+                    .properties
+                    .iter()
+                    .position(|property| {
+                        matches!(property.name.as_str(), "vertex_indices" | "vertex_index")
+                    })
+                    .ok_or("Faces have no vertex_indices property")?;
+                for face in 0..element.count {
+                    for (property_index, property) in element.properties.iter().enumerate() {
+                        match property.kind {
+                            PlyPropertyKind::List(count_type, item_type)
+                                if property_index == indices =>
+                            {
+                                let count = reader.read(count_type).map_err(|error| {
+                                    format!("Failed to read face {face}: {error}")
+                                })? as usize;
+                                // The count comes from the file, so it is bounded by what is left
+                                // before anything is allocated for it
+                                if count > reader.remaining(item_type) {
+                                    return Err(format!(
+                                        "Face {face} has {count} vertices, but the file ended early"
+                                    ));
+                                }
+                                let mut polygon = Vec::with_capacity(count);
+                                for _ in 0..count {
+                                    let index = reader.read(item_type).map_err(|error| {
+                                        format!("Failed to read face {face}: {error}")
+                                    })?;
+                                    if index < 0.0 || index as usize >= mesh.positions.len() {
+                                        return Err(format!(
+                                            "Face {face} refers to vertex {index}, but there are {} vertices",
+                                            mesh.positions.len()
+                                        ));
+                                    }
+                                    polygon.push(index as u32);
+                                }
+                                triangulate(&mut mesh.indices, &polygon);
+                            }
+                            PlyPropertyKind::List(..) => reader.skip_list(&property.kind, face)?,
+                            PlyPropertyKind::Scalar(scalar) => {
+                                reader.read(scalar).map_err(|error| {
+                                    format!("Failed to read face {face}: {error}")
+                                })?;
+                            }
+                        }
+                    }
+                }
+            }
+            // Other elements, such as edges, are read past and ignored
+            _ => {
+                for item in 0..element.count {
+                    for property in &element.properties {
+                        match property.kind {
+                            PlyPropertyKind::Scalar(scalar) => {
+                                reader.read(scalar).map_err(|error| {
+                                    format!("Failed to read {} {item}: {error}", element.name)
+                                })?;
+                            }
+                            PlyPropertyKind::List(..) => reader.skip_list(&property.kind, item)?,
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    if mesh.positions.is_empty() {
+        return Err("The file has no vertices".to_string());
+    }
+    if !has_normals {
+        mesh.normals.clear();
+    }
+    if !has_colors {
+        mesh.colors.clear();
+    }
+    Ok(vec![(name.to_string(), mesh_or_points(mesh))])
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum PlyFormat {
+    Ascii,
+    BinaryLittleEndian,
+    BinaryBigEndian,
+}
+
+struct PlyElement {
+    name: String,
+    count: usize,
+    properties: Vec<PlyProperty>,
+}
+
+struct PlyProperty {
+    name: String,
+    kind: PlyPropertyKind,
+}
+
+#[derive(Debug, Copy, Clone)]
+enum PlyPropertyKind {
+    Scalar(PlyScalar),
+
+    /// A count followed by that many items
+    List(PlyScalar, PlyScalar),
+}
+
+#[derive(Debug, Copy, Clone)]
+enum PlyScalar {
+    Int8,
+    UInt8,
+    Int16,
+    UInt16,
+    Int32,
+    UInt32,
+    Float32,
+    Float64,
+}
+
+impl PlyScalar {
+    fn parse(name: &str) -> Result<Self, String> {
+        Ok(match name {
+            "char" | "int8" => Self::Int8,
+            "uchar" | "uint8" => Self::UInt8,
+            "short" | "int16" => Self::Int16,
+            "ushort" | "uint16" => Self::UInt16,
+            "int" | "int32" => Self::Int32,
+            "uint" | "uint32" => Self::UInt32,
+            "float" | "float32" => Self::Float32,
+            "double" | "float64" => Self::Float64,
+            _ => return Err(format!("Unknown property type {name}")),
+        })
+    }
+
+    fn size(self) -> usize {
+        match self {
+            Self::Int8 | Self::UInt8 => 1,
+            Self::Int16 | Self::UInt16 => 2,
+            Self::Int32 | Self::UInt32 | Self::Float32 => 4,
+            Self::Float64 => 8,
+        }
+    }
+
+    /// Maps a color channel to the range of zero to one, since integer channels span their type
+    fn normalize(self, value: f32) -> f32 {
+        match self {
+            Self::UInt8 => value / u8::MAX as f32,
+            Self::UInt16 => value / u16::MAX as f32,
+            _ => value,
+        }
+    }
+}
+
+struct PlyReader<'a> {
+    format: PlyFormat,
+    bytes: &'a [u8],
+    position: usize,
+
+    /// The remaining values of an ASCII body, in reverse order
+    tokens: Vec<&'a str>,
+}
+
+impl PlyReader<'_> {
+    fn read(&mut self, scalar: PlyScalar) -> Result<f32, String> {
+        if self.format == PlyFormat::Ascii {
+            let token = self.tokens.pop().ok_or("The file ended early")?;
+            return token
+                .parse::<f64>()
+                .map(|value| value as f32)
+                .map_err(|_| format!("{token} is not a number"));
+        }
+
+        let size = scalar.size();
+        let bytes = self
+            .bytes
+            .get(self.position..self.position + size)
+            .ok_or("The file ended early")?;
+        self.position += size;
+        let mut buffer = [0; 8];
+        buffer[..size].copy_from_slice(bytes);
+        if self.format == PlyFormat::BinaryBigEndian {
+            buffer[..size].reverse();
+        }
+        Ok(match scalar {
+            PlyScalar::Int8 => buffer[0] as i8 as f32,
+            PlyScalar::UInt8 => buffer[0] as f32,
+            PlyScalar::Int16 => i16::from_le_bytes([buffer[0], buffer[1]]) as f32,
+            PlyScalar::UInt16 => u16::from_le_bytes([buffer[0], buffer[1]]) as f32,
+            PlyScalar::Int32 => {
+                i32::from_le_bytes([buffer[0], buffer[1], buffer[2], buffer[3]]) as f32
+            }
+            PlyScalar::UInt32 => {
+                u32::from_le_bytes([buffer[0], buffer[1], buffer[2], buffer[3]]) as f32
+            }
+            PlyScalar::Float32 => f32::from_le_bytes([buffer[0], buffer[1], buffer[2], buffer[3]]),
+            PlyScalar::Float64 => f64::from_le_bytes(buffer) as f32,
+        })
+    }
+
+    /// The number of values of a type that are left to read
+    fn remaining(&self, scalar: PlyScalar) -> usize {
+        match self.format {
+            PlyFormat::Ascii => self.tokens.len(),
+            _ => (self.bytes.len() - self.position) / scalar.size(),
+        }
+    }
+
+    fn skip_list(&mut self, kind: &PlyPropertyKind, item: usize) -> Result<(), String> {
+        let PlyPropertyKind::List(count_type, item_type) = *kind else {
+            return Ok(());
+        };
+        let count = self
+            .read(count_type)
+            .map_err(|error| format!("Failed to read item {item}: {error}"))?;
+        for _ in 0..count as usize {
+            self.read(item_type)
+                .map_err(|error| format!("Failed to read item {item}: {error}"))?;
+        }
+        Ok(())
+    }
+}
+
+/// Reads an STL file in binary or ASCII encoding.
+/// Triangles keep their facet normals, so flat CAD surfaces stay flat.
+fn parse_stl(name: &str, bytes: &[u8]) -> Result<Vec<(String, Geometry)>, String> {
+    // Binary files may also start with "solid", so their size is checked first
+    let binary_triangle_count = bytes
+        .get(80..84)
+        .map(|count| u32::from_le_bytes([count[0], count[1], count[2], count[3]]) as usize);
+    let mesh = match binary_triangle_count {
+        Some(count) if bytes.len() == 84 + count * 50 => parse_binary_stl(&bytes[84..], count),
+        _ if bytes.trim_ascii_start().starts_with(b"solid") => parse_ascii_stl(bytes)?,
+        Some(count) => {
+            return Err(format!(
+                "The binary file declares {count} triangles, which needs {} bytes, but it has {}",
+                84 + count * 50,
+                bytes.len()
+            ))
+        }
+        None => return Err("The file is too short".to_string()),
+    };
+    if mesh.indices.is_empty() {
+        return Err("The file has no triangles".to_string());
+    }
+    Ok(vec![(name.to_string(), Geometry::Mesh(mesh))])
+}
+
+fn parse_binary_stl(bytes: &[u8], count: usize) -> Mesh {
+    let mut mesh = Mesh::default();
+    let float = |chunk: &[u8], index: usize| {
+        let offset = index * 4;
+        f32::from_le_bytes([
+            chunk[offset],
+            chunk[offset + 1],
+            chunk[offset + 2],
+            chunk[offset + 3],
+        ])
+    };
+    for triangle in bytes.chunks_exact(50).take(count) {
+        let normal = nalgebra_glm::vec3(float(triangle, 0), float(triangle, 1), float(triangle, 2));
+        let corners = [3, 6, 9].map(|first| {
+            nalgebra_glm::vec3(
+                float(triangle, first),
+                float(triangle, first + 1),
+                float(triangle, first + 2),
+            )
+        });
+        push_stl_triangle(&mut mesh, normal, corners);
+    }
+    mesh
+}
+
+fn parse_ascii_stl(bytes: &[u8]) -> Result<Mesh, String> {
+    let text = std::str::from_utf8(bytes).map_err(|error| format!("Not a text file: {error}"))?;
+    let mut mesh = Mesh::default();
+    let mut normal = nalgebra_glm::Vec3::zeros();
+    let mut corners = Vec::with_capacity(3);
+    for (line_index, line) in text.lines().enumerate() {
+        let line_number = line_index + 1;
+        let tokens = line.split_whitespace().collect::<Vec<_>>();
+        match tokens.as_slice() {
+            ["facet", "normal", values @ ..] => {
+                let values = parse_floats(values, line_number)?;
+                if values.len() != 3 {
+                    return Err(format!("line {line_number}: expected 3 normal components"));
+                }
+                normal = nalgebra_glm::make_vec3(&values);
+                corners.clear();
+            }
+            ["vertex", values @ ..] => {
+                let values = parse_floats(values, line_number)?;
+                if values.len() != 3 {
+                    return Err(format!("line {line_number}: expected 3 coordinates"));
+                }
+                corners.push(nalgebra_glm::make_vec3(&values));
+            }
+            ["endfacet"] => {
+                let [a, b, c] = corners.as_slice() else {
+                    return Err(format!(
+                        "line {line_number}: a facet needs 3 vertices, found {}",
+                        corners.len()
+                    ));
+                };
+                push_stl_triangle(&mut mesh, normal, [*a, *b, *c]);
+            }
+            _ => {}
+        }
+    }
+    Ok(mesh)
+}
+
+fn push_stl_triangle(
+    mesh: &mut Mesh,
+    normal: nalgebra_glm::Vec3,
+    corners: [nalgebra_glm::Vec3; 3],
+) {
+    // Some exporters leave facet normals zeroed, so they are computed from the winding instead
+    let normal = normal.try_normalize(f32::EPSILON).unwrap_or_else(|| {
+        (corners[1] - corners[0])
+            .cross(&(corners[2] - corners[0]))
+            .try_normalize(f32::EPSILON)
+            .unwrap_or_default()
+    });
+    let first = mesh.positions.len() as u32;
+    mesh.positions.extend(corners);
+    mesh.normals.extend([normal; 3]);
+    mesh.indices.extend([first, first + 1, first + 2]);
+}
+
+/// Imports the default scene of a glTF 2.0 file.
+/// Nodes become entities parented under a new root entity, meshes become `Mesh` components
+/// colored by the base color of their material, and cameras become `Camera` components.
+pub fn import_gltf(
+    context: &mut Context,
+    name: &str,
+    bytes: &[u8],
+    directory: Option<&std::path::Path>,
+) -> Result<EntityId, String> {
+    let gltf::Gltf { document, blob } = gltf::Gltf::from_slice(bytes)
+        .map_err(|error| format!("Failed to import {name}: {error}"))?;
+    let buffers = document
+        .buffers()
+        .map(|buffer| read_gltf_buffer(&buffer, blob.as_deref(), directory))
+        .collect::<Result<Vec<_>, _>>()?;
+    let scene = document
+        .default_scene()
+        .or_else(|| document.scenes().next())
+        .ok_or_else(|| format!("{name} has no scenes"))?;
+
+    let root = spawn_imported_node(context, name.to_string(), LocalTransform::default(), None);
+    scene
+        .nodes()
+        .for_each(|node| import_gltf_node(context, &node, root, &buffers));
+    Ok(root)
+}
+
+fn read_gltf_buffer(
+    buffer: &gltf::Buffer<'_>,
+    blob: Option<&[u8]>,
+    directory: Option<&std::path::Path>,
+) -> Result<Vec<u8>, String> {
+    use base64::Engine as _;
+    match buffer.source() {
+        gltf::buffer::Source::Bin => blob
+            .map(<[u8]>::to_vec)
+            .ok_or_else(|| "The binary chunk of the file is missing".to_string()),
+        gltf::buffer::Source::Uri(uri) if uri.starts_with("data:") => {
+            let (_, data) = uri
+                .split_once(";base64,")
+                .ok_or_else(|| "Only base64 encoded data URIs are supported".to_string())?;
+            base64::engine::general_purpose::STANDARD
+                .decode(data)
+                .map_err(|error| format!("Failed to decode a data URI: {error}"))
+        }
+        gltf::buffer::Source::Uri(uri) => {
+            let directory = directory.ok_or_else(|| {
+                format!("The buffer {uri} is a separate file, which needs the path of the model")
+            })?;
+            read_external_file(&directory.join(uri))
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn read_external_file(path: &std::path::Path) -> Result<Vec<u8>, String> {
+    std::fs::read(path).map_err(|error| format!("Failed to read {}: {error}", path.display()))
+}
+
+#[cfg(target_arch = "wasm32")]
+fn read_external_file(path: &std::path::Path) -> Result<Vec<u8>, String> {
+    Err(format!(
+        "{} cannot be read in a browser, use a .glb file instead",
+        path.display()
+    ))
+}
+
+fn import_gltf_node(
+    context: &mut Context,
+    node: &gltf::Node<'_>,
+    parent: EntityId,
+    buffers: &[Vec<u8>],
+) {
+    let (translation, rotation, scale) = node.transform().decomposed();
+    let transform = LocalTransform {
+        translation: nalgebra_glm::make_vec3(&translation),
+        rotation: nalgebra_glm::quat(rotation[0], rotation[1], rotation[2], rotation[3]),
+        scale: nalgebra_glm::make_vec3(&scale),
+    };
+    let name = node
+        .name()
+        .map(str::to_string)
+        .unwrap_or_else(|| format!("Node {}", node.index()));
+    let entity = spawn_imported_node(context, name, transform, Some(parent));
+
+    if let Some(mesh) = node.mesh() {
+        add_components(context, entity, MESH);
+        if let Some(entity_mesh) = get_component_mut::<Mesh>(context, entity, MESH) {
+            *entity_mesh = import_gltf_mesh(&mesh, buffers);
+        }
+    }
+
+    if let Some(camera) = node.camera() {
+        add_components(context, entity, CAMERA);
+        if let Some(entity_camera) = get_component_mut::<Camera>(context, entity, CAMERA) {
+            *entity_camera = import_gltf_camera(&camera);
+        }
+        if context.resources.active_camera_entity.is_none() {
+            context.resources.active_camera_entity = Some(entity);
+        }
+    }
+
+    node.children()
+        .for_each(|child| import_gltf_node(context, &child, entity, buffers));
+}
+
+/// Merges the triangle primitives of a mesh into one `Mesh`.
+/// Base colors are baked into the vertex colors unless every vertex shares one color.
+fn import_gltf_mesh(mesh: &gltf::Mesh<'_>, buffers: &[Vec<u8>]) -> Mesh {
+    let mut imported = Mesh::default();
+    let mut has_uvs = false;
+    for primitive in mesh.primitives() {
+        if primitive.mode() != gltf::mesh::Mode::Triangles {
+            log::warn!(
+                "Skipping a {:?} primitive, only triangles are imported",
+                primitive.mode()
+            );
+            continue;
+        }
+        let reader = primitive.reader(|buffer| buffers.get(buffer.index()).map(Vec::as_slice));
+        let Some(positions) = reader.read_positions() else {
+            continue;
+        };
+
+        let mut part = Mesh {
+            positions: positions
+                .map(|position| nalgebra_glm::make_vec3(&position))
+                .collect(),
+            ..Default::default()
+        };
+        let vertex_count = part.positions.len();
+        part.indices = match reader.read_indices() {
+            Some(indices) => indices.into_u32().collect(),
+            None => (0..vertex_count as u32).collect(),
+        };
+        part.normals = match reader.read_normals() {
+            Some(normals) => normals
+                .map(|normal| nalgebra_glm::make_vec3(&normal))
+                .collect(),
+            None => part.smooth_normals(),
+        };
+        part.uvs = match reader.read_tex_coords(0) {
+            Some(uvs) => {
+                has_uvs = true;
+                uvs.into_f32()
+                    .map(|uv| nalgebra_glm::make_vec2(&uv))
+                    .collect()
+            }
+            None => vec![nalgebra_glm::Vec2::zeros(); vertex_count],
+        };
+        let base_color = nalgebra_glm::make_vec4(
+            &primitive
+                .material()
+                .pbr_metallic_roughness()
+                .base_color_factor(),
+        );
+        part.colors = match reader.read_colors(0) {
+            Some(colors) => colors
+                .into_rgba_f32()
+                .map(|color| base_color.component_mul(&nalgebra_glm::make_vec4(&color)))
+                .collect(),
+            None => vec![base_color; vertex_count],
+        };
+
+        let first_vertex = imported.positions.len() as u32;
+        imported
+            .indices
+            .extend(part.indices.iter().map(|index| first_vertex + index));
+        imported.positions.extend(part.positions);
+        imported.normals.extend(part.normals);
+        imported.uvs.extend(part.uvs);
+        imported.colors.extend(part.colors);
+    }
+
+    if !has_uvs {
+        imported.uvs.clear();
+    }
+    if let Some(first) = imported.colors.first().copied() {
+        if imported.colors.iter().all(|color| *color == first) {
+            imported.color = first;
+            imported.colors.clear();
+        }
+    }
+    imported
+}
+
+fn import_gltf_camera(camera: &gltf::Camera<'_>) -> Camera {
+    match camera.projection() {
+        gltf::camera::Projection::Perspective(perspective) => Camera {
+            projection: Projection::Perspective(PerspectiveCamera {
+                aspect_ratio: perspective.aspect_ratio(),
+                y_fov_rad: perspective.yfov(),
+                z_far: perspective.zfar(),
+                z_near: perspective.znear(),
+            }),
+            // The field of view of the camera takes precedence over the one in its projection
+            fov: perspective.yfov().to_degrees(),
+        },
+        gltf::camera::Projection::Orthographic(orthographic) => Camera {
+            projection: Projection::Orthographic(OrthographicCamera {
+                x_mag: orthographic.xmag(),
+                y_mag: orthographic.ymag(),
+                z_far: orthographic.zfar(),
+                z_near: orthographic.znear(),
+            }),
+            ..Default::default()
+        },
+    }
+}
+
+fn spawn_imported_node(
+    context: &mut Context,
+    name: String,
+    transform: LocalTransform,
+    parent: Option<EntityId>,
+) -> EntityId {
+    let entity = spawn_entities(context, LOCAL_TRANSFORM | GLOBAL_TRANSFORM | NAME, 1)[0];
+    if let Some(entity_name) = get_component_mut::<Name>(context, entity, NAME) {
+        *entity_name = Name(name);
+    }
+    if let Some(local_transform) =
+        get_component_mut::<LocalTransform>(context, entity, LOCAL_TRANSFORM)
+    {
+        *local_transform = transform;
+    }
+    if let Some(parent) = parent {
+        add_components(context, entity, PARENT);
+        if let Some(entity_parent) = get_component_mut::<Parent>(context, entity, PARENT) {
+            *entity_parent = Parent(parent);
+        }
+    }
+    entity
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn single_mesh(geometries: Result<Vec<(String, Geometry)>, String>) -> Mesh {
+        match geometries.map(|mut geometries| geometries.pop()) {
+            Ok(Some((_, Geometry::Mesh(mesh)))) => mesh,
+            Ok(_) => panic!("Expected a mesh"),
+            Err(error) => panic!("Expected a mesh, got {error}"),
+        }
+    }
+
+    fn error(geometries: Result<Vec<(String, Geometry)>, String>) -> String {
+        match geometries {
+            Ok(_) => panic!("Expected an error"),
+            Err(error) => error,
+        }
+    }
+
+    fn binary_stl(declared: u32, triangles: usize) -> Vec<u8> {
+        let mut bytes = vec![0; 80];
+        bytes.extend(declared.to_le_bytes());
+        for _ in 0..triangles {
+            let values = [
+                0.0f32, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0,
+            ];
+            bytes.extend(values.iter().flat_map(|value| value.to_le_bytes()));
+            bytes.extend([0, 0]);
+        }
+        bytes
+    }
+
+    #[test]
+    fn parse_obj_triangulates_faces() {
+        let obj = "v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\nf 1 2 3 4\n";
+        let mesh = single_mesh(parse_obj("quad", obj.as_bytes()));
+        assert_eq!(mesh.positions.len(), 4);
+        assert_eq!(mesh.indices, vec![0, 1, 2, 0, 2, 3]);
+        assert_eq!(mesh.normals.len(), 4);
+    }
+
+    #[test]
+    fn parse_obj_rejects_bad_indices() {
+        let vertices = "v 0 0 0\nv 1 0 0\nv 1 1 0\n";
+        for face in ["f 0 1 2", "f 1 2 4", "f 1 2 -4", "f 1 2 x"] {
+            let obj = format!("{vertices}{face}\n");
+            let error = error(parse_obj("bad", obj.as_bytes()));
+            assert!(error.starts_with("line 4:"), "{error}");
+        }
+    }
+
+    #[test]
+    fn parse_stl_reads_binary_files() {
+        let mesh = single_mesh(parse_stl("triangle", &binary_stl(1, 1)));
+        assert_eq!(mesh.indices, vec![0, 1, 2]);
+        assert_eq!(mesh.normals[0], nalgebra_glm::vec3(0.0, 0.0, 1.0));
+    }
+
+    #[test]
+    fn parse_stl_rejects_truncated_binary_files() {
+        let error = error(parse_stl("truncated", &binary_stl(2, 1)));
+        assert!(error.contains("declares 2 triangles"), "{error}");
+        assert!(parse_stl("short", &[0; 40]).is_err());
+    }
+
+    #[test]
+    fn parse_ply_reads_ascii_files() {
+        let ply = "ply\nformat ascii 1.0\nelement vertex 3\nproperty float x\nproperty float y\nproperty float z\nelement face 1\nproperty list uchar int vertex_indices\nend_header\n0 0 0\n1 0 0\n0 1 0\n3 0 1 2\n";
+        let mesh = single_mesh(parse_ply("triangle", ply.as_bytes()));
+        assert_eq!(mesh.positions.len(), 3);
+        assert_eq!(mesh.indices, vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn parse_ply_rejects_bad_headers() {
+        assert!(parse_ply("bad", b"plx\nformat ascii 1.0\nend_header\n").is_err());
+        assert!(parse_ply("bad", b"ply\nformat ascii 1.0\nelement vertex 1\n").is_err());
+        assert!(parse_ply(
+            "bad",
+            b"ply\nelement vertex 1\nproperty float x\nend_header\n"
+        )
+        .is_err());
+        assert!(parse_ply(
+            "bad",
+            b"ply\nformat ascii 1.0\nelement vertex 1\nproperty half x\nend_header\n"
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn parse_ply_rejects_short_bodies() {
+        let header = "ply\nformat ascii 1.0\nelement vertex 2\nproperty float x\nproperty float y\nproperty float z\nend_header\n";
+        let error = error(parse_ply(
+            "short",
+            format!("{header}0 0 0\n1 0\n").as_bytes(),
+        ));
+        assert!(error.contains("ended early"), "{error}");
+    }
+
+    #[test]
+    fn parse_ply_bounds_face_counts_by_the_remaining_bytes() {
+        let mut ply = b"ply\nformat binary_little_endian 1.0\nelement vertex 1\nproperty float x\nproperty float y\nproperty float z\nelement face 1\nproperty list uint int vertex_indices\nend_header\n".to_vec();
+        ply.extend([0.0f32; 3].iter().flat_map(|value| value.to_le_bytes()));
+        ply.extend(4_000_000_000u32.to_le_bytes());
+        ply.extend(0i32.to_le_bytes());
+        let error = error(parse_ply("hostile", &ply));
+        assert!(error.contains("has 4000000000 vertices"), "{error}");
+    }
+}
@@ -1,7 +1,8 @@
-mod api;
+pub mod api;
 mod context;
 mod ecs;
 mod graphics;
+mod import;
 mod input;
 mod paint;
 mod rpc;
@@ -13,8 +14,20 @@ mod window;
 #[cfg(not(target_arch = "wasm32"))]
 pub mod server;
 
+// Reading back renders for comparison blocks on the GPU, which browsers do not allow
+#[cfg(not(target_arch = "wasm32"))]
+pub mod golden;
+
 pub use context::Context;
-pub use run::run_frontend;
+pub use graphics::CapturedImage;
+pub use import::{add_framing_camera, import_bytes, import_gltf};
+pub use run::{find_camera, load_scene, run_frontend, run_frontend_with_commands, step_headless};
+#[cfg(not(target_arch = "wasm32"))]
+pub use {
+    graphics::render_camera_offscreen,
+    import::import_file,
+    run::{create_headless_context, create_software_context, spawn_frontend, ViewerHandle},
+};
 
 pub mod prelude {
     pub use crate::api::{publish_command, publish_event, Command, Event};
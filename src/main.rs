@@ -18,6 +18,8 @@ mod cli {
         pub command: Option<Command>,
     }
 
+    // The command is parsed once at startup, so boxing the server options gains nothing
+    #[allow(clippy::large_enum_variant)]
     #[derive(Default, Debug, StructOpt)]
     pub enum Command {
         /// Launches the standalone desktop client.
@@ -25,18 +27,241 @@ mod cli {
         #[default]
         Run,
 
+        /// Launches the desktop client with model files imported.
+        #[structopt(about = "Run the app with model files such as .gltf and .glb imported")]
+        Open(OpenOptions),
+
         /// Starts the server to allow remote client connections.
         #[structopt(about = "Launch a server to accept connections from remote clients")]
-        Server {
-            /// The port for the server to listen on
-            #[structopt(
-                short,
-                long,
-                default_value = "9003",
-                help = "The port the server will listen on"
-            )]
-            port: u16,
-        },
+        Server(ServerOptions),
+
+        /// Renders a scene to an image without opening a window.
+        #[structopt(about = "Render a scene to a PNG without a window or GPU")]
+        Render(RenderOptions),
+
+        /// Renders the golden image suite and compares it against the reference images.
+        #[structopt(about = "Compare renders of the golden image suite against reference images")]
+        Golden(GoldenOptions),
+    }
+
+    /// Files to import when the app starts
+    #[derive(Debug, StructOpt)]
+    pub struct OpenOptions {
+        #[structopt(
+            parse(from_os_str),
+            required = true,
+            help = "The model files to import"
+        )]
+        pub files: Vec<std::path::PathBuf>,
+    }
+
+    /// Flags for the golden image suite
+    #[derive(Debug, StructOpt)]
+    pub struct GoldenOptions {
+        /// A TOML file listing the cases of the suite
+        #[structopt(
+            long,
+            parse(from_os_str),
+            default_value = "golden/suite.toml",
+            help = "A TOML file listing the scenes, cameras and sizes to render"
+        )]
+        pub suite: std::path::PathBuf,
+
+        /// Where renders and diff images are written
+        #[structopt(
+            short,
+            long,
+            parse(from_os_str),
+            default_value = "target/golden",
+            help = "The directory renders and diff images are written to"
+        )]
+        pub output: std::path::PathBuf,
+
+        /// Stores the renders as the new reference images
+        #[structopt(long, help = "Replace the reference images with the current renders")]
+        pub update: bool,
+    }
+
+    /// Flags for offscreen rendering
+    #[derive(Debug, StructOpt)]
+    pub struct RenderOptions {
+        /// A JSON array of commands that builds the scene
+        #[structopt(
+            long,
+            parse(from_os_str),
+            help = "A JSON file containing an array of commands that builds the scene"
+        )]
+        pub scene: std::path::PathBuf,
+
+        /// The name of the camera to render from
+        #[structopt(long, help = "The camera to render from [default: the first camera]")]
+        pub camera: Option<String>,
+
+        #[structopt(
+            long,
+            default_value = "1280",
+            help = "The width of the image in pixels"
+        )]
+        pub width: u32,
+
+        #[structopt(
+            long,
+            default_value = "720",
+            help = "The height of the image in pixels"
+        )]
+        pub height: u32,
+
+        /// Where the PNG is written
+        #[structopt(short, long, parse(from_os_str), help = "The PNG file to write")]
+        pub output: std::path::PathBuf,
+    }
+
+    /// Flags for the server, which take precedence over the configuration file
+    #[derive(Debug, StructOpt)]
+    pub struct ServerOptions {
+        /// A TOML file containing the server configuration
+        #[structopt(
+            short,
+            long,
+            parse(from_os_str),
+            help = "A TOML file containing the server configuration"
+        )]
+        pub config: Option<std::path::PathBuf>,
+
+        /// The address of the interface to listen on
+        #[structopt(
+            short,
+            long,
+            help = "The address the server will listen on [default: 0.0.0.0]"
+        )]
+        pub bind: Option<String>,
+
+        /// The port for the server to listen on
+        #[structopt(
+            short,
+            long,
+            help = "The port the server will listen on [default: 9003]"
+        )]
+        pub port: Option<u16>,
+
+        /// A shared secret clients must present to connect as read-only viewers
+        #[structopt(
+            long,
+            env = "NIGHTSHADE_TOKEN",
+            help = "A token clients must present to connect as read-only viewers"
+        )]
+        pub token: Option<String>,
+
+        /// A secret that grants clients permission to send mutating commands
+        #[structopt(
+            long,
+            env = "NIGHTSHADE_PRODUCER_TOKEN",
+            help = "A token that grants clients permission to send mutating commands"
+        )]
+        pub producer_token: Option<String>,
+
+        /// A secret that grants frontends permission to answer queries and report
+        #[structopt(
+            long,
+            env = "NIGHTSHADE_FRONTEND_TOKEN",
+            help = "A token that grants frontends permission to answer queries and report"
+        )]
+        pub frontend_token: Option<String>,
+
+        /// The maximum number of simultaneously open connections
+        #[structopt(long, help = "The maximum number of simultaneously open connections")]
+        pub max_connections: Option<usize>,
+
+        /// Connections with no traffic for this many seconds are closed
+        #[structopt(long, help = "Close connections with no traffic for this many seconds")]
+        pub idle_timeout: Option<u64>,
+
+        /// A PEM encoded certificate chain, enabling TLS when given with a key
+        #[structopt(
+            long,
+            parse(from_os_str),
+            requires = "tls-key",
+            help = "A PEM encoded certificate chain to serve connections over TLS"
+        )]
+        pub tls_certificate: Option<std::path::PathBuf>,
+
+        /// The PEM encoded private key for the TLS certificate
+        #[structopt(
+            long,
+            parse(from_os_str),
+            requires = "tls-certificate",
+            help = "The PEM encoded private key for the TLS certificate"
+        )]
+        pub tls_key: Option<std::path::PathBuf>,
+
+        /// Upstream data sources streaming JSON encoded commands, one per line
+        #[structopt(
+            long = "source",
+            number_of_values = 1,
+            help = "A data source streaming one JSON command per line, such as tcp://host:port, udp://host:port, file:///path or exec://program"
+        )]
+        pub sources: Vec<String>,
+
+        /// The port for the HTTP control API
+        #[structopt(long, help = "Serve the HTTP control API on this port")]
+        pub http_port: Option<u16>,
+
+        /// A directory of static files, such as the web frontend, served over HTTP
+        #[structopt(
+            long,
+            parse(from_os_str),
+            help = "A directory of static files to serve over HTTP, such as the output of `trunk build`"
+        )]
+        pub static_dir: Option<std::path::PathBuf>,
+    }
+
+    impl ServerOptions {
+        /// Loads the configuration file, if any, and applies flags on top of it
+        pub fn into_config(
+            self,
+        ) -> Result<nightshade_core::server::ServerConfig, Box<dyn std::error::Error>> {
+            let mut config = match self.config {
+                Some(path) => nightshade_core::server::load_server_config(path)?,
+                None => nightshade_core::server::ServerConfig::default(),
+            };
+            if let Some(bind) = self.bind {
+                config.bind = bind;
+            }
+            if let Some(port) = self.port {
+                config.port = port;
+            }
+            if self.token.is_some() {
+                config.token = self.token;
+            }
+            if self.producer_token.is_some() {
+                config.producer_token = self.producer_token;
+            }
+            if self.frontend_token.is_some() {
+                config.frontend_token = self.frontend_token;
+            }
+            if self.max_connections.is_some() {
+                config.max_connections = self.max_connections;
+            }
+            if self.idle_timeout.is_some() {
+                config.idle_timeout_seconds = self.idle_timeout;
+            }
+            if let (Some(certificate), Some(key)) = (self.tls_certificate, self.tls_key) {
+                config.tls = Some(nightshade_core::server::TlsConfig { certificate, key });
+            }
+            if self.http_port.is_some() {
+                config.http_port = self.http_port;
+            }
+            if self.static_dir.is_some() {
+                config.static_dir = self.static_dir;
+            }
+            config.sources.extend(self.sources.into_iter().map(|url| {
+                nightshade_core::server::SourceConfig {
+                    url,
+                    mapping: nightshade_core::server::RecordMapping::default(),
+                }
+            }));
+            Ok(config)
+        }
     }
 }
 
@@ -48,10 +273,77 @@ pub async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let Options { command } = Options::from_args();
     match command {
         Some(Command::Run) | None => nightshade_core::run_frontend(),
-        Some(Command::Server { port }) => {
+        Some(Command::Open(options)) => {
+            let commands = options
+                .files
+                .into_iter()
+                .map(|path| nightshade_core::api::Command::Import {
+                    id: 0,
+                    source: nightshade_core::api::ImportSource::File {
+                        path: path.to_string_lossy().to_string(),
+                    },
+                })
+                .collect();
+            nightshade_core::run_frontend_with_commands(commands);
+        }
+        Some(Command::Server(options)) => {
+            env_logger::init();
+            nightshade_core::server::listen_for_rpc(options.into_config()?).await?;
+        }
+        Some(Command::Render(options)) => {
             env_logger::init();
-            nightshade_core::server::listen_for_rpc(port).await;
+            render(options)?;
         }
+        Some(Command::Golden(options)) => {
+            env_logger::init();
+            golden(options)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn render(options: cli::RenderOptions) -> Result<(), Box<dyn std::error::Error>> {
+    use nightshade_core::prelude::*;
+    let commands = serde_json::from_str::<Vec<Command>>(&std::fs::read_to_string(&options.scene)?)?;
+    let mut context = nightshade_core::create_headless_context(options.width, options.height)?;
+    nightshade_core::load_scene(&mut context, "scene", commands);
+    let camera = nightshade_core::find_camera(&context, options.camera.as_deref())
+        .ok_or("The scene has no matching camera")?;
+    let image = nightshade_core::render_camera_offscreen(
+        &mut context,
+        camera,
+        options.width,
+        options.height,
+    )?;
+    image.save_png(&options.output)?;
+    log::info!("Rendered {}", options.output.display());
+    Ok(())
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn golden(options: cli::GoldenOptions) -> Result<(), Box<dyn std::error::Error>> {
+    use nightshade_core::golden::GoldenOutcome;
+    let results =
+        nightshade_core::golden::run_golden_suite(&options.suite, &options.output, options.update)?;
+    let mut failures = 0;
+    for result in &results {
+        match &result.outcome {
+            GoldenOutcome::Passed { mismatched_pixels } => {
+                println!(
+                    "ok      {} ({mismatched_pixels} pixels differ)",
+                    result.name
+                )
+            }
+            GoldenOutcome::Updated => println!("updated {}", result.name),
+            GoldenOutcome::Failed { reason } => {
+                failures += 1;
+                println!("FAILED  {}: {reason}", result.name);
+            }
+        }
+    }
+    if failures > 0 {
+        return Err(format!("{failures} of {} golden image cases failed", results.len()).into());
     }
     Ok(())
 }
@@ -16,7 +16,12 @@ pub fn paint_quad(painting: &mut Painting, offset: Vec3, size: Vec2, color: Vec4
 }
 
 pub fn paint_line(painting: &mut Painting, start: Vec3, end: Vec3, color: Vec4) {
-    painting.lines.push(Line { start, end, color });
+    painting.lines.push(Line {
+        start,
+        end,
+        color,
+        ..Default::default()
+    });
 }
 
 pub fn paint_box(
@@ -1,30 +1,50 @@
-use crate::api::{publish_event, Event, WebsocketEvent};
+use crate::api::{
+    publish_connection_event, publish_remote_command, Event, Message, QueuedEvent, WebsocketEvent,
+};
 use crate::prelude::*;
 use enum2egui::{Gui, GuiInspect};
 use enum2str::EnumStr;
+use serde::{Deserialize, Serialize};
+
+/// Packed buffers can make single messages far larger than the websocket default limit
+const MAX_FRAME_SIZE: usize = 1 << 30;
 
 // Remote Procedure Calls
 #[derive(Default)]
 pub struct Rpc {
-    pub sender: Option<ewebsock::WsSender>,
-    pub receiver: Option<ewebsock::WsReceiver>,
+    /// Open connections, keyed by name
+    pub connections: std::collections::HashMap<String, RpcConnection>,
+
+    /// The scene root that entities spawned by each connection are parented to.
+    /// Roots outlive their connection, so reconnecting under the same name reuses them.
+    pub scene_roots: std::collections::HashMap<String, EntityId>,
+}
+
+pub struct RpcConnection {
+    pub url: String,
+    pub sender: ewebsock::WsSender,
+    pub receiver: ewebsock::WsReceiver,
     pub is_connected: bool,
 }
 
-#[derive(Default, Debug, Clone, Gui, EnumStr)]
+#[derive(Default, Debug, Clone, Gui, EnumStr, Serialize, Deserialize)]
 pub enum RpcCommand {
     #[default]
     Empty,
     Connect {
+        name: String,
         url: String,
     },
-    Disconnect,
+    Disconnect {
+        name: String,
+    },
     Send {
+        name: String,
         message: RpcMessage,
     },
 }
 
-#[derive(Default, Debug, Clone, Gui, EnumStr)]
+#[derive(Default, Debug, Clone, Gui, EnumStr, Serialize, Deserialize)]
 pub enum RpcMessage {
     #[default]
     Empty,
@@ -36,7 +56,7 @@ pub enum RpcMessage {
     },
 }
 
-#[derive(Default, Debug, Clone, Gui, EnumStr)]
+#[derive(Default, Debug, Clone, Gui, EnumStr, Serialize, Deserialize)]
 pub enum RpcEvent {
     #[default]
     Empty,
@@ -51,7 +71,7 @@ pub enum RpcEvent {
     },
 }
 
-#[derive(Default, Debug, Clone, Gui, EnumStr)]
+#[derive(Default, Debug, Clone, Gui, EnumStr, Serialize, Deserialize)]
 pub enum RpcError {
     #[default]
     Empty,
@@ -67,118 +87,263 @@ pub enum RpcError {
 }
 
 pub fn receive_rpc_events_system(context: &mut Context) {
-    dequeue_rpc_events(context).into_iter().for_each(|event| {
-        receive_rpc_event(context, event);
-    });
+    let names = context
+        .resources
+        .rpc
+        .connections
+        .keys()
+        .cloned()
+        .collect::<Vec<_>>();
+    for name in names {
+        dequeue_rpc_events(context, &name)
+            .into_iter()
+            .for_each(|event| {
+                receive_rpc_event(context, &name, event);
+            });
+    }
 }
 
-fn dequeue_rpc_events(context: &mut Context) -> Vec<ewebsock::WsEvent> {
-    let Some(receiver) = context.resources.rpc.receiver.as_mut() else {
+fn dequeue_rpc_events(context: &mut Context, name: &str) -> Vec<ewebsock::WsEvent> {
+    let Some(connection) = context.resources.rpc.connections.get_mut(name) else {
         return Vec::new();
     };
     let mut events = Vec::new();
-    while let Some(event) = receiver.try_recv() {
+    while let Some(event) = connection.receiver.try_recv() {
         events.push(event);
     }
     events
 }
 
-fn receive_rpc_event(context: &mut Context, event: ewebsock::WsEvent) {
+fn receive_rpc_event(context: &mut Context, name: &str, event: ewebsock::WsEvent) {
     match event {
         ewebsock::WsEvent::Opened => {
-            context.resources.rpc.is_connected = true;
-            handle_websocket_connected(context);
+            set_connected(context, name, true);
+            handle_websocket_connected(context, name);
         }
         ewebsock::WsEvent::Message(ws_message) => match ws_message {
             ewebsock::WsMessage::Text(text) => {
-                handle_websocket_message(context, text);
-            }
-            ewebsock::WsMessage::Binary(_) => {
-                handle_websocket_error(context, "Binary messages not supported".to_string());
+                receive_remote_message(context, name, &text);
+                handle_websocket_message(context, name, text);
             }
+            ewebsock::WsMessage::Binary(bytes) => match Message::from_bytes(&bytes) {
+                Ok(message) => receive_remote_api_message(context, name, message),
+                Err(error) => handle_websocket_error(
+                    context,
+                    name,
+                    format!("Received a binary message that is not an API message: {error}"),
+                ),
+            },
             _ => {}
         },
         ewebsock::WsEvent::Error(error) => {
-            context.resources.rpc.is_connected = false;
-            handle_websocket_error(context, error.to_string());
+            set_connected(context, name, false);
+            handle_websocket_error(context, name, error.to_string());
         }
         ewebsock::WsEvent::Closed => {
-            context.resources.rpc.is_connected = false;
-            handle_websocket_disconnected(context);
+            set_connected(context, name, false);
+            handle_websocket_disconnected(context, name);
         }
     }
 }
 
+fn set_connected(context: &mut Context, name: &str, is_connected: bool) {
+    if let Some(connection) = context.resources.rpc.connections.get_mut(name) {
+        connection.is_connected = is_connected;
+    }
+}
+
+fn is_connected(context: &Context, name: &str) -> bool {
+    context
+        .resources
+        .rpc
+        .connections
+        .get(name)
+        .is_some_and(|connection| connection.is_connected)
+}
+
 pub fn execute_rpc_command(context: &mut Context, command: RpcCommand) {
     match command {
-        RpcCommand::Connect { url } => {
-            if context.resources.rpc.is_connected {
-                handle_websocket_error(context, "Already connected".to_string());
+        RpcCommand::Connect { name, url } => {
+            if name.is_empty() {
+                handle_websocket_error(context, &name, "Connections must be named".to_string());
+                return;
+            }
+            if is_connected(context, &name) {
+                handle_websocket_error(context, &name, format!("Already connected as {name}"));
                 return;
             }
 
-            if let Ok((sender, receiver)) =
-                ewebsock::connect(format!("ws://{url}"), ewebsock::Options::default())
-            {
-                context.resources.rpc.sender = Some(sender);
-                context.resources.rpc.receiver = Some(receiver);
-                context.resources.rpc.is_connected = true;
-                handle_websocket_connected(context);
+            // Addresses without a scheme connect over plain websockets
+            let address = if url.contains("://") {
+                url.clone()
             } else {
-                handle_websocket_error(context, format!("Failed to connect to {}", url));
+                format!("ws://{url}")
+            };
+            let options = ewebsock::Options {
+                max_incoming_frame_size: MAX_FRAME_SIZE,
+                ..Default::default()
+            };
+            if let Ok((sender, receiver)) = ewebsock::connect(address, options) {
+                context.resources.rpc.connections.insert(
+                    name.clone(),
+                    RpcConnection {
+                        url,
+                        sender,
+                        receiver,
+                        is_connected: true,
+                    },
+                );
+                ensure_scene_root(context, &name);
+                handle_websocket_connected(context, &name);
+            } else {
+                handle_websocket_error(context, &name, format!("Failed to connect to {}", url));
             }
         }
-        RpcCommand::Send { message } => {
-            if !context.resources.rpc.is_connected {
-                handle_websocket_error(context, "Not connected".to_string());
+        RpcCommand::Send { name, message } => {
+            if !is_connected(context, &name) {
+                handle_websocket_error(context, &name, format!("Not connected as {name}"));
                 return;
             }
 
-            if let Some(sender) = &mut context.resources.rpc.sender {
-                sender.send(ewebsock::WsMessage::Text(message.to_string()));
-                handle_websocket_message(context, format!("Sent: {}", message));
+            if let Some(connection) = context.resources.rpc.connections.get_mut(&name) {
+                let summary = message.to_string();
+                connection.sender.send(match message {
+                    RpcMessage::Text { string } => ewebsock::WsMessage::Text(string),
+                    RpcMessage::Binary { bytes } => ewebsock::WsMessage::Binary(bytes),
+                    RpcMessage::Empty => return,
+                });
+                handle_websocket_message(context, &name, format!("Sent: {summary}"));
             }
         }
-        RpcCommand::Disconnect => {
-            context.resources.rpc.is_connected = false;
-            context.resources.rpc.sender.take();
-            context.resources.rpc.receiver.take();
-            handle_websocket_disconnected(context);
+        RpcCommand::Disconnect { name } => {
+            if context.resources.rpc.connections.remove(&name).is_some() {
+                handle_websocket_disconnected(context, &name);
+            }
         }
         _ => {}
     }
 }
 
-fn handle_websocket_connected(context: &mut Context) {
-    publish_event(
+/// Creates the scene root for a connection, unless one from an earlier connection still exists
+fn ensure_scene_root(context: &mut Context, name: &str) -> EntityId {
+    if let Some(scene) = context.resources.rpc.scene_roots.get(name).copied() {
+        if get_component::<Name>(context, scene, NAME).is_some() {
+            return scene;
+        }
+    }
+    let scene = spawn_entities(context, NAME | LOCAL_TRANSFORM | GLOBAL_TRANSFORM, 1)[0];
+    if let Some(scene_name) = get_component_mut::<Name>(context, scene, NAME) {
+        *scene_name = Name(name.to_string());
+    }
+    context
+        .resources
+        .rpc
+        .scene_roots
+        .insert(name.to_string(), scene);
+    scene
+}
+
+/// Parents an entity spawned by a remote command to the scene root of the connection that sent it
+pub fn attach_to_scene_root(context: &mut Context, connection: &str, entity: EntityId) {
+    let scene = ensure_scene_root(context, connection);
+    if !add_components(context, entity, PARENT) {
+        return;
+    }
+    if let Some(parent) = get_component_mut::<Parent>(context, entity, PARENT) {
+        *parent = Parent(scene);
+    }
+}
+
+/// Remote peers speak the engine API by sending JSON encoded `Message`s.
+/// Commands are queued for execution and events are published alongside local events,
+/// both tagged with the connection they came from.
+fn receive_remote_message(context: &mut Context, name: &str, text: &str) {
+    match serde_json::from_str::<Message>(text) {
+        Ok(message) => receive_remote_api_message(context, name, message),
+        Err(error) => log::trace!("[Rpc] Received a message that is not an API message: {error}"),
+    }
+}
+
+fn receive_remote_api_message(context: &mut Context, name: &str, message: Message) {
+    match message {
+        Message::Command { command } => publish_remote_command(context, name, command),
+        Message::Event { event } => context.resources.events.remote_events.push(QueuedEvent {
+            event,
+            connection: Some(name.to_string()),
+        }),
+    }
+}
+
+/// Sends query results and reports produced by this engine to connected servers,
+/// so remote producers can observe the outcome of the commands they send.
+/// Events addressed to a connection are only sent to it, while untagged events go to every connection.
+pub fn forward_events(context: &mut Context, events: &[QueuedEvent]) {
+    for QueuedEvent { event, connection } in events {
+        if !matches!(event, Event::QueryResult { .. } | Event::Report { .. }) {
+            continue;
+        }
+        let message = Message::Event {
+            event: event.clone(),
+        };
+        let websocket_message = if message.is_bulk() {
+            message.to_bytes().ok().map(ewebsock::WsMessage::Binary)
+        } else {
+            serde_json::to_string(&message)
+                .ok()
+                .map(ewebsock::WsMessage::Text)
+        };
+        let Some(websocket_message) = websocket_message else {
+            continue;
+        };
+        context
+            .resources
+            .rpc
+            .connections
+            .iter_mut()
+            .filter(|(name, rpc_connection)| {
+                rpc_connection.is_connected
+                    && connection
+                        .as_ref()
+                        .is_none_or(|connection| connection == *name)
+            })
+            .for_each(|(_, rpc_connection)| rpc_connection.sender.send(websocket_message.clone()));
+    }
+}
+
+fn handle_websocket_connected(context: &mut Context, name: &str) {
+    publish_connection_event(
         context,
+        name,
         Event::Websocket {
             event: WebsocketEvent::Connected,
         },
     );
 }
 
-fn handle_websocket_disconnected(context: &mut Context) {
-    publish_event(
+fn handle_websocket_disconnected(context: &mut Context, name: &str) {
+    publish_connection_event(
         context,
+        name,
         Event::Websocket {
             event: WebsocketEvent::Disconnected,
         },
     );
 }
 
-fn handle_websocket_message(context: &mut Context, text: String) {
-    publish_event(
+fn handle_websocket_message(context: &mut Context, name: &str, text: String) {
+    publish_connection_event(
         context,
+        name,
         Event::Websocket {
             event: WebsocketEvent::Message { message: text },
         },
     );
 }
 
-fn handle_websocket_error(context: &mut Context, error: String) {
-    publish_event(
+fn handle_websocket_error(context: &mut Context, name: &str, error: String) {
+    publish_connection_event(
         context,
+        name,
         Event::Websocket {
             event: WebsocketEvent::Error { error },
         },
@@ -1,18 +1,274 @@
-use crate::api::process_events_system;
+use crate::api::{process_events_system, publish_command, Command, Event, QueuedEvent};
 use crate::{api, context, graphics, input, rpc, ui, window};
 
 /// This is the entry point for the engine
 pub fn run_frontend() {
+    run_frontend_with_commands(Vec::new());
+}
+
+/// Runs the viewer with commands queued to execute on its first frame,
+/// such as imports of the files passed on the command line
+pub fn run_frontend_with_commands(commands: Vec<Command>) {
     let Ok(event_loop) = winit::event_loop::EventLoop::builder().build() else {
         eprintln!("Failed to create event loop!");
         return;
     };
     event_loop.set_control_flow(winit::event_loop::ControlFlow::Poll);
-    if let Err(error) = event_loop.run_app(&mut context::Context::default()) {
+    let mut context = context::Context::default();
+    commands
+        .into_iter()
+        .for_each(|command| publish_command(&mut context, command));
+    if let Err(error) = event_loop.run_app(&mut context) {
         eprintln!("Failed to run app: {error}");
     }
 }
 
+/// Channels to a host program that embeds the viewer,
+/// set when the viewer is started with `spawn_frontend`
+#[derive(Default)]
+pub struct Host {
+    /// Commands sent by the host, executed as if they were published locally
+    pub commands: Option<std::sync::mpsc::Receiver<Command>>,
+
+    /// Every event the engine publishes, including events from remote connections.
+    /// Events are dropped while the channel is full.
+    pub events: Option<std::sync::mpsc::SyncSender<Event>>,
+}
+
+/// A handle to a viewer running on its own thread.
+/// Dropping the handle closes the viewer.
+#[cfg(not(target_arch = "wasm32"))]
+pub struct ViewerHandle {
+    commands: std::sync::mpsc::Sender<Command>,
+    events: std::sync::mpsc::Receiver<Event>,
+    thread: std::thread::JoinHandle<()>,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl ViewerHandle {
+    /// Queues a command for the viewer, returning it back if the viewer has closed
+    #[allow(clippy::result_large_err)]
+    pub fn send(&self, command: Command) -> Result<(), Command> {
+        self.commands.send(command).map_err(|error| error.0)
+    }
+
+    /// Returns the next event published by the viewer without waiting
+    pub fn try_recv(&self) -> Option<Event> {
+        self.events.try_recv().ok()
+    }
+
+    /// Waits up to `timeout` for the next event published by the viewer
+    pub fn recv_timeout(&self, timeout: std::time::Duration) -> Option<Event> {
+        self.events.recv_timeout(timeout).ok()
+    }
+
+    /// Drains every event published since the last call
+    pub fn events(&self) -> impl Iterator<Item = Event> + '_ {
+        self.events.try_iter()
+    }
+
+    /// Whether the viewer window is still open
+    pub fn is_running(&self) -> bool {
+        !self.thread.is_finished()
+    }
+
+    /// Closes the viewer and waits for its window to close
+    pub fn close(self) {
+        let Self {
+            commands, thread, ..
+        } = self;
+        drop(commands);
+        let _ = thread.join();
+    }
+
+    /// Waits until the user closes the viewer window
+    pub fn join(self) {
+        let _ = self.thread.join();
+    }
+}
+
+/// How many events wait for the host before new ones are dropped
+#[cfg(not(target_arch = "wasm32"))]
+const HOST_EVENT_CAPACITY: usize = 1024;
+
+/// Starts the viewer on its own thread and returns a handle for driving it,
+/// so a host program such as a simulation or a Python session keeps running its own loop.
+///
+/// Only one viewer can run per process. macOS requires windows to live on the main thread,
+/// so hosts there must call `run_frontend` instead.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn spawn_frontend() -> Result<ViewerHandle, String> {
+    if cfg!(target_os = "macos") {
+        return Err("The viewer can only run on the main thread on macOS".to_string());
+    }
+    let (command_sender, command_receiver) = std::sync::mpsc::channel();
+    let (event_sender, event_receiver) = std::sync::mpsc::sync_channel(HOST_EVENT_CAPACITY);
+    let (started_sender, started_receiver) = std::sync::mpsc::channel();
+    let thread = std::thread::Builder::new()
+        .name("nightshade-viewer".to_string())
+        .spawn(move || {
+            let event_loop = match create_any_thread_event_loop() {
+                Ok(event_loop) => event_loop,
+                Err(error) => {
+                    let _ =
+                        started_sender.send(Err(format!("Failed to create event loop: {error}")));
+                    return;
+                }
+            };
+            let _ = started_sender.send(Ok(()));
+            event_loop.set_control_flow(winit::event_loop::ControlFlow::Poll);
+            let mut context = context::Context::default();
+            context.resources.host = Host {
+                commands: Some(command_receiver),
+                events: Some(event_sender),
+            };
+            if let Err(error) = event_loop.run_app(&mut context) {
+                log::error!("Failed to run app: {error}");
+            }
+        })
+        .map_err(|error| format!("Failed to start the viewer thread: {error}"))?;
+    match started_receiver.recv() {
+        Ok(Ok(())) => Ok(ViewerHandle {
+            commands: command_sender,
+            events: event_receiver,
+            thread,
+        }),
+        Ok(Err(error)) => Err(error),
+        Err(_) => Err("The viewer thread exited during startup".to_string()),
+    }
+}
+
+/// Event loops are normally restricted to the main thread
+#[cfg(not(target_arch = "wasm32"))]
+fn create_any_thread_event_loop(
+) -> Result<winit::event_loop::EventLoop<()>, winit::error::EventLoopError> {
+    let mut builder = winit::event_loop::EventLoop::builder();
+    #[cfg(any(
+        target_os = "linux",
+        target_os = "dragonfly",
+        target_os = "freebsd",
+        target_os = "netbsd",
+        target_os = "openbsd"
+    ))]
+    {
+        use winit::platform::{wayland::EventLoopBuilderExtWayland, x11::EventLoopBuilderExtX11};
+        EventLoopBuilderExtX11::with_any_thread(&mut builder, true);
+        EventLoopBuilderExtWayland::with_any_thread(&mut builder, true);
+    }
+    #[cfg(target_os = "windows")]
+    {
+        use winit::platform::windows::EventLoopBuilderExtWindows;
+        builder.with_any_thread(true);
+    }
+    builder.build()
+}
+
+/// Creates a context with an offscreen renderer and no window,
+/// for rendering scenes on machines without a display or GPU
+#[cfg(not(target_arch = "wasm32"))]
+pub fn create_headless_context(width: u32, height: u32) -> Result<context::Context, String> {
+    create_context_with_adapter(width, height, false)
+}
+
+/// Creates a headless context that always renders with the software fallback adapter,
+/// so renders are the same on machines with different GPUs
+#[cfg(not(target_arch = "wasm32"))]
+pub fn create_software_context(width: u32, height: u32) -> Result<context::Context, String> {
+    create_context_with_adapter(width, height, true)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn create_context_with_adapter(
+    width: u32,
+    height: u32,
+    force_fallback_adapter: bool,
+) -> Result<context::Context, String> {
+    let renderer = pollster::block_on(graphics::create_headless_renderer_async(
+        width,
+        height,
+        force_fallback_adapter,
+    ))?;
+    let mut context = context::Context::default();
+    context.resources.graphics.renderer = Some(renderer);
+    context.resources.graphics.viewport_size = (width, height);
+    Ok(context)
+}
+
+/// Advances a headless context by one frame.
+/// Commands, events and transforms are processed and camera recordings are written,
+/// while the UI and presentation are skipped.
+pub fn step_headless(context: &mut context::Context) {
+    window::update_frame_timing_system(context);
+    rpc::receive_rpc_events_system(context);
+    receive_host_commands_system(context);
+    process_events_systems(context);
+    api::execute_commands_system(context);
+    context::ensure_camera_transform_system(context);
+    context::update_global_transforms_system(context);
+    #[cfg(not(target_arch = "wasm32"))]
+    graphics::record_frame_system(context);
+}
+
+/// Executes commands as if they were sent by a connection with the given name,
+/// so every spawned entity is placed under one scene root that cameras can render
+pub fn load_scene(context: &mut context::Context, name: &str, commands: Vec<Command>) {
+    commands
+        .into_iter()
+        .for_each(|command| api::publish_remote_command(context, name, command));
+    step_headless(context);
+}
+
+/// Finds a camera by name, or the first camera when no name is given
+pub fn find_camera(context: &context::Context, name: Option<&str>) -> Option<context::EntityId> {
+    context::query_entities(context, context::CAMERA)
+        .into_iter()
+        .find(|camera| {
+            name.is_none_or(|name| {
+                context::get_component::<context::Name>(context, *camera, context::NAME)
+                    .is_some_and(|context::Name(camera_name)| camera_name == name)
+            })
+        })
+}
+
+/// Queues commands sent by the host program.
+/// The viewer closes once the host drops its handle.
+pub fn receive_host_commands_system(context: &mut context::Context) {
+    let Some(receiver) = context.resources.host.commands.as_ref() else {
+        return;
+    };
+    let mut commands = Vec::new();
+    loop {
+        match receiver.try_recv() {
+            Ok(command) => commands.push(command),
+            Err(std::sync::mpsc::TryRecvError::Empty) => break,
+            Err(std::sync::mpsc::TryRecvError::Disconnected) => {
+                context.resources.window.should_exit = true;
+                break;
+            }
+        }
+    }
+    commands
+        .into_iter()
+        .for_each(|command| publish_command(context, command));
+}
+
+/// Sends published events to the host program, if there is one
+pub fn forward_host_events(context: &context::Context, events: &[QueuedEvent]) {
+    let Some(sender) = context.resources.host.events.as_ref() else {
+        return;
+    };
+    for QueuedEvent { event, .. } in events {
+        match sender.try_send(event.clone()) {
+            Ok(()) => {}
+            Err(std::sync::mpsc::TrySendError::Full(_)) => {
+                log::warn!("[Host] Dropping an event, the host is not reading them");
+            }
+            // The host stopped listening
+            Err(std::sync::mpsc::TrySendError::Disconnected(_)) => return,
+        }
+    }
+}
+
 /// This is the main loop, driven by winit window events.
 /// Resources are updated and then systems are triggered continuously.
 pub(crate) fn step(context: &mut context::Context, event: &winit::event::WindowEvent) {
@@ -34,6 +290,9 @@ pub(crate) fn step(context: &mut context::Context, event: &winit::event::WindowE
             // external network events
             rpc::receive_rpc_events_system(context);
 
+            // commands from a host program embedding the viewer
+            receive_host_commands_system(context);
+
             // route queued engine events to their domains
             process_events_systems(context);
 
@@ -64,6 +323,10 @@ pub(crate) fn step(context: &mut context::Context, event: &winit::event::WindowE
             // Render the frame to the screen
             graphics::render_frame_system(context);
 
+            // Write the rendered frame to the active recording
+            #[cfg(not(target_arch = "wasm32"))]
+            graphics::record_frame_system(context);
+
             // Reset input states at the end of the frame
             input::reset_input_system(context);
         }
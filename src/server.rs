@@ -1,9 +1,147 @@
-use futures_util::{SinkExt, StreamExt, TryStreamExt};
-use tokio::net::TcpListener;
+use crate::api::{Command, Event, Message, WebsocketEvent};
+use futures_util::{SinkExt, StreamExt};
+use subtle::ConstantTimeEq as _;
+use tokio::net::{TcpListener, TcpStream};
+use tokio_tungstenite::tungstenite::{
+    self,
+    handshake::server::{ErrorResponse, Request, Response},
+};
 
+/// Control messages for the server
 #[derive(Debug, Clone)]
 pub enum ServerCommand {
-    Connect { url: String },
+    /// Starts pulling records from an upstream data source
+    Connect { url: String, mapping: RecordMapping },
+}
+
+/// Settings for the RPC server, loaded from a TOML file and overridden by command line flags
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerConfig {
+    /// The address of the interface to listen on
+    pub bind: String,
+
+    /// The port for the server to listen on
+    pub port: u16,
+
+    /// A shared secret every client must present to connect.
+    /// Clients presenting only this token are read-only viewers.
+    pub token: Option<String>,
+
+    /// A secret that grants the producer role, allowing mutating commands
+    pub producer_token: Option<String>,
+
+    /// A secret that grants the frontend role, allowing query results and reports
+    pub frontend_token: Option<String>,
+
+    /// The maximum number of simultaneously open connections
+    pub max_connections: Option<usize>,
+
+    /// Connections with no traffic in either direction for this many seconds are closed
+    pub idle_timeout_seconds: Option<u64>,
+
+    /// Serves connections over TLS when present
+    pub tls: Option<TlsConfig>,
+
+    /// Upstream data sources the server pulls records from
+    pub sources: Vec<SourceConfig>,
+
+    /// The port for the HTTP control API, which is disabled when absent
+    pub http_port: Option<u16>,
+
+    /// A directory of static files served over HTTP, such as the output of `trunk build`
+    pub static_dir: Option<std::path::PathBuf>,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            bind: "0.0.0.0".to_string(),
+            port: 9003,
+            token: None,
+            producer_token: None,
+            frontend_token: None,
+            max_connections: None,
+            idle_timeout_seconds: None,
+            tls: None,
+            sources: Vec::new(),
+            http_port: None,
+            static_dir: None,
+        }
+    }
+}
+
+/// Paths to PEM encoded files used to serve connections over TLS
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TlsConfig {
+    /// The certificate chain, leaf certificate first
+    pub certificate: std::path::PathBuf,
+
+    /// The private key matching the leaf certificate
+    pub key: std::path::PathBuf,
+}
+
+/// Reads a server configuration from a TOML file
+pub fn load_server_config(
+    path: impl AsRef<std::path::Path>,
+) -> Result<ServerConfig, Box<dyn std::error::Error>> {
+    let contents = std::fs::read_to_string(path)?;
+    Ok(toml::from_str(&contents)?)
+}
+
+/// The permissions granted to a connection during the websocket handshake
+#[derive(Debug, Copy, Clone, PartialEq, Eq, serde::Serialize)]
+pub enum Role {
+    /// May only send queries
+    Viewer,
+
+    /// May send queries and answer them with query results and reports, as frontends do
+    Frontend,
+
+    /// May send any command, including spawning and despawning entities,
+    /// as well as query results and reports
+    Producer,
+}
+
+/// A message relayed between connections, tagged with the id of the connection that sent it.
+/// Messages are JSON text, or bincode encoded binary for bulk data such as packed buffers.
+type Relay = (u64, tungstenite::Message);
+
+/// Packed buffers can make single messages far larger than the websocket default limit
+const MAX_MESSAGE_SIZE: usize = 1 << 30;
+
+/// The sender id of messages produced by the server itself, such as records from data sources
+const SERVER_CONNECTION_ID: u64 = u64::MAX;
+
+/// How long a client may take to complete the TLS handshake before it is dropped
+const TLS_HANDSHAKE_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+
+/// How long to wait before accepting again after a failed accept, such as when out of file descriptors
+const ACCEPT_BACKOFF: std::time::Duration = std::time::Duration::from_millis(100);
+
+/// A websocket connection that completed its handshake
+#[derive(Debug, Clone)]
+struct ConnectedClient {
+    address: std::net::SocketAddr,
+    role: Role,
+    connected_at: std::time::Instant,
+}
+
+/// The open websocket connections, keyed by connection id
+type Clients = std::sync::Arc<std::sync::Mutex<std::collections::HashMap<u64, ConnectedClient>>>;
+
+async fn receive_server_commands(
+    mut receiver: tokio::sync::mpsc::UnboundedReceiver<ServerCommand>,
+    relay_sender: tokio::sync::broadcast::Sender<Relay>,
+) {
+    while let Some(command) = receiver.recv().await {
+        match command {
+            ServerCommand::Connect { url, mapping } => {
+                tokio::spawn(run_connector(url, mapping, relay_sender.clone()));
+            }
+        }
+    }
 }
 
 // This backend listens for incoming RPC connections
@@ -11,39 +149,1486 @@ pub enum ServerCommand {
 // RPC fulfills requests from the frontend
 // that it is not able to fulfill on its own,
 // like creating and interacting with sockets.
-pub async fn listen_for_rpc(port: u16) {
-    let address = format!("0.0.0.0:{port}");
+//
+// Messages are JSON encoded `api::Message`s and are relayed to every other connection,
+// so producers can drive any number of connected viewers.
+//
+// Every listener is bound before serving begins, so a port that is already taken
+// fails the whole server instead of leaving it half running.
+pub async fn listen_for_rpc(config: ServerConfig) -> Result<(), String> {
+    let address = format!("{}:{}", config.bind, config.port);
 
-    let Ok(listener) = TcpListener::bind(&address).await else {
-        log::error!("[Server] Failed to bind");
-        return;
+    let tls_acceptor = config
+        .tls
+        .as_ref()
+        .map(create_tls_acceptor)
+        .transpose()
+        .map_err(|error| format!("Failed to load the TLS certificate and key: {error}"))?;
+
+    let listener = TcpListener::bind(&address)
+        .await
+        .map_err(|error| format!("Failed to bind to {address}: {error}"))?;
+
+    let http_listener = match config.http_port {
+        Some(http_port) => {
+            let http_address = format!("{}:{http_port}", config.bind);
+            let http_listener = TcpListener::bind(&http_address)
+                .await
+                .map_err(|error| format!("Failed to bind to {http_address}: {error}"))?;
+            Some((http_address, http_listener))
+        }
+        None => None,
     };
 
-    log::info!("[Server] Listening on: {address}");
+    let scheme = if tls_acceptor.is_some() { "wss" } else { "ws" };
+    log::info!("[Server] Listening on: {scheme}://{address}");
+    if config.token.is_none() && config.producer_token.is_none() && config.frontend_token.is_none()
+    {
+        log::warn!("[Server] No tokens configured, every connection is granted the producer role");
+    } else if config.frontend_token.is_none() {
+        log::warn!(
+            "[Server] No frontend token configured, only producers can answer queries and report"
+        );
+    }
+
+    let connection_limit = config
+        .max_connections
+        .map(|max_connections| std::sync::Arc::new(tokio::sync::Semaphore::new(max_connections)));
+    let config = std::sync::Arc::new(config);
+    let (relay_sender, _) = tokio::sync::broadcast::channel::<Relay>(1024);
+    let clients = Clients::default();
+    let mut next_connection_id = 0;
+
+    if let Some((http_address, http_listener)) = http_listener {
+        let scheme = if tls_acceptor.is_some() {
+            "https"
+        } else {
+            "http"
+        };
+        log::info!("[Server] Serving the HTTP API on: {scheme}://{http_address}");
+        let router = create_router(config.clone(), relay_sender.clone(), clients.clone());
+        tokio::spawn(serve_http(http_listener, tls_acceptor.clone(), router));
+    }
+
+    let (server_command_sender, server_command_receiver) =
+        tokio::sync::mpsc::unbounded_channel::<ServerCommand>();
+    tokio::spawn(receive_server_commands(
+        server_command_receiver,
+        relay_sender.clone(),
+    ));
+    config.sources.iter().for_each(|source| {
+        let _ = server_command_sender.send(ServerCommand::Connect {
+            url: source.url.clone(),
+            mapping: source.mapping.clone(),
+        });
+    });
 
-    while let Ok((stream, _)) = listener.accept().await {
+    loop {
+        let (stream, address) = accept_connection(&listener).await;
+        let permit = match connection_limit.as_ref() {
+            Some(connection_limit) => match connection_limit.clone().try_acquire_owned() {
+                Ok(permit) => Some(permit),
+                Err(_) => {
+                    log::warn!(
+                        "[Server] Refusing connection from {address}, connection limit reached"
+                    );
+                    continue;
+                }
+            },
+            None => None,
+        };
+        let config = config.clone();
+        let relay_sender = relay_sender.clone();
+        let clients = clients.clone();
+        let tls_acceptor = tls_acceptor.clone();
+        let connection_id = next_connection_id;
+        next_connection_id += 1;
         tokio::spawn(async move {
-            let Ok(address) = stream.peer_addr() else {
-                log::warn!("[Server] Connected streams should have a peer address");
-                return;
-            };
             log::info!("[Server] Accepting connection from peer address: {address}");
-            let Ok(websocket_stream) = tokio_tungstenite::accept_async(stream).await else {
-                log::error!("[Server] Error during the websocket handshake occurred");
-                return;
+            let connection = Connection {
+                id: connection_id,
+                address,
+                config,
+                relay_sender,
+                clients,
             };
-            log::info!("[Server] Opened new WebSocket connection: {address}");
-            let (mut write, mut read) = websocket_stream.split();
-            while let Ok(Some(message)) = read.try_next().await {
-                log::trace!("[Server] Received message: {message:?}");
+            match tls_acceptor {
+                Some(tls_acceptor) => {
+                    if let Some(stream) = tls_handshake(&tls_acceptor, stream, address).await {
+                        serve_connection(stream, connection).await;
+                    }
+                }
+                None => serve_connection(stream, connection).await,
+            }
+            drop(permit);
+        });
+    }
+}
+
+/// Accepts the next connection, retrying after failures so they never stop the server
+async fn accept_connection(listener: &TcpListener) -> (TcpStream, std::net::SocketAddr) {
+    loop {
+        match listener.accept().await {
+            Ok(connection) => return connection,
+            Err(error) => {
+                log::error!("[Server] Failed to accept a connection: {error}");
+                tokio::time::sleep(ACCEPT_BACKOFF).await;
+            }
+        }
+    }
+}
+
+/// Completes the TLS handshake, giving up on clients that stall
+/// so they cannot hold a connection slot indefinitely
+async fn tls_handshake(
+    tls_acceptor: &tokio_rustls::TlsAcceptor,
+    stream: TcpStream,
+    address: std::net::SocketAddr,
+) -> Option<tokio_rustls::server::TlsStream<TcpStream>> {
+    match tokio::time::timeout(TLS_HANDSHAKE_TIMEOUT, tls_acceptor.accept(stream)).await {
+        Ok(Ok(stream)) => Some(stream),
+        Ok(Err(error)) => {
+            log::error!("[Server] TLS handshake with {address} failed: {error}");
+            None
+        }
+        Err(_) => {
+            log::warn!("[Server] TLS handshake with {address} timed out");
+            None
+        }
+    }
+}
+
+/// State shared with the task serving a single connection
+struct Connection {
+    id: u64,
+    address: std::net::SocketAddr,
+    config: std::sync::Arc<ServerConfig>,
+    relay_sender: tokio::sync::broadcast::Sender<Relay>,
+    clients: Clients,
+}
 
-                log::info!("[Server] Echoing message back to client...");
+async fn serve_connection<S>(stream: S, connection: Connection)
+where
+    S: tokio::io::AsyncRead + tokio::io::AsyncWrite + Unpin,
+{
+    let Connection {
+        id: connection_id,
+        address,
+        config,
+        relay_sender,
+        clients,
+    } = connection;
+
+    let mut role = None;
+    let authorize = |request: &Request, response: Response| {
+        let presented = query_request_token(request);
+        match authorize_token(&config, presented.as_deref()) {
+            Some(granted) => {
+                role = Some(granted);
+                Ok(response)
+            }
+            None => Err(unauthorized_response()),
+        }
+    };
+    let websocket_config = tungstenite::protocol::WebSocketConfig {
+        max_message_size: Some(MAX_MESSAGE_SIZE),
+        max_frame_size: Some(MAX_MESSAGE_SIZE),
+        ..Default::default()
+    };
+    let websocket_stream = match tokio_tungstenite::accept_hdr_async_with_config(
+        stream,
+        authorize,
+        Some(websocket_config),
+    )
+    .await
+    {
+        Ok(websocket_stream) => websocket_stream,
+        Err(error) => {
+            log::error!("[Server] Error during the websocket handshake occurred: {error}");
+            return;
+        }
+    };
+    let Some(role) = role else {
+        return;
+    };
+    log::info!("[Server] Opened new WebSocket connection: {address} as {role:?}");
+    if let Ok(mut clients) = clients.lock() {
+        clients.insert(
+            connection_id,
+            ConnectedClient {
+                address,
+                role,
+                connected_at: std::time::Instant::now(),
+            },
+        );
+    }
+
+    let idle_timeout = config
+        .idle_timeout_seconds
+        .map(std::time::Duration::from_secs);
+    let idle = tokio::time::sleep(idle_timeout.unwrap_or_default());
+    tokio::pin!(idle);
+
+    let mut relay_receiver = relay_sender.subscribe();
+    let (mut write, mut read) = websocket_stream.split();
+    loop {
+        if let Some(idle_timeout) = idle_timeout {
+            idle.as_mut()
+                .reset(tokio::time::Instant::now() + idle_timeout);
+        }
+        tokio::select! {
+            message = read.next() => {
+                let message = match message {
+                    Some(Ok(message @ (tungstenite::Message::Text(_) | tungstenite::Message::Binary(_)))) => message,
+                    Some(Ok(tungstenite::Message::Close(_))) | None => break,
+                    Some(Ok(_)) => continue,
+                    Some(Err(error)) => {
+                        log::error!("[Server] Failed to receive message: {error}");
+                        break;
+                    }
+                };
+                log::trace!("[Server] Received message: {message}");
+                if let Err(error) = check_message(role, &message) {
+                    log::warn!("[Server] Rejected message from {address}: {error}");
+                    if write.send(rejection_message(&message, error)).await.is_err() {
+                        break;
+                    }
+                    continue;
+                }
+                // Sending only fails when no other connection is subscribed
+                let _ = relay_sender.send((connection_id, message));
+            }
+            relayed = relay_receiver.recv() => {
+                let (sender_id, message) = match relayed {
+                    Ok(relayed) => relayed,
+                    Err(tokio::sync::broadcast::error::RecvError::Lagged(skipped)) => {
+                        log::warn!("[Server] Connection {address} skipped {skipped} messages");
+                        continue;
+                    }
+                    Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+                };
+                if sender_id == connection_id {
+                    continue;
+                }
                 if let Err(error) = write.send(message).await {
                     log::error!("[Server] Failed to send response: {error}");
                     break;
                 }
             }
-            log::info!("[Server] Connection closed: {address}");
+            _ = &mut idle, if idle_timeout.is_some() => {
+                log::info!("[Server] Closing idle connection: {address}");
+                let _ = write.send(tungstenite::Message::Close(None)).await;
+                break;
+            }
+        }
+    }
+    if let Ok(mut clients) = clients.lock() {
+        clients.remove(&connection_id);
+    }
+    log::info!("[Server] Connection closed: {address}");
+}
+
+fn create_tls_acceptor(
+    tls: &TlsConfig,
+) -> Result<tokio_rustls::TlsAcceptor, Box<dyn std::error::Error>> {
+    let certificates = rustls_pemfile::certs(&mut std::io::BufReader::new(std::fs::File::open(
+        &tls.certificate,
+    )?))
+    .collect::<Result<Vec<_>, _>>()?;
+    let key =
+        rustls_pemfile::private_key(&mut std::io::BufReader::new(std::fs::File::open(&tls.key)?))?
+            .ok_or("No private key found")?;
+    let config = tokio_rustls::rustls::ServerConfig::builder_with_provider(std::sync::Arc::new(
+        tokio_rustls::rustls::crypto::ring::default_provider(),
+    ))
+    .with_safe_default_protocol_versions()?
+    .with_no_client_auth()
+    .with_single_cert(certificates, key)?;
+    Ok(tokio_rustls::TlsAcceptor::from(std::sync::Arc::new(config)))
+}
+
+/// Reads the token a client presented, either as an `Authorization: Bearer` header
+/// or as a `token` query parameter for clients such as browsers that cannot set headers
+fn query_request_token(request: &Request) -> Option<String> {
+    let authorization = request
+        .headers()
+        .get(tungstenite::http::header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok());
+    presented_token(authorization, request.uri().query())
+}
+
+/// Extracts a token from an `Authorization` header value or a URI query string
+fn presented_token(authorization: Option<&str>, query: Option<&str>) -> Option<String> {
+    if let Some(token) = authorization.and_then(|value| value.strip_prefix("Bearer ")) {
+        return Some(token.trim().to_string());
+    }
+    form_urlencoded::parse(query?.as_bytes())
+        .find_map(|(key, value)| (key == "token").then(|| value.into_owned()))
+}
+
+/// Determines the role granted by a presented token, or `None` if the connection is refused
+pub fn authorize_token(config: &ServerConfig, presented: Option<&str>) -> Option<Role> {
+    // Tokens are compared in constant time, so the time taken does not reveal how much matched
+    let matches = |expected: &Option<String>| match (expected, presented) {
+        (Some(expected), Some(presented)) => {
+            bool::from(expected.as_bytes().ct_eq(presented.as_bytes()))
+        }
+        _ => false,
+    };
+    if matches(&config.producer_token) {
+        return Some(Role::Producer);
+    }
+    if matches(&config.frontend_token) {
+        return Some(Role::Frontend);
+    }
+    match (
+        &config.token,
+        &config.producer_token,
+        &config.frontend_token,
+    ) {
+        (Some(_), _, _) => matches(&config.token).then_some(Role::Viewer),
+        (None, Some(_), _) | (None, _, Some(_)) => Some(Role::Viewer),
+        (None, None, None) => Some(Role::Producer),
+    }
+}
+
+fn unauthorized_response() -> ErrorResponse {
+    let mut response = ErrorResponse::new(Some("Invalid or missing token".to_string()));
+    *response.status_mut() = tungstenite::http::StatusCode::UNAUTHORIZED;
+    response
+}
+
+/// Validates an incoming message against the permissions of the connection that sent it
+fn check_message(role: Role, message: &tungstenite::Message) -> Result<(), String> {
+    match decode_message(message)? {
+        Message::Command { command } => check_command(role, &command),
+        Message::Event { event } => check_event(role, &event),
+    }
+}
+
+/// Only query results and reports are relayed, and only from frontends and producers.
+/// Clients match answers to their requests by id alone, so a viewer relaying them
+/// could forge the answer to another client's query.
+/// Websocket events describe a client's own connection,
+/// so relaying them would let one client speak for another.
+fn check_event(role: Role, event: &Event) -> Result<(), String> {
+    match event {
+        Event::QueryResult { .. } | Event::Report { .. } if role == Role::Viewer => Err(format!(
+            "Permission denied: viewers cannot send '{event}' events"
+        )),
+        Event::QueryResult { .. } | Event::Report { .. } => Ok(()),
+        Event::Websocket { .. } | Event::Empty => Err(format!("'{event}' events are not relayed")),
+    }
+}
+
+/// Decodes an API message from a JSON text frame or a bincode binary frame
+fn decode_message(message: &tungstenite::Message) -> Result<Message, String> {
+    match message {
+        tungstenite::Message::Text(text) => serde_json::from_str::<Message>(text)
+            .map_err(|error| format!("Malformed message: {error}")),
+        tungstenite::Message::Binary(bytes) => {
+            Message::from_bytes(bytes).map_err(|error| format!("Malformed message: {error}"))
+        }
+        _ => Err("Unsupported message type".to_string()),
+    }
+}
+
+fn check_command(role: Role, command: &Command) -> Result<(), String> {
+    if role != Role::Producer && command.is_mutating() {
+        return Err(format!(
+            "Permission denied: only producers can send '{command}' commands"
+        ));
+    }
+    Ok(())
+}
+
+/// Replies to a refused message. Commands that are answered are refused with their id,
+/// so the sender can fail the request waiting on it instead of waiting forever.
+fn rejection_message(message: &tungstenite::Message, error: String) -> tungstenite::Message {
+    let request_id = match decode_message(message) {
+        Ok(Message::Command { command }) => command.request_id(),
+        _ => None,
+    };
+    let event = match request_id {
+        Some(id) => WebsocketEvent::Rejected { id, error },
+        None => WebsocketEvent::Error { error },
+    };
+    let message = Message::Event {
+        event: Event::Websocket { event },
+    };
+    tungstenite::Message::Text(serde_json::to_string(&message).unwrap_or_default())
+}
+
+pub use control::*;
+mod control {
+    use super::{
+        accept_connection, authorize_token, check_command, decode_message, presented_token,
+        tls_handshake, Clients, Relay, Role, ServerConfig, SERVER_CONNECTION_ID,
+    };
+    use crate::api::{Command, Event, Message, QueryCommand};
+    use axum::{
+        extract::{RawQuery, State},
+        http::{header, HeaderMap, StatusCode},
+        response::{IntoResponse, Response},
+        routing::{get, post},
+        Json, Router,
+    };
+    use std::sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    };
+    use tokio_tungstenite::tungstenite;
+
+    /// How long a query waits for a connected client to answer
+    const QUERY_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+
+    /// Queries relayed from HTTP requests use ids from the upper half of the range,
+    /// so they do not collide with the ids websocket clients choose for their own queries
+    const FIRST_QUERY_ID: u64 = 1 << 63;
+
+    /// Queries awaiting a result, keyed by the id they were relayed with,
+    /// along with the id the HTTP client chose, which is restored in the response
+    type PendingQueries =
+        Arc<Mutex<std::collections::HashMap<u64, (u64, tokio::sync::oneshot::Sender<Event>)>>>;
+
+    #[derive(Clone)]
+    struct ApiState {
+        config: Arc<ServerConfig>,
+        relay_sender: tokio::sync::broadcast::Sender<Relay>,
+        clients: Clients,
+        pending_queries: PendingQueries,
+        next_query_id: Arc<AtomicU64>,
+    }
+
+    /// A connected websocket client, as listed by `GET /clients`
+    #[derive(Debug, Clone, serde::Serialize)]
+    pub struct ClientInfo {
+        pub id: u64,
+        pub address: String,
+        pub role: Role,
+        pub connected_seconds: u64,
+    }
+
+    /// Routes for the HTTP control API
+    ///
+    /// - `GET /health` reports that the server is up and how many clients are connected
+    /// - `GET /clients` lists the connected websocket clients
+    /// - `POST /command` relays a JSON encoded `Command` to every connected client.
+    ///   Queries wait for the first client to answer and respond with its `QueryResult`.
+    /// - Any other path is served from the static directory, if one is configured
+    pub(super) fn create_router(
+        config: Arc<ServerConfig>,
+        relay_sender: tokio::sync::broadcast::Sender<Relay>,
+        clients: Clients,
+    ) -> Router {
+        let pending_queries = PendingQueries::default();
+        tokio::spawn(resolve_queries(
+            relay_sender.subscribe(),
+            pending_queries.clone(),
+        ));
+        let static_dir = config.static_dir.clone();
+        let router = Router::new()
+            .route("/health", get(get_health))
+            .route("/clients", get(get_clients))
+            .route("/command", post(post_command))
+            .with_state(ApiState {
+                config,
+                relay_sender,
+                clients,
+                pending_queries,
+                next_query_id: Arc::new(AtomicU64::new(FIRST_QUERY_ID)),
+            });
+        match static_dir {
+            Some(static_dir) => {
+                router.fallback_service(tower_http::services::ServeDir::new(static_dir))
+            }
+            None => router,
+        }
+    }
+
+    pub(super) async fn serve_http(
+        listener: tokio::net::TcpListener,
+        tls_acceptor: Option<tokio_rustls::TlsAcceptor>,
+        router: Router,
+    ) {
+        loop {
+            let (stream, address) = accept_connection(&listener).await;
+            let router = router.clone();
+            let tls_acceptor = tls_acceptor.clone();
+            tokio::spawn(async move {
+                match tls_acceptor {
+                    Some(tls_acceptor) => {
+                        if let Some(stream) = tls_handshake(&tls_acceptor, stream, address).await {
+                            serve_http_connection(stream, address, router).await;
+                        }
+                    }
+                    None => serve_http_connection(stream, address, router).await,
+                }
+            });
+        }
+    }
+
+    async fn serve_http_connection<S>(stream: S, address: std::net::SocketAddr, router: Router)
+    where
+        S: tokio::io::AsyncRead + tokio::io::AsyncWrite + Unpin + Send + 'static,
+    {
+        let service = hyper_util::service::TowerToHyperService::new(router);
+        if let Err(error) = hyper::server::conn::http1::Builder::new()
+            .serve_connection(hyper_util::rt::TokioIo::new(stream), service)
+            .await
+        {
+            log::debug!("[Server] HTTP connection with {address} failed: {error}");
+        }
+    }
+
+    /// Completes pending queries as their results are relayed by connected clients
+    async fn resolve_queries(
+        mut relay_receiver: tokio::sync::broadcast::Receiver<Relay>,
+        pending_queries: PendingQueries,
+    ) {
+        loop {
+            let message = match relay_receiver.recv().await {
+                Ok((_, message)) => message,
+                Err(tokio::sync::broadcast::error::RecvError::Lagged(skipped)) => {
+                    log::warn!("[Server] Query results skipped {skipped} messages");
+                    continue;
+                }
+                Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+            };
+            if pending_queries
+                .lock()
+                .map_or(true, |pending_queries| pending_queries.is_empty())
+            {
+                continue;
+            }
+            let Ok(Message::Event {
+                event: Event::QueryResult { id, result },
+            }) = decode_message(&message)
+            else {
+                continue;
+            };
+            let Some((query_id, sender)) = pending_queries
+                .lock()
+                .ok()
+                .and_then(|mut pending_queries| pending_queries.remove(&id))
+            else {
+                continue;
+            };
+            let _ = sender.send(Event::QueryResult {
+                id: query_id,
+                result,
+            });
+        }
+    }
+
+    async fn get_health(State(state): State<ApiState>) -> Json<serde_json::Value> {
+        Json(serde_json::json!({
+            "status": "ok",
+            "clients": connected_clients(&state.clients),
+        }))
+    }
+
+    async fn get_clients(
+        State(state): State<ApiState>,
+        headers: HeaderMap,
+        RawQuery(query): RawQuery,
+    ) -> Result<Json<Vec<ClientInfo>>, Response> {
+        authorize(&state.config, &headers, query.as_deref())?;
+        let mut clients = state
+            .clients
+            .lock()
+            .map(|clients| {
+                clients
+                    .iter()
+                    .map(|(id, client)| ClientInfo {
+                        id: *id,
+                        address: client.address.to_string(),
+                        role: client.role,
+                        connected_seconds: client.connected_at.elapsed().as_secs(),
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+        clients.sort_by_key(|client| client.id);
+        Ok(Json(clients))
+    }
+
+    async fn post_command(
+        State(state): State<ApiState>,
+        headers: HeaderMap,
+        RawQuery(query): RawQuery,
+        body: String,
+    ) -> Result<Response, Response> {
+        let role = authorize(&state.config, &headers, query.as_deref())?;
+        let command = serde_json::from_str::<Command>(&body).map_err(|error| {
+            error_response(
+                StatusCode::BAD_REQUEST,
+                format!("Malformed command: {error}"),
+            )
+        })?;
+        check_command(role, &command)
+            .map_err(|error| error_response(StatusCode::FORBIDDEN, error))?;
+        let clients = connected_clients(&state.clients);
+        if clients == 0 {
+            return Err(error_response(
+                StatusCode::SERVICE_UNAVAILABLE,
+                "No clients are connected".to_string(),
+            ));
+        }
+        match command {
+            Command::Query { id, command } => run_query(&state, id, command).await,
+            command => {
+                relay_command(&state, command);
+                Ok((
+                    StatusCode::ACCEPTED,
+                    Json(serde_json::json!({ "clients": clients })),
+                )
+                    .into_response())
+            }
+        }
+    }
+
+    async fn run_query(
+        state: &ApiState,
+        id: u64,
+        command: QueryCommand,
+    ) -> Result<Response, Response> {
+        let relayed_id = state.next_query_id.fetch_add(1, Ordering::Relaxed);
+        let (sender, receiver) = tokio::sync::oneshot::channel();
+        if let Ok(mut pending_queries) = state.pending_queries.lock() {
+            pending_queries.insert(relayed_id, (id, sender));
+        }
+        relay_command(
+            state,
+            Command::Query {
+                id: relayed_id,
+                command,
+            },
+        );
+        let result = tokio::time::timeout(QUERY_TIMEOUT, receiver).await;
+        if let Ok(mut pending_queries) = state.pending_queries.lock() {
+            pending_queries.remove(&relayed_id);
+        }
+        match result {
+            Ok(Ok(event)) => Ok(Json(event).into_response()),
+            _ => Err(error_response(
+                StatusCode::GATEWAY_TIMEOUT,
+                "No client answered the query in time".to_string(),
+            )),
+        }
+    }
+
+    fn relay_command(state: &ApiState, command: Command) {
+        if let Ok(text) = serde_json::to_string(&Message::Command { command }) {
+            // Sending only fails when no connection is subscribed
+            let _ = state
+                .relay_sender
+                .send((SERVER_CONNECTION_ID, tungstenite::Message::Text(text)));
+        }
+    }
+
+    fn connected_clients(clients: &Clients) -> usize {
+        clients.lock().map_or(0, |clients| clients.len())
+    }
+
+    /// HTTP clients present tokens the same way websocket clients do
+    fn authorize(
+        config: &ServerConfig,
+        headers: &HeaderMap,
+        query: Option<&str>,
+    ) -> Result<Role, Response> {
+        let authorization = headers
+            .get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok());
+        let presented = presented_token(authorization, query);
+        authorize_token(config, presented.as_deref()).ok_or_else(|| {
+            error_response(
+                StatusCode::UNAUTHORIZED,
+                "Invalid or missing token".to_string(),
+            )
+        })
+    }
+
+    fn error_response(status: StatusCode, error: String) -> Response {
+        (status, Json(serde_json::json!({ "error": error }))).into_response()
+    }
+}
+
+pub use connectors::*;
+mod connectors {
+    use super::{Relay, SERVER_CONNECTION_ID};
+    use crate::api::{Command, Message};
+    use tokio::io::{AsyncReadExt, AsyncSeekExt};
+    use tokio_tungstenite::tungstenite;
+    use tokio_util::bytes::BytesMut;
+    use tokio_util::codec::{Decoder, LinesCodec, LinesCodecError};
+
+    /// An upstream data source and how its records become commands
+    #[derive(Debug, Clone, serde::Deserialize)]
+    #[serde(deny_unknown_fields)]
+    pub struct SourceConfig {
+        /// One of `tcp://host:port`, `udp://host:port`, `file:///path` or `exec://program arguments`, with shell style quoting
+        pub url: String,
+
+        /// Defaults to records being JSON encoded commands
+        #[serde(default)]
+        pub mapping: RecordMapping,
+    }
+
+    /// Translates newline delimited records into commands
+    ///
+    /// Templates are JSON encoded commands containing placeholders such as `{0}` or `{sensor.id}`,
+    /// which are replaced with the matching field of each record. Placeholders inside a JSON string
+    /// are filled with escaped text, while placeholders outside one must hold a single JSON value,
+    /// so CSV fields placed there must be numbers.
+    #[derive(Default, Debug, Clone, serde::Deserialize)]
+    #[serde(tag = "format", rename_all = "snake_case", deny_unknown_fields)]
+    pub enum RecordMapping {
+        /// Each record is a JSON encoded `Command`
+        #[default]
+        Command,
+
+        /// Each record is a row of delimited fields, referenced by index
+        Csv {
+            #[serde(default = "default_delimiter")]
+            delimiter: char,
+            template: String,
+        },
+
+        /// Each record is a JSON object, with fields referenced by dotted paths
+        Json { template: String },
+    }
+
+    fn default_delimiter() -> char {
+        ','
+    }
+
+    /// A parsed data source url
+    #[derive(Debug, Clone, PartialEq)]
+    pub enum DataSource {
+        /// Connects to a server that streams newline delimited records
+        Tcp { address: String },
+
+        /// Binds a socket and receives records in datagrams
+        Udp { address: String },
+
+        /// Follows records appended to a file
+        File { path: std::path::PathBuf },
+
+        /// Spawns a process and reads records from its standard output
+        Process {
+            program: String,
+            arguments: Vec<String>,
+        },
+    }
+
+    pub fn parse_data_source(url: &str) -> Result<DataSource, String> {
+        let Some((scheme, rest)) = url.split_once("://") else {
+            return Err(format!("Data source '{url}' is missing a scheme"));
+        };
+        match scheme {
+            "tcp" => Ok(DataSource::Tcp {
+                address: rest.to_string(),
+            }),
+            "udp" => Ok(DataSource::Udp {
+                address: rest.to_string(),
+            }),
+            "file" => Ok(DataSource::File {
+                path: std::path::PathBuf::from(rest),
+            }),
+            "exec" => {
+                let mut words = shell_words::split(rest)
+                    .map_err(|error| {
+                        format!("Data source '{url}' has malformed arguments: {error}")
+                    })?
+                    .into_iter();
+                let program = words
+                    .next()
+                    .ok_or_else(|| format!("Data source '{url}' is missing a program"))?;
+                Ok(DataSource::Process {
+                    program,
+                    arguments: words.collect(),
+                })
+            }
+            _ => Err(format!("Unsupported data source scheme '{scheme}'")),
+        }
+    }
+
+    /// Translates a single record into a command
+    pub fn map_record(mapping: &RecordMapping, record: &str) -> Result<Command, String> {
+        let command_json = match mapping {
+            RecordMapping::Command => record.to_string(),
+            RecordMapping::Csv {
+                delimiter,
+                template,
+            } => {
+                let fields = record.split(*delimiter).map(str::trim).collect::<Vec<_>>();
+                fill_template(template, |placeholder, quoted| {
+                    let field = placeholder
+                        .parse::<usize>()
+                        .ok()
+                        .and_then(|index| fields.get(index))
+                        .ok_or_else(|| missing_field(placeholder))?;
+                    if quoted {
+                        return Ok(escape_json_string(field));
+                    }
+                    field
+                        .parse::<serde_json::Number>()
+                        .map(|number| number.to_string())
+                        .map_err(|_| format!("Field {placeholder} ('{field}') is not a number"))
+                })?
+            }
+            RecordMapping::Json { template } => {
+                let value = serde_json::from_str::<serde_json::Value>(record)
+                    .map_err(|error| format!("Record is not valid JSON: {error}"))?;
+                fill_template(template, |placeholder, quoted| {
+                    let field = placeholder
+                        .split('.')
+                        .try_fold(&value, |value, key| value.get(key))
+                        .ok_or_else(|| missing_field(placeholder))?;
+                    Ok(match field {
+                        serde_json::Value::String(string) if quoted => escape_json_string(string),
+                        field if quoted => escape_json_string(&field.to_string()),
+                        field => field.to_string(),
+                    })
+                })?
+            }
+        };
+        serde_json::from_str::<Command>(&command_json)
+            .map_err(|error| format!("Record does not map to a command: {error}"))
+    }
+
+    /// Replaces every `{placeholder}` in a template, leaving JSON braces untouched.
+    /// The lookup is told whether the placeholder sits inside a JSON string.
+    fn fill_template(
+        template: &str,
+        mut lookup: impl FnMut(&str, bool) -> Result<String, String>,
+    ) -> Result<String, String> {
+        let mut output = String::with_capacity(template.len());
+        let mut rest = template;
+        let mut quoted = false;
+        let mut escaped = false;
+        while let Some(start) = rest.find('{') {
+            output.push_str(&rest[..start]);
+            for character in rest[..start].chars() {
+                match character {
+                    _ if escaped => escaped = false,
+                    '\\' if quoted => escaped = true,
+                    '"' => quoted = !quoted,
+                    _ => {}
+                }
+            }
+            let after = &rest[start + 1..];
+            let placeholder_length = after
+                .find(|character: char| {
+                    !(character.is_ascii_alphanumeric() || character == '_' || character == '.')
+                })
+                .unwrap_or(after.len());
+            if placeholder_length > 0 && after[placeholder_length..].starts_with('}') {
+                let placeholder = &after[..placeholder_length];
+                output.push_str(&lookup(placeholder, quoted)?);
+                rest = &after[placeholder_length + 1..];
+            } else {
+                output.push('{');
+                rest = after;
+            }
+        }
+        output.push_str(rest);
+        Ok(output)
+    }
+
+    fn missing_field(placeholder: &str) -> String {
+        format!("Record has no field '{placeholder}'")
+    }
+
+    /// Escapes a value so it can be placed between quotes in a JSON template
+    fn escape_json_string(value: &str) -> String {
+        let quoted = serde_json::Value::String(value.to_string()).to_string();
+        quoted[1..quoted.len() - 1].to_string()
+    }
+
+    pub(super) async fn run_connector(
+        url: String,
+        mapping: RecordMapping,
+        relay_sender: tokio::sync::broadcast::Sender<Relay>,
+    ) {
+        let source = match parse_data_source(&url) {
+            Ok(source) => source,
+            Err(error) => {
+                log::error!("[Connector] {error}");
+                return;
+            }
+        };
+        log::info!("[Connector] Connecting to data source: {url}");
+
+        let (record_sender, mut record_receiver) = tokio::sync::mpsc::channel(RECORD_CAPACITY);
+        let reader = tokio::spawn(read_records(source, record_sender));
+        while let Some(record) = record_receiver.recv().await {
+            let command = match map_record(&mapping, &record) {
+                Ok(command) => command,
+                Err(error) => {
+                    log::warn!("[Connector] Skipping record from {url}: {error}");
+                    continue;
+                }
+            };
+            let message = Message::Command { command };
+            let Ok(text) = serde_json::to_string(&message) else {
+                continue;
+            };
+            // Sending only fails when no connection is subscribed
+            let _ = relay_sender.send((SERVER_CONNECTION_ID, tungstenite::Message::Text(text)));
+        }
+
+        match reader.await {
+            Ok(Ok(())) => log::info!("[Connector] Data source closed: {url}"),
+            Ok(Err(error)) => log::error!("[Connector] Data source {url} failed: {error}"),
+            Err(error) => log::error!("[Connector] Data source {url} panicked: {error}"),
+        }
+    }
+
+    /// Records waiting to be relayed. Stream sources wait for room, datagrams are dropped.
+    const RECORD_CAPACITY: usize = 1024;
+
+    /// Longer records are dropped, so a source that never sends a newline can't exhaust memory
+    const MAX_RECORD_LENGTH: usize = 1 << 20;
+
+    type RecordSender = tokio::sync::mpsc::Sender<String>;
+
+    async fn read_records(source: DataSource, records: RecordSender) -> std::io::Result<()> {
+        match source {
+            DataSource::Tcp { address } => read_tcp_records(&address, &records).await,
+            DataSource::Udp { address } => read_udp_records(&address, &records).await,
+            DataSource::File { path } => read_file_records(&path, &records).await,
+            DataSource::Process { program, arguments } => {
+                read_process_records(&program, &arguments, &records).await
+            }
+        }
+    }
+
+    fn record_lines(text: &str) -> impl Iterator<Item = &str> {
+        text.lines().map(str::trim).filter(|line| !line.is_empty())
+    }
+
+    /// Waits for room in the channel, so slow clients slow down the source
+    async fn send_records(text: &str, records: &RecordSender) -> bool {
+        for line in record_lines(text) {
+            if records.send(line.to_string()).await.is_err() {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Splits bytes from a stream source into records, dropping records that are
+    /// too long or not UTF-8 instead of buffering them
+    struct RecordDecoder {
+        codec: LinesCodec,
+        buffer: BytesMut,
+    }
+
+    impl Default for RecordDecoder {
+        fn default() -> Self {
+            Self {
+                codec: LinesCodec::new_with_max_length(MAX_RECORD_LENGTH),
+                buffer: BytesMut::new(),
+            }
+        }
+    }
+
+    impl RecordDecoder {
+        /// Relays every complete record in the buffer, and the unterminated remainder
+        /// once the source has ended. Returns false once the relay has closed.
+        async fn send_records(
+            &mut self,
+            source: &str,
+            records: &RecordSender,
+            ended: bool,
+        ) -> bool {
+            loop {
+                let record = if ended {
+                    self.codec.decode_eof(&mut self.buffer)
+                } else {
+                    self.codec.decode(&mut self.buffer)
+                };
+                match record {
+                    Ok(Some(line)) => {
+                        if !send_records(&line, records).await {
+                            return false;
+                        }
+                    }
+                    Ok(None) => return true,
+                    Err(LinesCodecError::MaxLineLengthExceeded) => log::warn!(
+                        "[Connector] Dropping a record from {source} longer than {MAX_RECORD_LENGTH} bytes"
+                    ),
+                    Err(LinesCodecError::Io(error)) => {
+                        log::warn!("[Connector] Dropping a record from {source}: {error}");
+                    }
+                }
+            }
+        }
+    }
+
+    /// Relays each line of a stream until it ends. Returns false once the relay has closed.
+    async fn send_stream_records(
+        mut stream: impl tokio::io::AsyncRead + Unpin,
+        source: &str,
+        records: &RecordSender,
+    ) -> std::io::Result<bool> {
+        let mut decoder = RecordDecoder::default();
+        loop {
+            decoder.buffer.reserve(8192);
+            let ended = stream.read_buf(&mut decoder.buffer).await? == 0;
+            if !decoder.send_records(source, records, ended).await {
+                return Ok(false);
+            }
+            if ended {
+                return Ok(true);
+            }
+        }
+    }
+
+    /// Reconnects with exponential backoff whenever the upstream server goes away
+    async fn read_tcp_records(address: &str, records: &RecordSender) -> std::io::Result<()> {
+        let mut backoff = std::time::Duration::from_secs(1);
+        loop {
+            match tokio::net::TcpStream::connect(address).await {
+                Ok(stream) => {
+                    log::info!("[Connector] Connected to tcp://{address}");
+                    backoff = std::time::Duration::from_secs(1);
+                    let source = format!("tcp://{address}");
+                    if let Ok(false) = send_stream_records(stream, &source, records).await {
+                        return Ok(());
+                    }
+                    log::warn!("[Connector] Lost connection to tcp://{address}");
+                }
+                Err(error) => {
+                    log::warn!("[Connector] Failed to connect to tcp://{address}: {error}");
+                }
+            }
+            if records.is_closed() {
+                return Ok(());
+            }
+            tokio::time::sleep(backoff).await;
+            backoff = (backoff * 2).min(std::time::Duration::from_secs(30));
+        }
+    }
+
+    async fn read_udp_records(address: &str, records: &RecordSender) -> std::io::Result<()> {
+        let socket = tokio::net::UdpSocket::bind(address).await?;
+        log::info!("[Connector] Listening for datagrams on udp://{address}");
+        let mut buffer = vec![0; 65536];
+        loop {
+            let (length, _) = socket.recv_from(&mut buffer).await?;
+            for line in record_lines(&String::from_utf8_lossy(&buffer[..length])) {
+                match records.try_send(line.to_string()) {
+                    Ok(()) => {}
+                    Err(tokio::sync::mpsc::error::TrySendError::Full(_)) => {
+                        log::warn!(
+                            "[Connector] Dropping a record from udp://{address}, the relay is full"
+                        );
+                    }
+                    Err(tokio::sync::mpsc::error::TrySendError::Closed(_)) => return Ok(()),
+                }
+            }
+        }
+    }
+
+    /// Follows a file like `tail -f`, starting at its current end
+    async fn read_file_records(
+        path: &std::path::Path,
+        records: &RecordSender,
+    ) -> std::io::Result<()> {
+        let mut file = tokio::fs::File::open(path).await?;
+        let mut position = file.seek(std::io::SeekFrom::End(0)).await?;
+        let source = path.display().to_string();
+        let mut decoder = RecordDecoder::default();
+        let mut buffer = Vec::new();
+        loop {
+            let length = tokio::fs::metadata(path).await?.len();
+            if length < position {
+                log::info!(
+                    "[Connector] {} was truncated, reading from the start",
+                    path.display()
+                );
+                position = file.seek(std::io::SeekFrom::Start(0)).await?;
+                decoder = RecordDecoder::default();
+            }
+            buffer.clear();
+            position += file.read_to_end(&mut buffer).await? as u64;
+            decoder.buffer.extend_from_slice(&buffer);
+
+            // Only complete lines are records, the remainder waits for the next write
+            if !decoder.send_records(&source, records, false).await {
+                return Ok(());
+            }
+            if records.is_closed() {
+                return Ok(());
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(250)).await;
+        }
+    }
+
+    async fn read_process_records(
+        program: &str,
+        arguments: &[String],
+        records: &RecordSender,
+    ) -> std::io::Result<()> {
+        let mut child = tokio::process::Command::new(program)
+            .args(arguments)
+            .stdout(std::process::Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()?;
+        let Some(stdout) = child.stdout.take() else {
+            return Ok(());
+        };
+        send_stream_records(stdout, &format!("process '{program}'"), records).await?;
+        let status = child.wait().await?;
+        log::info!("[Connector] Process '{program}' exited with {status}");
+        Ok(())
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::api::SpawnCommand;
+
+        #[test]
+        fn parse_data_source_reads_each_scheme() {
+            assert_eq!(
+                parse_data_source("tcp://localhost:9000"),
+                Ok(DataSource::Tcp {
+                    address: "localhost:9000".to_string()
+                })
+            );
+            assert_eq!(
+                parse_data_source("udp://0.0.0.0:9001"),
+                Ok(DataSource::Udp {
+                    address: "0.0.0.0:9001".to_string()
+                })
+            );
+            assert_eq!(
+                parse_data_source("file:///var/log/sensors.csv"),
+                Ok(DataSource::File {
+                    path: std::path::PathBuf::from("/var/log/sensors.csv")
+                })
+            );
+        }
+
+        #[test]
+        fn parse_data_source_keeps_quoted_arguments_together() {
+            assert_eq!(
+                parse_data_source(r#"exec://python3 "my scripts/feed.py" --rate '10 hz'"#),
+                Ok(DataSource::Process {
+                    program: "python3".to_string(),
+                    arguments: vec![
+                        "my scripts/feed.py".to_string(),
+                        "--rate".to_string(),
+                        "10 hz".to_string(),
+                    ],
+                })
+            );
+        }
+
+        #[test]
+        fn parse_data_source_rejects_malformed_urls() {
+            assert!(parse_data_source("localhost:9000").is_err());
+            assert!(parse_data_source("http://localhost").is_err());
+            assert!(parse_data_source("exec://").is_err());
+            assert!(parse_data_source(r#"exec://python3 "unterminated"#).is_err());
+        }
+
+        #[test]
+        fn map_record_fills_csv_fields_by_index() {
+            let mapping = RecordMapping::Csv {
+                delimiter: ';',
+                template: r#"{"Spawn":{"command":{"Cube":{"position":{"x":{1},"y":{2},"z":0.0},"size":1.0,"name":"{0}"}}}}"#.to_string(),
+            };
+            let command = map_record(&mapping, r#"probe "a"; 1.5; -2"#).unwrap();
+            let Command::Spawn {
+                command: SpawnCommand::Cube { position, name, .. },
+                ..
+            } = command
+            else {
+                panic!("Expected a cube spawn, got {command:?}");
+            };
+            assert_eq!(name, r#"probe "a""#);
+            assert_eq!((position.x, position.y), (1.5, -2.0));
+        }
+
+        #[test]
+        fn map_record_fills_json_fields_by_path() {
+            let mapping = RecordMapping::Json {
+                template: r#"{"Despawn":{"entity_id":{"id":{sensor.id},"generation":0}}}"#
+                    .to_string(),
+            };
+            let command = map_record(&mapping, r#"{"sensor":{"id":7}}"#).unwrap();
+            assert!(matches!(
+                command,
+                Command::Despawn { entity_id } if entity_id.id == 7
+            ));
+        }
+
+        #[test]
+        fn map_record_reports_bad_records() {
+            let mapping = RecordMapping::Json {
+                template: r#"{"Despawn":{"entity_id":{"id":{id},"generation":0}}}"#.to_string(),
+            };
+            assert!(map_record(&mapping, "not json").is_err());
+            assert!(map_record(&mapping, r#"{"other":1}"#).is_err());
+            assert!(map_record(&mapping, r#"{"id":"seven"}"#).is_err());
+            assert!(map_record(&RecordMapping::Command, r#"{"Unknown":{}}"#).is_err());
+        }
+
+        #[test]
+        fn fill_template_leaves_json_braces_untouched() {
+            let filled = fill_template(r#"{"a":{x},"b":{ "c": {} }}"#, |placeholder, _| {
+                (placeholder == "x")
+                    .then(|| "1".to_string())
+                    .ok_or_else(|| missing_field(placeholder))
+            });
+            assert_eq!(filled, Ok(r#"{"a":1,"b":{ "c": {} }}"#.to_string()));
+        }
+
+        #[test]
+        fn fill_template_reports_whether_placeholders_are_quoted() {
+            let mut placements = Vec::new();
+            let filled = fill_template(
+                r#"{"a":{x},"b":"{y} \" {z}","c":{w}}"#,
+                |placeholder, quoted| {
+                    placements.push((placeholder.to_string(), quoted));
+                    Ok(String::new())
+                },
+            );
+            assert!(filled.is_ok());
+            assert_eq!(
+                placements,
+                [
+                    ("x".to_string(), false),
+                    ("y".to_string(), true),
+                    ("z".to_string(), true),
+                    ("w".to_string(), false),
+                ]
+            );
+        }
+
+        #[test]
+        fn map_record_rejects_structure_in_unquoted_fields() {
+            let mapping = RecordMapping::Csv {
+                delimiter: ';',
+                template: r#"{"Despawn":{"entity_id":{"id":{0},"generation":0}}}"#.to_string(),
+            };
+            assert!(matches!(
+                map_record(&mapping, "7"),
+                Ok(Command::Despawn { entity_id }) if entity_id.id == 7
+            ));
+            assert_eq!(
+                map_record(&mapping, r#"1,"generation":2}},"x":{"y":0"#).map(|_| ()),
+                Err(r#"Field 0 ('1,"generation":2}},"x":{"y":0') is not a number"#.to_string())
+            );
+            assert!(map_record(&mapping, "7]}").is_err());
+            assert!(map_record(&mapping, "seven").is_err());
+
+            let mapping = RecordMapping::Json {
+                template: r#"{"Despawn":{"entity_id":{"id":{id},"generation":0}}}"#.to_string(),
+            };
+            assert!(map_record(&mapping, r#"{"id":"7,\"generation\":2}}"}"#).is_err());
+        }
+
+        #[test]
+        fn fill_template_reports_missing_placeholders() {
+            assert_eq!(
+                fill_template("{missing}", |placeholder, _| Err(missing_field(
+                    placeholder
+                ))),
+                Err("Record has no field 'missing'".to_string())
+            );
+        }
+
+        #[tokio::test]
+        async fn stream_records_drop_oversized_lines() {
+            let oversized = "x".repeat(MAX_RECORD_LENGTH * 2);
+            let mut bytes = format!("first\n{oversized}\nsecond\n").into_bytes();
+            bytes.extend_from_slice(b"\xff\xfe\nlast");
+
+            let (sender, mut receiver) = tokio::sync::mpsc::channel(RECORD_CAPACITY);
+            assert!(matches!(
+                send_stream_records(bytes.as_slice(), "test", &sender).await,
+                Ok(true)
+            ));
+            drop(sender);
+
+            let mut received = Vec::new();
+            while let Some(record) = receiver.recv().await {
+                received.push(record);
+            }
+            assert_eq!(received, ["first", "second", "last"]);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(token: Option<&str>, producer_token: Option<&str>) -> ServerConfig {
+        ServerConfig {
+            token: token.map(str::to_string),
+            producer_token: producer_token.map(str::to_string),
+            ..Default::default()
+        }
+    }
+
+    fn event_message(event: Event) -> tungstenite::Message {
+        tungstenite::Message::Text(serde_json::to_string(&Message::Event { event }).unwrap())
+    }
+
+    #[test]
+    fn presented_token_prefers_the_authorization_header() {
+        assert_eq!(
+            presented_token(Some("Bearer header"), Some("token=query")),
+            Some("header".to_string())
+        );
+        assert_eq!(
+            presented_token(Some("Basic other"), Some("token=query")),
+            Some("query".to_string())
+        );
+    }
+
+    #[test]
+    fn presented_token_decodes_the_query() {
+        assert_eq!(
+            presented_token(None, Some("name=a&token=a%2Bb%26c%3D&x=1")),
+            Some("a+b&c=".to_string())
+        );
+        assert_eq!(
+            presented_token(None, Some("token=a+b")),
+            Some("a b".to_string())
+        );
+        assert_eq!(presented_token(None, Some("tokens=x&other=token")), None);
+        assert_eq!(presented_token(None, None), None);
+    }
+
+    #[test]
+    fn authorize_token_grants_roles() {
+        let both = config(Some("viewer"), Some("producer"));
+        assert_eq!(
+            authorize_token(&both, Some("producer")),
+            Some(Role::Producer)
+        );
+        assert_eq!(authorize_token(&both, Some("viewer")), Some(Role::Viewer));
+        assert_eq!(authorize_token(&both, Some("viewe")), None);
+        assert_eq!(authorize_token(&both, Some("producer2")), None);
+        assert_eq!(authorize_token(&both, Some("")), None);
+        assert_eq!(authorize_token(&both, None), None);
+
+        let producer_only = config(None, Some("producer"));
+        assert_eq!(
+            authorize_token(&producer_only, Some("producer")),
+            Some(Role::Producer)
+        );
+        assert_eq!(authorize_token(&producer_only, None), Some(Role::Viewer));
+        assert_eq!(
+            authorize_token(&producer_only, Some("wrong")),
+            Some(Role::Viewer)
+        );
+
+        let viewer_only = config(Some("viewer"), None);
+        assert_eq!(
+            authorize_token(&viewer_only, Some("viewer")),
+            Some(Role::Viewer)
+        );
+        assert_eq!(authorize_token(&viewer_only, None), None);
+
+        let with_frontend = ServerConfig {
+            frontend_token: Some("frontend".to_string()),
+            ..config(None, Some("producer"))
+        };
+        assert_eq!(
+            authorize_token(&with_frontend, Some("frontend")),
+            Some(Role::Frontend)
+        );
+        assert_eq!(
+            authorize_token(&with_frontend, Some("producer")),
+            Some(Role::Producer)
+        );
+        assert_eq!(
+            authorize_token(&with_frontend, Some("wrong")),
+            Some(Role::Viewer)
+        );
+
+        let open = config(None, None);
+        assert_eq!(authorize_token(&open, None), Some(Role::Producer));
+        assert_eq!(
+            authorize_token(&open, Some("anything")),
+            Some(Role::Producer)
+        );
+    }
+
+    #[test]
+    fn rejection_message_carries_the_request_id() {
+        let decode = |message: tungstenite::Message| match decode_message(&message) {
+            Ok(Message::Event {
+                event: Event::Websocket { event },
+            }) => event,
+            message => panic!("Expected a websocket event, got {message:?}"),
+        };
+        let spawn = tungstenite::Message::Text(
+            serde_json::to_string(&Message::Command {
+                command: Command::Spawn {
+                    id: 7,
+                    command: crate::api::SpawnCommand::Empty,
+                },
+            })
+            .unwrap(),
+        );
+        assert!(matches!(
+            decode(rejection_message(&spawn, "denied".to_string())),
+            WebsocketEvent::Rejected { id: 7, error } if error == "denied"
+        ));
+        let malformed = tungstenite::Message::Text("not json".to_string());
+        assert!(matches!(
+            decode(rejection_message(&malformed, "malformed".to_string())),
+            WebsocketEvent::Error { .. }
+        ));
+    }
+
+    #[test]
+    fn only_frontends_and_producers_answer_queries_and_report() {
+        let events = [
+            Event::Report {
+                report: crate::api::Report::EntityCreated {
+                    id: 1,
+                    entity_id: crate::prelude::EntityId::default(),
+                },
+            },
+            Event::QueryResult {
+                id: 1,
+                result: crate::api::QueryResult::default(),
+            },
+        ];
+        for event in events {
+            let message = event_message(event);
+            assert!(check_message(Role::Viewer, &message).is_err());
+            assert!(check_message(Role::Frontend, &message).is_ok());
+            assert!(check_message(Role::Producer, &message).is_ok());
+        }
+
+        let websocket_event = event_message(Event::Websocket {
+            event: WebsocketEvent::default(),
         });
+        for role in [Role::Viewer, Role::Frontend, Role::Producer] {
+            assert!(check_message(role, &websocket_event).is_err());
+        }
+    }
+
+    #[test]
+    fn frontends_cannot_send_mutating_commands() {
+        let spawn = Command::Spawn {
+            id: 1,
+            command: crate::api::SpawnCommand::Empty,
+        };
+        assert!(check_command(Role::Viewer, &spawn).is_err());
+        assert!(check_command(Role::Frontend, &spawn).is_err());
+        assert!(check_command(Role::Producer, &spawn).is_ok());
     }
 }
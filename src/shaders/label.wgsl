@@ -0,0 +1,68 @@
+struct VertexInput {
+    @location(0) corner: vec2<f32>,
+    // The point the label is centered above, with the height of a line of text in w
+    @location(1) anchor: vec4<f32>,
+    // The bottom left and top right corners relative to the anchor, in lines of text
+    @location(2) rect: vec4<f32>,
+    // The texture coordinates at those corners
+    @location(3) uv: vec4<f32>,
+    @location(4) color: vec4<f32>,
+    @location(5) billboard: f32,
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) color: vec4<f32>,
+    @location(1) uv: vec2<f32>,
+};
+
+struct Uniforms {
+    view_proj: mat4x4<f32>,
+    viewport_size: vec4<f32>,
+};
+
+@group(0) @binding(0)
+var<uniform> uniforms: Uniforms;
+
+// The model matrix of the entity being drawn, applied here rather than on the CPU
+struct Model {
+    matrix: mat4x4<f32>,
+    normal_matrix: mat4x4<f32>,
+};
+
+@group(1) @binding(0)
+var<uniform> model: Model;
+
+@group(2) @binding(0)
+var atlas: texture_2d<f32>;
+
+@group(2) @binding(1)
+var atlas_sampler: sampler;
+
+@vertex
+fn vs_main(in: VertexInput) -> VertexOutput {
+    let offset = mix(in.rect.xy, in.rect.zw, in.corner) * in.anchor.w;
+
+    var out: VertexOutput;
+    if (in.billboard > 0.5) {
+        // Billboards are offset in pixels from their projected anchor,
+        // which is snapped to a pixel so the text does not shimmer as the camera moves
+        let anchor = uniforms.view_proj * model.matrix * vec4<f32>(in.anchor.xyz, 1.0);
+        let viewport = uniforms.viewport_size.xy;
+        let pixel = round((anchor.xy / anchor.w * 0.5 + 0.5) * viewport) + offset;
+        let ndc = pixel / viewport * 2.0 - 1.0;
+        out.clip_position = vec4<f32>(ndc * anchor.w, anchor.z, anchor.w);
+    } else {
+        let position = in.anchor.xyz + vec3<f32>(offset, 0.0);
+        out.clip_position = uniforms.view_proj * model.matrix * vec4<f32>(position, 1.0);
+    }
+    out.color = in.color;
+    out.uv = mix(in.uv.xy, in.uv.zw, in.corner);
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let coverage = textureSample(atlas, atlas_sampler, in.uv).r;
+    return vec4<f32>(in.color.rgb, in.color.a * coverage);
+}
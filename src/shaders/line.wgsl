@@ -3,34 +3,152 @@ struct VertexInput {
     @location(1) start: vec4<f32>,
     @location(2) end: vec4<f32>,
     @location(3) color: vec4<f32>,
+    // Width, dash length, gap length and cap
+    @location(4) style: vec4<f32>,
 };
 
 struct VertexOutput {
     @builtin(position) clip_position: vec4<f32>,
     @location(0) color: vec4<f32>,
+    // Pixels along the line from its start, and across it from its center
+    @location(1) offset: vec2<f32>,
+    @location(2) @interpolate(flat) length: f32,
+    @location(3) @interpolate(flat) style: vec4<f32>,
 };
 
 struct Uniforms {
     view_proj: mat4x4<f32>,
+    viewport_size: vec4<f32>,
 };
 
 @group(0) @binding(0)
 var<uniform> uniforms: Uniforms;
 
+// The model matrix of the entity being drawn, applied here rather than on the CPU
+struct Model {
+    matrix: mat4x4<f32>,
+    normal_matrix: mat4x4<f32>,
+};
+
+@group(1) @binding(0)
+var<uniform> model: Model;
+
+const CAP_BUTT: f32 = 0.0;
+const CAP_ROUND: f32 = 2.0;
+
+// The pixels added around a line for its anti-aliased edge to fade out in
+const FEATHER: f32 = 1.0;
+
+// Points behind the camera are moved onto the near plane, so lines crossing it stay on screen
+const NEAR_W: f32 = 0.0001;
+
+fn clip_to_near(point: vec4<f32>, other: vec4<f32>) -> vec4<f32> {
+    if (point.w >= NEAR_W) {
+        return point;
+    }
+    let t = (NEAR_W - point.w) / (other.w - point.w);
+    return mix(point, other, t);
+}
+
+fn to_pixels(clip: vec4<f32>) -> vec2<f32> {
+    return (clip.xy / clip.w * 0.5 + 0.5) * uniforms.viewport_size.xy;
+}
+
 @vertex
-fn vs_main(model: VertexInput) -> VertexOutput {
+fn vs_main(in: VertexInput) -> VertexOutput {
     var out: VertexOutput;
+    out.color = in.color;
+    out.style = in.style;
 
-    // Interpolate between start and end based on vertex position
-    let pos = mix(model.start.xyz, model.end.xyz, model.position.x);
+    let start_clip = uniforms.view_proj * model.matrix * vec4<f32>(in.start.xyz, 1.0);
+    let end_clip = uniforms.view_proj * model.matrix * vec4<f32>(in.end.xyz, 1.0);
+    if (start_clip.w < NEAR_W && end_clip.w < NEAR_W) {
+        // Lines entirely behind the camera collapse to a point outside the depth range
+        out.clip_position = vec4<f32>(0.0, 0.0, -2.0, 1.0);
+        return out;
+    }
+    let start = clip_to_near(start_clip, end_clip);
+    let end = clip_to_near(end_clip, start_clip);
 
-    out.clip_position = uniforms.view_proj * vec4<f32>(pos, 1.0);
-    out.color = model.color;
+    // Each line is a quad expanded around the segment in screen space,
+    // wide enough to hold its caps and anti-aliased edges
+    let start_pixels = to_pixels(start);
+    let end_pixels = to_pixels(end);
+    let delta = end_pixels - start_pixels;
+    let segment_length = length(delta);
+    var direction = vec2<f32>(1.0, 0.0);
+    if (segment_length > 0.0001) {
+        direction = delta / segment_length;
+    }
+    let normal = vec2<f32>(-direction.y, direction.x);
+    let half_width = max(in.style.x, 1.0) * 0.5;
+    var extension = FEATHER;
+    if (in.style.w != CAP_BUTT) {
+        extension += half_width;
+    }
 
+    let along = in.position.x;
+    let across = in.position.y * (half_width + FEATHER);
+    let stretch = (along * 2.0 - 1.0) * extension;
+    let pixels = mix(start_pixels, end_pixels, along) + direction * stretch + normal * across;
+
+    // Depth is linear in screen space after the perspective divide, so w can be dropped
+    let depth = mix(start.z / start.w, end.z / end.w, along);
+    let ndc = pixels / uniforms.viewport_size.xy * 2.0 - 1.0;
+    out.clip_position = vec4<f32>(ndc, depth, 1.0);
+    out.offset = vec2<f32>(along * segment_length + stretch, across);
+    out.length = segment_length;
     return out;
 }
 
+// The coverage of a pixel by the line, its dashes and its caps
+fn line_coverage(in: VertexOutput) -> f32 {
+    // Lines thinner than a pixel are drawn a pixel wide and faded instead
+    let width = max(in.style.x, 1.0);
+    let half_width = width * 0.5;
+    let dash = in.style.y;
+    let gap = in.style.z;
+    let cap = in.style.w;
+
+    // The signed distance past the nearest end of the segment, or of the nearest dash
+    var along = max(-in.offset.x, in.offset.x - in.length);
+    if (dash > 0.0) {
+        let period = dash + max(gap, 0.0);
+        let phase = in.offset.x - floor(in.offset.x / period) * period;
+        let inside_dash = -min(phase, dash - phase);
+        let past_dash = min(phase - dash, period - phase);
+        along = max(along, select(past_dash, inside_dash, phase <= dash));
+    }
+
+    // Signed distance to the outline of the line in pixels, negative inside
+    var distance: f32;
+    let across = abs(in.offset.y) - half_width;
+    if (cap == CAP_ROUND) {
+        distance = length(vec2<f32>(max(along, 0.0), abs(in.offset.y))) - half_width;
+    } else if (cap == CAP_BUTT) {
+        distance = max(along, across);
+    } else {
+        distance = max(along - half_width, across);
+    }
+
+    return clamp(0.5 - distance, 0.0, 1.0) * min(in.style.x, 1.0);
+}
+
 @fragment
 fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
-    return in.color;
+    let coverage = line_coverage(in);
+    if (coverage <= 0.0) {
+        discard;
+    }
+    return vec4<f32>(in.color.rgb, in.color.a * coverage);
+}
+
+// Marks the lines of outlined entities in the outline mask
+@fragment
+fn fs_outline(in: VertexOutput) -> @location(0) vec4<f32> {
+    let coverage = line_coverage(in);
+    if (coverage <= 0.0) {
+        discard;
+    }
+    return vec4<f32>(coverage);
 }
@@ -0,0 +1,62 @@
+struct VertexInput {
+    @location(0) position: vec3<f32>,
+    @location(1) normal: vec3<f32>,
+    @location(2) color: vec4<f32>,
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) world_position: vec3<f32>,
+    @location(1) normal: vec3<f32>,
+    @location(2) color: vec4<f32>,
+};
+
+struct Uniforms {
+    view_proj: mat4x4<f32>,
+    camera_position: vec4<f32>,
+};
+
+@group(0) @binding(0)
+var<uniform> uniforms: Uniforms;
+
+// The model matrix of the entity being drawn, applied here rather than on the CPU
+struct Model {
+    matrix: mat4x4<f32>,
+    normal_matrix: mat4x4<f32>,
+};
+
+@group(1) @binding(0)
+var<uniform> model: Model;
+
+// Surfaces facing away from the light are never fully black
+const AMBIENT: f32 = 0.25;
+
+@vertex
+fn vs_main(in: VertexInput) -> VertexOutput {
+    var out: VertexOutput;
+    let world_position = model.matrix * vec4<f32>(in.position, 1.0);
+    out.clip_position = uniforms.view_proj * world_position;
+    out.world_position = world_position.xyz;
+    out.normal = (model.normal_matrix * vec4<f32>(in.normal, 0.0)).xyz;
+    out.color = in.color;
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    // A headlight shines from the camera, so every visible surface is lit.
+    // Both sides of a triangle are lit alike, since winding is not always consistent.
+    let to_camera = normalize(uniforms.camera_position.xyz - in.world_position);
+    var diffuse = 1.0;
+    if (length(in.normal) > 0.0) {
+        diffuse = abs(dot(normalize(in.normal), to_camera));
+    }
+    let shade = AMBIENT + (1.0 - AMBIENT) * diffuse;
+    return vec4<f32>(in.color.rgb * shade, in.color.a);
+}
+
+// Marks the surfaces of outlined entities in the outline mask
+@fragment
+fn fs_outline(in: VertexOutput) -> @location(0) vec4<f32> {
+    return vec4<f32>(1.0);
+}
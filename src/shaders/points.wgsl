@@ -0,0 +1,73 @@
+struct VertexInput {
+    @builtin(vertex_index) vertex_index: u32,
+    @location(0) position: vec3<f32>,
+    @location(1) color: vec4<f32>,
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) corner: vec2<f32>,
+    @location(1) color: vec4<f32>,
+};
+
+struct Uniforms {
+    view: mat4x4<f32>,
+    projection: mat4x4<f32>,
+    viewport_size: vec4<f32>,
+};
+
+struct Cloud {
+    model: mat4x4<f32>,
+    color: vec4<f32>,
+    size: f32,
+    sizing: u32,
+    shape: u32,
+    padding: u32,
+};
+
+@group(0) @binding(0)
+var<uniform> uniforms: Uniforms;
+
+@group(1) @binding(0)
+var<uniform> cloud: Cloud;
+
+const SIZING_WORLD: u32 = 1u;
+const SHAPE_ROUND: u32 = 0u;
+
+@vertex
+fn vs_main(in: VertexInput) -> VertexOutput {
+    // Each point is a camera facing square made of two triangles
+    var corners = array<vec2<f32>, 6>(
+        vec2<f32>(-1.0, -1.0),
+        vec2<f32>(1.0, -1.0),
+        vec2<f32>(1.0, 1.0),
+        vec2<f32>(-1.0, -1.0),
+        vec2<f32>(1.0, 1.0),
+        vec2<f32>(-1.0, 1.0),
+    );
+    let corner = corners[in.vertex_index];
+    let view_position = uniforms.view * cloud.model * vec4<f32>(in.position, 1.0);
+
+    var out: VertexOutput;
+    if (cloud.sizing == SIZING_WORLD) {
+        // World sized points are expanded in view space, so perspective shrinks them with distance
+        let offset = corner * cloud.size * 0.5;
+        out.clip_position = uniforms.projection * vec4<f32>(view_position.xy + offset, view_position.zw);
+    } else {
+        // Screen sized points are expanded after projection, scaled by w to undo the perspective divide
+        let clip_position = uniforms.projection * view_position;
+        let offset = corner * cloud.size / uniforms.viewport_size.xy * clip_position.w;
+        out.clip_position = vec4<f32>(clip_position.xy + offset, clip_position.zw);
+    }
+    out.corner = corner;
+    out.color = in.color * cloud.color;
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    if (cloud.shape == SHAPE_ROUND && dot(in.corner, in.corner) > 1.0) {
+        discard;
+    }
+    return in.color;
+}
@@ -0,0 +1,138 @@
+struct VertexInput {
+    @location(0) corner: vec2<f32>,
+    // The points before, at the start of, at the end of and after the segment.
+    // Neighbors repeat the segment's own points at the ends of the polyline.
+    @location(1) previous: vec3<f32>,
+    @location(2) start: vec3<f32>,
+    @location(3) end: vec3<f32>,
+    @location(4) next: vec3<f32>,
+    @location(5) start_color: vec4<f32>,
+    @location(6) end_color: vec4<f32>,
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) color: vec4<f32>,
+    // Pixels across the polyline from its center
+    @location(1) across: f32,
+};
+
+struct Uniforms {
+    view_proj: mat4x4<f32>,
+    viewport_size: vec4<f32>,
+};
+
+struct Polyline {
+    model: mat4x4<f32>,
+    color: vec4<f32>,
+    width: f32,
+    padding_0: f32,
+    padding_1: f32,
+    padding_2: f32,
+};
+
+@group(0) @binding(0)
+var<uniform> uniforms: Uniforms;
+
+@group(1) @binding(0)
+var<uniform> polyline: Polyline;
+
+// The pixels added around a polyline for its anti-aliased edge to fade out in
+const FEATHER: f32 = 1.0;
+
+const NEAR_W: f32 = 0.0001;
+
+// Sharp joins are cut off where the miter gets longer than this many half widths
+const MITER_LIMIT: f32 = 2.0;
+
+fn to_clip(position: vec3<f32>) -> vec4<f32> {
+    return uniforms.view_proj * polyline.model * vec4<f32>(position, 1.0);
+}
+
+fn clip_to_near(point: vec4<f32>, other: vec4<f32>) -> vec4<f32> {
+    if (point.w >= NEAR_W) {
+        return point;
+    }
+    let t = (NEAR_W - point.w) / (other.w - point.w);
+    return mix(point, other, t);
+}
+
+fn to_pixels(clip: vec4<f32>) -> vec2<f32> {
+    return (clip.xy / clip.w * 0.5 + 0.5) * uniforms.viewport_size.xy;
+}
+
+fn direction_between(first: vec2<f32>, second: vec2<f32>, fallback: vec2<f32>) -> vec2<f32> {
+    let delta = second - first;
+    if (length(delta) < 0.0001) {
+        return fallback;
+    }
+    return normalize(delta);
+}
+
+@vertex
+fn vs_main(in: VertexInput) -> VertexOutput {
+    var out: VertexOutput;
+    let along = in.corner.x;
+    let side = in.corner.y;
+    out.color = mix(in.start_color, in.end_color, along) * polyline.color;
+
+    let start_clip = to_clip(in.start);
+    let end_clip = to_clip(in.end);
+    if (start_clip.w < NEAR_W && end_clip.w < NEAR_W) {
+        // Segments entirely behind the camera collapse to a point outside the depth range
+        out.clip_position = vec4<f32>(0.0, 0.0, -2.0, 1.0);
+        return out;
+    }
+    let start = clip_to_near(start_clip, end_clip);
+    let end = clip_to_near(end_clip, start_clip);
+    let start_pixels = to_pixels(start);
+    let end_pixels = to_pixels(end);
+    let direction = direction_between(start_pixels, end_pixels, vec2<f32>(1.0, 0.0));
+    let normal = vec2<f32>(-direction.y, direction.x);
+
+    // Each end of the segment is joined to the neighboring segment along their shared miter.
+    // Ends of the polyline, and neighbors behind the camera, are cut off square instead.
+    var point = start_pixels;
+    var neighbor_direction = direction;
+    if (along < 0.5) {
+        let previous_clip = to_clip(in.previous);
+        if (any(in.previous != in.start) && previous_clip.w >= NEAR_W) {
+            neighbor_direction = direction_between(to_pixels(previous_clip), start_pixels, direction);
+        }
+    } else {
+        point = end_pixels;
+        let next_clip = to_clip(in.next);
+        if (any(in.next != in.end) && next_clip.w >= NEAR_W) {
+            neighbor_direction = direction_between(end_pixels, to_pixels(next_clip), direction);
+        }
+    }
+    var tangent = neighbor_direction + direction;
+    if (length(tangent) < 0.0001) {
+        tangent = direction;
+    }
+    tangent = normalize(tangent);
+    let miter = vec2<f32>(-tangent.y, tangent.x);
+    let miter_scale = min(1.0 / max(dot(miter, normal), 0.0001), MITER_LIMIT);
+
+    let half_width = max(polyline.width, 1.0) * 0.5 + FEATHER;
+    let pixels = point + miter * side * half_width * miter_scale;
+
+    // Depth is linear in screen space after the perspective divide, so w can be dropped
+    let depth = mix(start.z / start.w, end.z / end.w, along);
+    let ndc = pixels / uniforms.viewport_size.xy * 2.0 - 1.0;
+    out.clip_position = vec4<f32>(ndc, depth, 1.0);
+    // The miter corner is as far from the center of the segment as its edge is
+    out.across = side * half_width * miter_scale * dot(miter, normal);
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    // Polylines thinner than a pixel are drawn a pixel wide and faded instead
+    let half_width = max(polyline.width, 1.0) * 0.5;
+    let coverage = clamp(half_width + 0.5 - abs(in.across), 0.0, 1.0) * min(polyline.width, 1.0);
+    if (coverage <= 0.0) {
+        discard;
+    }
+    return vec4<f32>(in.color.rgb, in.color.a * coverage);
+}
@@ -1,10 +1,29 @@
+// Binding 0 is the texture each pass reads: the scene, a level of bloom or the composite
+@group(0) @binding(0) var source_texture: texture_2d<f32>;
+@group(0) @binding(1) var source_sampler: sampler;
 
-@group(0) @binding(0) var scene_texture: texture_2d<f32>;
-@group(0) @binding(1) var scene_sampler: sampler;
+// The pane covers the top left of each texture, so texture coordinates are scaled to its area
+struct PostProcess {
+    texel_size: vec2<f32>,
+    uv_scale: vec2<f32>,
+    bloom_texel_size: vec2<f32>,
+    bloom_uv_scale: vec2<f32>,
+    // x: threshold, y: intensity, which is zero without bloom
+    bloom: vec4<f32>,
+    outline_color: vec4<f32>,
+    // x: width in pixels, which is zero without outlines
+    outline: vec4<f32>,
+}
+
+@group(0) @binding(2) var<uniform> post_process: PostProcess;
+@group(0) @binding(3) var bloom_texture: texture_2d<f32>;
+@group(0) @binding(4) var outline_mask: texture_2d<f32>;
 
 struct VertexOutput {
     @builtin(position) position: vec4<f32>,
     @location(0) uv: vec2<f32>,
+    // The position across the pane, from zero to one
+    @location(1) pane_uv: vec2<f32>,
 }
 
 @vertex
@@ -25,15 +44,248 @@ fn vs_main(@builtin(vertex_index) vertex_index: u32) -> VertexOutput {
     }
 
     output.position = vec4<f32>(pos, 0.0, 1.0);
-    output.uv = pos * vec2<f32>(0.5, -0.5) + 0.5;
+    output.pane_uv = pos * vec2<f32>(0.5, -0.5) + 0.5;
+    output.uv = output.pane_uv * post_process.uv_scale;
 
     return output;
 }
 
+// Samples the source without reading past the edges of the pane
+fn source(uv: vec2<f32>) -> vec3<f32> {
+    let half_texel = post_process.texel_size * 0.5;
+    let clamped = clamp(uv, half_texel, post_process.uv_scale - half_texel);
+    return textureSampleLevel(source_texture, source_sampler, clamped, 0.0).rgb;
+}
+
+fn bloom(pane_uv: vec2<f32>) -> vec3<f32> {
+    let half_texel = post_process.bloom_texel_size * 0.5;
+    let uv = pane_uv * post_process.bloom_uv_scale;
+    let clamped = clamp(uv, half_texel, post_process.bloom_uv_scale - half_texel);
+    return textureSampleLevel(bloom_texture, source_sampler, clamped, 0.0).rgb;
+}
+
+// Outside the pane there is nothing to outline
+fn outline_coverage(uv: vec2<f32>) -> f32 {
+    if any(uv < vec2<f32>(0.0)) || any(uv > post_process.uv_scale) {
+        return 0.0;
+    }
+    return textureSampleLevel(outline_mask, source_sampler, uv, 0.0).r;
+}
+
+// The 13 tap downsample from Call of Duty: Advanced Warfare,
+// which weighs overlapping boxes of four texels to avoid flickering
+fn downsample(uv: vec2<f32>) -> vec3<f32> {
+    let t = post_process.texel_size;
+    let a = source(uv + t * vec2<f32>(-2.0, -2.0));
+    let b = source(uv + t * vec2<f32>(0.0, -2.0));
+    let c = source(uv + t * vec2<f32>(2.0, -2.0));
+    let d = source(uv + t * vec2<f32>(-2.0, 0.0));
+    let e = source(uv);
+    let f = source(uv + t * vec2<f32>(2.0, 0.0));
+    let g = source(uv + t * vec2<f32>(-2.0, 2.0));
+    let h = source(uv + t * vec2<f32>(0.0, 2.0));
+    let i = source(uv + t * vec2<f32>(2.0, 2.0));
+    let j = source(uv + t * vec2<f32>(-1.0, -1.0));
+    let k = source(uv + t * vec2<f32>(1.0, -1.0));
+    let l = source(uv + t * vec2<f32>(-1.0, 1.0));
+    let m = source(uv + t * vec2<f32>(1.0, 1.0));
+    return e * 0.125
+        + (a + c + g + i) * 0.03125
+        + (b + d + f + h) * 0.0625
+        + (j + k + l + m) * 0.125;
+}
+
+// Keeps the part of each color brighter than the threshold
 @fragment
-fn fs_main(input: VertexOutput) -> @location(0) vec4<f32> {
-    let r = textureSample(scene_texture, scene_sampler, input.uv).r;
-    let g = textureSample(scene_texture, scene_sampler, input.uv).g;
-    let b = textureSample(scene_texture, scene_sampler, input.uv).b;
-    return vec4<f32>(r, g, b, 1.0);
+fn fs_prefilter(input: VertexOutput) -> @location(0) vec4<f32> {
+    let color = downsample(input.uv);
+    let brightness = max(color.r, max(color.g, color.b));
+    let contribution = max(brightness - post_process.bloom.x, 0.0) / max(brightness, 0.0001);
+    return vec4<f32>(color * contribution, 1.0);
+}
+
+@fragment
+fn fs_downsample(input: VertexOutput) -> @location(0) vec4<f32> {
+    return vec4<f32>(downsample(input.uv), 1.0);
+}
+
+// A 3x3 tent filter, added onto the larger level by the pipeline's blending
+@fragment
+fn fs_upsample(input: VertexOutput) -> @location(0) vec4<f32> {
+    let t = post_process.texel_size;
+    let uv = input.uv;
+    let color = source(uv) * 4.0
+        + (source(uv + vec2<f32>(-t.x, 0.0))
+            + source(uv + vec2<f32>(t.x, 0.0))
+            + source(uv + vec2<f32>(0.0, -t.y))
+            + source(uv + vec2<f32>(0.0, t.y))) * 2.0
+        + source(uv + vec2<f32>(-t.x, -t.y))
+        + source(uv + vec2<f32>(t.x, -t.y))
+        + source(uv + vec2<f32>(-t.x, t.y))
+        + source(uv + vec2<f32>(t.x, t.y));
+    return vec4<f32>(color / 16.0, 1.0);
+}
+
+const OUTLINE_DIRECTIONS: i32 = 16;
+
+// Adds bloom to the scene, then draws outlines where the mask spreads past its own edge
+@fragment
+fn fs_composite(input: VertexOutput) -> @location(0) vec4<f32> {
+    var color = source(input.uv);
+    if post_process.bloom.y > 0.0 {
+        color += bloom(input.pane_uv) * post_process.bloom.y;
+    }
+
+    let width = post_process.outline.x;
+    if width > 0.0 {
+        // The greatest coverage on rings at the outline's width and halfway to it,
+        // so thin entities are not stepped over
+        var neighbors = 0.0;
+        for (var i = 0; i < OUTLINE_DIRECTIONS; i++) {
+            let angle = f32(i) * 6.2831853 / f32(OUTLINE_DIRECTIONS);
+            let direction = vec2<f32>(cos(angle), sin(angle)) * post_process.texel_size;
+            neighbors = max(neighbors, outline_coverage(input.uv + direction * width));
+            neighbors = max(neighbors, outline_coverage(input.uv + direction * width * 0.5));
+        }
+        let outline = neighbors * (1.0 - outline_coverage(input.uv)) * post_process.outline_color.a;
+        color = mix(color, post_process.outline_color.rgb, outline);
+    }
+
+    return vec4<f32>(color, 1.0);
+}
+
+fn luma(color: vec3<f32>) -> f32 {
+    return dot(color, vec3<f32>(0.299, 0.587, 0.114));
+}
+
+fn source_luma(uv: vec2<f32>) -> f32 {
+    return luma(source(uv));
+}
+
+const EDGE_THRESHOLD_MIN: f32 = 0.0312;
+const EDGE_THRESHOLD_MAX: f32 = 0.125;
+const SUBPIXEL_QUALITY: f32 = 0.75;
+const SEARCH_STEPS: i32 = 12;
+
+// FXAA 3.11: finds edges from the contrast of each pixel with its neighbours,
+// searches along them for their ends and blends across them by how far the pixel is from an end
+@fragment
+fn fs_fxaa(input: VertexOutput) -> @location(0) vec4<f32> {
+    let uv = input.uv;
+    let texel = post_process.texel_size;
+    let center = source(uv);
+
+    // Down and up are towards smaller and larger texture coordinates
+    let luma_center = luma(center);
+    let luma_down = source_luma(uv + vec2<f32>(0.0, -texel.y));
+    let luma_up = source_luma(uv + vec2<f32>(0.0, texel.y));
+    let luma_left = source_luma(uv + vec2<f32>(-texel.x, 0.0));
+    let luma_right = source_luma(uv + vec2<f32>(texel.x, 0.0));
+
+    let luma_min = min(luma_center, min(min(luma_down, luma_up), min(luma_left, luma_right)));
+    let luma_max = max(luma_center, max(max(luma_down, luma_up), max(luma_left, luma_right)));
+    let luma_range = luma_max - luma_min;
+    if luma_range < max(EDGE_THRESHOLD_MIN, luma_max * EDGE_THRESHOLD_MAX) {
+        return vec4<f32>(center, 1.0);
+    }
+
+    let luma_down_left = source_luma(uv + vec2<f32>(-texel.x, -texel.y));
+    let luma_up_right = source_luma(uv + vec2<f32>(texel.x, texel.y));
+    let luma_up_left = source_luma(uv + vec2<f32>(-texel.x, texel.y));
+    let luma_down_right = source_luma(uv + vec2<f32>(texel.x, -texel.y));
+
+    let luma_down_up = luma_down + luma_up;
+    let luma_left_right = luma_left + luma_right;
+    let luma_left_corners = luma_down_left + luma_up_left;
+    let luma_down_corners = luma_down_left + luma_down_right;
+    let luma_right_corners = luma_down_right + luma_up_right;
+    let luma_up_corners = luma_up_right + luma_up_left;
+
+    let edge_horizontal = abs(-2.0 * luma_left + luma_left_corners)
+        + abs(-2.0 * luma_center + luma_down_up) * 2.0
+        + abs(-2.0 * luma_right + luma_right_corners);
+    let edge_vertical = abs(-2.0 * luma_up + luma_up_corners)
+        + abs(-2.0 * luma_center + luma_left_right) * 2.0
+        + abs(-2.0 * luma_down + luma_down_corners);
+    let is_horizontal = edge_horizontal >= edge_vertical;
+
+    // The side of the edge with the steepest gradient is the one blended towards
+    let luma_1 = select(luma_left, luma_down, is_horizontal);
+    let luma_2 = select(luma_right, luma_up, is_horizontal);
+    let gradient_1 = luma_1 - luma_center;
+    let gradient_2 = luma_2 - luma_center;
+    let is_1_steepest = abs(gradient_1) >= abs(gradient_2);
+    let gradient_scaled = 0.25 * max(abs(gradient_1), abs(gradient_2));
+
+    var step_length = select(texel.x, texel.y, is_horizontal);
+    var luma_local_average: f32;
+    if is_1_steepest {
+        step_length = -step_length;
+        luma_local_average = 0.5 * (luma_1 + luma_center);
+    } else {
+        luma_local_average = 0.5 * (luma_2 + luma_center);
+    }
+
+    // Search in both directions along the edge, from halfway between the pixel and its neighbour
+    var edge_uv = uv;
+    if is_horizontal {
+        edge_uv.y += step_length * 0.5;
+    } else {
+        edge_uv.x += step_length * 0.5;
+    }
+    let offset = select(vec2<f32>(0.0, texel.y), vec2<f32>(texel.x, 0.0), is_horizontal);
+
+    var steps = array<f32, 12>(1.0, 1.0, 1.0, 1.0, 1.0, 1.5, 2.0, 2.0, 2.0, 2.0, 4.0, 8.0);
+    var uv_1 = edge_uv - offset;
+    var uv_2 = edge_uv + offset;
+    var luma_end_1 = 0.0;
+    var luma_end_2 = 0.0;
+    var reached_1 = false;
+    var reached_2 = false;
+    for (var i = 0; i < SEARCH_STEPS; i++) {
+        if !reached_1 {
+            luma_end_1 = source_luma(uv_1) - luma_local_average;
+            reached_1 = abs(luma_end_1) >= gradient_scaled;
+        }
+        if !reached_2 {
+            luma_end_2 = source_luma(uv_2) - luma_local_average;
+            reached_2 = abs(luma_end_2) >= gradient_scaled;
+        }
+        if reached_1 && reached_2 {
+            break;
+        }
+        if !reached_1 {
+            uv_1 -= offset * steps[i];
+        }
+        if !reached_2 {
+            uv_2 += offset * steps[i];
+        }
+    }
+
+    let distance_1 = select(uv.y - uv_1.y, uv.x - uv_1.x, is_horizontal);
+    let distance_2 = select(uv_2.y - uv.y, uv_2.x - uv.x, is_horizontal);
+    let is_direction_1 = distance_1 < distance_2;
+    let distance_final = min(distance_1, distance_2);
+    let edge_thickness = distance_1 + distance_2;
+
+    // Only blend when the end of the edge that is closest varies the same way as the pixel
+    let is_luma_center_smaller = luma_center < luma_local_average;
+    let luma_end = select(luma_end_2, luma_end_1, is_direction_1);
+    let correct_variation = (luma_end < 0.0) != is_luma_center_smaller;
+    var final_offset = select(0.0, 0.5 - distance_final / edge_thickness, correct_variation);
+
+    // Pixels thinner than an edge are blended by their contrast with their neighbourhood
+    let luma_average = (1.0 / 12.0)
+        * (2.0 * (luma_down_up + luma_left_right) + luma_left_corners + luma_right_corners);
+    let subpixel_1 = clamp(abs(luma_average - luma_center) / luma_range, 0.0, 1.0);
+    let subpixel_2 = (-2.0 * subpixel_1 + 3.0) * subpixel_1 * subpixel_1;
+    final_offset = max(final_offset, subpixel_2 * subpixel_2 * SUBPIXEL_QUALITY);
+
+    var final_uv = uv;
+    if is_horizontal {
+        final_uv.y += final_offset * step_length;
+    } else {
+        final_uv.x += final_offset * step_length;
+    }
+    return vec4<f32>(source(final_uv), 1.0);
 }
@@ -19,9 +19,18 @@ struct Uniforms {
 @group(0) @binding(0)
 var<uniform> uniforms: Uniforms;
 
+// The model matrix of the entity being drawn, applied here rather than on the CPU
+struct Model {
+    matrix: mat4x4<f32>,
+    normal_matrix: mat4x4<f32>,
+};
+
+@group(1) @binding(0)
+var<uniform> model: Model;
+
 @vertex
 fn vs_main(in: VertexInput) -> VertexOutput {
-    let model = mat4x4<f32>(
+    let instance = mat4x4<f32>(
         in.model_matrix_0,
         in.model_matrix_1,
         in.model_matrix_2,
@@ -29,7 +38,7 @@ fn vs_main(in: VertexInput) -> VertexOutput {
     );
 
     var out: VertexOutput;
-    out.clip_position = uniforms.view_proj * model * vec4<f32>(in.position, 1.0);
+    out.clip_position = uniforms.view_proj * model.matrix * instance * vec4<f32>(in.position, 1.0);
     out.color = in.color;
     return out;
 }
@@ -38,3 +47,9 @@ fn vs_main(in: VertexInput) -> VertexOutput {
 fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
     return in.color;
 }
+
+// Marks the quads of outlined entities in the outline mask
+@fragment
+fn fs_outline(in: VertexOutput) -> @location(0) vec4<f32> {
+    return vec4<f32>(1.0);
+}
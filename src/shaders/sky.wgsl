@@ -3,6 +3,12 @@ struct Uniform {
     proj_inv: mat4x4<f32>,
     view: mat4x4<f32>,
     cam_pos: vec4<f32>,
+    // x: operator (0 none, 1 exponential, 2 reinhard, 3 aces), y: exposure, z: gamma
+    tonemapping: vec4<f32>,
+    // x: 0 for the environment map, 1 for a gradient and 2 for a solid color
+    background: vec4<f32>,
+    top: vec4<f32>,
+    bottom: vec4<f32>,
 };
 
 @group(0) @binding(0)
@@ -40,22 +46,50 @@ fn vs_sky(@builtin(vertex_index) vertex_index: u32) -> VertexOutput {
     return result;
 }
 
+// Maps the sky's radiance into the displayable range, leaving it linear when tonemapping is off
 fn tone_map(hdr: vec3<f32>) -> vec3<f32> {
-    let exposure = 1.0;
-    let gamma = 2.2;
-    
-    // Apply exposure
-    var color = vec3<f32>(1.0) - exp(-hdr * exposure);
-    
-    // Apply gamma correction
-    color = pow(color, vec3<f32>(1.0 / gamma));
-    
-    return color;
+    let curve = u32(u.tonemapping.x);
+    if curve == 0u {
+        return hdr;
+    }
+
+    let exposed = hdr * u.tonemapping.y;
+    var color: vec3<f32>;
+    switch curve {
+        case 2u: {
+            color = exposed / (vec3<f32>(1.0) + exposed);
+        }
+        case 3u: {
+            // Narkowicz's fit of the ACES filmic curve
+            color = clamp(
+                (exposed * (2.51 * exposed + 0.03)) / (exposed * (2.43 * exposed + 0.59) + 0.14),
+                vec3<f32>(0.0),
+                vec3<f32>(1.0),
+            );
+        }
+        default: {
+            color = vec3<f32>(1.0) - exp(-exposed);
+        }
+    }
+
+    return pow(color, vec3<f32>(1.0 / u.tonemapping.z));
 }
 
+// Gradients and solid colors are display colors already, so only the environment map is tonemapped
 @fragment
 fn fs_sky(in: VertexOutput) -> @location(0) vec4<f32> {
-    let hdr = textureSample(t_diffuse, s_diffuse, normalize(in.uv)).rgb;
-    let color = tone_map(hdr);
-    return vec4<f32>(color, 1.0);
+    let direction = normalize(in.uv);
+    let hdr = textureSample(t_diffuse, s_diffuse, direction).rgb;
+    switch u32(u.background.x) {
+        case 1u: {
+            let blend = clamp(direction.y * 0.5 + 0.5, 0.0, 1.0);
+            return vec4<f32>(mix(u.bottom.rgb, u.top.rgb, blend), 1.0);
+        }
+        case 2u: {
+            return vec4<f32>(u.top.rgb, 1.0);
+        }
+        default: {
+            return vec4<f32>(tone_map(hdr), 1.0);
+        }
+    }
 }
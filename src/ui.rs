@@ -19,6 +19,10 @@ pub struct UserInterface {
     pub dragging_viewport: Option<(egui_tiles::TileId, egui::Pos2)>,
     pub api_log: Vec<ApiLogEntry>,
     pub draft_message: Message,
+    pub import_path: String,
+
+    /// The path typed into the environment settings of a pane, before it is loaded
+    pub environment_path: String,
 }
 
 /// A context shared between all the panes in the tile tree
@@ -27,6 +31,9 @@ pub struct TileTreeContext {
     pub tile_rects: std::collections::HashMap<egui_tiles::TileId, egui::Rect>,
     pub add_child_to: Option<egui_tiles::TileId>,
     pub viewport_tiles: std::collections::HashMap<egui_tiles::TileId, (PaneKind, egui::Rect)>,
+
+    /// The settings of each scene pane shown this frame, which the renderer reads
+    pub pane_settings: std::collections::HashMap<egui_tiles::TileId, crate::api::PaneSettings>,
     pub selected_tile: Option<egui_tiles::TileId>,
     pub tile_mapping: std::collections::HashMap<egui_tiles::TileId, usize>,
     pub context: Option<*mut crate::context::Context>,
@@ -52,6 +59,7 @@ impl Default for PaneKind {
 #[derive(Default, Debug, Clone, PartialEq)]
 pub struct Pane {
     pub kind: PaneKind,
+    pub settings: crate::api::PaneSettings,
 }
 
 impl egui_tiles::Behavior<crate::ui::Pane> for crate::ui::TileTreeContext {
@@ -124,6 +132,7 @@ impl egui_tiles::Behavior<crate::ui::Pane> for crate::ui::TileTreeContext {
 
         if matches!(pane.kind, PaneKind::Scene { .. } | PaneKind::Color(_)) {
             self.viewport_tiles.insert(tile_id, (pane.kind, rect));
+            self.pane_settings.insert(tile_id, pane.settings.clone());
         }
 
         if let Some(Some(context)) = self.context.as_mut().map(|ctx| unsafe { ctx.as_mut() }) {
@@ -282,6 +291,19 @@ impl egui_tiles::Behavior<crate::ui::Pane> for crate::ui::TileTreeContext {
                                                         .wrap(),
                                                     );
 
+                                                    // Show the connection, if any
+                                                    if let Some(connection) = &entry.connection {
+                                                        ui.label(
+                                                            egui::RichText::new(format!(
+                                                                "[{connection}]"
+                                                            ))
+                                                            .monospace()
+                                                            .color(egui::Color32::from_rgb(
+                                                                255, 200, 130,
+                                                            )),
+                                                        );
+                                                    }
+
                                                     // Show message content
                                                     ui.with_layout(
                                                         egui::Layout::left_to_right(
@@ -505,6 +527,86 @@ impl egui_tiles::Behavior<crate::ui::Pane> for crate::ui::TileTreeContext {
                             }
                         });
                 }
+
+                // Rendering settings for Scene panes
+                if matches!(pane.kind, PaneKind::Scene { .. }) {
+                    let sample_counts = context
+                        .resources
+                        .graphics
+                        .renderer
+                        .as_ref()
+                        .map(|renderer| renderer.gpu.sample_counts.clone())
+                        .unwrap_or_else(|| vec![1]);
+                    ui.menu_button("⚙", |ui| {
+                        ui.label("MSAA");
+                        for sample_count in sample_counts {
+                            let text = match sample_count {
+                                1 => "Off".to_string(),
+                                _ => format!("{sample_count}x"),
+                            };
+                            ui.radio_value(&mut pane.settings.msaa_samples, sample_count, text);
+                        }
+                        ui.separator();
+                        ui.checkbox(&mut pane.settings.fxaa, "FXAA");
+                        ui.separator();
+                        environment_ui(
+                            ui,
+                            &mut pane.settings.environment,
+                            &mut context.resources.user_interface.environment_path,
+                        );
+                        ui.separator();
+                        post_process_ui(ui, &mut pane.settings);
+                    })
+                    .response
+                    .on_hover_text("Rendering settings of this pane");
+                }
+
+                #[cfg(not(target_arch = "wasm32"))]
+                if let (PaneKind::Scene { .. } | PaneKind::Color(_), Some(index)) =
+                    (pane.kind, self.tile_mapping.get(&tile_id).copied())
+                {
+                    if ui.button("📷").on_hover_text("Save a PNG of this pane").clicked() {
+                        publish_command(
+                            context,
+                            crate::api::Command::Capture {
+                                id: 0,
+                                target: crate::api::CaptureTarget::Pane { index },
+                                output: crate::api::CaptureOutput::File {
+                                    path: format!("{}.png", capture_name(&format!("pane-{index}"))),
+                                },
+                            },
+                        );
+                    }
+
+                    let is_recording = context.resources.graphics.recording.as_ref().is_some_and(
+                        |recording| {
+                            matches!(
+                                recording.target,
+                                crate::api::CaptureTarget::Pane { index: recorded } if recorded == index
+                            )
+                        },
+                    );
+                    let command = if is_recording {
+                        ui.button("⏹")
+                            .on_hover_text("Stop recording this pane")
+                            .clicked()
+                            .then_some(crate::api::RecordCommand::Stop)
+                    } else {
+                        ui.button("⏺")
+                            .on_hover_text("Record this pane to a PNG sequence at 60 frames per second")
+                            .clicked()
+                            .then(|| crate::api::RecordCommand::Start {
+                                id: 0,
+                                target: crate::api::CaptureTarget::Pane { index },
+                                directory: capture_name(&format!("pane-{index}")),
+                                frames_per_second: 60.0,
+                                frame_count: 0,
+                            })
+                    };
+                    if let Some(command) = command {
+                        publish_command(context, crate::api::Command::Record { command });
+                    }
+                }
             });
 
             // Draw selection border
@@ -552,6 +654,7 @@ impl egui_tiles::Behavior<crate::ui::Pane> for crate::ui::TileTreeContext {
         if let Some(egui_tiles::Tile::Pane(_)) = tiles.remove(tile_id) {
             // Clean up any viewport data for this tile
             self.viewport_tiles.remove(&tile_id);
+            self.pane_settings.remove(&tile_id);
             self.tile_rects.remove(&tile_id);
             self.tile_mapping.remove(&tile_id);
             if self.selected_tile == Some(tile_id) {
@@ -660,6 +763,7 @@ pub fn ensure_tile_tree_system(context: &mut crate::context::Context) {
 
     let tab_tile_child = tiles.insert_pane(Pane {
         kind: PaneKind::Empty,
+        ..Default::default()
     });
     let tab_tile = tiles.insert_tab_tile(vec![tab_tile_child]);
     tab_tiles.push(tab_tile);
@@ -704,6 +808,27 @@ fn create_ui(context: &mut crate::context::Context, ui: &egui::Context) {
     top_panel_ui(context, ui);
     left_panel_ui(context, ui);
     central_panel_ui(context, ui);
+    import_dropped_files(context, ui);
+}
+
+/// Imports model files dropped onto the window
+fn import_dropped_files(context: &mut crate::context::Context, ui: &egui::Context) {
+    use crate::api::{publish_command, Command, FileBytes, ImportSource};
+
+    let dropped_files = ui.input(|input| input.raw.dropped_files.clone());
+    for file in dropped_files {
+        let source = match (file.path, file.bytes) {
+            (Some(path), _) => ImportSource::File {
+                path: path.to_string_lossy().to_string(),
+            },
+            (None, Some(bytes)) => ImportSource::Bytes {
+                name: file.name,
+                bytes: FileBytes(bytes),
+            },
+            (None, None) => continue,
+        };
+        publish_command(context, Command::Import { id: 0, source });
+    }
 }
 
 fn central_panel_ui(context: &mut crate::context::Context, ui: &egui::Context) {
@@ -716,6 +841,12 @@ fn central_panel_ui(context: &mut crate::context::Context, ui: &egui::Context) {
                 .tile_tree_context
                 .viewport_tiles
                 .clear();
+            context
+                .resources
+                .user_interface
+                .tile_tree_context
+                .pane_settings
+                .clear();
             context
                 .resources
                 .user_interface
@@ -755,6 +886,7 @@ fn central_panel_ui(context: &mut crate::context::Context, ui: &egui::Context) {
                 ) {
                     let new_pane = Pane {
                         kind: PaneKind::Empty,
+                        ..Default::default()
                     };
 
                     let new_child = tile_tree.tiles.insert_pane(new_pane);
@@ -802,6 +934,26 @@ fn entity_inspector_ui(
                 {
                     add_components(context, entity, QUADS);
                 }
+                if get_component::<Mesh>(context, entity, MESH).is_none()
+                    && ui.button("Mesh").clicked()
+                {
+                    add_components(context, entity, MESH);
+                }
+                if get_component::<Points>(context, entity, POINTS).is_none()
+                    && ui.button("Points").clicked()
+                {
+                    add_components(context, entity, POINTS);
+                }
+                if get_component::<Polyline>(context, entity, POLYLINE).is_none()
+                    && ui.button("Polyline").clicked()
+                {
+                    add_components(context, entity, POLYLINE);
+                }
+                if get_component::<Label>(context, entity, LABEL).is_none()
+                    && ui.button("Label").clicked()
+                {
+                    add_components(context, entity, LABEL);
+                }
             });
         });
     });
@@ -833,6 +985,26 @@ fn entity_inspector_ui(
         quads_inspector_ui(context, ui, entity);
         ui.separator();
     }
+
+    if get_component::<Mesh>(context, entity, MESH).is_some() {
+        mesh_inspector_ui(context, ui, entity);
+        ui.separator();
+    }
+
+    if get_component::<Points>(context, entity, POINTS).is_some() {
+        points_inspector_ui(context, ui, entity);
+        ui.separator();
+    }
+
+    if get_component::<Polyline>(context, entity, POLYLINE).is_some() {
+        polyline_inspector_ui(context, ui, entity);
+        ui.separator();
+    }
+
+    if get_component::<Label>(context, entity, LABEL).is_some() {
+        label_inspector_ui(context, ui, entity);
+        ui.separator();
+    }
 }
 
 fn name_inspector_ui(
@@ -844,8 +1016,10 @@ fn name_inspector_ui(
 
     ui.group(|ui| {
         ui.label("Name");
-        if let Some(Name(name)) = get_component_mut::<Name>(context, entity, NAME) {
-            ui.text_edit_singleline(name);
+        if let Some(Name(name)) = get_component_mut_untracked::<Name>(context, entity, NAME) {
+            if ui.text_edit_singleline(name).changed() {
+                mark_component_changed(context, entity, NAME);
+            }
             if ui.button("Remove Component").clicked() {
                 remove_components(context, entity, NAME);
             }
@@ -862,7 +1036,9 @@ fn lines_inspector_ui(
 
     ui.group(|ui| {
         ui.label("Lines");
-        if let Some(Lines(lines)) = get_component_mut::<Lines>(context, entity, LINES) {
+        // Inspectors draw every frame, so changes are only recorded when a widget changes a line
+        let mut changed = false;
+        if let Some(Lines(lines)) = get_component_mut_untracked::<Lines>(context, entity, LINES) {
             let mut lines_to_remove = Vec::new();
             for (index, line) in lines.iter_mut().enumerate() {
                 ui.group(|ui| {
@@ -877,11 +1053,17 @@ fn lines_inspector_ui(
                         ui.horizontal(|ui| {
                             ui.label("Start:");
                             ui.label("x");
-                            ui.add(egui::DragValue::new(&mut line.start.x).speed(0.1));
+                            changed |= ui
+                                .add(egui::DragValue::new(&mut line.start.x).speed(0.1))
+                                .changed();
                             ui.label("y");
-                            ui.add(egui::DragValue::new(&mut line.start.y).speed(0.1));
+                            changed |= ui
+                                .add(egui::DragValue::new(&mut line.start.y).speed(0.1))
+                                .changed();
                             ui.label("z");
-                            ui.add(egui::DragValue::new(&mut line.start.z).speed(0.1));
+                            changed |= ui
+                                .add(egui::DragValue::new(&mut line.start.z).speed(0.1))
+                                .changed();
                         });
                     });
 
@@ -889,11 +1071,17 @@ fn lines_inspector_ui(
                         ui.horizontal(|ui| {
                             ui.label("End:");
                             ui.label("x");
-                            ui.add(egui::DragValue::new(&mut line.end.x).speed(0.1));
+                            changed |= ui
+                                .add(egui::DragValue::new(&mut line.end.x).speed(0.1))
+                                .changed();
                             ui.label("y");
-                            ui.add(egui::DragValue::new(&mut line.end.y).speed(0.1));
+                            changed |= ui
+                                .add(egui::DragValue::new(&mut line.end.y).speed(0.1))
+                                .changed();
                             ui.label("z");
-                            ui.add(egui::DragValue::new(&mut line.end.z).speed(0.1));
+                            changed |= ui
+                                .add(egui::DragValue::new(&mut line.end.z).speed(0.1))
+                                .changed();
                         });
                     });
 
@@ -907,6 +1095,7 @@ fn lines_inspector_ui(
                                 (line.color.w * 255.0) as u8,
                             );
                             if ui.color_edit_button_srgba(&mut color).changed() {
+                                changed = true;
                                 line.color.x = color.r() as f32 / 255.0;
                                 line.color.y = color.g() as f32 / 255.0;
                                 line.color.z = color.b() as f32 / 255.0;
@@ -914,23 +1103,68 @@ fn lines_inspector_ui(
                             }
                         });
                     });
+
+                    ui.group(|ui| {
+                        ui.horizontal(|ui| {
+                            ui.label("Width:");
+                            changed |= ui
+                                .add(
+                                    egui::DragValue::new(&mut line.width)
+                                        .speed(0.1)
+                                        .range(0.0..=f32::MAX),
+                                )
+                                .changed();
+                            ui.label("Cap:");
+                            for (cap, name) in [
+                                (crate::api::LineCap::Butt, "Butt"),
+                                (crate::api::LineCap::Square, "Square"),
+                                (crate::api::LineCap::Round, "Round"),
+                            ] {
+                                changed |= ui.selectable_value(&mut line.cap, cap, name).changed();
+                            }
+                        });
+                        ui.horizontal(|ui| {
+                            let mut dashed = line.dash.is_some();
+                            if ui.checkbox(&mut dashed, "Dashed").changed() {
+                                changed = true;
+                                line.dash = dashed.then(|| nalgebra_glm::vec2(8.0, 4.0));
+                            }
+                            if let Some(dash) = line.dash.as_mut() {
+                                ui.label("dash");
+                                changed |= ui
+                                    .add(egui::DragValue::new(&mut dash.x).speed(0.1))
+                                    .changed();
+                                ui.label("gap");
+                                changed |= ui
+                                    .add(egui::DragValue::new(&mut dash.y).speed(0.1))
+                                    .changed();
+                            }
+                        });
+                    });
                 });
             }
 
             // Remove any lines marked for deletion (in reverse order to maintain indices)
+            changed |= !lines_to_remove.is_empty();
             for index in lines_to_remove.into_iter().rev() {
                 lines.remove(index);
             }
 
             // Add new line button
             if ui.button("Add Line").clicked() {
+                changed = true;
                 lines.push(Line {
                     start: nalgebra_glm::vec3(0.0, 0.0, 0.0),
                     end: nalgebra_glm::vec3(1.0, 1.0, 1.0),
                     color: nalgebra_glm::vec4(1.0, 1.0, 1.0, 1.0),
+                    ..Default::default()
                 });
             }
 
+            if changed {
+                mark_component_changed(context, entity, LINES);
+            }
+
             if ui.button("Remove").clicked() {
                 remove_components(context, entity, LINES);
             }
@@ -947,7 +1181,8 @@ pub fn quads_inspector_ui(
 
     ui.group(|ui| {
         ui.label("Quads");
-        if let Some(Quads(quads)) = get_component_mut::<Quads>(context, entity, QUADS) {
+        let mut changed = false;
+        if let Some(Quads(quads)) = get_component_mut_untracked::<Quads>(context, entity, QUADS) {
             // Show existing quads with edit/delete capabilities
             let mut quads_to_remove = Vec::new();
             for (index, quad) in quads.iter_mut().enumerate() {
@@ -963,9 +1198,13 @@ pub fn quads_inspector_ui(
                         ui.horizontal(|ui| {
                             ui.label("Size:");
                             ui.label("width");
-                            ui.add(egui::DragValue::new(&mut quad.size.x).speed(0.1));
+                            changed |= ui
+                                .add(egui::DragValue::new(&mut quad.size.x).speed(0.1))
+                                .changed();
                             ui.label("height");
-                            ui.add(egui::DragValue::new(&mut quad.size.y).speed(0.1));
+                            changed |= ui
+                                .add(egui::DragValue::new(&mut quad.size.y).speed(0.1))
+                                .changed();
                         });
                     });
 
@@ -973,11 +1212,17 @@ pub fn quads_inspector_ui(
                         ui.horizontal(|ui| {
                             ui.label("Offset:");
                             ui.label("x");
-                            ui.add(egui::DragValue::new(&mut quad.offset.x).speed(0.1));
+                            changed |= ui
+                                .add(egui::DragValue::new(&mut quad.offset.x).speed(0.1))
+                                .changed();
                             ui.label("y");
-                            ui.add(egui::DragValue::new(&mut quad.offset.y).speed(0.1));
+                            changed |= ui
+                                .add(egui::DragValue::new(&mut quad.offset.y).speed(0.1))
+                                .changed();
                             ui.label("z");
-                            ui.add(egui::DragValue::new(&mut quad.offset.z).speed(0.1));
+                            changed |= ui
+                                .add(egui::DragValue::new(&mut quad.offset.z).speed(0.1))
+                                .changed();
                         });
                     });
 
@@ -991,6 +1236,7 @@ pub fn quads_inspector_ui(
                                 (quad.color.w * 255.0) as u8,
                             );
                             if ui.color_edit_button_srgba(&mut color).changed() {
+                                changed = true;
                                 quad.color.x = color.r() as f32 / 255.0;
                                 quad.color.y = color.g() as f32 / 255.0;
                                 quad.color.z = color.b() as f32 / 255.0;
@@ -1002,12 +1248,14 @@ pub fn quads_inspector_ui(
             }
 
             // Remove any quads marked for deletion (in reverse order to maintain indices)
+            changed |= !quads_to_remove.is_empty();
             for index in quads_to_remove.into_iter().rev() {
                 quads.remove(index);
             }
 
             // Add new quad button
             if ui.button("Add Quad").clicked() {
+                changed = true;
                 quads.push(Quad {
                     size: nalgebra_glm::vec2(1.0, 1.0),
                     offset: nalgebra_glm::vec3(0.0, 0.0, 0.0),
@@ -1015,6 +1263,10 @@ pub fn quads_inspector_ui(
                 });
             }
 
+            if changed {
+                mark_component_changed(context, entity, QUADS);
+            }
+
             if ui.button("Remove Component").clicked() {
                 remove_components(context, entity, QUADS);
             }
@@ -1022,6 +1274,416 @@ pub fn quads_inspector_ui(
     });
 }
 
+fn mesh_inspector_ui(
+    context: &mut crate::context::Context,
+    ui: &mut egui::Ui,
+    entity: crate::context::EntityId,
+) {
+    use crate::context::*;
+
+    ui.group(|ui| {
+        ui.label("Mesh");
+        let mut changed = false;
+        if let Some(mesh) = get_component_mut_untracked::<Mesh>(context, entity, MESH) {
+            // Vertex data is set through the API, so only the appearance is editable here
+            ui.label(format!(
+                "{} vertices, {} triangles",
+                mesh.positions.len(),
+                mesh.indices.len() / 3
+            ));
+
+            ui.horizontal(|ui| {
+                ui.label("Color:");
+                changed |= color_edit_ui(ui, &mut mesh.color);
+            });
+
+            ui.horizontal(|ui| {
+                let mut wireframe = mesh.wireframe.is_some();
+                if ui.checkbox(&mut wireframe, "Wireframe").changed() {
+                    changed = true;
+                    mesh.wireframe = wireframe.then(|| nalgebra_glm::vec4(0.0, 0.0, 0.0, 1.0));
+                }
+                if let Some(color) = mesh.wireframe.as_mut() {
+                    changed |= color_edit_ui(ui, color);
+                }
+            });
+
+            if changed {
+                mark_component_changed(context, entity, MESH);
+            }
+
+            if ui.button("Remove Component").clicked() {
+                remove_components(context, entity, MESH);
+            }
+        }
+    });
+}
+
+fn points_inspector_ui(
+    context: &mut crate::context::Context,
+    ui: &mut egui::Ui,
+    entity: crate::context::EntityId,
+) {
+    use crate::api::{PointShape, PointSizing};
+    use crate::context::*;
+
+    ui.group(|ui| {
+        ui.label("Points");
+        let mut changed = false;
+        if let Some(points) = get_component_mut_untracked::<Points>(context, entity, POINTS) {
+            // Point data is set through the API, so only the appearance is editable here
+            ui.label(format!("{} points", points.positions.len()));
+
+            ui.horizontal(|ui| {
+                ui.label("Color:");
+                changed |= color_edit_ui(ui, &mut points.color);
+            });
+
+            ui.horizontal(|ui| {
+                ui.label("Size:");
+                let speed = match points.sizing {
+                    PointSizing::Screen => 0.1,
+                    PointSizing::World => 0.001,
+                };
+                changed |= ui
+                    .add(
+                        egui::DragValue::new(&mut points.size)
+                            .speed(speed)
+                            .range(0.0..=f32::MAX),
+                    )
+                    .changed();
+                changed |= ui
+                    .selectable_value(&mut points.sizing, PointSizing::Screen, "Pixels")
+                    .changed();
+                changed |= ui
+                    .selectable_value(&mut points.sizing, PointSizing::World, "World")
+                    .changed();
+            });
+
+            ui.horizontal(|ui| {
+                ui.label("Shape:");
+                changed |= ui
+                    .selectable_value(&mut points.shape, PointShape::Round, "Round")
+                    .changed();
+                changed |= ui
+                    .selectable_value(&mut points.shape, PointShape::Square, "Square")
+                    .changed();
+            });
+
+            if changed {
+                mark_component_changed(context, entity, POINTS);
+            }
+
+            if ui.button("Remove Component").clicked() {
+                remove_components(context, entity, POINTS);
+            }
+        }
+    });
+}
+
+fn polyline_inspector_ui(
+    context: &mut crate::context::Context,
+    ui: &mut egui::Ui,
+    entity: crate::context::EntityId,
+) {
+    use crate::context::*;
+
+    ui.group(|ui| {
+        ui.label("Polyline");
+        let mut changed = false;
+        if let Some(polyline) = get_component_mut_untracked::<Polyline>(context, entity, POLYLINE) {
+            // Points are set through the API, so only the appearance is editable here
+            ui.label(format!("{} points", polyline.points.len()));
+
+            ui.horizontal(|ui| {
+                ui.label("Color:");
+                changed |= color_edit_ui(ui, &mut polyline.color);
+            });
+
+            ui.horizontal(|ui| {
+                ui.label("Width:");
+                changed |= ui
+                    .add(
+                        egui::DragValue::new(&mut polyline.width)
+                            .speed(0.1)
+                            .range(0.0..=f32::MAX),
+                    )
+                    .changed();
+            });
+
+            if changed {
+                mark_component_changed(context, entity, POLYLINE);
+            }
+
+            if ui.button("Remove Component").clicked() {
+                remove_components(context, entity, POLYLINE);
+            }
+        }
+    });
+}
+
+fn label_inspector_ui(
+    context: &mut crate::context::Context,
+    ui: &mut egui::Ui,
+    entity: crate::context::EntityId,
+) {
+    use crate::api::LabelMode;
+    use crate::context::*;
+
+    ui.group(|ui| {
+        ui.label("Label");
+        let mut changed = false;
+        if let Some(label) = get_component_mut_untracked::<Label>(context, entity, LABEL) {
+            changed |= ui.text_edit_multiline(&mut label.text).changed();
+
+            ui.horizontal(|ui| {
+                ui.label("Offset:");
+                changed |= ui
+                    .add(egui::DragValue::new(&mut label.offset.x).speed(0.1))
+                    .changed();
+                changed |= ui
+                    .add(egui::DragValue::new(&mut label.offset.y).speed(0.1))
+                    .changed();
+                changed |= ui
+                    .add(egui::DragValue::new(&mut label.offset.z).speed(0.1))
+                    .changed();
+            });
+
+            ui.horizontal(|ui| {
+                ui.label("Size:");
+                let speed = match label.mode {
+                    LabelMode::Billboard => 0.1,
+                    LabelMode::World => 0.01,
+                };
+                changed |= ui
+                    .add(
+                        egui::DragValue::new(&mut label.size)
+                            .speed(speed)
+                            .range(0.0..=f32::MAX),
+                    )
+                    .changed();
+                changed |= ui
+                    .selectable_value(&mut label.mode, LabelMode::Billboard, "Billboard")
+                    .changed();
+                changed |= ui
+                    .selectable_value(&mut label.mode, LabelMode::World, "World")
+                    .changed();
+            });
+
+            ui.horizontal(|ui| {
+                ui.label("Color:");
+                changed |= color_edit_ui(ui, &mut label.color);
+            });
+
+            ui.horizontal(|ui| {
+                let mut background = label.background.is_some();
+                if ui.checkbox(&mut background, "Background").changed() {
+                    changed = true;
+                    label.background = background.then(|| nalgebra_glm::vec4(0.0, 0.0, 0.0, 0.6));
+                }
+                if let Some(color) = label.background.as_mut() {
+                    changed |= color_edit_ui(ui, color);
+                }
+            });
+
+            ui.horizontal(|ui| {
+                let mut leader = label.leader.is_some();
+                if ui.checkbox(&mut leader, "Leader Line").changed() {
+                    changed = true;
+                    label.leader = leader.then(|| nalgebra_glm::vec4(1.0, 1.0, 1.0, 1.0));
+                }
+                if let Some(color) = label.leader.as_mut() {
+                    changed |= color_edit_ui(ui, color);
+                }
+            });
+
+            if changed {
+                mark_component_changed(context, entity, LABEL);
+            }
+
+            if ui.button("Remove Component").clicked() {
+                remove_components(context, entity, LABEL);
+            }
+        }
+    });
+}
+
+/// Edits a color, returning whether it changed
+fn color_edit_ui(ui: &mut egui::Ui, color: &mut nalgebra_glm::Vec4) -> bool {
+    let mut color32 = egui::Color32::from_rgba_unmultiplied(
+        (color.x * 255.0) as u8,
+        (color.y * 255.0) as u8,
+        (color.z * 255.0) as u8,
+        (color.w * 255.0) as u8,
+    );
+    let changed = ui.color_edit_button_srgba(&mut color32).changed();
+    if changed {
+        color.x = color32.r() as f32 / 255.0;
+        color.y = color32.g() as f32 / 255.0;
+        color.z = color32.b() as f32 / 255.0;
+        color.w = color32.a() as f32 / 255.0;
+    }
+    changed
+}
+
+/// Chooses what is drawn behind the entities of a scene pane
+fn environment_ui(ui: &mut egui::Ui, environment: &mut crate::api::Environment, path: &mut String) {
+    use crate::api::Environment;
+
+    let name = |environment: &Environment| match environment {
+        Environment::Sky => "Sky",
+        Environment::HdrFile { .. } => "HDR File",
+        Environment::HdrBytes { .. } => "HDR Image",
+        Environment::Gradient { .. } => "Gradient",
+        Environment::Solid { .. } => "Solid Color",
+        Environment::None => "None",
+    };
+    egui::ComboBox::new("environment", "Environment")
+        .selected_text(name(environment))
+        .show_ui(ui, |ui| {
+            for option in [
+                Environment::Sky,
+                Environment::Gradient {
+                    top: [0.35, 0.5, 0.8, 1.0].into(),
+                    bottom: [0.12, 0.12, 0.14, 1.0].into(),
+                },
+                Environment::Solid {
+                    color: [0.12, 0.12, 0.14, 1.0].into(),
+                },
+                Environment::None,
+            ] {
+                let selected =
+                    std::mem::discriminant(environment) == std::mem::discriminant(&option);
+                if ui.selectable_label(selected, name(&option)).clicked() && !selected {
+                    *environment = option;
+                }
+            }
+        });
+
+    // Paths are loaded once they are confirmed, rather than on every keystroke
+    #[cfg(not(target_arch = "wasm32"))]
+    ui.horizontal(|ui| {
+        ui.add(
+            egui::TextEdit::singleline(path)
+                .hint_text("environment.hdr")
+                .desired_width(160.0),
+        );
+        if ui.button("Load").clicked() && !path.trim().is_empty() {
+            *environment = Environment::HdrFile {
+                path: std::mem::take(path).trim().to_string(),
+            };
+        }
+    });
+    #[cfg(target_arch = "wasm32")]
+    let _ = path;
+
+    let edit_color = |ui: &mut egui::Ui, label: &str, color: &mut crate::api::Vec4| {
+        ui.horizontal(|ui| {
+            ui.label(label);
+            let mut edited = color.clone().into();
+            color_edit_ui(ui, &mut edited);
+            *color = edited.into();
+        });
+    };
+    match environment {
+        Environment::HdrFile { path } => {
+            ui.label(path.as_str());
+        }
+        Environment::HdrBytes { bytes } => {
+            ui.label(format!("{} bytes", bytes.0.len()));
+        }
+        Environment::Gradient { top, bottom } => {
+            edit_color(ui, "Top:", top);
+            edit_color(ui, "Bottom:", bottom);
+        }
+        Environment::Solid { color } => edit_color(ui, "Color:", color),
+        Environment::Sky | Environment::None => {}
+    }
+}
+
+/// Toggles and tunes the effects a scene pane is put through
+fn post_process_ui(ui: &mut egui::Ui, settings: &mut crate::api::PaneSettings) {
+    use crate::api::TonemapOperator;
+
+    let tonemapping = &mut settings.tonemapping;
+    ui.checkbox(&mut tonemapping.enabled, "Tonemapping");
+    ui.add_enabled_ui(tonemapping.enabled, |ui| {
+        egui::ComboBox::new("tonemap_operator", "Operator")
+            .selected_text(format!("{:?}", tonemapping.operator))
+            .show_ui(ui, |ui| {
+                for operator in [
+                    TonemapOperator::Exponential,
+                    TonemapOperator::Reinhard,
+                    TonemapOperator::Aces,
+                ] {
+                    ui.selectable_value(
+                        &mut tonemapping.operator,
+                        operator,
+                        format!("{operator:?}"),
+                    );
+                }
+            });
+        ui.horizontal(|ui| {
+            ui.label("Exposure:");
+            ui.add(
+                egui::DragValue::new(&mut tonemapping.exposure)
+                    .speed(0.01)
+                    .range(0.0..=16.0),
+            );
+        });
+        ui.horizontal(|ui| {
+            ui.label("Gamma:");
+            ui.add(
+                egui::DragValue::new(&mut tonemapping.gamma)
+                    .speed(0.01)
+                    .range(0.1..=4.0),
+            );
+        });
+    });
+    ui.separator();
+
+    let bloom = &mut settings.bloom;
+    ui.checkbox(&mut bloom.enabled, "Bloom");
+    ui.add_enabled_ui(bloom.enabled, |ui| {
+        ui.horizontal(|ui| {
+            ui.label("Threshold:");
+            ui.add(
+                egui::DragValue::new(&mut bloom.threshold)
+                    .speed(0.01)
+                    .range(0.0..=f32::MAX),
+            );
+        });
+        ui.horizontal(|ui| {
+            ui.label("Intensity:");
+            ui.add(
+                egui::DragValue::new(&mut bloom.intensity)
+                    .speed(0.01)
+                    .range(0.0..=f32::MAX),
+            );
+        });
+    });
+    ui.separator();
+
+    let outline = &mut settings.outline;
+    ui.checkbox(&mut outline.enabled, "Selection Outline");
+    ui.add_enabled_ui(outline.enabled, |ui| {
+        ui.horizontal(|ui| {
+            ui.label("Color:");
+            let mut color = outline.color.clone().into();
+            color_edit_ui(ui, &mut color);
+            outline.color = color.into();
+        });
+        ui.horizontal(|ui| {
+            ui.label("Width:");
+            ui.add(
+                egui::DragValue::new(&mut outline.width)
+                    .speed(0.1)
+                    .range(0.0..=16.0),
+            );
+        });
+    });
+}
+
 fn camera_inspector_ui(
     context: &mut crate::context::Context,
     ui: &mut egui::Ui,
@@ -1031,7 +1693,8 @@ fn camera_inspector_ui(
 
     ui.group(|ui| {
         ui.label("Camera");
-        if let Some(camera) = get_component_mut::<Camera>(context, entity, CAMERA) {
+        let mut changed = false;
+        if let Some(camera) = get_component_mut_untracked::<Camera>(context, entity, CAMERA) {
             // Projection type selector
             ui.horizontal(|ui| {
                 ui.label("Projection:");
@@ -1040,12 +1703,14 @@ fn camera_inspector_ui(
                     .radio_value(&mut is_perspective, true, "Perspective")
                     .clicked()
                 {
+                    changed = true;
                     camera.projection = Projection::Perspective(PerspectiveCamera::default());
                 }
                 if ui
                     .radio_value(&mut is_perspective, false, "Orthographic")
                     .clicked()
                 {
+                    changed = true;
                     camera.projection = Projection::Orthographic(OrthographicCamera::default());
                 }
             });
@@ -1055,39 +1720,55 @@ fn camera_inspector_ui(
                 Projection::Perspective(perspective) => {
                     ui.horizontal(|ui| {
                         ui.label("FOV:");
-                        ui.add(egui::Slider::new(&mut camera.fov, 1.0..=120.0).suffix("°"));
+                        changed |= ui
+                            .add(egui::Slider::new(&mut camera.fov, 1.0..=120.0).suffix("°"))
+                            .changed();
                     });
                     ui.horizontal(|ui| {
                         ui.label("Near:");
-                        ui.add(egui::DragValue::new(&mut perspective.z_near).speed(0.1));
+                        changed |= ui
+                            .add(egui::DragValue::new(&mut perspective.z_near).speed(0.1))
+                            .changed();
                     });
                     if let Some(far) = &mut perspective.z_far {
                         ui.horizontal(|ui| {
                             ui.label("Far:");
-                            ui.add(egui::DragValue::new(far).speed(0.1));
+                            changed |= ui.add(egui::DragValue::new(far).speed(0.1)).changed();
                         });
                     }
                 }
                 Projection::Orthographic(ortho) => {
                     ui.horizontal(|ui| {
                         ui.label("Width:");
-                        ui.add(egui::DragValue::new(&mut ortho.x_mag).speed(0.1));
+                        changed |= ui
+                            .add(egui::DragValue::new(&mut ortho.x_mag).speed(0.1))
+                            .changed();
                     });
                     ui.horizontal(|ui| {
                         ui.label("Height:");
-                        ui.add(egui::DragValue::new(&mut ortho.y_mag).speed(0.1));
+                        changed |= ui
+                            .add(egui::DragValue::new(&mut ortho.y_mag).speed(0.1))
+                            .changed();
                     });
                     ui.horizontal(|ui| {
                         ui.label("Near:");
-                        ui.add(egui::DragValue::new(&mut ortho.z_near).speed(0.1));
+                        changed |= ui
+                            .add(egui::DragValue::new(&mut ortho.z_near).speed(0.1))
+                            .changed();
                     });
                     ui.horizontal(|ui| {
                         ui.label("Far:");
-                        ui.add(egui::DragValue::new(&mut ortho.z_far).speed(0.1));
+                        changed |= ui
+                            .add(egui::DragValue::new(&mut ortho.z_far).speed(0.1))
+                            .changed();
                     });
                 }
             }
 
+            if changed {
+                mark_component_changed(context, entity, CAMERA);
+            }
+
             if ui.button("Remove Component").clicked() {
                 remove_components(context, entity, CAMERA);
             }
@@ -1145,6 +1826,27 @@ fn left_panel_ui(context: &mut crate::context::Context, ui: &egui::Context) {
                         context.resources.user_interface.selected_entity = Some(scene);
                     }
 
+                    // Model files are imported by path, so no file dialog is needed
+                    #[cfg(not(target_arch = "wasm32"))]
+                    ui.horizontal(|ui| {
+                        let import_path = &mut context.resources.user_interface.import_path;
+                        ui.add(
+                            egui::TextEdit::singleline(import_path)
+                                .hint_text("model.glb, .obj, .ply or .stl")
+                                .desired_width(160.0),
+                        );
+                        if ui.button("Import").clicked() && !import_path.trim().is_empty() {
+                            let path = std::mem::take(import_path).trim().to_string();
+                            crate::api::publish_command(
+                                context,
+                                crate::api::Command::Import {
+                                    id: 0,
+                                    source: crate::api::ImportSource::File { path },
+                                },
+                            );
+                        }
+                    });
+
                     // Only show scene entities at root level
                     let root_scenes: Vec<_> = query_entities(context, LOCAL_TRANSFORM)
                         .into_iter()
@@ -1201,6 +1903,24 @@ fn top_panel_ui(context: &mut crate::context::Context, ui: &egui::Context) {
             );
             ui.separator();
 
+            #[cfg(not(target_arch = "wasm32"))]
+            if ui
+                .button("📷 Screenshot")
+                .on_hover_text("Save a PNG of the window")
+                .clicked()
+            {
+                publish_command(
+                    context,
+                    crate::api::Command::Capture {
+                        id: 0,
+                        target: crate::api::CaptureTarget::Window,
+                        output: crate::api::CaptureOutput::File {
+                            path: format!("{}.png", capture_name("window")),
+                        },
+                    },
+                );
+            }
+
             ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
                 ui.label(format!(
                     "FPS: {:>3}", // Right-align with width of 3
@@ -1484,6 +2204,51 @@ fn update_tile_mappings(
     }
 }
 
+/// Replaces the settings of the pane at an index in tile tree order,
+/// returning false when there is no such pane
+pub fn set_pane_settings(
+    context: &mut crate::context::Context,
+    index: usize,
+    settings: crate::api::PaneSettings,
+) -> bool {
+    let crate::ui::UserInterface {
+        tile_tree: Some(tile_tree),
+        tile_tree_context,
+        ..
+    } = &mut context.resources.user_interface
+    else {
+        return false;
+    };
+    let Some(tile_id) = tile_tree_context
+        .tile_mapping
+        .iter()
+        .filter(|(tile_id, _)| {
+            matches!(
+                tile_tree.tiles.get(**tile_id),
+                Some(egui_tiles::Tile::Pane(_))
+            )
+        })
+        .find_map(|(tile_id, pane_index)| (*pane_index == index).then_some(*tile_id))
+    else {
+        return false;
+    };
+    let Some(egui_tiles::Tile::Pane(pane)) = tile_tree.tiles.get_mut(tile_id) else {
+        return false;
+    };
+    pane.settings = settings;
+    true
+}
+
+/// Names captures and recordings after the time they were taken, so they never overwrite each other
+#[cfg(not(target_arch = "wasm32"))]
+fn capture_name(subject: &str) -> String {
+    let milliseconds = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|duration| duration.as_millis())
+        .unwrap_or_default();
+    format!("nightshade-{subject}-{milliseconds}")
+}
+
 fn create_scene_pane(context: &mut crate::context::Context) -> Pane {
     // Count only root nodes (no Parent component) for scene numbering
     let scene_count = query_entities(context, LOCAL_TRANSFORM)
@@ -1527,6 +2292,7 @@ fn create_scene_pane(context: &mut crate::context::Context) -> Pane {
             scene_entity: scene,
             camera_entity: Some(camera),
         },
+        ..Default::default()
     }
 }
 
@@ -1575,6 +2341,9 @@ fn would_create_cycle(
 #[derive(Default, Clone)]
 pub struct ApiLogEntry {
     pub message: Message,
+
+    /// The RPC connection the message came from or was addressed to
+    pub connection: Option<String>,
 }
 
 // Update the function to check both text editing and window focus
@@ -26,6 +26,10 @@ pub struct Window {
 
     /// Milliseconds that the process has been running continuously
     pub uptime_milliseconds: u64,
+
+    /// Replaces the measured delta time while set,
+    /// so recordings advance by exactly one frame per rendered frame
+    pub fixed_delta_time: Option<f32>,
 }
 
 #[cfg(target_arch = "wasm32")]
@@ -141,7 +145,8 @@ pub fn initialize(context: &mut crate::context::Context) {
 
     #[cfg(not(target_arch = "wasm32"))]
     {
-        env_logger::init();
+        // A host program embedding the viewer may have installed its own logger
+        let _ = env_logger::try_init();
         let renderer = pollster::block_on(async move {
             crate::graphics::create_renderer_async(window_handle.clone(), width, height).await
         });
@@ -216,6 +221,7 @@ pub fn update_frame_timing_system(context: &mut crate::context::Context) {
                         frame_counter,
                         uptime_milliseconds,
                         frames_per_second,
+                        fixed_delta_time,
                         ..
                     },
                 ..
@@ -229,8 +235,9 @@ pub fn update_frame_timing_system(context: &mut crate::context::Context) {
     }
 
     // Delta time
-    *delta_time =
-        last_frame_start_instant.map_or(0.0, |last_frame| (now - last_frame).as_secs_f32());
+    *delta_time = fixed_delta_time.unwrap_or_else(|| {
+        last_frame_start_instant.map_or(0.0, |last_frame| (now - last_frame).as_secs_f32())
+    });
 
     // Last frame start
     *last_frame_start_instant = Some(now);